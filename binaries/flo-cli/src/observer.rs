@@ -17,6 +17,9 @@ pub enum Command {
   GenerateReplay {
     game_id: i32,
   },
+  WatchReplay {
+    path: String,
+  },
 }
 
 impl Command {
@@ -39,6 +42,11 @@ impl Command {
         client.watch(token).await?;
         client.serve().await;
       }
+      Command::WatchReplay { ref path } => {
+        let client = flo_client::start(Default::default()).await?;
+        client.watch_replay(path.clone()).await?;
+        client.serve().await;
+      }
       Command::GenerateReplay { game_id } => {
         tracing::info!("fetching game information...");
 
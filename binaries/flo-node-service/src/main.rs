@@ -20,6 +20,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   tracing::info!("starting.");
 
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut stream = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+      loop {
+        stream.recv().await;
+        tracing::info!("reloading");
+        if let Err(err) = flo_node::reload_config() {
+          tracing::error!("reload error: {}", err);
+        }
+      }
+    });
+  }
+
   serve().await?;
 
   Ok(())
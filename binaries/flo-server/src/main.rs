@@ -0,0 +1,124 @@
+use flo_controller::lan::{LanConfig, LAN_TOKEN_PREFIX};
+use flo_controller::{serve_grpc, serve_socket, ControllerState};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+const SECRETS_FILENAME: &str = "secrets.json";
+const DATABASE_FILENAME: &str = "flo.db";
+
+/// Runs the controller and a node in one process, for local network events.
+///
+/// The database is read from `DATABASE_URL`, a SQLite database in `--data-dir` is used if not set.
+#[derive(Debug, StructOpt)]
+struct Opt {
+  /// Directory to store generated secrets and the database
+  #[structopt(long, default_value = "flo-server-data")]
+  data_dir: PathBuf,
+  /// Address players use to connect, detected if not specified
+  #[structopt(long)]
+  public_ip: Option<Ipv4Addr>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Secrets {
+  jwt_secret_base64: String,
+  node_secret: String,
+  api_client_secret: String,
+}
+
+impl Secrets {
+  fn load_or_create(dir: &Path) -> anyhow::Result<Self> {
+    let path = dir.join(SECRETS_FILENAME);
+    if path.exists() {
+      return Ok(serde_json::from_slice(&fs::read(path)?)?);
+    }
+
+    let secrets = Secrets {
+      jwt_secret_base64: base64::encode(rand::thread_rng().gen::<[u8; 32]>()),
+      node_secret: random_string(),
+      api_client_secret: random_string(),
+    };
+    fs::create_dir_all(dir)?;
+    fs::write(path, serde_json::to_vec_pretty(&secrets)?)?;
+    Ok(secrets)
+  }
+}
+
+fn random_string() -> String {
+  rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(32)
+    .map(char::from)
+    .collect()
+}
+
+// no packet is sent, connecting only selects the interface of the default route
+fn detect_ip() -> Option<Ipv4Addr> {
+  let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+  socket.connect((Ipv4Addr::new(203, 0, 113, 1), 9)).ok()?;
+  match socket.local_addr().ok()?.ip() {
+    std::net::IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+    _ => None,
+  }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  #[cfg(windows)]
+  unsafe {
+    winapi::um::timeapi::timeBeginPeriod(1);
+  }
+
+  flo_log_subscriber::init_env_override("flo_server=info,flo_controller=info,flo_node=info");
+
+  let opt = Opt::from_args();
+  let secrets = Secrets::load_or_create(&opt.data_dir)?;
+  let ip = match opt.public_ip.or_else(detect_ip) {
+    Some(ip) => ip,
+    None => {
+      tracing::warn!("unable to detect the LAN address, only local clients can connect");
+      Ipv4Addr::LOCALHOST
+    }
+  };
+
+  // the controller and the node read their settings from the environment
+  std::env::set_var("JWT_SECRET_BASE64", &secrets.jwt_secret_base64);
+  std::env::set_var("FLO_NODE_SECRET", &secrets.node_secret);
+  std::env::set_var("OBSERVER_DISABLED", "1");
+  if std::env::var_os("DATABASE_URL").is_none() {
+    let path = opt.data_dir.join(DATABASE_FILENAME);
+    std::env::set_var("DATABASE_URL", format!("sqlite://{}", path.display()));
+  }
+
+  let state = ControllerState::init_lan(LanConfig {
+    node_ip_addr: ip.to_string(),
+    node_secret: secrets.node_secret,
+    api_client_secret: secrets.api_client_secret.clone(),
+  })
+  .await?
+  .into_ref();
+
+  tracing::info!(
+    "players connect with `controller_host = \"{}\"` and token `{}<name>`",
+    ip,
+    LAN_TOKEN_PREFIX
+  );
+  tracing::info!("api client secret: {}", secrets.api_client_secret);
+
+  tokio::try_join!(
+    async { flo_node::serve().await.map_err(anyhow::Error::from) },
+    async { serve_grpc(state.clone()).await.map_err(anyhow::Error::from) },
+    async {
+      serve_socket(state.clone())
+        .await
+        .map_err(anyhow::Error::from)
+    },
+  )?;
+
+  Ok(())
+}
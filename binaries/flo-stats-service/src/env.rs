@@ -1,5 +1,28 @@
 use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
 use std::env;
 
+/// Name of the API client using `FLO_ADMIN_SECRET`.
+pub const ADMIN_CLIENT_NAME: &str = "admin";
+
 pub static ADMIN_SECRET: Lazy<String> =
   Lazy::new(|| env::var("FLO_ADMIN_SECRET").ok().unwrap_or_default());
+
+/// API client name by key, parsed from `FLO_API_CLIENT_KEYS` (`name:key,name:key`).
+/// `FLO_ADMIN_SECRET` is still accepted as the key of the `admin` client.
+pub static API_CLIENT_KEYS: Lazy<BTreeMap<String, String>> = Lazy::new(|| {
+  let mut map = BTreeMap::new();
+  if let Ok(value) = env::var("FLO_API_CLIENT_KEYS") {
+    for item in value.split(',') {
+      if let Some((name, key)) = item.trim().split_once(':') {
+        if !name.is_empty() && !key.is_empty() {
+          map.insert(key.to_string(), name.to_string());
+        }
+      }
+    }
+  }
+  if !ADMIN_SECRET.is_empty() {
+    map.insert(ADMIN_SECRET.clone(), ADMIN_CLIENT_NAME.to_string());
+  }
+  map
+});
@@ -1,14 +1,26 @@
-use async_graphql::{Context, Error, Object, Result, Schema, SimpleObject, Subscription, Union};
+use async_graphql::{
+  Context, Error, ErrorExtensions, Object, Result, Schema, SimpleObject, Subscription, Union,
+};
+use chrono::{DateTime, Utc};
+use flo_observer::token::{
+  create_observer_token_with_options, validate_observer_token, CreateObserverTokenOptions,
+  TOKEN_EXPIRATION_SECS,
+};
 use flo_observer_edge::{
+  chat::ChatFeedOptions,
+  cluster::InstanceInfo,
   game::snapshot::GameSnapshot,
   game::{
     event::{GameListUpdateEvent, GameUpdateEvent},
     snapshot::GameSnapshotWithStats,
   },
+  index::{GameHistory, GameHistoryPage, GameHistoryQuery},
   FloObserverEdgeHandle,
 };
+use std::time::Duration;
 use tokio_stream::{once, Stream, StreamExt};
 
+use crate::env::ADMIN_CLIENT_NAME;
 use crate::RequestData;
 
 pub type FloLiveSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
@@ -20,6 +32,46 @@ impl QueryRoot {
     let handle: &FloObserverEdgeHandle = ctx.data()?;
     handle.list_games().await.map_err(Into::into)
   }
+
+  async fn instances(&self, ctx: &Context<'_>) -> Result<Vec<InstanceInfo>> {
+    let handle: &FloObserverEdgeHandle = ctx.data()?;
+    handle.list_instances().await.map_err(Into::into)
+  }
+
+  async fn game_history(
+    &self,
+    ctx: &Context<'_>,
+    player_name: Option<String>,
+    map_name: Option<String>,
+    node_id: Option<i32>,
+    started_after: Option<DateTime<Utc>>,
+    started_before: Option<DateTime<Utc>>,
+    take: Option<u32>,
+    since_id: Option<i32>,
+  ) -> Result<GameHistoryPage> {
+    let handle: &FloObserverEdgeHandle = ctx.data()?;
+    let data: &RequestData = ctx.data()?;
+    handle
+      .query_game_history(GameHistoryQuery {
+        player_name,
+        map_name,
+        node_id,
+        started_after,
+        started_before,
+        include_private: data.is_admin,
+        take: take.map(|v| v as usize),
+        since_id,
+      })
+      .await
+      .map_err(Into::into)
+  }
+
+  async fn game_history_item(&self, ctx: &Context<'_>, id: i32) -> Result<Option<GameHistory>> {
+    let handle: &FloObserverEdgeHandle = ctx.data()?;
+    let data: &RequestData = ctx.data()?;
+    let item = handle.get_game_history(id).await?;
+    Ok(item.filter(|item| !item.entry.game.is_private || data.is_admin))
+  }
 }
 
 pub struct MutationRoot;
@@ -31,8 +83,11 @@ impl MutationRoot {
     ctx: &Context<'_>,
     game_id: i32,
     delay_secs: Option<u16>,
+    expires_in_secs: Option<i64>,
+    max_viewers: Option<u32>,
   ) -> Result<ObserverTokenPayload> {
     let handle: &FloObserverEdgeHandle = ctx.data()?;
+    check_game_instance(handle, game_id).await?;
     let game = handle.get_game(game_id).await?;
     let data: &RequestData = ctx.data()?;
 
@@ -47,23 +102,108 @@ impl MutationRoot {
         return Err(Error::new("Only admin can specify delay value."));
       }
     } else {
-      if game.is_live {
-        0
-      } else {
-        180
-      }
+      default_delay_secs(&game)
     };
     let delay_secs = if delay_secs == 0 {
       None
     } else {
       Some(delay_secs)
     };
+
+    if let Some(value) = expires_in_secs {
+      if value > TOKEN_EXPIRATION_SECS && !data.is_admin {
+        return Err(Error::new("Only admin can extend token expiration."));
+      }
+    }
+
+    let (token, claims) = create_observer_token_with_options(CreateObserverTokenOptions {
+      game_id,
+      delay_secs,
+      issuer: data.api_client.clone(),
+      expires_in_secs,
+      max_viewers,
+    })?;
+
     Ok(ObserverTokenPayload {
       game,
       delay_secs: delay_secs.clone(),
-      token: flo_observer::token::create_observer_token(game_id, delay_secs)?,
+      token,
+      token_id: claims.jti,
+      expires_at: claims.exp as i64,
+      max_viewers: claims.max_viewers,
+      stream_host: handle.stream_host().map(ToString::to_string),
     })
   }
+
+  /// Only the API client that issued the token or the admin client can revoke it.
+  async fn revoke_observer_token(&self, ctx: &Context<'_>, token: String) -> Result<bool> {
+    let handle: &FloObserverEdgeHandle = ctx.data()?;
+    let data: &RequestData = ctx.data()?;
+
+    let claims = match validate_observer_token(&token) {
+      Ok(claims) => claims,
+      // expired tokens are rejected already
+      Err(flo_observer::error::Error::ObserverTokenExpired) => return Ok(true),
+      Err(err) => return Err(err.into()),
+    };
+
+    let api_client = data.api_client.as_deref();
+    if api_client != Some(claims.iss.as_str()) && api_client != Some(ADMIN_CLIENT_NAME) {
+      return Err(Error::new(
+        "Only the issuing client or admin can revoke the token.",
+      ));
+    }
+
+    handle
+      .revoke_observer_token(claims.jti, claims.exp as i64)
+      .await?;
+    Ok(true)
+  }
+
+  async fn set_chat_player_hidden(
+    &self,
+    ctx: &Context<'_>,
+    game_id: i32,
+    player_id: i32,
+    #[graphql(default = true)] hidden: bool,
+  ) -> Result<bool> {
+    let handle: &FloObserverEdgeHandle = ctx.data()?;
+    let data: &RequestData = ctx.data()?;
+
+    if !data.is_admin {
+      return Err(Error::new("Only admin can moderate chat."));
+    }
+
+    handle.set_chat_player_hidden(game_id, player_id, hidden);
+    Ok(hidden)
+  }
+}
+
+// Games held by another edge instance must be requested from that instance
+async fn check_game_instance(handle: &FloObserverEdgeHandle, game_id: i32) -> Result<()> {
+  if let Some(instance) = handle.find_remote_instance(game_id).await? {
+    return Err(
+      Error::new("Game is served by another instance.").extend_with(|_, e| {
+        e.set("instanceId", instance.id.clone());
+        if let Some(ref url) = instance.graphql_url {
+          e.set("graphqlUrl", url.clone());
+        }
+        if let Some(ref host) = instance.stream_host {
+          e.set("streamHost", host.clone());
+        }
+      }),
+    );
+  }
+  Ok(())
+}
+
+// Live games are streamed without delay
+fn default_delay_secs(game: &GameSnapshot) -> i64 {
+  if game.is_live {
+    0
+  } else {
+    180
+  }
 }
 
 #[derive(SimpleObject)]
@@ -71,6 +211,11 @@ pub struct ObserverTokenPayload {
   pub game: GameSnapshot,
   pub delay_secs: Option<i64>,
   pub token: String,
+  pub token_id: String,
+  pub expires_at: i64,
+  pub max_viewers: Option<u32>,
+  /// Host to connect to with the token, when the edge runs in a cluster.
+  pub stream_host: Option<String>,
 }
 
 pub struct SubscriptionRoot;
@@ -82,10 +227,9 @@ impl SubscriptionRoot {
     ctx: &Context<'_>,
   ) -> Result<impl Stream<Item = GameListUpdateEventItem>> {
     let handle: &FloObserverEdgeHandle = ctx.data()?;
-    let (snapshots, rx) = handle.subscribe_game_list_updates().await?;
-    let events = rx
-      .into_stream()
-      .map(|event| GameListUpdateEventItem::Event(GameListUpdateEventItemEvent { event }));
+    let (snapshots, events) = handle.subscribe_game_list_updates().await?;
+    let events =
+      events.map(|event| GameListUpdateEventItem::Event(GameListUpdateEventItemEvent { event }));
     Ok(
       once(GameListUpdateEventItem::Initial(
         GameListUpdateEventItemInitial { snapshots },
@@ -98,10 +242,41 @@ impl SubscriptionRoot {
     &self,
     ctx: &Context<'_>,
     id: i32,
+    chat_delay_secs: Option<u16>,
   ) -> Result<impl Stream<Item = GameUpdateEventItem>> {
     let handle: &FloObserverEdgeHandle = ctx.data()?;
-    let (snapshot, rx) = handle.subscribe_game_updates(id).await?;
-    let events = rx.into_stream().map(GameUpdateEventItem::Event);
+    check_game_instance(handle, id).await?;
+    // websocket connections don't carry the admin header
+    let is_admin = ctx
+      .data_opt::<RequestData>()
+      .map(|data| data.is_admin)
+      .unwrap_or_default();
+
+    let delay_secs = if let Some(value) = chat_delay_secs {
+      if is_admin {
+        value as i64
+      } else {
+        return Err(Error::new("Only admin can specify delay value."));
+      }
+    } else {
+      default_delay_secs(&handle.get_game(id).await?)
+    };
+
+    let (snapshot, events) = handle
+      .subscribe_game_updates_with_chat(
+        id,
+        ChatFeedOptions {
+          delay: if delay_secs > 0 {
+            Some(Duration::from_secs(delay_secs as u64))
+          } else {
+            None
+          },
+          include_private_scopes: is_admin,
+          include_hidden: is_admin,
+        },
+      )
+      .await?;
+    let events = events.map(GameUpdateEventItem::Event);
     Ok(once(GameUpdateEventItem::Initial(snapshot)).chain(events))
   }
 }
@@ -1,5 +1,6 @@
 mod env;
 mod graphql;
+mod replay;
 
 use crate::graphql::{FloLiveSchema, MutationRoot, QueryRoot, SubscriptionRoot};
 use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
@@ -15,6 +16,20 @@ use tower_http::cors::{CorsLayer, Origin};
 
 pub struct RequestData {
   pub is_admin: bool,
+  pub api_client: Option<String>,
+}
+
+impl RequestData {
+  pub fn from_headers(headers: &HeaderMap) -> Self {
+    let api_client = headers
+      .get("x-flo-admin-secret")
+      .and_then(|v| v.to_str().ok())
+      .and_then(|v| crate::env::API_CLIENT_KEYS.get(v).cloned());
+    Self {
+      is_admin: api_client.is_some(),
+      api_client,
+    }
+  }
 }
 
 async fn graphql_handler(
@@ -22,12 +37,7 @@ async fn graphql_handler(
   req: GraphQLRequest,
   headers: HeaderMap,
 ) -> GraphQLResponse {
-  let req = req.into_inner().data(RequestData {
-    is_admin: headers
-      .get("x-flo-admin-secret")
-      .map(|v| v.as_bytes() == crate::env::ADMIN_SECRET.as_bytes())
-      .unwrap_or_default(),
-  });
+  let req = req.into_inner().data(RequestData::from_headers(&headers));
   schema.execute(req).await.into()
 }
 
@@ -52,8 +62,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
   let edge = FloObserverEdge::from_env().await?;
 
+  let handle = edge.handle();
   let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
-    .data(edge.handle())
+    .data(handle.clone())
     .finish();
 
   tokio::spawn(async move {
@@ -65,7 +76,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   let app = Router::new()
     .route("/", get(graphql_playground).post(graphql_handler))
     .route("/ws", GraphQLSubscription::new(schema.clone()))
+    .route("/replays/:game_id", get(replay::download_replay))
     .layer(Extension(schema))
+    .layer(Extension(handle))
     .layer({
       let allowed_list: [HeaderValue; 4] = [
         "http://localhost:3000".parse().unwrap(),
@@ -75,7 +88,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
       ];
       CorsLayer::new()
         .allow_origin(Origin::list(allowed_list))
-        .allow_methods(vec![Method::POST])
+        .allow_methods(vec![Method::GET, Method::POST])
         .allow_headers(tower_http::cors::Any)
     });
 
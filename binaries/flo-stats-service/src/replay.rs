@@ -0,0 +1,99 @@
+use axum::extract::{Extension, Path, Query};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use flo_observer_edge::error::Error;
+use flo_observer_edge::{FloObserverEdgeHandle, Replay};
+use http::header::HeaderMap;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadReplayParams {
+  #[serde(default)]
+  pub include_chats: bool,
+}
+
+pub async fn download_replay(
+  Extension(handle): Extension<FloObserverEdgeHandle>,
+  Path(game_id): Path<i32>,
+  Query(params): Query<DownloadReplayParams>,
+  headers: HeaderMap,
+) -> Response {
+  let is_admin = crate::RequestData::from_headers(&headers).is_admin;
+
+  if params.include_chats && !is_admin {
+    return (StatusCode::FORBIDDEN, "Only admin can download chats.").into_response();
+  }
+
+  match handle
+    .get_replay(game_id, params.include_chats, is_admin)
+    .await
+  {
+    Ok(replay) => replay_response(replay),
+    Err(err) => {
+      let status = error_status(&err);
+      if status == StatusCode::INTERNAL_SERVER_ERROR {
+        tracing::error!(game_id, "replay: {}", err);
+      }
+      (status, err.to_string()).into_response()
+    }
+  }
+}
+
+fn replay_response(replay: Replay) -> Response {
+  (
+    [
+      (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+      (
+        header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"{}\"", replay.filename),
+      ),
+    ],
+    replay.data,
+  )
+    .into_response()
+}
+
+fn error_status(err: &Error) -> StatusCode {
+  match err {
+    Error::GameNotFound(_) | Error::InvalidGameId(_) | Error::ArchiveNotFound(_) => {
+      StatusCode::NOT_FOUND
+    }
+    Error::PrivateGame(_) => StatusCode::FORBIDDEN,
+    Error::ArchiverDisabled => StatusCode::SERVICE_UNAVAILABLE,
+    _ => StatusCode::INTERNAL_SERVER_ERROR,
+  }
+}
+
+#[test]
+fn test_replay_response() {
+  let res = replay_response(Replay {
+    filename: "flo_1.w3g".to_string(),
+    data: vec![1, 2, 3].into(),
+  });
+  assert_eq!(res.status(), StatusCode::OK);
+  assert_eq!(
+    res.headers()[header::CONTENT_DISPOSITION],
+    "attachment; filename=\"flo_1.w3g\""
+  );
+  assert_eq!(
+    res.headers()[header::CONTENT_TYPE],
+    "application/octet-stream"
+  );
+}
+
+#[test]
+fn test_error_status() {
+  assert_eq!(
+    error_status(&Error::ArchiveNotFound(1)),
+    StatusCode::NOT_FOUND
+  );
+  assert_eq!(error_status(&Error::PrivateGame(1)), StatusCode::FORBIDDEN);
+  assert_eq!(
+    error_status(&Error::ArchiverDisabled),
+    StatusCode::SERVICE_UNAVAILABLE
+  );
+  assert_eq!(
+    error_status(&Error::GameVersionUnknown),
+    StatusCode::INTERNAL_SERVER_ERROR
+  );
+}
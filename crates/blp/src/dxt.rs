@@ -0,0 +1,192 @@
+//! DXT1/3/5 (S3TC) block decoding and encoding for BLP2 mipmaps.
+
+use flo_util::binary::BinDecodeError;
+use image::codecs::dxt::{DxtEncoder, DxtVariant};
+use image::{ImageBuffer, Rgba, RgbaImage};
+
+use crate::error::{EncodeError, Result};
+use crate::mipmap_len;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dxt {
+  Dxt1 { alpha: bool },
+  Dxt3,
+  Dxt5,
+}
+
+impl Dxt {
+  fn block_size(self) -> usize {
+    match self {
+      Dxt::Dxt1 { .. } => 8,
+      Dxt::Dxt3 | Dxt::Dxt5 => 16,
+    }
+  }
+}
+
+pub(crate) fn decode(
+  variant: Dxt,
+  width: u32,
+  height: u32,
+  data: &[u8],
+) -> Result<RgbaImage, BinDecodeError> {
+  let blocks_x = width.div_ceil(4) as usize;
+  let blocks_y = height.div_ceil(4) as usize;
+  let block_size = variant.block_size();
+  if data.len() < mipmap_len(width.div_ceil(4), height.div_ceil(4), block_size)? {
+    return Err(BinDecodeError::incomplete().context("dxt mipmap"));
+  }
+
+  let mut image = ImageBuffer::new(width, height);
+  for (i, block) in data
+    .chunks_exact(block_size)
+    .take(blocks_x * blocks_y)
+    .enumerate()
+  {
+    let pixels = match variant {
+      Dxt::Dxt1 { alpha } => decode_color_block(block, !alpha, true),
+      Dxt::Dxt3 => {
+        let mut pixels = decode_color_block(&block[8..], false, false);
+        let mut bits = [0; 8];
+        bits.copy_from_slice(&block[..8]);
+        let bits = u64::from_le_bytes(bits);
+        for (j, p) in pixels.iter_mut().enumerate() {
+          let v = ((bits >> (j * 4)) & 0xF) as u8;
+          p[3] = v << 4 | v;
+        }
+        pixels
+      }
+      Dxt::Dxt5 => {
+        let mut pixels = decode_color_block(&block[8..], false, false);
+        let alphas = alpha_palette(block[0], block[1]);
+        let mut bits = [0; 8];
+        bits[..6].copy_from_slice(&block[2..8]);
+        let bits = u64::from_le_bytes(bits);
+        for (j, p) in pixels.iter_mut().enumerate() {
+          p[3] = alphas[((bits >> (j * 3)) & 0x7) as usize];
+        }
+        pixels
+      }
+    };
+
+    let bx = (i % blocks_x) as u32 * 4;
+    let by = (i / blocks_x) as u32 * 4;
+    for (j, p) in pixels.iter().enumerate() {
+      let x = bx + (j % 4) as u32;
+      let y = by + (j / 4) as u32;
+      if x < width && y < height {
+        image.put_pixel(x, y, Rgba(*p));
+      }
+    }
+  }
+  Ok(image)
+}
+
+/// Encodes a mipmap with the S3TC encoder from the `image` crate.
+/// The image is padded to whole 4x4 blocks by repeating its edge pixels.
+pub(crate) fn encode(variant: Dxt, image: &RgbaImage) -> Result<Vec<u8>> {
+  let (width, height) = image.dimensions();
+  let padded_width = width.next_multiple_of(4);
+  let padded_height = height.next_multiple_of(4);
+  let padded = ImageBuffer::from_fn(padded_width, padded_height, |x, y| {
+    *image.get_pixel(x.min(width - 1), y.min(height - 1))
+  });
+
+  let (variant, data): (_, Vec<u8>) = match variant {
+    Dxt::Dxt1 { alpha: false } => (
+      DxtVariant::DXT1,
+      padded
+        .pixels()
+        .flat_map(|Rgba([r, g, b, _])| [*r, *g, *b])
+        .collect(),
+    ),
+    Dxt::Dxt3 => (DxtVariant::DXT3, padded.into_raw()),
+    Dxt::Dxt5 => (DxtVariant::DXT5, padded.into_raw()),
+    Dxt::Dxt1 { alpha: true } => {
+      return Err(EncodeError::UnsupportedFormat(crate::BLPFormat::Blp2Dxt1 {
+        alpha_bits: 1,
+      }))
+    }
+  };
+
+  let mut bytes = vec![];
+  DxtEncoder::new(&mut bytes)
+    .encode(&data, padded_width, padded_height, variant)
+    .map_err(EncodeError::Dxt)?;
+  Ok(bytes)
+}
+
+fn decode_color_block(block: &[u8], opaque: bool, allow_three_color: bool) -> [[u8; 4]; 16] {
+  let c0 = u16::from_le_bytes([block[0], block[1]]);
+  let c1 = u16::from_le_bytes([block[2], block[3]]);
+  let indices = u32::from_le_bytes(to_array(&block[4..8]));
+  let [r0, g0, b0] = expand_565(c0);
+  let [r1, g1, b1] = expand_565(c1);
+
+  let colors = if c0 > c1 || !allow_three_color {
+    [
+      [r0, g0, b0, 255],
+      [r1, g1, b1, 255],
+      [
+        lerp(r0, r1, 2, 1, 3),
+        lerp(g0, g1, 2, 1, 3),
+        lerp(b0, b1, 2, 1, 3),
+        255,
+      ],
+      [
+        lerp(r0, r1, 1, 2, 3),
+        lerp(g0, g1, 1, 2, 3),
+        lerp(b0, b1, 1, 2, 3),
+        255,
+      ],
+    ]
+  } else {
+    [
+      [r0, g0, b0, 255],
+      [r1, g1, b1, 255],
+      [
+        lerp(r0, r1, 1, 1, 2),
+        lerp(g0, g1, 1, 1, 2),
+        lerp(b0, b1, 1, 1, 2),
+        255,
+      ],
+      [0, 0, 0, if opaque { 255 } else { 0 }],
+    ]
+  };
+
+  let mut pixels = [[0; 4]; 16];
+  for (i, p) in pixels.iter_mut().enumerate() {
+    *p = colors[((indices >> (i * 2)) & 0x3) as usize];
+  }
+  pixels
+}
+
+fn alpha_palette(a0: u8, a1: u8) -> [u8; 8] {
+  let mut alphas = [a0, a1, 0, 0, 0, 0, 0, 255];
+  if a0 > a1 {
+    for i in 1..7 {
+      alphas[i + 1] = lerp(a0, a1, 7 - i as u32, i as u32, 7);
+    }
+  } else {
+    for i in 1..5 {
+      alphas[i + 1] = lerp(a0, a1, 5 - i as u32, i as u32, 5);
+    }
+  }
+  alphas
+}
+
+fn expand_565(v: u16) -> [u8; 3] {
+  let r = ((v >> 11) & 0x1F) as u8;
+  let g = ((v >> 5) & 0x3F) as u8;
+  let b = (v & 0x1F) as u8;
+  [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2]
+}
+
+fn lerp(a: u8, b: u8, wa: u32, wb: u32, d: u32) -> u8 {
+  ((a as u32 * wa + b as u32 * wb) / d) as u8
+}
+
+fn to_array(slice: &[u8]) -> [u8; 4] {
+  let mut v = [0; 4];
+  v.copy_from_slice(&slice[..4]);
+  v
+}
@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+use crate::BLPFormat;
+
+#[derive(Error, Debug)]
+pub enum EncodeError {
+  #[error("invalid image dimensions: {0}x{1}")]
+  InvalidDimensions(u32, u32),
+  #[error("unsupported format: {0:?}")]
+  UnsupportedFormat(BLPFormat),
+  #[error("encode jpeg: {0}")]
+  Jpeg(image::ImageError),
+  #[error("encode dxt: {0}")]
+  Dxt(image::ImageError),
+}
+
+pub type Result<T, E = EncodeError> = std::result::Result<T, E>;
@@ -0,0 +1,84 @@
+//! BLP1 JPEG mipmaps.
+//!
+//! Every mipmap is a JPEG stream split in two: a header shared by all mipmaps and
+//! stored once after the BLP header, and the remaining bytes stored at the mipmap offset.
+//! Color channels are stored as BGR.
+
+use flo_util::binary::BinDecodeError;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ColorType, ImageBuffer, ImageFormat, Rgb, RgbaImage};
+
+use crate::error::{EncodeError, Result};
+use crate::mipmap_len;
+
+/// Warcraft III refuses to load images with larger shared headers.
+pub(crate) const MAX_HEADER_SIZE: usize = 624;
+
+const SOI_HEADER: &[u8] = &[
+  0xFF, 0xD8, 0xFF, 0xEE, 0x00, 0x0E, //App14Marker
+  b'A', b'd', b'o', b'b', b'e', 0, 0, 0, 0, 0, 0, 0,
+];
+
+pub(crate) fn decode(header: &[u8], data: &[u8]) -> Result<RgbaImage, BinDecodeError> {
+  let mut img_buf = Vec::with_capacity(header.len() + data.len());
+  img_buf.extend(header);
+  img_buf.extend(data);
+
+  let image = image::load_from_memory_with_format(&img_buf, ImageFormat::Jpeg)
+    .or_else(|e| {
+      if e.to_string().contains("Adobe APP14") {
+        let mut patched = Vec::with_capacity(img_buf.len() - 2 + SOI_HEADER.len());
+        patched.extend(SOI_HEADER);
+        patched.extend(&img_buf[2..]);
+        image::load_from_memory_with_format(&patched, ImageFormat::Jpeg)
+      } else {
+        Err(e)
+      }
+    })
+    .map_err(|e| BinDecodeError::failure(format!("decode jpeg: {:?}", e)))?;
+
+  if let Some(rbg_image) = image.as_rgb8() {
+    let (w, h) = rbg_image.dimensions();
+    let mut raw = Vec::with_capacity(mipmap_len(w, h, 4)?);
+    for Rgb([r, g, b]) in rbg_image.pixels() {
+      raw.extend(&[*b, *g, *r, 255])
+    }
+    ImageBuffer::from_raw(w, h, raw)
+      .ok_or_else(|| BinDecodeError::failure("decode jpeg: invalid image size"))
+  } else {
+    Err(BinDecodeError::failure(
+      "decode jpeg: pixel format is not rgb",
+    ))
+  }
+}
+
+/// Encodes all mipmaps and returns the shared header and the per mipmap data.
+pub(crate) fn encode(mipmaps: &[RgbaImage], quality: u8) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+  let mut streams = Vec::with_capacity(mipmaps.len());
+  for mipmap in mipmaps {
+    let (w, h) = mipmap.dimensions();
+    let bgr: Vec<u8> = mipmap.pixels().flat_map(|p| [p[2], p[1], p[0]]).collect();
+    let mut bytes = vec![];
+    JpegEncoder::new_with_quality(&mut bytes, quality)
+      .encode(&bgr, w, h, ColorType::Rgb8)
+      .map_err(EncodeError::Jpeg)?;
+    streams.push(bytes);
+  }
+
+  let first = &streams[0];
+  let header_len = streams[1..]
+    .iter()
+    .fold(first.len().min(MAX_HEADER_SIZE), |len, stream| {
+      first[..len]
+        .iter()
+        .zip(stream.iter())
+        .take_while(|(a, b)| a == b)
+        .count()
+    });
+  let header = first[..header_len].to_vec();
+  let data = streams
+    .into_iter()
+    .map(|mut stream| stream.split_off(header_len))
+    .collect();
+  Ok((header, data))
+}
@@ -1,38 +1,120 @@
-//! BLIzzard Picture image format decoder.
+//! BLIzzard Picture image format decoder and encoder.
 //!
 //! Author:  Niels A.D.
 //! Project: gowarcraft3 (https://github.com/nielsAD/gowarcraft3)
 //! License: Mozilla Public License, v2.0
 //!
 //! Ported from https://github.com/nielsAD/gowarcraft3/blob/master/file/blp/blp.go
+//!
+//! Supported formats:
+//! - BLP1: JPEG and 256-color palette with 0/1/4/8-bit alpha
+//! - BLP2: 256-color palette, DXT1/3/5 and uncompressed BGRA
 
 use flo_util::binary::*;
-use flo_util::BinDecode;
-use image::{ImageBuffer, ImageFormat, Rgb, Rgba};
+use flo_util::{BinDecode, BinEncode};
+use image::imageops::FilterType;
+use image::{ImageBuffer, Rgba, RgbaImage};
+
+pub mod error;
+
+mod dxt;
+mod jpeg;
+mod palette;
+
+use self::dxt::Dxt;
+use self::error::{EncodeError, Result};
+use self::palette::{Palette, Quantizer, PALETTE_SIZE};
+
+const MAX_MIPMAPS: usize = 16;
 
 const COMPRESSION_JPEG: u32 = 0;
-const SOI_HEADER: &[u8] = &[
-  0xFF, 0xD8, 0xFF, 0xEE, 0x00, 0x0E, //App14Marker
-  b'A', b'd', b'o', b'b', b'e', 0, 0, 0, 0, 0, 0, 0,
-];
+const COMPRESSION_PALETTE: u32 = 1;
+
+const BLP2_TYPE_DIRECT: u32 = 1;
+const BLP2_COMPRESSION_PALETTE: u8 = 1;
+const BLP2_COMPRESSION_DXT: u8 = 2;
+const BLP2_COMPRESSION_RAW: u8 = 3;
+const BLP2_ALPHA_TYPE_DXT1: u8 = 0;
+const BLP2_ALPHA_TYPE_DXT3: u8 = 1;
+const BLP2_ALPHA_TYPE_DXT5: u8 = 7;
+const BLP2_ALPHA_TYPE_RAW: u8 = 8;
+
+/// Storage format of a BLP image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BLPFormat {
+  /// BLP1 with JPEG compressed mipmaps.
+  /// Alpha channel is not supported by the encoder.
+  Blp1Jpeg { alpha_bits: u8 },
+  /// BLP1 with 256-color palette and 0/1/4/8-bit alpha.
+  Blp1Palette { alpha_bits: u8 },
+  /// BLP2 with 256-color palette and 0/1/4/8-bit alpha.
+  Blp2Palette { alpha_bits: u8 },
+  /// BLP2 with DXT1 compressed mipmaps and 0/1-bit alpha.
+  /// 1-bit alpha is not supported by the encoder.
+  Blp2Dxt1 { alpha_bits: u8 },
+  /// BLP2 with DXT3 compressed mipmaps.
+  Blp2Dxt3,
+  /// BLP2 with DXT5 compressed mipmaps.
+  Blp2Dxt5,
+  /// BLP2 with uncompressed BGRA mipmaps.
+  Blp2Raw,
+}
 
 pub struct BLPImage {
+  format: BLPFormat,
   image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+  data: Bytes,
+  levels: MipmapLevels,
+  decoder: MipmapDecoder,
 }
 
 impl BLPImage {
   pub fn buffer(&self) -> &ImageBuffer<Rgba<u8>, Vec<u8>> {
     &self.image
   }
+
+  pub fn format(&self) -> BLPFormat {
+    self.format
+  }
+
+  /// Number of stored mipmap levels, including the full size image.
+  pub fn mipmap_count(&self) -> usize {
+    self.levels.0.len()
+  }
+
+  /// Decodes the mipmap at `level`, level 0 is the full size image.
+  ///
+  /// Only the full size image is decoded by `BLPImage::decode`,
+  /// a corrupt lower level fails here instead.
+  pub fn mipmap(&self, level: usize) -> Option<Result<RgbaImage, BinDecodeError>> {
+    if level == 0 {
+      return Some(Ok(self.image.clone()));
+    }
+    self.levels.0.get(level)?;
+    Some(
+      self
+        .levels
+        .decode(&self.data, level, |width, height, bytes| {
+          self.decoder.decode(width, height, bytes)
+        }),
+    )
+  }
+
+  /// Re-encodes the full size image in `format`, with a regenerated mipmap chain.
+  pub fn encode(&self, format: BLPFormat) -> Result<Vec<u8>> {
+    BLPEncoder::new(format).encode(self.buffer())
+  }
 }
 
 impl std::fmt::Debug for BLPImage {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(
       f,
-      "BLPImage(witdh = {}, height = {})",
+      "BLPImage(format = {:?}, witdh = {}, height = {}, mipmaps = {})",
+      self.format,
       self.image.width(),
-      self.image.height()
+      self.image.height(),
+      self.mipmap_count()
     )
   }
 }
@@ -41,7 +123,7 @@ impl std::ops::Deref for BLPImage {
   type Target = ImageBuffer<Rgba<u8>, Vec<u8>>;
 
   fn deref(&self) -> &ImageBuffer<Rgba<u8>, Vec<u8>> {
-    &self.image
+    self.buffer()
   }
 }
 
@@ -49,85 +131,502 @@ impl BinDecode for BLPImage {
   const MIN_SIZE: usize = BLP1Header::MIN_SIZE;
   const FIXED_SIZE: bool = false;
   fn decode<T: Buf>(buf: &mut T) -> Result<Self, BinDecodeError> {
-    let size = buf.remaining();
-    let header = BLP1Header::decode(buf)?;
-    match header.alpha_bits {
-      0 | 8 => {}
-      v => return Err(BinDecodeError::failure(format!("invalid alpha bit: {}", v))),
+    buf.check_size(4)?;
+    // mipmap offsets are relative to the start of the file
+    let data = buf.copy_to_bytes(buf.remaining());
+    let (format, levels, decoder) = match &data[..4] {
+      b"BLP1" => decode_blp1_header(&data)?,
+      b"BLP2" => decode_blp2_header(&data)?,
+      magic => {
+        return Err(BinDecodeError::failure(format!(
+          "unsupported magic: {:?}",
+          magic
+        )))
+      }
+    };
+    if levels.0.is_empty() {
+      return Err(BinDecodeError::failure("invalid mipmap data"));
     }
+    let image = levels.decode(&data, 0, |width, height, bytes| {
+      decoder.decode(width, height, bytes)
+    })?;
+    Ok(BLPImage {
+      format,
+      image,
+      data,
+      levels,
+      decoder,
+    })
+  }
+}
+
+fn decode_blp1_header(
+  data: &[u8],
+) -> Result<(BLPFormat, MipmapLevels, MipmapDecoder), BinDecodeError> {
+  let mut buf = data;
+  let header = BLP1Header::decode(&mut buf)?;
+  let alpha_bits = header.alpha_bits as u8;
+  if header.alpha_bits > 8 || !palette::check_alpha_bits(alpha_bits) {
+    return Err(BinDecodeError::failure(format!(
+      "invalid alpha bit: {}",
+      header.alpha_bits
+    )));
+  }
+
+  let levels = MipmapLevels::new(
+    header.width,
+    header.height,
+    header.has_mipmap != 0,
+    &header.mipmap_offsets,
+    &header.mipmap_lengths,
+  );
 
-    if header.compression != COMPRESSION_JPEG {
+  match header.compression {
+    COMPRESSION_JPEG => {
+      buf.check_size(4)?;
+      let jpeg_header_size = u32::decode(&mut buf)? as usize;
+      buf.check_size(jpeg_header_size)?;
+      let jpeg_header = Bytes::copy_from_slice(&buf[..jpeg_header_size]);
+      Ok((
+        BLPFormat::Blp1Jpeg { alpha_bits },
+        levels,
+        MipmapDecoder::Jpeg(jpeg_header),
+      ))
+    }
+    COMPRESSION_PALETTE => {
+      buf.check_size(PALETTE_SIZE * 4)?;
+      let palette: Palette = decode_palette(&mut buf)?;
+      Ok((
+        BLPFormat::Blp1Palette { alpha_bits },
+        levels,
+        MipmapDecoder::Palette {
+          palette,
+          alpha_bits,
+        },
+      ))
+    }
+    other => Err(BinDecodeError::failure(format!(
+      "unsupported compression type: {}",
+      other
+    ))),
+  }
+}
+
+fn decode_blp2_header(
+  data: &[u8],
+) -> Result<(BLPFormat, MipmapLevels, MipmapDecoder), BinDecodeError> {
+  let mut buf = data;
+  let header = BLP2Header::decode(&mut buf)?;
+  // BLP2 JPEG content is not used by Warcraft III
+  if header.content_type != BLP2_TYPE_DIRECT {
+    return Err(BinDecodeError::failure(format!(
+      "unsupported content type: {}",
+      header.content_type
+    )));
+  }
+
+  let levels = MipmapLevels::new(
+    header.width,
+    header.height,
+    header.has_mipmap != 0,
+    &header.mipmap_offsets,
+    &header.mipmap_lengths,
+  );
+
+  let alpha_bits = header.alpha_depth;
+  let (format, decoder) = match header.compression {
+    BLP2_COMPRESSION_PALETTE => {
+      if !palette::check_alpha_bits(alpha_bits) {
+        return Err(BinDecodeError::failure(format!(
+          "invalid alpha bit: {}",
+          alpha_bits
+        )));
+      }
+      (
+        BLPFormat::Blp2Palette { alpha_bits },
+        MipmapDecoder::Palette {
+          palette: header.palette,
+          alpha_bits,
+        },
+      )
+    }
+    BLP2_COMPRESSION_DXT => match (alpha_bits, header.alpha_type) {
+      (0, _) | (1, _) => (
+        BLPFormat::Blp2Dxt1 { alpha_bits },
+        MipmapDecoder::Dxt(Dxt::Dxt1 {
+          alpha: alpha_bits > 0,
+        }),
+      ),
+      (_, BLP2_ALPHA_TYPE_DXT5) => (BLPFormat::Blp2Dxt5, MipmapDecoder::Dxt(Dxt::Dxt5)),
+      _ => (BLPFormat::Blp2Dxt3, MipmapDecoder::Dxt(Dxt::Dxt3)),
+    },
+    BLP2_COMPRESSION_RAW => (BLPFormat::Blp2Raw, MipmapDecoder::Raw),
+    other => {
       return Err(BinDecodeError::failure(format!(
         "unsupported compression type: {}",
-        header.compression
-      )));
+        other
+      )))
     }
+  };
 
-    let mipmap_offset_original = header.mipmap_offsets[0] as usize;
-    let mipmap_len = header.mipmap_lengths[0] as usize;
+  Ok((format, levels, decoder))
+}
 
-    if mipmap_offset_original == 0 || mipmap_len == 0 {
-      return Err(BinDecodeError::failure("invalid mipmap data"));
+fn decode_palette<T: Buf>(buf: &mut T) -> Result<Palette, BinDecodeError> {
+  let mut palette = [0; PALETTE_SIZE];
+  for entry in palette.iter_mut() {
+    *entry = u32::decode(buf)?;
+  }
+  Ok(palette)
+}
+
+/// Returns the byte length of a `width` x `height` mipmap,
+/// or an error if the header dimensions overflow `usize`.
+pub(crate) fn mipmap_len(
+  width: u32,
+  height: u32,
+  bytes_per_pixel: usize,
+) -> Result<usize, BinDecodeError> {
+  (width as usize)
+    .checked_mul(height as usize)
+    .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+    .ok_or_else(|| BinDecodeError::failure(format!("invalid mipmap size: {}x{}", width, height)))
+}
+
+/// Decodes the data of a single mipmap.
+enum MipmapDecoder {
+  /// BLP1 JPEG mipmaps share a JPEG header.
+  Jpeg(Bytes),
+  Palette {
+    palette: Palette,
+    alpha_bits: u8,
+  },
+  Dxt(Dxt),
+  Raw,
+}
+
+impl MipmapDecoder {
+  fn decode(&self, width: u32, height: u32, bytes: &[u8]) -> Result<RgbaImage, BinDecodeError> {
+    match *self {
+      MipmapDecoder::Jpeg(ref header) => jpeg::decode(header, bytes),
+      MipmapDecoder::Palette {
+        ref palette,
+        alpha_bits,
+      } => palette::decode(palette, alpha_bits, width, height, bytes),
+      MipmapDecoder::Dxt(variant) => dxt::decode(variant, width, height, bytes),
+      MipmapDecoder::Raw => {
+        let len = mipmap_len(width, height, 4)?;
+        if bytes.len() < len {
+          return Err(BinDecodeError::incomplete().context("raw mipmap"));
+        }
+        let raw = bytes[..len]
+          .chunks_exact(4)
+          .flat_map(|p| [p[2], p[1], p[0], p[3]])
+          .collect();
+        ImageBuffer::from_raw(width, height, raw)
+          .ok_or_else(|| BinDecodeError::failure("invalid raw mipmap size"))
+      }
     }
+  }
+}
 
-    buf.check_size(4)?;
-    let image_buf_size = u32::decode(buf)? as usize;
+/// Location and dimensions of each stored mipmap.
+struct MipmapLevels(Vec<(u32, u32, usize, usize)>);
 
-    buf.check_size(image_buf_size as usize)?;
-    let mut img_buf = BytesMut::with_capacity((image_buf_size + mipmap_len) as usize);
-    img_buf.resize(img_buf.capacity(), 0);
-    buf.copy_to_slice(&mut img_buf[..image_buf_size]);
+impl MipmapLevels {
+  fn new(
+    width: u32,
+    height: u32,
+    has_mipmap: bool,
+    offsets: &[u32; MAX_MIPMAPS],
+    lengths: &[u32; MAX_MIPMAPS],
+  ) -> Self {
+    let count = if has_mipmap { MAX_MIPMAPS } else { 1 };
+    let levels = (0..count)
+      .take_while(|&i| {
+        ((width >> i) > 0 || (height >> i) > 0) && offsets[i] != 0 && lengths[i] != 0
+      })
+      .map(|i| {
+        (
+          (width >> i).max(1),
+          (height >> i).max(1),
+          offsets[i] as usize,
+          lengths[i] as usize,
+        )
+      })
+      .collect();
+    MipmapLevels(levels)
+  }
 
-    let offset = mipmap_offset_original
-      .checked_sub(size - buf.remaining())
+  fn decode<F>(&self, data: &[u8], level: usize, f: F) -> Result<RgbaImage, BinDecodeError>
+  where
+    F: FnOnce(u32, u32, &[u8]) -> Result<RgbaImage, BinDecodeError>,
+  {
+    let (width, height, offset, len) = self
+      .0
+      .get(level)
+      .cloned()
+      .ok_or_else(|| BinDecodeError::failure("invalid mipmap data"))?;
+    let bytes = offset
+      .checked_add(len)
+      .and_then(|end| data.get(offset..end))
       .ok_or_else(|| BinDecodeError::failure("invalid mipmap offset"))?;
+    f(width, height, bytes).map_err(|e| e.context(format!("mipmap {}", level)))
+  }
+}
 
-    buf.check_size(offset + mipmap_len)?;
-    buf.advance(offset);
-    buf.copy_to_slice(&mut img_buf[image_buf_size..]);
-
-    let image = image::load_from_memory_with_format(&img_buf, ImageFormat::Jpeg)
-      .or_else(|e| {
-        if e.to_string().contains("Adobe APP14") {
-          let mut patched = Vec::with_capacity(image_buf_size - 2 + SOI_HEADER.len());
-          patched.extend(SOI_HEADER);
-          patched.extend(&img_buf[2..]);
-          image::load_from_memory_with_format(&patched, ImageFormat::Jpeg)
-        } else {
-          Err(e)
+/// Encodes RGBA images into BLP files.
+#[derive(Debug, Clone)]
+pub struct BLPEncoder {
+  format: BLPFormat,
+  jpeg_quality: u8,
+  mipmaps: bool,
+}
+
+impl BLPEncoder {
+  pub fn new(format: BLPFormat) -> Self {
+    BLPEncoder {
+      format,
+      jpeg_quality: 90,
+      mipmaps: true,
+    }
+  }
+
+  /// JPEG quality from 1 to 100, only used by `BLPFormat::Blp1Jpeg`.
+  pub fn jpeg_quality(mut self, quality: u8) -> Self {
+    self.jpeg_quality = quality.clamp(1, 100);
+    self
+  }
+
+  /// Generate the full mipmap chain down to 1x1, enabled by default.
+  pub fn mipmaps(mut self, generate: bool) -> Self {
+    self.mipmaps = generate;
+    self
+  }
+
+  pub fn encode(&self, image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<Vec<u8>> {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 || (width.max(height) >> MAX_MIPMAPS) > 0 {
+      return Err(EncodeError::InvalidDimensions(width, height));
+    }
+    self.validate_format()?;
+
+    let mipmaps = self.generate_mipmaps(image);
+    let mut offsets = [0; MAX_MIPMAPS];
+    let mut lengths = [0; MAX_MIPMAPS];
+    let has_mipmap = if mipmaps.len() > 1 { 1 } else { 0 };
+
+    let mut buf = BytesMut::new();
+    let data = match self.format {
+      BLPFormat::Blp1Jpeg { .. } => {
+        let (jpeg_header, data) = jpeg::encode(&mipmaps, self.jpeg_quality)?;
+        let data_offset = BLP1Header::MIN_SIZE + 4 + jpeg_header.len();
+        fill_mipmap_table(data_offset, &data, &mut offsets, &mut lengths);
+        BLP1Header {
+          _magic: *b"BLP1",
+          compression: COMPRESSION_JPEG,
+          alpha_bits: 0,
+          width,
+          height,
+          flags: 5,
+          has_mipmap,
+          mipmap_offsets: offsets,
+          mipmap_lengths: lengths,
         }
-      })
-      .map_err(|e| BinDecodeError::failure(format!("decode jpeg: {:?}", e)))?;
+        .encode(&mut buf);
+        (jpeg_header.len() as u32).encode(&mut buf);
+        buf.put_slice(&jpeg_header);
+        data
+      }
+      BLPFormat::Blp1Palette { alpha_bits } => {
+        let quantizer = Quantizer::new(image);
+        let data: Vec<_> = mipmaps
+          .iter()
+          .map(|mipmap| quantizer.encode(alpha_bits, mipmap))
+          .collect();
+        let data_offset = BLP1Header::MIN_SIZE + PALETTE_SIZE * 4;
+        fill_mipmap_table(data_offset, &data, &mut offsets, &mut lengths);
+        BLP1Header {
+          _magic: *b"BLP1",
+          compression: COMPRESSION_PALETTE,
+          alpha_bits: alpha_bits as u32,
+          width,
+          height,
+          flags: if alpha_bits > 0 { 4 } else { 5 },
+          has_mipmap,
+          mipmap_offsets: offsets,
+          mipmap_lengths: lengths,
+        }
+        .encode(&mut buf);
+        for entry in quantizer.palette().iter() {
+          entry.encode(&mut buf);
+        }
+        data
+      }
+      BLPFormat::Blp2Palette { alpha_bits } => {
+        let quantizer = Quantizer::new(image);
+        let data: Vec<_> = mipmaps
+          .iter()
+          .map(|mipmap| quantizer.encode(alpha_bits, mipmap))
+          .collect();
+        fill_mipmap_table(BLP2Header::MIN_SIZE, &data, &mut offsets, &mut lengths);
+        BLP2Header {
+          _magic: *b"BLP2",
+          content_type: BLP2_TYPE_DIRECT,
+          compression: BLP2_COMPRESSION_PALETTE,
+          alpha_depth: alpha_bits,
+          alpha_type: 0,
+          has_mipmap: has_mipmap as u8,
+          width,
+          height,
+          mipmap_offsets: offsets,
+          mipmap_lengths: lengths,
+          palette: quantizer.palette(),
+        }
+        .encode(&mut buf);
+        data
+      }
+      BLPFormat::Blp2Dxt1 { .. } | BLPFormat::Blp2Dxt3 | BLPFormat::Blp2Dxt5 => {
+        let (variant, alpha_depth, alpha_type) = match self.format {
+          BLPFormat::Blp2Dxt1 { .. } => (Dxt::Dxt1 { alpha: false }, 0, BLP2_ALPHA_TYPE_DXT1),
+          BLPFormat::Blp2Dxt3 => (Dxt::Dxt3, 8, BLP2_ALPHA_TYPE_DXT3),
+          _ => (Dxt::Dxt5, 8, BLP2_ALPHA_TYPE_DXT5),
+        };
+        let data = mipmaps
+          .iter()
+          .map(|mipmap| dxt::encode(variant, mipmap))
+          .collect::<Result<Vec<_>>>()?;
+        fill_mipmap_table(BLP2Header::MIN_SIZE, &data, &mut offsets, &mut lengths);
+        BLP2Header {
+          _magic: *b"BLP2",
+          content_type: BLP2_TYPE_DIRECT,
+          compression: BLP2_COMPRESSION_DXT,
+          alpha_depth,
+          alpha_type,
+          has_mipmap: has_mipmap as u8,
+          width,
+          height,
+          mipmap_offsets: offsets,
+          mipmap_lengths: lengths,
+          palette: [0; PALETTE_SIZE],
+        }
+        .encode(&mut buf);
+        data
+      }
+      BLPFormat::Blp2Raw => {
+        let data: Vec<Vec<u8>> = mipmaps
+          .iter()
+          .map(|mipmap| {
+            mipmap
+              .pixels()
+              .flat_map(|Rgba([r, g, b, a])| [*b, *g, *r, *a])
+              .collect()
+          })
+          .collect();
+        fill_mipmap_table(BLP2Header::MIN_SIZE, &data, &mut offsets, &mut lengths);
+        BLP2Header {
+          _magic: *b"BLP2",
+          content_type: BLP2_TYPE_DIRECT,
+          compression: BLP2_COMPRESSION_RAW,
+          alpha_depth: 8,
+          alpha_type: BLP2_ALPHA_TYPE_RAW,
+          has_mipmap: has_mipmap as u8,
+          width,
+          height,
+          mipmap_offsets: offsets,
+          mipmap_lengths: lengths,
+          palette: [0; PALETTE_SIZE],
+        }
+        .encode(&mut buf);
+        data
+      }
+    };
+
+    for bytes in data {
+      buf.put_slice(&bytes);
+    }
+    Ok(buf.to_vec())
+  }
 
-    let image = if let Some(rbg_image) = image.as_rgb8() {
-      let (w, h) = rbg_image.dimensions();
-      let mut raw = Vec::with_capacity((w * h * 4) as usize);
-      for Rgb([r, g, b]) in rbg_image.pixels() {
-        raw.extend(&[*b, *g, *r, 255])
+  fn validate_format(&self) -> Result<()> {
+    let supported = match self.format {
+      BLPFormat::Blp1Jpeg { alpha_bits } => alpha_bits == 0,
+      BLPFormat::Blp1Palette { alpha_bits } | BLPFormat::Blp2Palette { alpha_bits } => {
+        palette::check_alpha_bits(alpha_bits)
       }
-      ImageBuffer::from_raw(w, h, raw).unwrap()
+      BLPFormat::Blp2Dxt1 { alpha_bits } => alpha_bits == 0,
+      BLPFormat::Blp2Dxt3 | BLPFormat::Blp2Dxt5 | BLPFormat::Blp2Raw => true,
+    };
+    if supported {
+      Ok(())
     } else {
-      return Err(BinDecodeError::failure(
-        "decode jpeg: pixel format is not rgb",
+      Err(EncodeError::UnsupportedFormat(self.format))
+    }
+  }
+
+  fn generate_mipmaps(&self, image: &RgbaImage) -> Vec<RgbaImage> {
+    let mut mipmaps = vec![image.clone()];
+    if !self.mipmaps {
+      return mipmaps;
+    }
+    let (width, height) = image.dimensions();
+    for level in 1..MAX_MIPMAPS {
+      if (width >> level) == 0 && (height >> level) == 0 {
+        break;
+      }
+      mipmaps.push(image::imageops::resize(
+        image,
+        (width >> level).max(1),
+        (height >> level).max(1),
+        FilterType::Triangle,
       ));
-    };
+    }
+    mipmaps
+  }
+}
 
-    Ok(Self { image })
+fn fill_mipmap_table(
+  data_offset: usize,
+  data: &[Vec<u8>],
+  offsets: &mut [u32; MAX_MIPMAPS],
+  lengths: &mut [u32; MAX_MIPMAPS],
+) {
+  let mut offset = data_offset;
+  for (i, bytes) in data.iter().enumerate() {
+    offsets[i] = offset as u32;
+    lengths[i] = bytes.len() as u32;
+    offset += bytes.len();
   }
 }
 
-#[derive(Debug, BinDecode)]
+#[derive(Debug, BinDecode, BinEncode)]
 struct BLP1Header {
   #[bin(eq = & b"BLP1")]
   _magic: [u8; 4],
   compression: u32,
   alpha_bits: u32,
-  _width: u32,
-  _height: u32,
-  _flags: u32,
-  _has_mipmap: u32,
+  width: u32,
+  height: u32,
+  flags: u32,
+  has_mipmap: u32,
+  mipmap_offsets: [u32; 16],
+  mipmap_lengths: [u32; 16],
+}
+
+#[derive(Debug, BinDecode, BinEncode)]
+struct BLP2Header {
+  #[bin(eq = & b"BLP2")]
+  _magic: [u8; 4],
+  content_type: u32,
+  compression: u8,
+  alpha_depth: u8,
+  alpha_type: u8,
+  has_mipmap: u8,
+  width: u32,
+  height: u32,
   mipmap_offsets: [u32; 16],
   mipmap_lengths: [u32; 16],
+  palette: [u32; 256],
 }
 
 #[test]
@@ -135,3 +634,103 @@ fn test_blp_to_jpg() {
   let buf = std::fs::read("../../deps/wc3-samples/map/war3mapMap.blp").unwrap();
   dbg!(BLPImage::decode(&mut buf.as_slice()).unwrap());
 }
+
+#[cfg(test)]
+fn test_image(width: u32, height: u32) -> RgbaImage {
+  ImageBuffer::from_fn(width, height, |x, y| {
+    let a = if (x / 8 + y / 8) % 2 == 0 { 255 } else { 0 };
+    Rgba([(x / 4 * 16) as u8, (y / 2 * 16) as u8, 0x80, a])
+  })
+}
+
+#[cfg(test)]
+fn assert_similar(a: &RgbaImage, b: &RgbaImage, color_tolerance: u8, check_alpha: bool) {
+  assert_eq!(a.dimensions(), b.dimensions());
+  for (pa, pb) in a.pixels().zip(b.pixels()) {
+    for c in 0..3 {
+      let d = (pa[c] as i16 - pb[c] as i16).abs();
+      assert!(d <= color_tolerance as i16, "{:?} != {:?}", pa, pb);
+    }
+    if check_alpha {
+      assert_eq!(pa[3], pb[3], "{:?} != {:?}", pa, pb);
+    }
+  }
+}
+
+#[test]
+fn test_blp_roundtrip() {
+  let image = test_image(64, 32);
+  let cases = vec![
+    (BLPFormat::Blp1Jpeg { alpha_bits: 0 }, 24, false),
+    (BLPFormat::Blp1Palette { alpha_bits: 8 }, 0, true),
+    (BLPFormat::Blp2Palette { alpha_bits: 1 }, 0, true),
+    (BLPFormat::Blp2Dxt1 { alpha_bits: 0 }, 24, false),
+    (BLPFormat::Blp2Dxt3, 24, true),
+    (BLPFormat::Blp2Dxt5, 24, true),
+    (BLPFormat::Blp2Raw, 0, true),
+  ];
+  for (format, tolerance, check_alpha) in cases {
+    let bytes = BLPEncoder::new(format).encode(&image).unwrap();
+    let decoded = BLPImage::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!(decoded.format(), format);
+    assert_eq!(decoded.mipmap_count(), 7, "{:?}", format);
+    assert_eq!(
+      decoded.mipmap(6).unwrap().unwrap().dimensions(),
+      (1, 1),
+      "{:?}",
+      format
+    );
+    assert_similar(&image, decoded.buffer(), tolerance, check_alpha);
+  }
+}
+
+#[test]
+fn test_blp_unsupported_encode() {
+  let image = test_image(4, 4);
+  assert!(matches!(
+    BLPEncoder::new(BLPFormat::Blp2Dxt1 { alpha_bits: 1 }).encode(&image),
+    Err(EncodeError::UnsupportedFormat(_))
+  ));
+  assert!(matches!(
+    BLPEncoder::new(BLPFormat::Blp1Palette { alpha_bits: 2 }).encode(&image),
+    Err(EncodeError::UnsupportedFormat(_))
+  ));
+}
+
+#[test]
+fn test_blp_decode_invalid_size() {
+  let image = test_image(4, 4);
+  for format in [
+    BLPFormat::Blp1Palette { alpha_bits: 8 },
+    BLPFormat::Blp2Palette { alpha_bits: 1 },
+    BLPFormat::Blp2Dxt5,
+    BLPFormat::Blp2Raw,
+  ] {
+    let mut bytes = BLPEncoder::new(format).encode(&image).unwrap();
+    // width and height are at the same offset in both header versions
+    for (width, height) in [(0x10000, 0x10000), (u32::MAX, u32::MAX), (4, 64)] {
+      bytes[12..16].copy_from_slice(&u32::to_le_bytes(width));
+      bytes[16..20].copy_from_slice(&u32::to_le_bytes(height));
+      assert!(
+        BLPImage::decode(&mut bytes.as_slice()).is_err(),
+        "{:?} {}x{}",
+        format,
+        width,
+        height
+      );
+    }
+  }
+}
+
+#[test]
+fn test_blp_decode_invalid_mipmap() {
+  let image = test_image(64, 32);
+  let mut bytes = BLPEncoder::new(BLPFormat::Blp2Raw).encode(&image).unwrap();
+  // points mipmap 1 past the end of the file, offsets start after the 20 byte header prefix
+  bytes[24..28].copy_from_slice(&u32::to_le_bytes(u32::MAX));
+  let decoded = BLPImage::decode(&mut bytes.as_slice()).unwrap();
+  assert_similar(&image, decoded.buffer(), 0, true);
+  assert!(decoded.mipmap(1).unwrap().is_err());
+  assert!(decoded.mipmap(2).unwrap().is_ok());
+  assert!(decoded.mipmap(7).is_none());
+}
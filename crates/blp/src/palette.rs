@@ -0,0 +1,156 @@
+//! 256-color palette mipmaps with a separate 0/1/4/8-bit alpha list,
+//! shared by BLP1 and BLP2.
+
+use color_quant::NeuQuant;
+use flo_util::binary::BinDecodeError;
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::collections::hash_map::{Entry, HashMap};
+
+use crate::mipmap_len;
+
+pub(crate) const PALETTE_SIZE: usize = 256;
+
+/// Palette entries are stored as little-endian `0xAARRGGBB` values.
+pub(crate) type Palette = [u32; PALETTE_SIZE];
+
+pub(crate) fn check_alpha_bits(alpha_bits: u8) -> bool {
+  matches!(alpha_bits, 0 | 1 | 4 | 8)
+}
+
+fn alpha_len(alpha_bits: u8, pixels: usize) -> usize {
+  let bits = alpha_bits as usize;
+  pixels / 8 * bits + (pixels % 8 * bits).div_ceil(8)
+}
+
+pub(crate) fn decode(
+  palette: &Palette,
+  alpha_bits: u8,
+  width: u32,
+  height: u32,
+  data: &[u8],
+) -> Result<RgbaImage, BinDecodeError> {
+  let pixels = mipmap_len(width, height, 1)?;
+  let len = pixels
+    .checked_add(alpha_len(alpha_bits, pixels))
+    .ok_or_else(|| BinDecodeError::failure("invalid palette mipmap size"))?;
+  if data.len() < len {
+    return Err(BinDecodeError::incomplete().context("palette mipmap"));
+  }
+  let (indices, alpha) = data.split_at(pixels);
+
+  let mut raw = Vec::with_capacity(pixels * 4);
+  for (i, index) in indices.iter().enumerate() {
+    let [b, g, r, _] = palette[*index as usize].to_le_bytes();
+    let a = match alpha_bits {
+      1 => {
+        if alpha[i / 8] & (1 << (i % 8)) != 0 {
+          255
+        } else {
+          0
+        }
+      }
+      4 => {
+        let v = (alpha[i / 2] >> ((i % 2) * 4)) & 0xF;
+        v << 4 | v
+      }
+      8 => alpha[i],
+      _ => 255,
+    };
+    raw.extend(&[r, g, b, a]);
+  }
+  ImageBuffer::from_raw(width, height, raw)
+    .ok_or_else(|| BinDecodeError::failure("invalid palette mipmap size"))
+}
+
+/// Maps colors to palette indices.
+/// Images with at most 256 distinct colors get an exact palette,
+/// other images are quantized with NeuQuant.
+pub(crate) enum Quantizer {
+  Exact(Vec<[u8; 3]>, HashMap<[u8; 3], u8>),
+  NeuQuant(NeuQuant),
+}
+
+impl Quantizer {
+  pub fn new(image: &RgbaImage) -> Self {
+    let mut colors = Vec::with_capacity(PALETTE_SIZE);
+    let mut indices = HashMap::with_capacity(PALETTE_SIZE);
+    for Rgba([r, g, b, _]) in image.pixels() {
+      let color = [*r, *g, *b];
+      if let Entry::Vacant(e) = indices.entry(color) {
+        if colors.len() == PALETTE_SIZE {
+          let pixels: Vec<u8> = image
+            .pixels()
+            .flat_map(|Rgba([r, g, b, _])| [*r, *g, *b, 255])
+            .collect();
+          return Quantizer::NeuQuant(NeuQuant::new(1, PALETTE_SIZE, &pixels));
+        }
+        e.insert(colors.len() as u8);
+        colors.push(color);
+      }
+    }
+    Quantizer::Exact(colors, indices)
+  }
+
+  pub fn palette(&self) -> Palette {
+    let mut palette = [0; PALETTE_SIZE];
+    for (i, entry) in palette.iter_mut().enumerate() {
+      let color = match *self {
+        Quantizer::Exact(ref colors, _) => colors.get(i).cloned(),
+        Quantizer::NeuQuant(ref nq) => nq.lookup(i).map(|[r, g, b, _]| [r, g, b]),
+      };
+      if let Some([r, g, b]) = color {
+        *entry = u32::from_le_bytes([b, g, r, 0]);
+      }
+    }
+    palette
+  }
+
+  fn index_of(&self, r: u8, g: u8, b: u8) -> u8 {
+    match *self {
+      Quantizer::Exact(ref colors, ref indices) => indices
+        .get(&[r, g, b])
+        .cloned()
+        .unwrap_or_else(|| nearest(colors, [r, g, b])),
+      Quantizer::NeuQuant(ref nq) => nq.index_of(&[r, g, b, 255]) as u8,
+    }
+  }
+
+  /// Maps each pixel to a palette index, followed by the packed alpha list.
+  pub fn encode(&self, alpha_bits: u8, image: &RgbaImage) -> Vec<u8> {
+    let pixels = (image.width() * image.height()) as usize;
+    let mut data = Vec::with_capacity(pixels + alpha_len(alpha_bits, pixels));
+    data.extend(
+      image
+        .pixels()
+        .map(|Rgba([r, g, b, _])| self.index_of(*r, *g, *b)),
+    );
+
+    let mut alpha = vec![0_u8; alpha_len(alpha_bits, pixels)];
+    for (i, Rgba([_, _, _, a])) in image.pixels().enumerate() {
+      match alpha_bits {
+        1 if *a >= 0x80 => alpha[i / 8] |= 1 << (i % 8),
+        4 => alpha[i / 2] |= (*a >> 4) << ((i % 2) * 4),
+        8 => alpha[i] = *a,
+        _ => {}
+      }
+    }
+    data.extend(alpha);
+    data
+  }
+}
+
+/// Colors introduced by mipmap filtering are mapped to the closest palette entry.
+fn nearest(colors: &[[u8; 3]], color: [u8; 3]) -> u8 {
+  let distance = |c: &[u8; 3]| -> u32 {
+    c.iter()
+      .zip(color.iter())
+      .map(|(a, b)| (*a as i32 - *b as i32).pow(2) as u32)
+      .sum()
+  };
+  colors
+    .iter()
+    .enumerate()
+    .min_by_key(|(_, c)| distance(c))
+    .map(|(i, _)| i as u8)
+    .unwrap_or_default()
+}
@@ -120,7 +120,7 @@ impl ControllerStream {
           )
         }
         p: proto::PacketClientConnectReject => {
-          return Err(Error::ConnectionRequestRejected(S2ProtoEnum::unpack_enum(p.reason())))
+          return Err(Error::ConnectionRequestRejected(S2ProtoEnum::unpack_enum(p.reason()), p.message))
         }
       }
     };
@@ -352,6 +352,36 @@ impl ControllerStream {
             OutgoingMessage::GameStartReject(p)
           ).notify(parent).await?;
         }
+        p: proto::PacketGameChat => {
+          SendWs::new(
+            id,
+            OutgoingMessage::GameChat(p)
+          ).notify(parent).await?;
+        }
+        p: proto::PacketGameChatReject => {
+          SendWs::new(
+            id,
+            OutgoingMessage::GameChatReject(p)
+          ).notify(parent).await?;
+        }
+        p: proto::PacketMatchmakingQueueUpdate => {
+          SendWs::new(
+            id,
+            OutgoingMessage::MatchmakingQueueUpdate(p)
+          ).notify(parent).await?;
+        }
+        p: proto::PacketMatchmakingMatchFound => {
+          SendWs::new(
+            id,
+            OutgoingMessage::MatchmakingMatchFound(p)
+          ).notify(parent).await?;
+        }
+        p: proto::PacketMatchmakingMatchCancelled => {
+          SendWs::new(
+            id,
+            OutgoingMessage::MatchmakingMatchCancelled(p)
+          ).notify(parent).await?;
+        }
         p: proto::PacketGameStarting => {
           let info = owner.send(GetGameStartClientInfo {
             game_id: p.game_id
@@ -464,7 +494,10 @@ impl Actor for ControllerStream {
             SendWs::new(
               id,
               OutgoingMessage::ConnectRejected(message::ErrorMessage::new(match &err {
-                Error::ConnectionRequestRejected(reason) => {
+                Error::ConnectionRequestRejected(_, message) if !message.is_empty() => {
+                  message.clone()
+                }
+                Error::ConnectionRequestRejected(reason, _) => {
                   format!("server rejected: {:?}", reason)
                 }
                 other => other.to_string(),
@@ -32,12 +32,16 @@ pub enum Error {
   InvalidMapInfo,
   #[error("Invalid observer data frame")]
   InvalidObserverDataFrame,
+  #[error("Seeking is not supported by live streams")]
+  ObserverSeekNotSupported,
+  #[error("Observer game host exited")]
+  ObserverHostClosed,
   #[error("Ping: {0}")]
   Ping(#[from] PingError),
   #[error("Warcraft III not located")]
   War3NotLocated,
-  #[error("Connection request rejected by server: {0:?}")]
-  ConnectionRequestRejected(flo_types::game::RejectReason),
+  #[error("Connection request rejected by server: {0:?}: {1}")]
+  ConnectionRequestRejected(flo_types::game::RejectReason, String),
   #[error("Connection request rejected by server: {0:?}")]
   ObserverConnectionRequestRejected(flo_net::observer::ObserverConnectRejectReason),
   #[error("Local game info not yet received")]
@@ -59,6 +63,8 @@ pub enum Error {
   Websocket(#[from] async_tungstenite::tungstenite::error::Error),
   #[error("W3GS: {0}")]
   W3GS(#[from] flo_w3gs::error::Error),
+  #[error("Replay: {0}")]
+  W3Replay(#[from] flo_w3replay::error::Error),
   #[error("Map: {0}")]
   War3Map(#[from] flo_w3map::error::Error),
   #[error("War3 data: {0}")]
@@ -11,7 +11,7 @@ mod version;
 
 use crate::message::{GetPort, Listener};
 use flo_state::Registry;
-use observer::{ObserverClient, WatchGame};
+use observer::{ObserverClient, WatchGame, WatchReplay};
 use std::path::PathBuf;
 pub use version::FLO_VERSION;
 
@@ -56,6 +56,14 @@ impl FloClient {
     Ok(())
   }
 
+  pub async fn watch_replay(&self, path: String) -> Result<(), error::Error> {
+    let obs = self._registry.resolve::<ObserverClient>().await?;
+
+    obs.send(WatchReplay { path }).await??;
+
+    Ok(())
+  }
+
   pub async fn serve(self) {
     futures::future::pending().await
   }
@@ -5,13 +5,16 @@ use std::borrow::Cow;
 use std::str::FromStr;
 
 use flo_net::proto::flo_connect::{
-  PacketGamePlayerLeave, PacketGamePlayerPingMapSnapshot, PacketGamePlayerPingMapSnapshotRequest,
-  PacketGameSelectNode, PacketGameSelectNodeRequest, PacketGameStartReject, PacketGameStartRequest,
-  PacketGameStarting, PacketPlayerPingMapUpdate,
+  PacketGameChat, PacketGameChatReject, PacketGameChatRequest, PacketGamePlayerLeave,
+  PacketGamePlayerPingMapSnapshot, PacketGamePlayerPingMapSnapshotRequest, PacketGameSelectNode,
+  PacketGameSelectNodeRequest, PacketGameStartReject, PacketGameStartRequest, PacketGameStarting,
+  PacketMatchmakingMatchAcceptRequest, PacketMatchmakingMatchCancelled,
+  PacketMatchmakingMatchFound, PacketMatchmakingQueueEnterRequest, PacketMatchmakingQueueUpdate,
+  PacketPlayerPingMapUpdate,
 };
 
 use crate::error::{Error, Result};
-use crate::observer::WatchGame;
+use crate::observer::{ObserverHostShared, WatchGame, WatchReplay};
 use crate::ping::PingUpdate;
 use crate::platform::{PlatformStateError, StartTestGame};
 pub use flo_types::game::{
@@ -32,12 +35,21 @@ pub enum IncomingMessage {
   GamePlayerPingMapSnapshotRequest(PacketGamePlayerPingMapSnapshotRequest),
   ListNodesRequest,
   GameStartRequest(PacketGameStartRequest),
+  GameChatRequest(PacketGameChatRequest),
+  MatchmakingQueueEnterRequest(PacketMatchmakingQueueEnterRequest),
+  MatchmakingQueueLeaveRequest,
+  MatchmakingMatchAcceptRequest(PacketMatchmakingMatchAcceptRequest),
   StartTestGame(StartTestGame),
   KillTestGame,
   SetNodeAddrOverrides(SetNodeAddrOverrides),
   ClearNodeAddrOverrides,
   WatchGame(WatchGame),
+  WatchReplay(WatchReplay),
   WatchGameSetSpeed(WatchGameSetSpeed),
+  WatchGamePause,
+  WatchGameResume,
+  WatchGameSeek(WatchGameSeek),
+  WatchGameFastForwardToLive,
 }
 
 #[derive(Debug, Serialize)]
@@ -68,11 +80,18 @@ pub enum OutgoingMessage {
   GameStartError(ErrorMessage),
   GameSlotClientStatusUpdate(ClientUpdateSlotClientStatus),
   GameStatusUpdate(GameStatusUpdate),
+  GameChat(PacketGameChat),
+  GameChatReject(PacketGameChatReject),
+  MatchmakingQueueUpdate(PacketMatchmakingQueueUpdate),
+  MatchmakingMatchFound(PacketMatchmakingMatchFound),
+  MatchmakingMatchCancelled(PacketMatchmakingMatchCancelled),
   GameDisconnect,
   SetNodeAddrOverridesError(ErrorMessage),
   WatchGame(WatchGameInfo),
   WatchGameError(ErrorMessage),
   WatchGameSetSpeedError(ErrorMessage),
+  WatchGameProgress(WatchGameProgress),
+  WatchGameControlError(ErrorMessage),
 }
 
 impl FromStr for IncomingMessage {
@@ -195,4 +214,48 @@ pub struct WatchGameInfo {
 #[derive(Debug, Deserialize)]
 pub struct WatchGameSetSpeed {
   pub speed: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchGameSeek {
+  pub time_millis: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatchGameProgress {
+  pub game_id: i32,
+  pub game_time_millis: u64,
+  /// Only known after the whole stream was received
+  pub total_millis: Option<u64>,
+  pub delay_secs: u64,
+  pub speed: f64,
+  pub joined: bool,
+  pub paused: bool,
+  pub seeking: bool,
+  pub seekable: bool,
+  /// Number of connected game instances
+  pub viewers: u64,
+  pub finished: bool,
+}
+
+impl<'a> From<&'a ObserverHostShared> for WatchGameProgress {
+  fn from(host: &'a ObserverHostShared) -> Self {
+    Self {
+      game_id: host.game_id,
+      game_time_millis: host.game_time_millis(),
+      total_millis: if host.stream_finished() {
+        Some(host.stream_total_millis())
+      } else {
+        None
+      },
+      delay_secs: host.delay_secs(),
+      speed: host.speed(),
+      joined: host.joined(),
+      paused: host.paused(),
+      seeking: host.seeking(),
+      seekable: host.seekable,
+      viewers: host.viewers(),
+      finished: host.finished() || host.closed(),
+    }
+  }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use super::message::{
   ClientInfo, ErrorMessage, IncomingMessage, MapList, MapPath, OutgoingMessage, War3Info,
-  WatchGameInfo,
+  WatchGameInfo, WatchGameProgress,
 };
 use super::{ConnectController, MessageEvent};
 use crate::controller::{
@@ -15,8 +15,9 @@ use crate::platform::{
 };
 use flo_net::packet::FloPacket;
 use flo_net::proto::flo_connect::{
-  PacketGamePlayerPingMapSnapshotRequest, PacketGameSlotUpdateRequest, PacketGameStartRequest,
-  PacketListNodesRequest,
+  PacketGameChatRequest, PacketGamePlayerPingMapSnapshotRequest, PacketGameSlotUpdateRequest,
+  PacketGameStartRequest, PacketListNodesRequest, PacketMatchmakingMatchAcceptRequest,
+  PacketMatchmakingQueueEnterRequest, PacketMatchmakingQueueLeaveRequest,
 };
 use flo_platform::ClientPlatformInfo;
 use flo_state::Addr;
@@ -24,9 +25,12 @@ use flo_task::{SpawnScope, SpawnScopeHandle};
 use parking_lot::Mutex;
 use s2_grpc_utils::S2ProtoPack;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tracing_futures::Instrument;
 
+const WATCH_GAME_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
 pub struct Session {
   _scope: SpawnScope,
@@ -175,6 +179,24 @@ impl Worker {
       IncomingMessage::GameStartRequest(req) => {
         self.send_frame::<PacketGameStartRequest>(req).await?;
       }
+      IncomingMessage::GameChatRequest(req) => {
+        self.send_frame::<PacketGameChatRequest>(req).await?;
+      }
+      IncomingMessage::MatchmakingQueueEnterRequest(req) => {
+        self
+          .send_frame::<PacketMatchmakingQueueEnterRequest>(req)
+          .await?;
+      }
+      IncomingMessage::MatchmakingQueueLeaveRequest => {
+        self
+          .send_frame(PacketMatchmakingQueueLeaveRequest {})
+          .await?;
+      }
+      IncomingMessage::MatchmakingMatchAcceptRequest(req) => {
+        self
+          .send_frame::<PacketMatchmakingMatchAcceptRequest>(req)
+          .await?;
+      }
       IncomingMessage::StartTestGame(msg) => {
         self.platform.send(msg).await??;
       }
@@ -208,24 +230,11 @@ impl Worker {
       }
       IncomingMessage::WatchGame(msg) => {
         let res = self.observer_client.send(msg).await?;
-        match res {
-          Ok(shared) => {
-            reply_sender
-              .send(OutgoingMessage::WatchGame(WatchGameInfo {
-                game_id: shared.game_id,
-                delay_secs: shared.initial_delay_secs.clone(),
-                speed: shared.speed(),
-              }))
-              .await?;
-            self.current_observer_host.lock().replace(shared);
-          }
-          Err(err) => {
-            tracing::error!("watch game: {}", err);
-            reply_sender
-              .send(OutgoingMessage::WatchGameError(ErrorMessage::new(err)))
-              .await?;
-          }
-        }
+        self.handle_watch_result(reply_sender, res).await?;
+      }
+      IncomingMessage::WatchReplay(msg) => {
+        let res = self.observer_client.send(msg).await?;
+        self.handle_watch_result(reply_sender, res).await?;
       }
       IncomingMessage::WatchGameSetSpeed(msg) => {
         let reply = {
@@ -245,6 +254,26 @@ impl Worker {
           .send(reply)
           .await?;
       }
+      IncomingMessage::WatchGamePause => {
+        self
+          .handle_watch_control(reply_sender, |host| host.pause())
+          .await?;
+      }
+      IncomingMessage::WatchGameResume => {
+        self
+          .handle_watch_control(reply_sender, |host| host.resume())
+          .await?;
+      }
+      IncomingMessage::WatchGameSeek(msg) => {
+        self
+          .handle_watch_control(reply_sender, |host| host.seek(msg.time_millis))
+          .await?;
+      }
+      IncomingMessage::WatchGameFastForwardToLive => {
+        self
+          .handle_watch_control(reply_sender, |host| host.fast_forward_to_live())
+          .await?;
+      }
     }
     Ok(())
   }
@@ -315,6 +344,52 @@ impl Worker {
     Ok(())
   }
 
+  async fn handle_watch_result(
+    &self,
+    sender: &Sender<OutgoingMessage>,
+    res: Result<ObserverHostShared>,
+  ) -> Result<()> {
+    match res {
+      Ok(shared) => {
+        sender
+          .send(OutgoingMessage::WatchGame(WatchGameInfo {
+            game_id: shared.game_id,
+            delay_secs: shared.initial_delay_secs.clone(),
+            speed: shared.speed(),
+          }))
+          .await?;
+        tokio::spawn(send_watch_game_progress(sender.clone(), shared.clone()));
+        self.current_observer_host.lock().replace(shared);
+      }
+      Err(err) => {
+        tracing::error!("watch game: {}", err);
+        sender
+          .send(OutgoingMessage::WatchGameError(ErrorMessage::new(err)))
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  async fn handle_watch_control<F>(&self, sender: &Sender<OutgoingMessage>, f: F) -> Result<()>
+  where
+    F: FnOnce(&ObserverHostShared) -> Result<()>,
+  {
+    let reply = {
+      let host = self.current_observer_host.lock();
+      if let Some(host) = host.as_ref() {
+        match f(host) {
+          Ok(_) => OutgoingMessage::WatchGameProgress(WatchGameProgress::from(host)),
+          Err(err) => OutgoingMessage::WatchGameControlError(ErrorMessage::new(err)),
+        }
+      } else {
+        OutgoingMessage::WatchGameControlError(ErrorMessage::new("No active stream."))
+      }
+    };
+    sender.send(reply).await?;
+    Ok(())
+  }
+
   async fn send_frame<T: FloPacket>(&self, pkt: T) -> Result<()> {
     self
       .controller_client
@@ -324,6 +399,23 @@ impl Worker {
   }
 }
 
+async fn send_watch_game_progress(sender: Sender<OutgoingMessage>, host: ObserverHostShared) {
+  let mut interval = tokio::time::interval(WATCH_GAME_PROGRESS_INTERVAL);
+  loop {
+    interval.tick().await;
+    let progress = WatchGameProgress::from(&host);
+    let finished = progress.finished;
+    if sender
+      .send(OutgoingMessage::WatchGameProgress(progress))
+      .await
+      .is_err()
+      || finished
+    {
+      break;
+    }
+  }
+}
+
 fn get_war3_info(info: Result<ClientPlatformInfo, PlatformStateError>) -> War3Info {
   match info {
     Ok(info) => War3Info {
@@ -1,15 +1,17 @@
 use super::send_queue::SendQueue;
+use super::source::ObserverSource;
 use crate::error::{Error, Result};
 use crate::lan::game::slot::{LanSlotInfo, SelfPlayer};
 use crate::platform::{GetClientPlatformInfo, OpenMap, Platform};
 use flo_lan::MdnsPublisher;
 use flo_observer::record::GameRecordData;
 use flo_state::Addr;
+use flo_task::SpawnScope;
 use flo_types::observer::GameInfo;
 use flo_util::binary::SockAddr;
 use flo_w3gs::action::IncomingAction;
 use flo_w3gs::chat::ChatFromHost;
-use flo_w3gs::constants::{PacketTypeId, ProtoBufMessageTypeId};
+use flo_w3gs::constants::{LeaveReason, PacketTypeId, ProtoBufMessageTypeId};
 use flo_w3gs::game::{GameSettings, GameSettingsMap};
 use flo_w3gs::lag::{LagPlayer, StartLag, StopLag};
 use flo_w3gs::leave::PlayerLeft;
@@ -23,18 +25,21 @@ use flo_w3gs::protocol::map::{MapCheck, MapSize};
 use flo_w3gs::protocol::packet::ProtoBufPayload;
 use flo_w3gs::protocol::player::{PlayerInfo, PlayerProfileMessage, PlayerSkinsMessage};
 use flo_w3map::MapChecksum;
-use futures::Stream;
-use std::collections::VecDeque;
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, VecDeque};
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{atomic::AtomicU64, Arc};
 use std::time::{Duration, Instant, SystemTime};
-use tokio::sync::Notify;
+use tokio::sync::{broadcast, mpsc, watch, Notify};
 use tokio::time::sleep;
 use tokio_stream::StreamExt;
+use tracing_futures::Instrument;
 
 const DESYNC_GRACE_PERIOD_TICKS: usize = 256;
 const BUFFER_DURATION: Duration = Duration::from_secs(1);
+/// Max number of local game instances watching the stream at the same time
+const MAX_VIEWERS: usize = 8;
 
 pub struct ObserverGameHost<S> {
   map_checksum: MapChecksum,
@@ -44,12 +49,21 @@ pub struct ObserverGameHost<S> {
   delay_millis: Option<i64>,
   source: S,
   shared: ObserverHostShared,
+  commands: mpsc::UnboundedReceiver<Command>,
   game_version: String,
 }
 
+enum PlayOutcome {
+  Finished,
+  /// The viewer has to rejoin, then fast-forward to `seek_millis`
+  Restart {
+    seek_millis: u64,
+  },
+}
+
 impl<S> ObserverGameHost<S>
 where
-  S: Stream<Item = Result<GameRecordData>> + Unpin,
+  S: ObserverSource,
 {
   pub async fn new(
     info: GameInfo,
@@ -97,6 +111,8 @@ where
     );
 
     let game_id = info.id;
+    let (commands_tx, commands) = mpsc::unbounded_channel();
+    let seekable = source.seekable();
 
     Ok(Self {
       map_checksum: map.checksum,
@@ -105,50 +121,161 @@ where
       info,
       delay_millis: delay_secs.map(|v| v * 1000),
       source,
-      shared: ObserverHostShared::new(game_id, delay_secs),
+      shared: ObserverHostShared::new(game_id, delay_secs, seekable, commands_tx),
+      commands,
       game_version: client_info.version,
     })
   }
 
-  pub async fn play(mut self) -> Result<()> {
-    let map_sha1: [u8; 20] = self.map_checksum.sha1;
+  /// Reads the source once and plays it to every game instance
+  /// that joins the published LAN game, each one as a separate viewer.
+  pub async fn play(self) -> Result<()> {
+    let Self {
+      map_checksum,
+      game_settings,
+      mut listener,
+      info,
+      delay_millis,
+      mut source,
+      shared,
+      mut commands,
+      game_version,
+    } = self;
+
+    let map_sha1: [u8; 20] = map_checksum.sha1;
     let lan_game_info = {
-      let mut game_info = flo_lan::GameInfo::new(
-        1,
-        "FLO-STREAM",
-        &self.info.map.path,
-        map_sha1,
-        self.map_checksum.xoro,
-      )?;
-      game_info.set_port(self.listener.port());
+      let mut game_info =
+        flo_lan::GameInfo::new(1, "FLO-STREAM", &info.map.path, map_sha1, map_checksum.xoro)?;
+      game_info.set_port(listener.port());
       game_info
     };
 
-    let _p = MdnsPublisher::start(self.game_version.clone(), lan_game_info).await?;
+    let _p = MdnsPublisher::start(game_version, lan_game_info).await?;
     let slot_info = crate::lan::game::slot::build_player_slot_info(
       SelfPlayer::StreamObserver,
-      self.info.random_seed,
-      &self.info.slots,
+      info.random_seed,
+      &info.slots,
     )?;
 
-    let mut stream: W3GSStream = loop {
+    let ctx = Arc::new(ViewerContext {
+      map_checksum,
+      game_settings,
+      slot_info,
+      start_time_millis: info.start_time_millis,
+      delay_millis,
+      shared: shared.clone(),
+      records: Arc::new(RecordLog::new()),
+    });
+
+    let scope = SpawnScope::new();
+    let (command_tx, _) = broadcast::channel(16);
+    let (exit_tx, mut exit_rx) = mpsc::unbounded_channel();
+    let mut viewers: BTreeMap<u64, Arc<ViewerState>> = BTreeMap::new();
+    let mut next_viewer_id: u64 = 0;
+    let mut source_done = false;
+    // seek target and number of viewers that have to rejoin after a rewind
+    let mut rejoin: Option<(u64, usize)> = None;
+
+    loop {
       tokio::select! {
-        res = self.listener.accept() => {
-          let mut stream = if let Some(stream) = res? {
+        res = listener.accept() => {
+          let stream = if let Some(stream) = res? {
             stream
           } else {
-            return Ok(())
+            break
           };
-          if self.handle_lobby(&slot_info, &mut stream).await? {
-            break stream;
+
+          if viewers.len() >= MAX_VIEWERS {
+            tracing::warn!("max viewers reached, connection dropped");
+            continue;
+          }
+
+          // late joiners catch up with the other viewers
+          let seek_millis = if let Some((millis, remaining)) = rejoin.take() {
+            if remaining > 1 {
+              rejoin.replace((millis, remaining - 1));
+            }
+            Some(millis)
+          } else {
+            viewers.values().map(|v| v.time_millis()).max().filter(|v| *v > 0)
+          };
+
+          next_viewer_id += 1;
+          let id = next_viewer_id;
+          let state = Arc::new(ViewerState::default());
+          viewers.insert(id, state.clone());
+          update_viewers(&shared, &viewers);
+
+          tracing::debug!(viewer_id = id, "viewer connected, seek = {:?}", seek_millis);
+
+          let viewer = Viewer {
+            ctx: ctx.clone(),
+            state,
+            records: RecordCursor::new(ctx.records.clone()),
+            commands: command_tx.subscribe(),
+          };
+          let mut scope = scope.handle();
+          let exit_tx = exit_tx.clone();
+          tokio::spawn(
+            async move {
+              tokio::select! {
+                _ = scope.left() => {},
+                res = viewer.run(stream, seek_millis) => {
+                  exit_tx.send((id, res)).ok();
+                }
+              }
+            }
+            .instrument(tracing::debug_span!("viewer", id)),
+          );
+        }
+        Some(cmd) = commands.recv() => {
+          match cmd {
+            Command::Pause => shared.paused.store(true, Ordering::Relaxed),
+            Command::Resume => shared.paused.store(false, Ordering::Relaxed),
+            Command::Seek(_) => {}
           }
+          command_tx.send(cmd).ok();
         }
-      }
-    };
+        r = source.try_next(), if !source_done => {
+          match r {
+            Ok(Some(record)) => ctx.records.push(record),
+            Ok(None) => {
+              source_done = true;
+              ctx.records.finish();
+            }
+            Err(err) => {
+              tracing::error!("observer source: {}", err);
+              source_done = true;
+              ctx.records.finish();
+            }
+          }
+        }
+        Some((id, res)) = exit_rx.recv() => {
+          viewers.remove(&id);
+          update_viewers(&shared, &viewers);
 
-    tracing::debug!("game loop");
+          match res {
+            Ok(PlayOutcome::Finished) => {
+              tracing::debug!(viewer_id = id, "viewer exited");
+            }
+            Ok(PlayOutcome::Restart { seek_millis }) => {
+              let remaining = rejoin.map(|(_, remaining)| remaining).unwrap_or_default();
+              rejoin.replace((seek_millis, remaining + 1));
+            }
+            Err(err) => {
+              tracing::error!(viewer_id = id, "observer viewer: {}", err);
+            }
+          }
+
+          if viewers.is_empty() && rejoin.is_none() && shared.joined() {
+            break;
+          }
+        }
+      }
+    }
 
-    self.play_source(&slot_info, &mut stream).await?;
+    shared.finished.store(true, Ordering::Relaxed);
+    shared.finished_notify.notify_one();
 
     Ok(())
   }
@@ -156,8 +283,74 @@ where
   pub fn shared(&self) -> ObserverHostShared {
     self.shared.clone()
   }
+}
+
+/// Marks the first connected viewer as the one reporting progress
+fn update_viewers(shared: &ObserverHostShared, viewers: &BTreeMap<u64, Arc<ViewerState>>) {
+  for (i, state) in viewers.values().enumerate() {
+    state.primary.store(i == 0, Ordering::Relaxed);
+  }
+  shared
+    .viewers
+    .store(viewers.len() as u64, Ordering::Relaxed);
+}
+
+/// Data shared by all viewers of a game
+struct ViewerContext {
+  map_checksum: MapChecksum,
+  game_settings: GameSettings,
+  slot_info: LanSlotInfo,
+  start_time_millis: i64,
+  delay_millis: Option<i64>,
+  shared: ObserverHostShared,
+  records: Arc<RecordLog>,
+}
+
+#[derive(Debug, Default)]
+struct ViewerState {
+  primary: AtomicBool,
+  time_millis: AtomicU64,
+}
+
+impl ViewerState {
+  fn primary(&self) -> bool {
+    self.primary.load(Ordering::Relaxed)
+  }
+
+  fn time_millis(&self) -> u64 {
+    self.time_millis.load(Ordering::Relaxed)
+  }
+}
+
+/// A local game instance watching the stream,
+/// with its own send queue, ack and checksum state.
+struct Viewer {
+  ctx: Arc<ViewerContext>,
+  state: Arc<ViewerState>,
+  records: RecordCursor,
+  commands: broadcast::Receiver<Command>,
+}
+
+impl Viewer {
+  async fn run(mut self, mut stream: W3GSStream, seek_millis: Option<u64>) -> Result<PlayOutcome> {
+    let ctx = self.ctx.clone();
+    let slot_info = &ctx.slot_info;
+
+    if !self.handle_lobby(slot_info, &mut stream).await? {
+      return Ok(PlayOutcome::Finished);
+    }
+
+    tracing::debug!("game loop");
+
+    self.play(slot_info, &mut stream, seek_millis).await
+  }
 
-  async fn play_source(mut self, slots: &LanSlotInfo, stream: &mut W3GSStream) -> Result<()> {
+  async fn play(
+    &mut self,
+    slots: &LanSlotInfo,
+    stream: &mut W3GSStream,
+    seek_millis: Option<u64>,
+  ) -> Result<PlayOutcome> {
     let mut loaded = false;
     let mut tick: u32 = 0;
     let mut time: u32 = 0;
@@ -166,6 +359,14 @@ where
     let mut pending_local_checksums = VecDeque::new();
     let mut source_done = false;
     let mut send_queue = SendQueue::new();
+    send_queue.set_paused(self.ctx.shared.paused());
+    if let Some(millis) = seek_millis {
+      send_queue.seek(millis);
+    }
+    let mut seeking = send_queue.seeking();
+    if self.state.primary() {
+      self.ctx.shared.seeking.store(seeking, Ordering::Relaxed);
+    }
     let mut desync_ticks = 0;
     let base_time = SystemTime::now()
       .duration_since(SystemTime::UNIX_EPOCH)
@@ -173,8 +374,8 @@ where
       .unwrap_or_default()
       .as_millis() as i64;
     let base_instant = Instant::now();
-    let start_time_millis = self.info.start_time_millis;
-    let started_duration_millis = base_time.saturating_sub(self.info.start_time_millis);
+    let start_time_millis = self.ctx.start_time_millis;
+    let started_duration_millis = base_time.saturating_sub(self.ctx.start_time_millis);
 
     tracing::debug!("started duration = {}", started_duration_millis);
 
@@ -216,14 +417,51 @@ where
 
     'main: loop {
       tokio::select! {
-        r = self.source.try_next(), if loaded && !source_done => {
-          if let Some(r) = r? {
+        Ok(cmd) = self.commands.recv() => {
+          let msg = match cmd {
+            Command::Pause if send_queue.paused() => continue,
+            Command::Resume if !send_queue.paused() => continue,
+            Command::Pause => {
+              send_queue.set_paused(true);
+              "[FLO] Paused.".to_string()
+            },
+            Command::Resume => {
+              send_queue.set_paused(false);
+              "[FLO] Resumed.".to_string()
+            },
+            Command::Seek(millis) => {
+              let millis = if source_done {
+                std::cmp::min(millis, send_queue.total_millis())
+              } else {
+                millis
+              };
+              if millis < time as u64 {
+                stream.send(Packet::simple(
+                  ChatFromHost::private_to_self(slots.my_slot_player_id, format!("[FLO] Rewinding to {}, please rejoin the game.", format_millis(millis)))
+                )?).await?;
+                self.end_session(slots, stream, &left_players).await?;
+                return Ok(PlayOutcome::Restart { seek_millis: millis });
+              }
+              send_queue.seek(millis);
+              seeking = send_queue.seeking();
+              if self.state.primary() {
+                self.ctx.shared.seeking.store(seeking, Ordering::Relaxed);
+              }
+              format!("[FLO] Seeking to {}...", format_millis(millis))
+            },
+          };
+          stream.send(Packet::simple(
+            ChatFromHost::private_to_self(slots.my_slot_player_id, msg)
+          )?).await?;
+        },
+        r = self.records.next(), if loaded && !source_done => {
+          if let Some(r) = r {
             self.handle_record(time, r, slots, &mut send_queue, &mut agreed_checksums, &mut left_players).await?;
           } else {
             source_done = true;
             send_queue.finish();
-            self.shared.stream_total_millis.store(send_queue.total_millis(), Ordering::Relaxed);
-            self.shared.stream_finished.store(true, Ordering::Relaxed);
+            self.ctx.shared.stream_total_millis.store(send_queue.total_millis(), Ordering::Relaxed);
+            self.ctx.shared.stream_finished.store(true, Ordering::Relaxed);
             tracing::debug!("source finished, received {}ms", send_queue.total_millis());
           }
         },
@@ -250,13 +488,25 @@ where
 
                 if let Some(time_increment_ms) = pending_ticks.pop_front() {
                   time += time_increment_ms as u32;
-                  self.shared.game_time_millis.store(time as u64, Ordering::Relaxed);
+                  self.state.time_millis.store(time as u64, Ordering::Relaxed);
                 }
 
                 let delay_secs = get_delay_secs(source_done, &send_queue, time);
-                self.shared.delay_secs.store(delay_secs as _, Ordering::Relaxed);
 
-                let new_speed = self.shared.speed();
+                if self.state.primary() {
+                  self.ctx.shared.game_time_millis.store(time as u64, Ordering::Relaxed);
+                  self.ctx.shared.delay_secs.store(delay_secs as _, Ordering::Relaxed);
+                  self.ctx.shared.seeking.store(send_queue.seeking(), Ordering::Relaxed);
+                }
+
+                if seeking && !send_queue.seeking() {
+                  seeking = false;
+                  stream.send(Packet::simple(
+                    ChatFromHost::private_to_self(slots.my_slot_player_id, format!("[FLO] Seeked to {}.", format_millis(time as u64)))
+                  )?).await?;
+                }
+
+                let new_speed = self.ctx.shared.speed();
                 if new_speed != send_queue.speed() {
                   send_queue.set_speed(new_speed);
                   stream.send(Packet::simple(
@@ -266,20 +516,20 @@ where
 
                 if send_queue.speed() > 1. {
                   if send_queue.buffered_duration() <= BUFFER_DURATION {
-                    self.shared.set_speed(1.);
+                    self.ctx.shared.set_speed(1.);
                     send_queue.set_speed(1.);
                     stream.send(Packet::simple(
                       ChatFromHost::private_to_self(slots.my_slot_player_id, format!("[FLO] Synced with {}s delay.", delay_secs))
                     )?).await?;
                   } else {
-                    if let Some(delay_millis) = self.delay_millis.as_ref() {
+                    if let Some(delay_millis) = self.ctx.delay_millis.as_ref() {
                       let aprox_game_time = get_aprox_game_time();
                       let local_game_time = get_local_game_time(time);
 
                       // tracing::debug!("delay = {}s, buffered duration = {}s", delay_secs, (send_queue.buffered_duration().as_millis() as f64 / 1000.));
 
                       if aprox_game_time.saturating_sub(local_game_time) <= *delay_millis {
-                        self.shared.set_speed(1.);
+                        self.ctx.shared.set_speed(1.);
                         send_queue.set_speed(1.);
                         stream.send(Packet::simple(
                           ChatFromHost::private_to_self(slots.my_slot_player_id, format!("[FLO] Synced with {}s delay.", delay_secs))
@@ -366,8 +616,37 @@ where
       (time as f64) / (play_time as f64)
     );
 
-    self.shared.finished.store(true, Ordering::Relaxed);
-    self.shared.finished_notify.notify_one();
+    Ok(PlayOutcome::Finished)
+  }
+
+  /// Ends the game for the observer by removing all players,
+  /// then waits for the observer to leave the game.
+  async fn end_session(
+    &mut self,
+    slots: &LanSlotInfo,
+    stream: &mut W3GSStream,
+    left_players: &[u8],
+  ) -> Result<()> {
+    let packets = slots
+      .player_infos
+      .iter()
+      .filter(|p| !left_players.contains(&p.slot_player_id))
+      .map(|p| {
+        Ok(Packet::simple(PlayerLeft {
+          player_id: p.slot_player_id,
+          reason: LeaveReason::LeaveDisconnect,
+        })?)
+      })
+      .collect::<Result<Vec<_>>>()?;
+    stream.send_all(packets).await?;
+
+    while let Some(pkt) = stream.recv().await? {
+      if pkt.type_id() == PacketTypeId::LeaveReq {
+        stream.send(Packet::simple(LeaveAck)?).await?;
+        stream.flush().await?;
+        break;
+      }
+    }
 
     Ok(())
   }
@@ -538,7 +817,7 @@ where
       }
     }
 
-    self.shared.joined.store(true, Ordering::Relaxed);
+    self.ctx.shared.joined.store(true, Ordering::Relaxed);
     tracing::debug!("starting game");
     stream.send(Packet::simple(CountDownStart)?).await?;
     sleep(Duration::from_secs(6)).await;
@@ -633,14 +912,14 @@ where
 
     // map check
     replies.push(Packet::simple(MapCheck::new(
-      self.map_checksum.file_size as u32,
-      self.map_checksum.crc32,
-      &self.game_settings,
+      self.ctx.map_checksum.file_size as u32,
+      self.ctx.map_checksum.crc32,
+      &self.ctx.game_settings,
     ))?);
     tracing::debug!(
       "-> map check: file_size = {}, crc32 = {}",
-      self.map_checksum.file_size,
-      self.map_checksum.crc32
+      self.ctx.map_checksum.file_size,
+      self.ctx.map_checksum.crc32
     );
 
     stream.send_all(replies).await?;
@@ -649,10 +928,90 @@ where
   }
 }
 
+/// Records received from the source, kept for viewers joining later
+struct RecordLog {
+  records: RwLock<Vec<GameRecordData>>,
+  finished: AtomicBool,
+  len_tx: watch::Sender<usize>,
+  len_rx: watch::Receiver<usize>,
+}
+
+impl RecordLog {
+  fn new() -> Self {
+    let (len_tx, len_rx) = watch::channel(0);
+    Self {
+      records: RwLock::new(vec![]),
+      finished: AtomicBool::new(false),
+      len_tx,
+      len_rx,
+    }
+  }
+
+  fn push(&self, record: GameRecordData) {
+    let len = {
+      let mut records = self.records.write();
+      records.push(record);
+      records.len()
+    };
+    self.len_tx.send(len).ok();
+  }
+
+  fn finish(&self) {
+    self.finished.store(true, Ordering::Release);
+    let len = self.records.read().len();
+    self.len_tx.send(len).ok();
+  }
+}
+
+struct RecordCursor {
+  log: Arc<RecordLog>,
+  len_rx: watch::Receiver<usize>,
+  position: usize,
+}
+
+impl RecordCursor {
+  fn new(log: Arc<RecordLog>) -> Self {
+    let len_rx = log.len_rx.clone();
+    Self {
+      log,
+      len_rx,
+      position: 0,
+    }
+  }
+
+  /// Returns `None` after all records were read and the source finished
+  async fn next(&mut self) -> Option<GameRecordData> {
+    loop {
+      let finished = self.log.finished.load(Ordering::Acquire);
+      if let Some(record) = self.log.records.read().get(self.position).cloned() {
+        self.position += 1;
+        return Some(record);
+      }
+      if finished || self.len_rx.changed().await.is_err() {
+        return None;
+      }
+    }
+  }
+}
+
+fn format_millis(millis: u64) -> String {
+  let secs = millis / 1000;
+  format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Command {
+  Pause,
+  Resume,
+  Seek(u64),
+}
+
 #[derive(Debug, Clone)]
 pub struct ObserverHostShared {
   pub game_id: i32,
   pub initial_delay_secs: Option<i64>,
+  /// Whether the source supports seeking, i.e. it's not a live stream
+  pub seekable: bool,
   speed_x10: Arc<AtomicU64>,
   game_time_millis: Arc<AtomicU64>,
   delay_secs: Arc<AtomicU64>,
@@ -661,6 +1020,10 @@ pub struct ObserverHostShared {
   stream_finished: Arc<AtomicBool>,
   stream_total_millis: Arc<AtomicU64>,
   finished: Arc<AtomicBool>,
+  paused: Arc<AtomicBool>,
+  seeking: Arc<AtomicBool>,
+  viewers: Arc<AtomicU64>,
+  commands: mpsc::UnboundedSender<Command>,
 }
 
 impl ObserverHostShared {
@@ -701,13 +1064,70 @@ impl ObserverHostShared {
   pub fn finished_notify(&self) -> &Notify {
     self.finished_notify.as_ref()
   }
+
+  pub fn paused(&self) -> bool {
+    self.paused.load(Ordering::Relaxed)
+  }
+
+  pub fn seeking(&self) -> bool {
+    self.seeking.load(Ordering::Relaxed)
+  }
+
+  /// Number of game instances connected to the host
+  pub fn viewers(&self) -> u64 {
+    self.viewers.load(Ordering::Relaxed)
+  }
+
+  /// Returns true if the game host has exited
+  pub fn closed(&self) -> bool {
+    self.commands.is_closed()
+  }
+
+  pub fn pause(&self) -> Result<()> {
+    self.send_command(Command::Pause)
+  }
+
+  pub fn resume(&self) -> Result<()> {
+    self.send_command(Command::Resume)
+  }
+
+  /// Jumps to `millis` of game time on every viewer.
+  /// Seeking backward restarts the game session, viewers have to rejoin the game.
+  pub fn seek(&self, millis: u64) -> Result<()> {
+    if !self.seekable {
+      return Err(Error::ObserverSeekNotSupported);
+    }
+    self.send_command(Command::Seek(millis))
+  }
+
+  /// Catches up with the live stream at `OBSERVER_FAST_FORWARDING_SPEED`
+  pub fn fast_forward_to_live(&self) -> Result<()> {
+    if self.seekable {
+      return Err(Error::ObserverSeekNotSupported);
+    }
+    self.set_speed(flo_constants::OBSERVER_FAST_FORWARDING_SPEED);
+    self.send_command(Command::Resume)
+  }
+
+  fn send_command(&self, cmd: Command) -> Result<()> {
+    self
+      .commands
+      .send(cmd)
+      .map_err(|_| Error::ObserverHostClosed)
+  }
 }
 
 impl ObserverHostShared {
-  pub fn new(game_id: i32, initial_delay_secs: Option<i64>) -> Self {
+  fn new(
+    game_id: i32,
+    initial_delay_secs: Option<i64>,
+    seekable: bool,
+    commands: mpsc::UnboundedSender<Command>,
+  ) -> Self {
     Self {
       game_id,
       initial_delay_secs,
+      seekable,
       speed_x10: Arc::new(AtomicU64::new(10)),
       game_time_millis: Arc::new(AtomicU64::new(0)),
       delay_secs: Arc::new(AtomicU64::new(0)),
@@ -716,10 +1136,44 @@ impl ObserverHostShared {
       stream_finished: Arc::new(AtomicBool::new(false)),
       stream_total_millis: Arc::new(AtomicU64::new(0)),
       finished: Arc::new(AtomicBool::new(false)),
+      paused: Arc::new(AtomicBool::new(false)),
+      seeking: Arc::new(AtomicBool::new(false)),
+      viewers: Arc::new(AtomicU64::new(0)),
+      commands,
     }
   }
 }
 
+#[tokio::test]
+async fn test_record_log() {
+  let log = Arc::new(RecordLog::new());
+  let mut early = RecordCursor::new(log.clone());
+  log.push(GameRecordData::StopLag(1));
+
+  let reader = tokio::spawn(async move {
+    let mut n = 0;
+    while let Some(_) = early.next().await {
+      n += 1;
+    }
+    n
+  });
+
+  log.push(GameRecordData::StopLag(2));
+  log.finish();
+
+  let mut late = RecordCursor::new(log.clone());
+  assert!(matches!(
+    late.next().await,
+    Some(GameRecordData::StopLag(1))
+  ));
+  assert!(matches!(
+    late.next().await,
+    Some(GameRecordData::StopLag(2))
+  ));
+  assert!(late.next().await.is_none());
+  assert_eq!(reader.await.unwrap(), 2);
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_reader() {
   use flo_observer::record::GameRecordData;
@@ -1,9 +1,11 @@
 use crate::error::{Error, Result};
 use crate::observer::game::ObserverGameHost;
-use crate::observer::source::NetworkSource;
-use crate::platform::{GetClientConfig, Platform};
+use crate::observer::source::{NetworkSource, ReplayFileSource};
+use crate::platform::{GetClientConfig, GetClientPlatformInfo, Platform};
 use crate::StartConfig;
+use flo_observer::record::GameRecordData;
 use flo_state::{async_trait, Actor, Addr, Handler, Message, RegistryRef, Service};
+use futures::Stream;
 use serde::Deserialize;
 use tokio_util::sync::CancellationToken;
 pub use crate::observer::game::ObserverHostShared;
@@ -26,6 +28,26 @@ impl ObserverClient {
   }
 }
 
+impl ObserverClient {
+  fn start_playing<S>(&mut self, ctx: &mut flo_state::Context<Self>, host: ObserverGameHost<S>)
+  where
+    S: Stream<Item = Result<GameRecordData>> + Unpin + Send + 'static,
+  {
+    let ct = CancellationToken::new();
+    self.playing.replace(Playing { ct: ct.clone() });
+    ctx.spawn(async move {
+      tokio::select! {
+        _ = ct.cancelled() => {},
+        r = host.play() => {
+          if let Err(err) = r {
+            tracing::error!("observer game host play: {}", err)
+          }
+        }
+      }
+    });
+  }
+}
+
 impl Actor for ObserverClient {}
 
 #[async_trait]
@@ -69,18 +91,41 @@ impl Handler<WatchGame> for ObserverClient {
     let host =
       ObserverGameHost::new(game, source.delay_secs(), source, self.platform.clone()).await?;
     let shared = host.shared();
-    let ct = CancellationToken::new();
-    self.playing.replace(Playing { ct: ct.clone() });
-    ctx.spawn(async move {
-      tokio::select! {
-        _ = ct.cancelled() => {},
-        r = host.play() => {
-          if let Err(err) = r {
-            tracing::error!("observer game host play: {}", err)
-          }
-        }
-      }
-    });
+    self.start_playing(ctx, host);
+    Ok(shared)
+  }
+}
+
+/// Plays a local `.w3g` replay file
+#[derive(Debug, Deserialize)]
+pub struct WatchReplay {
+  pub path: String,
+}
+
+impl Message for WatchReplay {
+  type Result = Result<ObserverHostShared>;
+}
+
+#[async_trait]
+impl Handler<WatchReplay> for ObserverClient {
+  async fn handle(
+    &mut self,
+    ctx: &mut flo_state::Context<Self>,
+    WatchReplay { path }: WatchReplay,
+  ) -> Result<ObserverHostShared> {
+    let client_info = self
+      .platform
+      .send(GetClientPlatformInfo::default())
+      .await?
+      .map_err(|_| Error::War3NotLocated)?;
+
+    // the replay is played with the installed game regardless of the version it was recorded with
+    let (game, source) = ReplayFileSource::load(&path, client_info.version).await?;
+    tracing::debug!("replay: {}", game.name);
+
+    let host = ObserverGameHost::new(game, None, source, self.platform.clone()).await?;
+    let shared = host.shared();
+    self.start_playing(ctx, host);
     Ok(shared)
   }
 }
@@ -18,6 +18,9 @@ pub struct SendQueue {
   exhausted_waker: Option<Waker>,
   delayed: Option<W3GSPacket>,
   last_deadline: Option<Instant>,
+  sent_millis: u64,
+  paused: bool,
+  seek_target_millis: Option<u64>,
 }
 
 impl SendQueue {
@@ -32,6 +35,9 @@ impl SendQueue {
       exhausted_waker: None,
       delayed: None,
       last_deadline: None,
+      sent_millis: 0,
+      paused: false,
+      seek_target_millis: None,
     }
   }
 
@@ -57,6 +63,41 @@ impl SendQueue {
     self.total_millis
   }
 
+  /// Game time of the packets already sent
+  pub fn sent_millis(&self) -> u64 {
+    self.sent_millis
+  }
+
+  pub fn paused(&self) -> bool {
+    self.paused
+  }
+
+  pub fn set_paused(&mut self, paused: bool) {
+    if self.paused == paused {
+      return;
+    }
+    self.paused = paused;
+    if !paused {
+      // don't try to catch up the paused duration
+      self.last_deadline.take();
+      self.exhausted_waker.take().map(|w| w.wake());
+    }
+  }
+
+  /// Sends packets at `OBSERVER_SEEKING_SPEED` until the game time reaches `millis`
+  pub fn seek(&mut self, millis: u64) {
+    if millis > self.sent_millis {
+      self.seek_target_millis.replace(millis);
+      self.exhausted_waker.take().map(|w| w.wake());
+    } else {
+      self.seek_target_millis.take();
+    }
+  }
+
+  pub fn seeking(&self) -> bool {
+    self.seek_target_millis.is_some()
+  }
+
   pub fn finish(&mut self) {
     self.finished = true;
   }
@@ -74,12 +115,28 @@ impl SendQueue {
     self.packets.push_back((packet, increase_millis));
     self.exhausted_waker.take().map(|w| w.wake());
   }
+
+  fn register_waker(&mut self, cx: &mut Context<'_>) {
+    if !self
+      .exhausted_waker
+      .as_ref()
+      .map(|w| w.will_wake(cx.waker()))
+      .unwrap_or_default()
+    {
+      self.exhausted_waker.replace(cx.waker().clone());
+    }
+  }
 }
 
 impl Stream for SendQueue {
   type Item = W3GSPacket;
 
   fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    if self.paused {
+      self.register_waker(cx);
+      return Poll::Pending;
+    }
+
     futures::ready!(self.sleep.as_mut().poll(cx));
 
     if let Some(pkt) = self.delayed.take() {
@@ -89,7 +146,16 @@ impl Stream for SendQueue {
     if let Some((pkt, ms)) = self.packets.pop_front() {
       if let Some(ms) = ms {
         self.buffered_millis = self.buffered_millis.saturating_sub(ms);
-        let delay = Duration::from_millis(if let Some(f) = self.speed.clone() {
+        self.sent_millis += ms;
+        let speed = match self.seek_target_millis {
+          Some(target) if self.sent_millis < target => Some(flo_constants::OBSERVER_SEEKING_SPEED),
+          Some(_) => {
+            self.seek_target_millis.take();
+            self.speed.clone()
+          }
+          None => self.speed.clone(),
+        };
+        let delay = Duration::from_millis(if let Some(f) = speed {
           if f > 0. {
             (ms as f64 / f).floor() as u64
           } else {
@@ -123,18 +189,63 @@ impl Stream for SendQueue {
       }
     } else {
       if self.finished {
+        self.seek_target_millis.take();
         return Poll::Ready(None);
       }
 
-      if !self
-        .exhausted_waker
-        .as_ref()
-        .map(|w| w.will_wake(cx.waker()))
-        .unwrap_or_default()
-      {
-        self.exhausted_waker.replace(cx.waker().clone());
-      }
+      self.register_waker(cx);
       Poll::Pending
     }
   }
 }
+
+#[tokio::test]
+async fn test_send_queue_seek() {
+  use flo_w3gs::action::{IncomingAction, TimeSlot};
+  use flo_w3gs::packet::Packet;
+  use futures::StreamExt;
+
+  let mut q = SendQueue::new();
+  for _ in 0..10 {
+    q.push(
+      Packet::with_payload(IncomingAction(TimeSlot {
+        time_increment_ms: 100,
+        actions: vec![],
+      }))
+      .unwrap(),
+      Some(100),
+    );
+  }
+  q.finish();
+  q.seek(1000);
+  assert!(q.seeking());
+
+  let t = Instant::now();
+  while let Some(_) = q.next().await {}
+  assert!(t.elapsed() < Duration::from_millis(500));
+  assert_eq!(q.sent_millis(), 1000);
+  assert!(!q.seeking());
+}
+
+#[tokio::test]
+async fn test_send_queue_pause() {
+  use flo_w3gs::action::{IncomingAction, TimeSlot};
+  use flo_w3gs::packet::Packet;
+  use futures::StreamExt;
+
+  let mut q = SendQueue::new();
+  q.push(
+    Packet::with_payload(IncomingAction(TimeSlot {
+      time_increment_ms: 0,
+      actions: vec![],
+    }))
+    .unwrap(),
+    Some(0),
+  );
+  q.set_paused(true);
+  assert!(tokio::time::timeout(Duration::from_millis(50), q.next())
+    .await
+    .is_err());
+  q.set_paused(false);
+  assert!(q.next().await.is_some());
+}
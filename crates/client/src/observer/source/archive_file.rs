@@ -31,6 +31,12 @@ impl ArchiveFileSource {
   }
 }
 
+impl super::ObserverSource for ArchiveFileSource {
+  fn seekable(&self) -> bool {
+    true
+  }
+}
+
 impl Stream for ArchiveFileSource {
   type Item = Result<GameRecordData>;
 
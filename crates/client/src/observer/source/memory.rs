@@ -40,6 +40,12 @@ impl MemorySource {
   }
 }
 
+impl super::ObserverSource for MemorySource {
+  fn seekable(&self) -> bool {
+    true
+  }
+}
+
 impl Stream for MemorySource {
   type Item = Result<GameRecordData>;
 
@@ -1,6 +1,20 @@
 mod network;
 mod memory;
 mod archive_file;
+mod replay_file;
 
 pub use self::network::NetworkSource;
-pub use self::archive_file::ArchiveFileSource;
\ No newline at end of file
+pub use self::archive_file::ArchiveFileSource;
+pub use self::replay_file::ReplayFileSource;
+
+use crate::error::Result;
+use flo_observer::record::GameRecordData;
+use futures::Stream;
+
+/// A stream of game records played by the observer game host
+pub trait ObserverSource: Stream<Item = Result<GameRecordData>> + Unpin {
+  /// Whether the source contains the whole game, i.e. it's not a live stream
+  fn seekable(&self) -> bool {
+    false
+  }
+}
\ No newline at end of file
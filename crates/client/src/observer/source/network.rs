@@ -147,6 +147,8 @@ impl Worker {
   }
 }
 
+impl super::ObserverSource for NetworkSource {}
+
 impl Stream for NetworkSource {
   type Item = Result<GameRecordData>;
 
@@ -0,0 +1,231 @@
+use crate::error::{Error, Result};
+use crate::lan::game::slot::index_to_player_id;
+use flo_observer::record::GameRecordData;
+use flo_types::observer::{
+  Computer, GameInfo, Map, PlayerInfo, Race, Slot, SlotSettings, SlotStatus,
+};
+use flo_w3gs::action::{IncomingAction, IncomingAction2, PlayerAction, TimeSlot};
+use flo_w3gs::leave::PlayerLeft;
+use flo_w3gs::packet::Packet;
+use flo_w3gs::slot::{RacePref, SlotData, AI};
+use flo_w3replay::{Record, ReplayInfo, W3Replay};
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::{
+  path::{Path, PathBuf},
+  pin::Pin,
+  task::{Context, Poll},
+};
+
+use super::memory::MemorySource;
+
+/// Plays a standard `.w3g` replay file.
+///
+/// The replay's slots are re-mapped to the layout of the flo LAN game,
+/// so player ids in the action records are rewritten to match.
+pub struct ReplayFileSource {
+  inner: MemorySource,
+}
+
+impl ReplayFileSource {
+  /// `game_version` is the version of the installed game,
+  /// replays recorded by other versions are played as-is.
+  pub async fn load<P: AsRef<Path>>(path: P, game_version: String) -> Result<(GameInfo, Self)> {
+    let path: PathBuf = path.as_ref().to_owned();
+    let (game, records) = tokio::task::spawn_blocking(move || -> Result<_> {
+      let (info, iter) = W3Replay::inspect(&path)?;
+      let records = iter
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(flo_w3replay::error::Error::from)?;
+      convert(info, records, game_version)
+    })
+    .await??;
+
+    let inner = MemorySource::new(records);
+
+    tracing::debug!("replay duration: {}ms", inner.remaining_millis());
+
+    Ok((game, Self { inner }))
+  }
+}
+
+impl super::ObserverSource for ReplayFileSource {
+  fn seekable(&self) -> bool {
+    true
+  }
+}
+
+impl Stream for ReplayFileSource {
+  type Item = Result<GameRecordData>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.inner.poll_next_unpin(cx)
+  }
+}
+
+fn convert(
+  info: ReplayInfo,
+  records: Vec<Record>,
+  game_version: String,
+) -> Result<(GameInfo, Vec<GameRecordData>)> {
+  let settings = &info.game.game_settings;
+
+  let mut names: HashMap<u8, String> = info
+    .players
+    .iter()
+    .map(|p| (p.id, p.name.to_string_lossy().to_string()))
+    .collect();
+  names.insert(
+    info.game.host_player_info.id,
+    info
+      .game
+      .host_player_info
+      .name
+      .to_string_lossy()
+      .to_string(),
+  );
+
+  // replays of 12-slot games use team 12 for observers
+  let observer_team = if info.slots.slots().len() <= 12 {
+    12
+  } else {
+    24
+  };
+
+  let mut player_id_map = HashMap::new();
+  let mut slots = Vec::with_capacity(24);
+  for (i, slot) in info.slots.slots().iter().enumerate().take(24) {
+    let player = if slot.slot_status == flo_w3gs::slot::SlotStatus::Occupied && !slot.computer {
+      player_id_map.insert(slot.player_id, index_to_player_id(i));
+      Some(PlayerInfo {
+        id: slot.player_id as i32,
+        name: names
+          .get(&slot.player_id)
+          .cloned()
+          .unwrap_or_else(|| format!("Player {}", slot.player_id)),
+      })
+    } else {
+      None
+    };
+    slots.push(Slot {
+      player,
+      settings: get_slot_settings(slot, observer_team),
+    });
+  }
+  slots.resize(24, Slot::default());
+
+  let remap_actions = |actions: Vec<PlayerAction>| -> Vec<PlayerAction> {
+    actions
+      .into_iter()
+      .map(|action| PlayerAction {
+        player_id: player_id_map
+          .get(&action.player_id)
+          .cloned()
+          .unwrap_or(action.player_id),
+        data: action.data,
+      })
+      .collect()
+  };
+
+  let mut items = Vec::with_capacity(records.len());
+  for record in records {
+    match record {
+      Record::TimeSlotFragment(f) => {
+        items.push(GameRecordData::W3GS(Packet::with_payload(
+          IncomingAction2(TimeSlot {
+            time_increment_ms: f.0.time_increment_ms,
+            actions: remap_actions(f.0.actions),
+          }),
+        )?));
+      }
+      Record::TimeSlot(f) => {
+        items.push(GameRecordData::W3GS(Packet::with_payload(IncomingAction(
+          TimeSlot {
+            time_increment_ms: f.time_increment_ms,
+            actions: remap_actions(f.actions),
+          },
+        ))?));
+      }
+      Record::PlayerLeft(p) => {
+        items.push(GameRecordData::W3GS(Packet::simple(PlayerLeft {
+          player_id: player_id_map
+            .get(&p.player_id)
+            .cloned()
+            .unwrap_or(p.player_id),
+          reason: p.reason,
+        })?));
+      }
+      _ => {}
+    }
+  }
+  items.push(GameRecordData::GameEnd);
+
+  let sha1 = settings.map_sha1.to_vec();
+  if sha1.iter().all(|v| *v == 0) {
+    return Err(Error::InvalidMapInfo);
+  }
+
+  let game = GameInfo {
+    id: 0,
+    name: info.game.game_name.to_string_lossy().to_string(),
+    map: Map {
+      sha1,
+      checksum: settings.map_checksum,
+      path: settings.map_path.to_string_lossy().to_string(),
+    },
+    slots,
+    random_seed: info.slots.random_seed as i32,
+    game_version,
+    start_time_millis: 0,
+  };
+
+  Ok((game, items))
+}
+
+fn get_slot_settings(slot: &SlotData, observer_team: u8) -> SlotSettings {
+  use flo_w3gs::slot::SlotStatus as W3GSSlotStatus;
+  SlotSettings {
+    team: if slot.team == observer_team {
+      24
+    } else {
+      slot.team as i32
+    },
+    color: slot.color as i32,
+    computer: match slot.computer_type {
+      AI::ComputerEasy => Computer::Easy,
+      AI::ComputerInsane => Computer::Insane,
+      _ => Computer::Normal,
+    },
+    handicap: slot.handicap as i32,
+    status: match slot.slot_status {
+      W3GSSlotStatus::Occupied => SlotStatus::Occupied,
+      W3GSSlotStatus::Closed => SlotStatus::Closed,
+      _ => SlotStatus::Open,
+    },
+    race: if slot.race.contains(RacePref::HUMAN) {
+      Race::Human
+    } else if slot.race.contains(RacePref::ORC) {
+      Race::Orc
+    } else if slot.race.contains(RacePref::NIGHTELF) {
+      Race::NightElf
+    } else if slot.race.contains(RacePref::UNDEAD) {
+      Race::Undead
+    } else {
+      Race::Random
+    },
+  }
+}
+
+#[test]
+fn test_convert() {
+  let path = flo_util::sample_path!("replay", "bn.w3g");
+  let (info, iter) = W3Replay::inspect(&path).unwrap();
+  let records = iter.collect::<Result<Vec<_>, _>>().unwrap();
+  let (game, items) = convert(info, records, "1.0".to_string()).unwrap();
+  assert_eq!(game.slots.len(), 24);
+  assert!(game.slots.iter().any(|s| s.player.is_some()));
+  assert!(matches!(items.last(), Some(GameRecordData::GameEnd)));
+
+  let source = MemorySource::new(items);
+  assert!(source.remaining_millis() > 0);
+}
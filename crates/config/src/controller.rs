@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+use crate::error::*;
+use crate::{env_parse, load_toml};
+
+pub const CONTROLLER_CONFIG_PATH: &str = "flo-controller.toml";
+
+/// Controller service config, loaded from `flo-controller.toml` or the file specified by
+/// `FLO_CONTROLLER_CONFIG`.
+///
+/// Every field is optional in the file, environment variables override the file.
+/// `min_client_version`, `lobby_chat_rate_limit` and the `chat_ban_*` settings are applied
+/// on reload, other settings require a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControllerConfig {
+  pub bind_address: Ipv4Addr,
+  pub grpc_port: u16,
+  pub socket_port: u16,
+  pub database_url: String,
+  pub jwt_secret_base64: String,
+  /// Clients older than this version are rejected, `major.minor.patch`
+  pub min_client_version: String,
+  /// Chat violations reported by nodes within `chat_ban_window_secs` after which the player
+  /// is banned from chatting for `chat_ban_duration_secs`, `0` disables automatic chat bans.
+  pub chat_ban_violations: u32,
+  pub chat_ban_window_secs: u64,
+  pub chat_ban_duration_secs: u64,
+  /// Lobby chat messages each player can send per minute, `0` disables the limit.
+  pub lobby_chat_rate_limit: u32,
+}
+
+impl Default for ControllerConfig {
+  fn default() -> Self {
+    ControllerConfig {
+      bind_address: Ipv4Addr::UNSPECIFIED,
+      grpc_port: flo_constants::CONTROLLER_GRPC_PORT,
+      socket_port: flo_constants::CONTROLLER_SOCKET_PORT,
+      database_url: String::new(),
+      jwt_secret_base64: String::new(),
+      min_client_version: flo_constants::MIN_FLO_VERSION.to_string(),
+      chat_ban_violations: 0,
+      chat_ban_window_secs: 7 * 24 * 3600,
+      chat_ban_duration_secs: 24 * 3600,
+      lobby_chat_rate_limit: 20,
+    }
+  }
+}
+
+impl ControllerConfig {
+  pub fn load() -> Result<Self> {
+    let mut config: Self = load_toml("FLO_CONTROLLER_CONFIG", CONTROLLER_CONFIG_PATH)?;
+    config.apply_env()?;
+    Ok(config)
+  }
+
+  pub fn validate(&self) -> Result<()> {
+    if self.database_url.is_empty() {
+      return Err(Error::Invalid(
+        "database_url or env `DATABASE_URL` is required".to_string(),
+      ));
+    }
+    if self.jwt_secret_base64.is_empty() {
+      return Err(Error::Invalid(
+        "jwt_secret_base64 or env `JWT_SECRET_BASE64` is required".to_string(),
+      ));
+    }
+    if self.grpc_port == 0 || self.socket_port == 0 || self.grpc_port == self.socket_port {
+      return Err(Error::Invalid(format!(
+        "invalid ports: grpc_port = {}, socket_port = {}",
+        self.grpc_port, self.socket_port
+      )));
+    }
+    self.min_client_version()?;
+    if self.chat_ban_violations > 0
+      && (self.chat_ban_window_secs == 0 || self.chat_ban_duration_secs == 0)
+    {
+      return Err(Error::Invalid(
+        "chat_ban_window_secs and chat_ban_duration_secs must be greater than 0".to_string(),
+      ));
+    }
+    Ok(())
+  }
+
+  pub fn min_client_version(&self) -> Result<flo_constants::version::Version> {
+    let parts: Vec<i32> = self
+      .min_client_version
+      .split('.')
+      .map(|v| v.parse())
+      .collect::<Result<_, _>>()
+      .map_err(|_| self.invalid_min_client_version())?;
+    if parts.len() != 3 {
+      return Err(self.invalid_min_client_version());
+    }
+    Ok(flo_constants::version::Version {
+      major: parts[0],
+      minor: parts[1],
+      patch: parts[2],
+    })
+  }
+
+  fn invalid_min_client_version(&self) -> Error {
+    Error::Invalid(format!(
+      "invalid min_client_version: {}",
+      self.min_client_version
+    ))
+  }
+
+  /// Returns the current config with the reloadable settings taken from `new`,
+  /// and the names of changed settings that require a restart.
+  pub fn reload(&self, new: &Self) -> (Self, Vec<&'static str>) {
+    let mut ignored = vec![];
+    if self.bind_address != new.bind_address {
+      ignored.push("bind_address");
+    }
+    if self.grpc_port != new.grpc_port {
+      ignored.push("grpc_port");
+    }
+    if self.socket_port != new.socket_port {
+      ignored.push("socket_port");
+    }
+    if self.database_url != new.database_url {
+      ignored.push("database_url");
+    }
+    if self.jwt_secret_base64 != new.jwt_secret_base64 {
+      ignored.push("jwt_secret_base64");
+    }
+    let config = ControllerConfig {
+      min_client_version: new.min_client_version.clone(),
+      chat_ban_violations: new.chat_ban_violations,
+      chat_ban_window_secs: new.chat_ban_window_secs,
+      chat_ban_duration_secs: new.chat_ban_duration_secs,
+      lobby_chat_rate_limit: new.lobby_chat_rate_limit,
+      ..self.clone()
+    };
+    (config, ignored)
+  }
+
+  fn apply_env(&mut self) -> Result<()> {
+    use std::env;
+
+    if let Some(v) = env_parse("FLO_CONTROLLER_BIND_ADDRESS")? {
+      self.bind_address = v;
+    }
+
+    if let Some(v) = env_parse("FLO_CONTROLLER_GRPC_PORT")? {
+      self.grpc_port = v;
+    }
+
+    if let Some(v) = env_parse("FLO_CONTROLLER_SOCKET_PORT")? {
+      self.socket_port = v;
+    }
+
+    if let Ok(v) = env::var("DATABASE_URL") {
+      self.database_url = v;
+    }
+
+    if let Ok(v) = env::var("JWT_SECRET_BASE64") {
+      self.jwt_secret_base64 = v;
+    }
+
+    if let Ok(v) = env::var("FLO_MIN_CLIENT_VERSION") {
+      self.min_client_version = v;
+    }
+
+    Ok(())
+  }
+}
+
+#[test]
+fn test_controller_config() {
+  let mut config: ControllerConfig = toml::from_str(
+    r#"
+    database_url = "sqlite://flo.db"
+    min_client_version = "0.10.0"
+    "#,
+  )
+  .unwrap();
+  assert_eq!(config.grpc_port, flo_constants::CONTROLLER_GRPC_PORT);
+  assert!(config.validate().is_err());
+
+  config.jwt_secret_base64 = "MTExMQ==".to_string();
+  config.validate().unwrap();
+  assert_eq!(config.min_client_version().unwrap().minor, 10);
+
+  let mut new = config.clone();
+  new.min_client_version = "0.11.0".to_string();
+  new.jwt_secret_base64 = "MjIyMg==".to_string();
+  new.lobby_chat_rate_limit = 0;
+  let (reloaded, ignored) = config.reload(&new);
+  assert_eq!(reloaded.min_client_version, "0.11.0");
+  assert_eq!(reloaded.lobby_chat_rate_limit, 0);
+  assert_eq!(reloaded.jwt_secret_base64, "MTExMQ==");
+  assert_eq!(ignored, vec!["jwt_secret_base64"]);
+
+  new.min_client_version = "0.11".to_string();
+  assert!(new.validate().is_err());
+
+  let mut invalid = config.clone();
+  invalid.chat_ban_violations = 3;
+  invalid.chat_ban_window_secs = 0;
+  assert!(invalid.validate().is_err());
+}
@@ -1,8 +1,18 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+mod controller;
 pub mod error;
+mod node;
+
+pub use controller::{ControllerConfig, CONTROLLER_CONFIG_PATH};
+pub use node::{
+  ChatFilterAction, NodeChatConfig, NodeConfig, NodeGameConfig, NodeObserverConfig,
+  NODE_CONFIG_PATH,
+};
 
 use error::*;
 
@@ -105,3 +115,26 @@ impl ClientConfig {
     }
   }
 }
+
+// the file at `default_path` is optional, a file specified by env `path_env_key` must exist
+fn load_toml<T>(path_env_key: &str, default_path: &str) -> Result<T>
+where
+  T: DeserializeOwned + Default,
+{
+  let path = match std::env::var(path_env_key) {
+    Ok(path) => PathBuf::from(path),
+    Err(_) if !Path::new(default_path).exists() => return Ok(T::default()),
+    Err(_) => PathBuf::from(default_path),
+  };
+  toml::from_str(&fs::read_to_string(path)?).map_err(Into::into)
+}
+
+fn env_parse<T>(key: &'static str) -> Result<Option<T>>
+where
+  T: FromStr,
+{
+  std::env::var(key)
+    .ok()
+    .map(|v| v.parse().map_err(|_| Error::InvalidEnv(key)))
+    .transpose()
+}
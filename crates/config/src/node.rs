@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+use crate::error::*;
+use crate::{env_parse, load_toml};
+
+pub const NODE_CONFIG_PATH: &str = "flo-node.toml";
+
+#[cfg(not(debug_assertions))]
+const DEFAULT_MAX_DELAY_MS: u64 = 100;
+#[cfg(debug_assertions)]
+const DEFAULT_MAX_DELAY_MS: u64 = 60 * 1000;
+
+/// Node service config, loaded from `flo-node.toml` or the file specified by `FLO_NODE_CONFIG`.
+///
+/// Every field is optional in the file, environment variables override the file.
+/// `admin_secret` and settings under `[game]` and `[chat]` are applied on reload,
+/// other settings require a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+  pub bind_address: Ipv4Addr,
+  /// The echo port, the controller, client and http ports follow it
+  /// with the offsets defined in `flo_constants`.
+  pub port: u16,
+  pub secret_key: String,
+  /// Secret of the admin HTTP API, the API is disabled if empty.
+  pub admin_secret: String,
+  pub observer: NodeObserverConfig,
+  pub game: NodeGameConfig,
+  pub chat: NodeChatConfig,
+}
+
+impl Default for NodeConfig {
+  fn default() -> Self {
+    NodeConfig {
+      bind_address: Ipv4Addr::UNSPECIFIED,
+      port: flo_constants::NODE_PORT,
+      secret_key: String::new(),
+      admin_secret: String::new(),
+      observer: NodeObserverConfig::default(),
+      game: NodeGameConfig::default(),
+      chat: NodeChatConfig::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeObserverConfig {
+  /// Drops records instead of pushing them to kinesis, for nodes without internet access.
+  pub disabled: bool,
+  /// `TEST`, `PTR` or `PROD`
+  pub source: String,
+}
+
+impl Default for NodeObserverConfig {
+  fn default() -> Self {
+    NodeObserverConfig {
+      disabled: false,
+      source: "TEST".to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeGameConfig {
+  pub step_ms: u16,
+  /// Range accepted by the `!delay` command
+  pub min_delay_ms: u64,
+  pub max_delay_ms: u64,
+  /// Players who haven't acked a tick within this time are considered lagging.
+  pub lagging_threshold_ms: u32,
+}
+
+impl Default for NodeGameConfig {
+  fn default() -> Self {
+    NodeGameConfig {
+      step_ms: 30,
+      min_delay_ms: 25,
+      max_delay_ms: DEFAULT_MAX_DELAY_MS,
+      lagging_threshold_ms: 3000,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatFilterAction {
+  /// Masks the filtered words with `*`
+  Replace,
+  /// Drops the whole message
+  Drop,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeChatConfig {
+  /// Case-insensitive words filtered from chat messages
+  pub filter_words: Vec<String>,
+  pub filter_action: ChatFilterAction,
+  /// Messages a player can send within `rate_limit_window_secs`, `0` disables the limit.
+  pub rate_limit_messages: u32,
+  pub rate_limit_window_secs: u32,
+  /// Filter and rate limit violations after which the player is muted
+  /// for the rest of the game, `0` disables muting.
+  pub mute_after_violations: u32,
+}
+
+impl Default for NodeChatConfig {
+  fn default() -> Self {
+    NodeChatConfig {
+      filter_words: vec![],
+      filter_action: ChatFilterAction::Replace,
+      rate_limit_messages: 0,
+      rate_limit_window_secs: 10,
+      mute_after_violations: 0,
+    }
+  }
+}
+
+impl NodeConfig {
+  pub fn load() -> Result<Self> {
+    let mut config: Self = load_toml("FLO_NODE_CONFIG", NODE_CONFIG_PATH)?;
+    config.apply_env()?;
+    Ok(config)
+  }
+
+  pub fn validate(&self) -> Result<()> {
+    if self.port == 0 || self.port > u16::MAX - flo_constants::NODE_HTTP_PORT_OFFSET {
+      return Err(Error::Invalid(format!("port out of range: {}", self.port)));
+    }
+    if self.game.step_ms == 0 || self.game.step_ms > 1000 {
+      return Err(Error::Invalid(format!(
+        "game.step_ms must be in 1..=1000: {}",
+        self.game.step_ms
+      )));
+    }
+    if self.game.min_delay_ms == 0 || self.game.min_delay_ms > self.game.max_delay_ms {
+      return Err(Error::Invalid(format!(
+        "invalid delay range: {}..={}",
+        self.game.min_delay_ms, self.game.max_delay_ms
+      )));
+    }
+    if self.game.lagging_threshold_ms <= self.game.step_ms as u32 {
+      return Err(Error::Invalid(
+        "game.lagging_threshold_ms must be greater than game.step_ms".to_string(),
+      ));
+    }
+    if self.chat.rate_limit_messages > 0 && self.chat.rate_limit_window_secs == 0 {
+      return Err(Error::Invalid(
+        "chat.rate_limit_window_secs must be greater than 0".to_string(),
+      ));
+    }
+    if self.chat.filter_words.iter().any(|w| w.trim().is_empty()) {
+      return Err(Error::Invalid(
+        "chat.filter_words must not contain empty words".to_string(),
+      ));
+    }
+    Ok(())
+  }
+
+  /// Returns the current config with the reloadable settings taken from `new`,
+  /// and the names of changed settings that require a restart.
+  pub fn reload(&self, new: &Self) -> (Self, Vec<&'static str>) {
+    let mut ignored = vec![];
+    if self.bind_address != new.bind_address {
+      ignored.push("bind_address");
+    }
+    if self.port != new.port {
+      ignored.push("port");
+    }
+    if self.secret_key != new.secret_key {
+      ignored.push("secret_key");
+    }
+    if self.observer != new.observer {
+      ignored.push("observer");
+    }
+    let config = NodeConfig {
+      admin_secret: new.admin_secret.clone(),
+      game: new.game.clone(),
+      chat: new.chat.clone(),
+      ..self.clone()
+    };
+    (config, ignored)
+  }
+
+  fn apply_env(&mut self) -> Result<()> {
+    use std::env;
+
+    if let Some(v) = env_parse("FLO_NODE_BIND_ADDRESS")? {
+      self.bind_address = v;
+    }
+
+    if let Some(v) = env_parse("FLO_NODE_PORT")? {
+      self.port = v;
+    }
+
+    if let Ok(v) = env::var("FLO_NODE_SECRET") {
+      self.secret_key = v;
+    }
+
+    if let Ok(v) = env::var("FLO_NODE_ADMIN_SECRET") {
+      self.admin_secret = v;
+    }
+
+    if let Ok(v) = env::var("OBSERVER_DISABLED") {
+      self.observer.disabled = v == "1" || v == "true";
+    }
+
+    if let Ok(v) = env::var("OBSERVER_SOURCE") {
+      self.observer.source = v;
+    }
+
+    if let Some(v) = env_parse("FLO_GAME_STEP_MS")? {
+      self.game.step_ms = v;
+    }
+
+    Ok(())
+  }
+}
+
+#[test]
+fn test_node_config() {
+  let config: NodeConfig = toml::from_str(
+    r#"
+    port = 4552
+    [game]
+    max_delay_ms = 200
+    "#,
+  )
+  .unwrap();
+  assert_eq!(config.port, 4552);
+  assert_eq!(config.game.max_delay_ms, 200);
+  assert_eq!(config.game.step_ms, 30);
+  assert_eq!(config.observer, NodeObserverConfig::default());
+  config.validate().unwrap();
+
+  let mut invalid = config.clone();
+  invalid.game.min_delay_ms = 300;
+  assert!(invalid.validate().is_err());
+
+  assert!(toml::from_str::<NodeConfig>("unknown = 1").is_err());
+
+  let mut new = config.clone();
+  new.port = 5552;
+  new.game.lagging_threshold_ms = 5000;
+  new.admin_secret = "admin".to_string();
+  let (reloaded, ignored) = config.reload(&new);
+  assert_eq!(reloaded.port, 4552);
+  assert_eq!(reloaded.game.lagging_threshold_ms, 5000);
+  assert_eq!(reloaded.admin_secret, "admin");
+  assert_eq!(ignored, vec!["port"]);
+}
+
+#[test]
+fn test_node_chat_config() {
+  let config: NodeConfig = toml::from_str(
+    r#"
+    [chat]
+    filter_words = ["foo", "bar"]
+    filter_action = "drop"
+    rate_limit_messages = 5
+    "#,
+  )
+  .unwrap();
+  assert_eq!(config.chat.filter_words, vec!["foo", "bar"]);
+  assert_eq!(config.chat.filter_action, ChatFilterAction::Drop);
+  assert_eq!(config.chat.rate_limit_window_secs, 10);
+  config.validate().unwrap();
+
+  let mut invalid = config.clone();
+  invalid.chat.rate_limit_window_secs = 0;
+  assert!(invalid.validate().is_err());
+
+  let mut new = config.clone();
+  new.chat.mute_after_violations = 3;
+  let (reloaded, ignored) = config.reload(&new);
+  assert_eq!(reloaded.chat.mute_after_violations, 3);
+  assert!(ignored.is_empty());
+}
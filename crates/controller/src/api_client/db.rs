@@ -0,0 +1,129 @@
+use diesel::prelude::*;
+use serde_json::Value;
+use tonic::Status;
+
+use crate::db::{DbConn, ExecutorRef};
+use crate::error::*;
+use crate::schema::api_audit_log;
+
+#[derive(Debug, Insertable)]
+#[table_name = "api_audit_log"]
+struct AuditLogInsert<'a> {
+  api_client_id: i32,
+  method: &'a str,
+  params: Value,
+  status_code: i32,
+  status_message: Option<&'a str>,
+}
+
+/// Records a gRPC call, `status_code` is the gRPC status code of the reply
+pub fn insert_audit_log(
+  conn: &DbConn,
+  api_client_id: i32,
+  method: &str,
+  params: Value,
+  status_code: i32,
+  status_message: Option<&str>,
+) -> Result<()> {
+  db_dispatch!(conn, {
+    diesel::insert_into(api_audit_log::table)
+      .values(&AuditLogInsert {
+        api_client_id,
+        method,
+        params,
+        status_code,
+        status_message,
+      })
+      .execute(conn)?;
+    Ok(())
+  })
+}
+
+/// Records a privileged or denied gRPC call, `status` is `None` if the call succeeded.
+/// Failing to write the record is only logged.
+pub async fn audit(
+  db: &ExecutorRef,
+  api_client_id: i32,
+  method: &str,
+  params: Value,
+  status: Option<&Status>,
+) {
+  let status_code = status.map(|s| s.code() as i32).unwrap_or_default();
+  let status_message = status.map(|s| s.message().to_string());
+  tracing::info!(api_client_id, method, status_code, "audit: {}", params);
+  let method_name = method.to_string();
+  let res = db
+    .exec(move |conn| {
+      insert_audit_log(
+        conn,
+        api_client_id,
+        &method_name,
+        params,
+        status_code,
+        status_message.as_deref(),
+      )
+    })
+    .await;
+  if let Err(err) = res {
+    tracing::error!(api_client_id, method, "insert audit log: {}", err);
+  }
+}
+
+#[tokio::test]
+async fn test_insert_audit_log() {
+  use chrono::{DateTime, Utc};
+  use serde_json::json;
+
+  let (rows, since) = crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let since = Utc::now() - chrono::Duration::minutes(1);
+    let params = json!({ "player_id": 1, "reason": "spam", "ip_ranges": ["10.0.0.0/8"] });
+    insert_audit_log(
+      conn,
+      setup.api_client_id,
+      "CreatePlayerBan",
+      params,
+      0,
+      None,
+    )?;
+    insert_audit_log(
+      conn,
+      setup.api_client_id,
+      "RemoveIpBan",
+      json!({ "id": 2 }),
+      7,
+      Some("API client scope `ban` is required"),
+    )?;
+    let rows: Vec<(i32, String, Value, i32, Option<String>, DateTime<Utc>)> = db_dispatch!(conn, {
+      api_audit_log::table
+        .select((
+          api_audit_log::api_client_id,
+          api_audit_log::method,
+          api_audit_log::params,
+          api_audit_log::status_code,
+          api_audit_log::status_message,
+          api_audit_log::created_at,
+        ))
+        .order(api_audit_log::id)
+        .load(conn)?
+    });
+    assert!(rows.iter().all(|row| row.0 == setup.api_client_id));
+    Ok((rows, since))
+  })
+  .await;
+
+  assert_eq!(rows.len(), 2);
+  assert_eq!(rows[0].1, "CreatePlayerBan");
+  assert_eq!(rows[0].2["reason"], "spam");
+  assert_eq!(rows[0].2["ip_ranges"][0], "10.0.0.0/8");
+  assert_eq!(rows[0].3, 0);
+  assert_eq!(rows[0].4, None);
+  assert_eq!(rows[1].1, "RemoveIpBan");
+  assert_eq!(rows[1].2, json!({ "id": 2 }));
+  assert_eq!(rows[1].3, 7);
+  assert_eq!(
+    rows[1].4.as_deref(),
+    Some("API client scope `ban` is required")
+  );
+  assert!(rows.iter().all(|row| row.5 >= since && row.5 <= Utc::now()));
+}
@@ -0,0 +1,6 @@
+pub mod db;
+mod rate_limit;
+mod scope;
+
+pub use rate_limit::RateLimiter;
+pub use scope::{ApiScope, ApiScopes};
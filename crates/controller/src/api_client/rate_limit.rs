@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Token buckets keyed by id (API client or player), `limit` requests are allowed per minute
+/// with bursts up to `limit`
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+  buckets: BTreeMap<i32, Bucket>,
+  pruned_at: Option<Instant>,
+}
+
+/// A bucket refills completely within a minute, after that it is the same as a new bucket
+const BUCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Bucket {
+  limit: u32,
+  tokens: f64,
+  updated_at: Instant,
+}
+
+impl RateLimiter {
+  /// Takes a token from the bucket of `id`,
+  /// returns the time until the next token is available if the bucket is empty
+  pub fn check(&mut self, id: i32, limit: u32) -> Result<(), Duration> {
+    self.check_at(id, limit, Instant::now())
+  }
+
+  fn check_at(&mut self, id: i32, limit: u32, now: Instant) -> Result<(), Duration> {
+    self.prune(now);
+
+    let bucket = self.buckets.entry(id).or_insert_with(|| Bucket {
+      limit,
+      tokens: limit as f64,
+      updated_at: now,
+    });
+
+    // the limit was changed by a reload
+    if bucket.limit != limit {
+      bucket.limit = limit;
+      bucket.tokens = bucket.tokens.min(limit as f64);
+    }
+
+    let per_sec = limit as f64 / 60.0;
+    let elapsed = now.saturating_duration_since(bucket.updated_at);
+    bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * per_sec).min(limit as f64);
+    bucket.updated_at = now;
+
+    if bucket.tokens >= 1.0 {
+      bucket.tokens -= 1.0;
+      Ok(())
+    } else {
+      Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+    }
+  }
+
+  /// Removes the buckets idle long enough to be full again, at most once per `BUCKET_IDLE_TIMEOUT`
+  fn prune(&mut self, now: Instant) {
+    match self.pruned_at {
+      Some(pruned_at) if now.saturating_duration_since(pruned_at) < BUCKET_IDLE_TIMEOUT => return,
+      Some(_) => {
+        self.buckets.retain(|_, bucket| {
+          now.saturating_duration_since(bucket.updated_at) < BUCKET_IDLE_TIMEOUT
+        });
+      }
+      None => {}
+    }
+    self.pruned_at = Some(now);
+  }
+}
+
+#[test]
+fn test_rate_limiter() {
+  let mut limiter = RateLimiter::default();
+  let now = Instant::now();
+
+  for _ in 0..60 {
+    assert!(limiter.check_at(1, 60, now).is_ok());
+  }
+  let retry_after = limiter.check_at(1, 60, now).unwrap_err();
+  assert_eq!(retry_after.as_secs(), 1);
+
+  // other clients have their own bucket
+  assert!(limiter.check_at(2, 60, now).is_ok());
+
+  // refills 1 token per second
+  let now = now + Duration::from_secs(1);
+  assert!(limiter.check_at(1, 60, now).is_ok());
+  assert!(limiter.check_at(1, 60, now).is_err());
+
+  // lowering the limit caps the tokens
+  let now = now + Duration::from_secs(30);
+  for _ in 0..10 {
+    assert!(limiter.check_at(1, 10, now).is_ok());
+  }
+  assert!(limiter.check_at(1, 10, now).is_err());
+
+  // idle buckets are removed
+  let now = now + BUCKET_IDLE_TIMEOUT;
+  assert!(limiter.check_at(1, 10, now).is_ok());
+  assert_eq!(limiter.buckets.len(), 1);
+}
@@ -0,0 +1,167 @@
+use serde_json::Value;
+
+/// Permission groups of the controller gRPC methods.
+///
+/// Stored as a JSON array of names in `api_client.scopes`, `"all"` grants every scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+  /// Read-only methods
+  Read,
+  /// Create and update players
+  Player,
+  /// Create, join, start and control games
+  Game,
+  /// Create and remove player bans
+  Ban,
+  /// Read game chat logs
+  Moderation,
+  /// Import map checksums and upload maps
+  Map,
+  /// Manage matchmaking queues and ratings
+  Matchmaking,
+  /// Manage webhooks
+  Webhook,
+  /// Reload the controller config
+  Reload,
+}
+
+impl ApiScope {
+  pub const ALL: &'static [ApiScope] = &[
+    Self::Read,
+    Self::Player,
+    Self::Game,
+    Self::Ban,
+    Self::Moderation,
+    Self::Map,
+    Self::Matchmaking,
+    Self::Webhook,
+    Self::Reload,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match *self {
+      Self::Read => "read",
+      Self::Player => "player",
+      Self::Game => "game",
+      Self::Ban => "ban",
+      Self::Moderation => "moderation",
+      Self::Map => "map",
+      Self::Matchmaking => "matchmaking",
+      Self::Webhook => "webhook",
+      Self::Reload => "reload",
+    }
+  }
+
+  pub fn parse(value: &str) -> Option<Self> {
+    Self::ALL.iter().find(|s| s.as_str() == value).cloned()
+  }
+
+  /// Scope required by a gRPC method, by method name, e.g. `CreateGame`
+  pub fn for_method(name: &str) -> Option<Self> {
+    let scope = match name {
+      "GetPlayer"
+      | "GetPlayerByToken"
+      | "ListNodes"
+      | "ListGames"
+      | "GetGame"
+      | "SearchMapChecksum"
+      | "GetPlayersBySourceIds"
+      | "GetPlayerPingMaps"
+      | "ListPlayerBans" => Self::Read,
+      "UpdateAndGetPlayer" => Self::Player,
+      "CreateGame"
+      | "JoinGame"
+      | "CreateJoinGameToken"
+      | "JoinGameByToken"
+      | "LeaveGame"
+      | "SelectGameNode"
+      | "CancelGame"
+      | "CreateGameAsBot"
+      | "StartGameAsBot"
+      | "CancelGameAsBot" => Self::Game,
+      "CreatePlayerBan" | "RemovePlayerBan" => Self::Ban,
+      // not served until flo-grpc publishes the chat log messages
+      "ListGameChatMessages" => Self::Moderation,
+      "ImportMapChecksums" => Self::Map,
+      "Reload" => Self::Reload,
+      _ => return None,
+    };
+    Some(scope)
+  }
+
+  fn bit(&self) -> u32 {
+    1 << (*self as u32)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ApiScopes(u32);
+
+impl ApiScopes {
+  pub fn all() -> Self {
+    ApiScopes(ApiScope::ALL.iter().fold(0, |bits, s| bits | s.bit()))
+  }
+
+  pub fn from_bits(bits: u32) -> Self {
+    ApiScopes(bits & Self::all().0)
+  }
+
+  pub fn bits(&self) -> u32 {
+    self.0
+  }
+
+  pub fn contains(&self, scope: ApiScope) -> bool {
+    self.0 & scope.bit() != 0
+  }
+
+  /// Parses the `api_client.scopes` column, unknown names are ignored
+  pub fn from_value(value: &Value) -> Self {
+    let mut bits = 0;
+    let names = match value.as_array() {
+      Some(names) => names,
+      None => {
+        tracing::warn!("invalid api client scopes: {}", value);
+        return ApiScopes(0);
+      }
+    };
+    for name in names.iter().filter_map(|v| v.as_str()) {
+      if name == "all" {
+        return Self::all();
+      }
+      match ApiScope::parse(name) {
+        Some(scope) => bits |= scope.bit(),
+        None => tracing::warn!("unknown api client scope: {}", name),
+      }
+    }
+    ApiScopes(bits)
+  }
+}
+
+#[test]
+fn test_scopes_from_value() {
+  use serde_json::json;
+
+  let all = ApiScopes::from_value(&json!(["all"]));
+  assert_eq!(all, ApiScopes::all());
+  assert!(ApiScope::ALL.iter().all(|s| all.contains(*s)));
+
+  let scopes = ApiScopes::from_value(&json!(["read", "ban", "unknown"]));
+  assert!(scopes.contains(ApiScope::Read));
+  assert!(scopes.contains(ApiScope::Ban));
+  assert!(!scopes.contains(ApiScope::Game));
+  assert!(!scopes.contains(ApiScope::Reload));
+  assert_eq!(ApiScopes::from_bits(scopes.bits()), scopes);
+
+  assert_eq!(ApiScopes::from_value(&json!("read")), ApiScopes::default());
+}
+
+#[test]
+fn test_scope_for_method() {
+  assert_eq!(ApiScope::for_method("GetGame"), Some(ApiScope::Read));
+  assert_eq!(ApiScope::for_method("CreatePlayerBan"), Some(ApiScope::Ban));
+  assert_eq!(
+    ApiScope::for_method("ListGameChatMessages"),
+    Some(ApiScope::Moderation)
+  );
+  assert_eq!(ApiScope::for_method("Unknown"), None);
+}
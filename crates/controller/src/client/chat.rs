@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use crate::player::BanInfo;
+
+/// Lobby chat messages are truncated to the length limit of in-game chat
+const GAME_CHAT_MAX_LEN: usize = 255;
+
+/// Returns the trimmed and truncated message, or `None` if it is empty
+pub fn normalize_message(message: &str) -> Option<String> {
+  let message: String = message.trim().chars().take(GAME_CHAT_MAX_LEN).collect();
+  if message.is_empty() {
+    None
+  } else {
+    Some(message)
+  }
+}
+
+#[derive(Debug)]
+pub enum LobbyChatDelivery {
+  /// The sender is banned from chatting
+  Reject(BanInfo),
+  /// Players receiving the message
+  Broadcast(Vec<i32>),
+}
+
+/// Resolves who receives a lobby chat message,
+/// players who muted the sender don't receive it
+pub fn resolve_delivery(
+  player_id: i32,
+  players: Vec<i32>,
+  ban: Option<BanInfo>,
+  mute_list_map: &BTreeMap<i32, Vec<i32>>,
+) -> LobbyChatDelivery {
+  if let Some(ban) = ban {
+    return LobbyChatDelivery::Reject(ban);
+  }
+  let targets = players
+    .into_iter()
+    .filter(|id| {
+      mute_list_map
+        .get(id)
+        .map(|mute_list| !mute_list.contains(&player_id))
+        .unwrap_or(true)
+    })
+    .collect();
+  LobbyChatDelivery::Broadcast(targets)
+}
+
+#[test]
+fn test_normalize_message() {
+  assert_eq!(normalize_message("  "), None);
+  assert_eq!(normalize_message(" gl hf \n"), Some("gl hf".to_string()));
+  let long = "ü".repeat(GAME_CHAT_MAX_LEN + 1);
+  assert_eq!(
+    normalize_message(&long).unwrap().chars().count(),
+    GAME_CHAT_MAX_LEN
+  );
+}
+
+#[test]
+fn test_resolve_delivery() {
+  let mut mute_list_map = BTreeMap::new();
+  mute_list_map.insert(2, vec![1]);
+  mute_list_map.insert(3, vec![4]);
+
+  match resolve_delivery(1, vec![1, 2, 3], None, &mute_list_map) {
+    LobbyChatDelivery::Broadcast(targets) => assert_eq!(targets, vec![1, 3]),
+    other => panic!("{:?}", other),
+  }
+  match resolve_delivery(4, vec![1, 2, 3, 4], None, &mute_list_map) {
+    LobbyChatDelivery::Broadcast(targets) => assert_eq!(targets, vec![1, 2, 4]),
+    other => panic!("{:?}", other),
+  }
+}
+
+#[test]
+fn test_resolve_delivery_chat_banned() {
+  use crate::player::PlayerBanType;
+
+  let ban = BanInfo {
+    ban_type: Some(PlayerBanType::Chat),
+    reason: Some("spam".to_string()),
+    ban_expires_at: None,
+  };
+  match resolve_delivery(1, vec![1, 2], Some(ban), &BTreeMap::new()) {
+    LobbyChatDelivery::Reject(ban) => {
+      assert_eq!(
+        ban.to_string(),
+        "You are banned from chatting permanently. Reason: spam."
+      )
+    }
+    other => panic!("{:?}", other),
+  }
+}
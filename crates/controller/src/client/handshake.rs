@@ -5,20 +5,34 @@ use flo_net::stream::FloStream;
 use crate::error::*;
 use crate::game::Game;
 use crate::player::token::validate_player_token;
+use crate::state::ControllerStateRef;
 use flo_constants::version::Version;
 
-pub async fn handle_handshake(stream: &mut FloStream) -> Result<ConnectState> {
+pub async fn handle_handshake(
+  state: &ControllerStateRef,
+  stream: &mut FloStream,
+) -> Result<ConnectState> {
   let req: PacketClientConnect = stream.recv().await?;
   let client_version = req.connect_version.extract()?;
 
   tracing::debug!("client version = {}", client_version);
 
-  let token = validate_player_token(&req.token)?;
+  let player_id = match (state.lan.as_ref(), crate::lan::parse_token(&req.token)) {
+    (Some(lan), Some(name)) => {
+      let api_client_id = lan.api_client_id;
+      let name = name.to_string();
+      state
+        .db
+        .exec(move |conn| crate::lan::get_or_create_player(conn, api_client_id, name))
+        .await?
+    }
+    _ => validate_player_token(&req.token)?.player_id,
+  };
 
-  tracing::debug!(token.player_id);
+  tracing::debug!(player_id);
 
   Ok(ConnectState {
-    player_id: token.player_id,
+    player_id,
     joined_game: None,
     client_version: Version {
       major: client_version.major,
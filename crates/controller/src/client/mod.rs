@@ -4,13 +4,15 @@ use flo_net::packet::FloPacket;
 use flo_net::packet::OptionalFieldExt;
 use flo_net::proto;
 use flo_net::stream::FloStream;
-use s2_grpc_utils::{S2ProtoPack, S2ProtoUnpack};
+use s2_grpc_utils::{S2ProtoEnum, S2ProtoPack, S2ProtoUnpack};
 use std::collections::HashMap;
+use std::net::SocketAddrV4;
 use std::time::Duration;
 
 use crate::error::*;
 use crate::state::{ActorMapExt, ControllerStateRef};
 
+mod chat;
 mod handshake;
 mod sender;
 use crate::game::messages::{ResolveGamePlayerPingBroadcastTargets, UpdateSlot};
@@ -18,10 +20,12 @@ use crate::game::state::node::SelectNode;
 use crate::game::state::player::GetGamePlayers;
 use crate::game::state::registry::UpdateGameNodeCache;
 use crate::game::state::start::{StartGameCheck, StartGamePlayerAck};
-use crate::game::SlotSettings;
+use crate::game::{GameChatFilterAction, Race, SlotSettings};
+use crate::matchmaking::messages::{MatchAccept, PlayerDisconnect, QueueEnter, QueueLeave};
 use crate::node::messages::ListNode;
 use crate::player::state::conn::{Connect, Disconnect};
 use crate::player::state::ping::{GetPlayersPingSnapshot, UpdatePing};
+use crate::player::PlayerBanType;
 use flo_net::ping::{PingMsg, PingStream};
 use flo_types::ping::PingStats;
 use futures::{StreamExt, TryStreamExt};
@@ -36,7 +40,12 @@ pub async fn serve(state: ControllerStateRef) -> Result<()> {
     .exec(|conn| crate::game::db::reset_instance_state(conn))
     .await?;
 
-  let mut listener = FloListener::bind_v4(flo_constants::CONTROLLER_SOCKET_PORT).await?;
+  let settings = crate::config::settings();
+  let mut listener = FloListener::bind(SocketAddrV4::new(
+    settings.bind_address,
+    settings.socket_port,
+  ))
+  .await?;
   tracing::info!("listening on port {}", listener.port());
 
   while let Some(res) = listener
@@ -59,7 +68,7 @@ pub async fn serve(state: ControllerStateRef) -> Result<()> {
     tokio::spawn(async move {
       tracing::debug!("connected: {}", stream.peer_addr()?);
 
-      let accepted = match handshake::handle_handshake(&mut stream).await {
+      let accepted = match handshake::handle_handshake(&state, &mut stream).await {
         Ok(accepted) => accepted,
         Err(e) => {
           tracing::debug!("dropping: handshake error: {}", e);
@@ -70,11 +79,35 @@ pub async fn serve(state: ControllerStateRef) -> Result<()> {
       let player_id = accepted.player_id;
       tracing::debug!("accepted: player_id = {}", player_id);
 
-      if accepted.client_version < flo_constants::MIN_FLO_VERSION {
+      // validated on startup and reload
+      let min_client_version = crate::config::settings()
+        .min_client_version()
+        .unwrap_or(flo_constants::MIN_FLO_VERSION);
+      if accepted.client_version < min_client_version {
         stream
           .send(proto::flo_connect::PacketClientConnectReject {
             lobby_version: Some(From::from(crate::version::FLO_LOBBY_VERSION)),
             reason: proto::flo_connect::ClientConnectRejectReason::ClientVersionTooOld.into(),
+            ..Default::default()
+          })
+          .await?;
+        stream.shutdown().await?;
+        return Ok(());
+      }
+
+      let ip = stream.peer_addr()?.ip();
+      let ip_ban = state
+        .db
+        .exec(move |conn| crate::player::db::get_active_ip_ban(conn, player_id, ip))
+        .await?;
+      if let Some(ban) = ip_ban {
+        tracing::debug!(player_id, "rejected: ip banned: {}", ip);
+        stream
+          .send(proto::flo_connect::PacketClientConnectReject {
+            lobby_version: Some(From::from(crate::version::FLO_LOBBY_VERSION)),
+            reason: proto::flo_connect::ClientConnectRejectReason::Banned.into(),
+            message: ban.to_string(),
+            ban_expires_at: ban.ban_expires_at.map(|t| t.timestamp()),
           })
           .await?;
         stream.shutdown().await?;
@@ -86,6 +119,10 @@ pub async fn serve(state: ControllerStateRef) -> Result<()> {
       }
 
       state.players.send(Disconnect { player_id }).await?;
+      state
+        .matchmaking
+        .notify(PlayerDisconnect { player_id })
+        .await?;
       tracing::debug!("exiting: player_id = {}", player_id);
       Ok::<_, crate::error::Error>(())
     });
@@ -182,6 +219,22 @@ async fn handle_stream(
             packet: proto::flo_connect::PacketPlayerMuteRemoveRequest => {
               handle_player_mute_list_update_request(state.clone(), player_id, packet.into()).await?;
             }
+            packet: proto::flo_connect::PacketMatchmakingQueueEnterRequest => {
+              handle_matchmaking_queue_enter_request(state.clone(), player_id, packet).await?;
+            }
+            _packet: proto::flo_connect::PacketMatchmakingQueueLeaveRequest => {
+              state.matchmaking.notify(QueueLeave { player_id }).await?;
+            }
+            packet: proto::flo_connect::PacketGameChatRequest => {
+              handle_game_chat_request(state.clone(), player_id, packet).await?;
+            }
+            packet: proto::flo_connect::PacketMatchmakingMatchAcceptRequest => {
+              state.matchmaking.notify(MatchAccept {
+                player_id,
+                match_id: packet.match_id,
+                accept: packet.accept,
+              }).await?;
+            }
           }
         }
       }
@@ -443,6 +496,132 @@ async fn handle_game_start_player_client_info_request(
   Ok(())
 }
 
+async fn handle_matchmaking_queue_enter_request(
+  state: ControllerStateRef,
+  player_id: i32,
+  packet: proto::flo_connect::PacketMatchmakingQueueEnterRequest,
+) -> Result<()> {
+  state
+    .matchmaking
+    .notify(QueueEnter {
+      player_id,
+      queue_id: packet.queue_id,
+      race: Race::unpack_enum(packet.race()),
+      map_ids: packet.map_ids,
+    })
+    .await?;
+  Ok(())
+}
+
+async fn handle_game_chat_request(
+  state: ControllerStateRef,
+  player_id: i32,
+  packet: proto::flo_connect::PacketGameChatRequest,
+) -> Result<()> {
+  let game_id = packet.game_id;
+  let message = match chat::normalize_message(&packet.message) {
+    Some(message) => message,
+    None => return Ok(()),
+  };
+
+  let rate_limit = crate::config::settings().lobby_chat_rate_limit;
+  if rate_limit > 0 {
+    let res = state.chat_rate_limiter.lock().check(player_id, rate_limit);
+    if let Err(retry_after) = res {
+      tracing::debug!(game_id, player_id, "game chat: rate limited");
+      state
+        .player_packet_sender
+        .send(
+          player_id,
+          proto::flo_connect::PacketGameChatReject {
+            game_id,
+            message: format!(
+              "You are sending messages too fast, try again in {} seconds.",
+              retry_after.as_secs() + 1
+            ),
+            ban_expires_at: None,
+          }
+          .encode_as_frame()?,
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
+  let players = match state.games.send_to(game_id, GetGamePlayers).await {
+    Ok(players) => players,
+    Err(err) => {
+      tracing::debug!(game_id, "game chat: {}", err);
+      return Ok(());
+    }
+  };
+  if !players.contains(&player_id) {
+    tracing::debug!(game_id, "game chat: player not in game");
+    return Ok(());
+  }
+
+  let (ban, mute_list_map) = state
+    .db
+    .exec({
+      let players = players.clone();
+      let message = message.clone();
+      move |conn| -> Result<_> {
+        let ban = crate::player::db::get_active_ban(conn, &[player_id], PlayerBanType::Chat)?;
+        let filter_action = if ban.is_some() {
+          GameChatFilterAction::Muted
+        } else {
+          GameChatFilterAction::None
+        };
+        crate::game::db::insert_lobby_chat_message(
+          conn,
+          game_id,
+          player_id,
+          message,
+          filter_action,
+        )?;
+        let mute_list_map = if ban.is_none() {
+          crate::player::db::get_mute_list_map(conn, &players)?
+        } else {
+          Default::default()
+        };
+        Ok((ban, mute_list_map))
+      }
+    })
+    .await?;
+
+  let targets = match chat::resolve_delivery(player_id, players, ban, &mute_list_map) {
+    chat::LobbyChatDelivery::Broadcast(targets) => targets,
+    chat::LobbyChatDelivery::Reject(ban) => {
+      state
+        .player_packet_sender
+        .send(
+          player_id,
+          proto::flo_connect::PacketGameChatReject {
+            game_id,
+            message: ban.to_string(),
+            ban_expires_at: ban.ban_expires_at.map(|t| t.timestamp()),
+          }
+          .encode_as_frame()?,
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+  state
+    .player_packet_sender
+    .broadcast(
+      targets,
+      proto::flo_connect::PacketGameChat {
+        game_id,
+        player_id,
+        message,
+      }
+      .encode_as_frame()?,
+    )
+    .await?;
+  Ok(())
+}
+
 enum PlayerMuteListUpdate {
   Add(proto::flo_connect::PacketPlayerMuteAddRequest),
   Remove(proto::flo_connect::PacketPlayerMuteRemoveRequest),
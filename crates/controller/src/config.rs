@@ -1,13 +1,19 @@
+use crate::db::{DbConn, ExecutorRef};
 use arc_swap::ArcSwap;
-use bs_diesel_utils::{DbConn, ExecutorRef};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
-use once_cell::sync::Lazy;
+use flo_config::ControllerConfig;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde_json::Value;
 use std::collections::BTreeMap;
-use std::env;
 use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use tonic::codegen::{http, Service as TowerService};
+use tonic::transport::NamedService;
 use tonic::{metadata::MetadataValue, service::Interceptor, Request, Status};
 
+use crate::api_client::{ApiScope, ApiScopes, RateLimiter};
 use crate::error::*;
 
 use crate::player::PlayerSource;
@@ -15,8 +21,46 @@ use crate::schema::{api_client, player};
 use crate::state::{Data, Reload};
 use flo_state::{async_trait, Actor, Context, Handler, Message, RegistryRef, Service};
 
-pub static JWT_SECRET_BASE64: Lazy<String> =
-  Lazy::new(|| env::var("JWT_SECRET_BASE64").expect("env `JWT_SECRET_BASE64`"));
+pub static JWT_SECRET_BASE64: Lazy<String> = Lazy::new(|| settings().jwt_secret_base64.clone());
+
+static SETTINGS: OnceCell<ArcSwap<ControllerConfig>> = OnceCell::new();
+
+/// Loads and validates the service config from `flo-controller.toml` and environment variables,
+/// called on startup so errors are reported before serving.
+pub fn init_settings() -> Result<()> {
+  let config = Arc::new(load_settings()?);
+  SETTINGS
+    .get_or_init(|| ArcSwap::new(config.clone()))
+    .store(config);
+  Ok(())
+}
+
+pub fn settings() -> Arc<ControllerConfig> {
+  SETTINGS
+    .get_or_init(|| {
+      ArcSwap::from_pointee(ControllerConfig::load().expect("load controller config"))
+    })
+    .load_full()
+}
+
+/// Reloads the config file, only `min_client_version` is applied.
+fn reload_settings() -> Result<()> {
+  let new = load_settings()?;
+  let (config, ignored) = settings().reload(&new);
+  if !ignored.is_empty() {
+    tracing::warn!("restart required to apply: {}", ignored.join(", "));
+  }
+  SETTINGS
+    .get_or_init(|| ArcSwap::from_pointee(config.clone()))
+    .store(Arc::new(config));
+  Ok(())
+}
+
+fn load_settings() -> Result<ControllerConfig> {
+  let config = ControllerConfig::load()?;
+  config.validate()?;
+  Ok(config)
+}
 
 #[derive(Debug, Queryable)]
 pub struct ApiClient {
@@ -24,12 +68,25 @@ pub struct ApiClient {
   _name: String,
   secret_key: String,
   _created_at: DateTime<Utc>,
+  scopes: Value,
+  rate_limit: Option<i32>,
+  player_id: i32,
+}
+
+/// Authenticated API client, keyed by secret key
+#[derive(Debug)]
+pub struct ApiClientAuth {
+  id: i32,
   player_id: i32,
+  scopes: ApiScopes,
+  /// Requests per minute
+  rate_limit: Option<u32>,
 }
 
 pub struct ConfigStorage {
   db: ExecutorRef,
-  api_client_map: Arc<ArcSwap<BTreeMap<Vec<u8>, ApiClient>>>,
+  api_client_map: Arc<ArcSwap<BTreeMap<Vec<u8>, ApiClientAuth>>>,
+  rate_limiter: Arc<Mutex<RateLimiter>>,
 }
 
 impl Actor for ConfigStorage {}
@@ -45,6 +102,7 @@ impl Service<Data> for ConfigStorage {
     let storage = ConfigStorage {
       db,
       api_client_map: Arc::new(ArcSwap::new(Arc::new(map))),
+      rate_limiter: Arc::new(Mutex::new(RateLimiter::default())),
     };
 
     Ok(storage)
@@ -54,6 +112,7 @@ impl Service<Data> for ConfigStorage {
 #[async_trait]
 impl Handler<Reload> for ConfigStorage {
   async fn handle(&mut self, _: &mut Context<Self>, _: Reload) -> <Reload as Message>::Result {
+    reload_settings()?;
     let map = Self::load_map(&self.db).await?;
     self.api_client_map.swap(Arc::new(map));
     Ok(())
@@ -73,7 +132,9 @@ impl Handler<GetInterceptor> for ConfigStorage {
     _: GetInterceptor,
   ) -> <GetInterceptor as Message>::Result {
     FloGrpcInterceptor {
+      db: self.db.clone(),
       api_client_map: self.api_client_map.clone(),
+      rate_limiter: self.rate_limiter.clone(),
     }
   }
 }
@@ -81,10 +142,15 @@ impl Handler<GetInterceptor> for ConfigStorage {
 pub const REQUEST_META_SECRET: &str = "x-flo-secret";
 pub const REQUEST_META_API_CLIENT_ID: &str = "x-flo-api-client-id-bin";
 pub const REQUEST_META_API_PLAYER_ID: &str = "x-flo-api-player-id-bin";
+pub const REQUEST_META_METHOD: &str = "x-flo-method";
 
+/// Authenticates API clients by secret key, enforces the per-client rate limits
+/// and the scope of the called method, and attaches the client's id to the request
 #[derive(Clone)]
 pub struct FloGrpcInterceptor {
-  api_client_map: Arc<ArcSwap<BTreeMap<Vec<u8>, ApiClient>>>,
+  db: ExecutorRef,
+  api_client_map: Arc<ArcSwap<BTreeMap<Vec<u8>, ApiClientAuth>>>,
+  rate_limiter: Arc<Mutex<RateLimiter>>,
 }
 
 impl Interceptor for FloGrpcInterceptor {
@@ -93,6 +159,23 @@ impl Interceptor for FloGrpcInterceptor {
     match secret {
       Some(secret) => match self.api_client_map.load().get(secret.as_bytes()) {
         Some(client) => {
+          if let Some(limit) = client.rate_limit {
+            if let Err(retry_after) = self.rate_limiter.lock().check(client.id, limit) {
+              return Err(Status::resource_exhausted(format!(
+                "rate limit exceeded: {} requests per minute, retry after {}ms",
+                limit,
+                retry_after.as_millis()
+              )));
+            }
+          }
+          let method = req
+            .metadata()
+            .get(REQUEST_META_METHOD)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or_default()
+            .to_string();
+          self.check_scope(client, &method)?;
           let meta = req.metadata_mut();
           meta.insert_bin(
             REQUEST_META_API_CLIENT_ID,
@@ -113,46 +196,119 @@ impl Interceptor for FloGrpcInterceptor {
   }
 }
 
+impl FloGrpcInterceptor {
+  fn check_scope(&self, client: &ApiClientAuth, method: &str) -> Result<(), Status> {
+    let status = match ApiScope::for_method(method) {
+      Some(scope) if client.scopes.contains(scope) => return Ok(()),
+      Some(scope) => {
+        Status::permission_denied(format!("API client scope `{}` is required", scope.as_str()))
+      }
+      None => Status::permission_denied(format!("unknown method `{}`", method)),
+    };
+    let db = self.db.clone();
+    let api_client_id = client.id;
+    let method = method.to_string();
+    let audit_status = status.clone();
+    tokio::spawn(async move {
+      crate::api_client::db::audit(
+        &db,
+        api_client_id,
+        &method,
+        serde_json::json!({}),
+        Some(&audit_status),
+      )
+      .await
+    });
+    Err(status)
+  }
+}
+
+/// Copies the gRPC method path into the `x-flo-method` metadata
+/// so `FloGrpcInterceptor` can check the scope of the called method
+#[derive(Clone)]
+pub struct MethodPathService<S>(pub S);
+
+impl<S, B> TowerService<http::Request<B>> for MethodPathService<S>
+where
+  S: TowerService<http::Request<B>>,
+{
+  type Response = S::Response;
+  type Error = S::Error;
+  type Future = S::Future;
+
+  fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+    self.0.poll_ready(cx)
+  }
+
+  fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+    match http::HeaderValue::from_str(req.uri().path()) {
+      Ok(value) => {
+        req.headers_mut().insert(REQUEST_META_METHOD, value);
+      }
+      Err(_) => {
+        req.headers_mut().remove(REQUEST_META_METHOD);
+      }
+    }
+    self.0.call(req)
+  }
+}
+
+impl<S: NamedService> NamedService for MethodPathService<S> {
+  const NAME: &'static str = S::NAME;
+}
+
 impl ConfigStorage {
-  async fn load_map(db: &ExecutorRef) -> Result<BTreeMap<Vec<u8>, ApiClient>> {
+  async fn load_map(db: &ExecutorRef) -> Result<BTreeMap<Vec<u8>, ApiClientAuth>> {
     let mut map = BTreeMap::new();
 
     let (api_player_map, items) = db
       .exec(|conn| -> Result<_> {
         create_api_players(conn)?;
 
-        let api_player_map: BTreeMap<i32, i32> = player::table
-          .select((player::api_client_id, player::id))
-          .filter(
-            player::source
-              .eq(PlayerSource::Api)
-              .and(player::source_id.eq("")),
-          )
-          .load::<(i32, i32)>(conn)?
-          .into_iter()
-          .collect();
-
-        let items = api_client::table
-          .select((
-            api_client::id,
-            api_client::name,
-            api_client::secret_key,
-            api_client::created_at,
-            diesel::dsl::sql::<diesel::sql_types::Integer>("0"),
-          ))
-          .load::<ApiClient>(conn)?;
-        Ok((api_player_map, items))
+        db_dispatch!(conn, {
+          let api_player_map: BTreeMap<i32, i32> = player::table
+            .select((player::api_client_id, player::id))
+            .filter(
+              player::source
+                .eq(PlayerSource::Api)
+                .and(player::source_id.eq("")),
+            )
+            .load::<(i32, i32)>(conn)?
+            .into_iter()
+            .collect();
+
+          let items = api_client::table
+            .select((
+              api_client::id,
+              api_client::name,
+              api_client::secret_key,
+              api_client::created_at,
+              api_client::scopes,
+              api_client::rate_limit,
+              diesel::dsl::sql::<diesel::sql_types::Integer>("0"),
+            ))
+            .load::<ApiClient>(conn)?;
+          Ok((api_player_map, items))
+        })
       })
       .await?;
 
-    for mut item in items {
-      item.player_id = if let Some(player_id) = api_player_map.get(&item.id).cloned() {
+    for item in items {
+      let player_id = if let Some(player_id) = api_player_map.get(&item.id).cloned() {
         player_id
       } else {
         tracing::error!(id = item.id, "api player not found");
         continue;
       };
-      map.insert(item.secret_key.as_bytes().to_vec(), item);
+      map.insert(
+        item.secret_key.as_bytes().to_vec(),
+        ApiClientAuth {
+          id: item.id,
+          player_id,
+          scopes: ApiScopes::from_value(&item.scopes),
+          rate_limit: item.rate_limit.filter(|v| *v > 0).map(|v| v as u32),
+        },
+      );
     }
 
     Ok(map)
@@ -177,7 +333,7 @@ fn create_api_players(conn: &DbConn) -> Result<()> {
     left join player p on p.source = 2 and p.api_client_id = c.id and p.source_id = ''
     where p.id is null;
   "#;
-  diesel::sql_query(sql).execute(conn)?;
+  db_dispatch!(conn, diesel::sql_query(sql).execute(conn))?;
   Ok(())
 }
 
@@ -1 +0,0 @@
-pub use bs_diesel_utils::{lock::transaction_with_advisory_lock, DbConn, Executor, ExecutorRef};
@@ -0,0 +1,118 @@
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PoolError};
+use diesel::{PgConnection, SqliteConnection};
+use std::sync::Arc;
+use thiserror::Error;
+
+use super::DbConn;
+
+const SQLITE_BUSY_TIMEOUT_MS: u32 = 5000;
+
+#[derive(Error, Debug)]
+pub enum DbError {
+  #[error("{0}")]
+  Query(#[from] diesel::result::Error),
+  #[error("connection pool: {0}")]
+  Pool(#[from] PoolError),
+  #[error("blocking task: {0}")]
+  Join(#[from] tokio::task::JoinError),
+}
+
+#[derive(Error, Debug)]
+pub enum ExecutorError<E> {
+  #[error("{0}")]
+  Task(E),
+  #[error("executor: {0}")]
+  Executor(DbError),
+}
+
+#[derive(Clone)]
+enum ConnPool {
+  Pg(Pool<ConnectionManager<PgConnection>>),
+  Sqlite(Pool<ConnectionManager<SqliteConnection>>),
+}
+
+/// Runs blocking diesel queries on a connection pool.
+pub struct Executor {
+  pool: ConnPool,
+}
+
+pub type ExecutorRef = Arc<Executor>;
+
+impl Executor {
+  /// `postgres://` and `postgresql://` urls use PostgreSQL,
+  /// anything else is a SQLite file path, optionally prefixed by `sqlite://`.
+  pub fn connect(url: &str) -> Result<Self, DbError> {
+    let pool = if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+      ConnPool::Pg(Pool::builder().build(ConnectionManager::new(url))?)
+    } else {
+      let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+        .unwrap_or(url);
+      let in_memory = path == ":memory:";
+      let builder = Pool::builder().connection_customizer(Box::new(SqliteCustomizer { in_memory }));
+      // every connection opens a separate in-memory database
+      let builder = if in_memory {
+        builder.max_size(1).idle_timeout(None).max_lifetime(None)
+      } else {
+        builder
+      };
+      ConnPool::Sqlite(builder.build(ConnectionManager::new(path))?)
+    };
+    Ok(Self { pool })
+  }
+
+  pub fn into_ref(self) -> ExecutorRef {
+    Arc::new(self)
+  }
+
+  pub fn is_sqlite(&self) -> bool {
+    matches!(self.pool, ConnPool::Sqlite(_))
+  }
+
+  pub async fn exec<F, T, E>(&self, f: F) -> Result<T, ExecutorError<E>>
+  where
+    F: FnOnce(&DbConn) -> Result<T, E> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+  {
+    let pool = self.pool.clone();
+    tokio::task::spawn_blocking(move || match pool {
+      ConnPool::Pg(pool) => {
+        let conn = pool
+          .get()
+          .map_err(|err| ExecutorError::Executor(err.into()))?;
+        f(&*conn).map_err(ExecutorError::Task)
+      }
+      ConnPool::Sqlite(pool) => {
+        let conn = pool
+          .get()
+          .map_err(|err| ExecutorError::Executor(err.into()))?;
+        f(&*conn).map_err(ExecutorError::Task)
+      }
+    })
+    .await
+    .map_err(|err| ExecutorError::Executor(err.into()))?
+  }
+}
+
+#[derive(Debug)]
+struct SqliteCustomizer {
+  in_memory: bool,
+}
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for SqliteCustomizer {
+  fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+    let mut sql = format!(
+      "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {};",
+      SQLITE_BUSY_TIMEOUT_MS
+    );
+    if !self.in_memory {
+      sql.push_str("PRAGMA journal_mode = WAL;");
+    }
+    conn
+      .batch_execute(&sql)
+      .map_err(diesel::r2d2::Error::QueryError)
+  }
+}
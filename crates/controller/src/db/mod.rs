@@ -0,0 +1,83 @@
+//! Database access, PostgreSQL or SQLite is selected by the scheme of `DATABASE_URL`.
+//!
+//! Query functions take `&DbConn` and use `db_dispatch!` to run the same diesel query
+//! against the concrete connection type. Backend specific statements (upsert, `RETURNING`)
+//! match on `DbConnection::backend` directly.
+
+mod executor;
+pub mod sql_types;
+
+pub use executor::{DbError, Executor, ExecutorError, ExecutorRef};
+
+use diesel::{Connection, PgConnection, SqliteConnection};
+
+/// A connection of any supported backend.
+pub type DbConn = dyn DbConnection;
+
+pub trait DbConnection {
+  fn backend(&self) -> Backend<'_>;
+}
+
+pub enum Backend<'a> {
+  Pg(&'a PgConnection),
+  Sqlite(&'a SqliteConnection),
+}
+
+impl DbConnection for PgConnection {
+  fn backend(&self) -> Backend<'_> {
+    Backend::Pg(self)
+  }
+}
+
+impl DbConnection for SqliteConnection {
+  fn backend(&self) -> Backend<'_> {
+    Backend::Sqlite(self)
+  }
+}
+
+impl DbConn {
+  pub fn transaction<T, E, F>(&self, f: F) -> Result<T, E>
+  where
+    F: FnOnce() -> Result<T, E>,
+    E: From<diesel::result::Error>,
+  {
+    match self.backend() {
+      Backend::Pg(conn) => conn.transaction(f),
+      Backend::Sqlite(conn) => conn.transaction(f),
+    }
+  }
+}
+
+sql_function! {
+  /// `ilike` is PostgreSQL only, `lower(column).like(pattern.to_lowercase())` works everywhere.
+  fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text;
+}
+
+no_arg_sql_function!(
+  last_insert_rowid,
+  diesel::sql_types::Integer,
+  "Returns the rowid of the last inserted row, SQLite only"
+);
+
+/// Returns the id of the row inserted by the last statement on this connection.
+pub fn sqlite_last_insert_id(conn: &SqliteConnection) -> Result<i32, diesel::result::Error> {
+  use diesel::RunQueryDsl;
+  diesel::select(last_insert_rowid).get_result(conn)
+}
+
+/// Runs `f` on a new in-memory SQLite database with all migrations applied.
+#[cfg(test)]
+pub(crate) async fn exec_test<F, T>(f: F) -> T
+where
+  F: FnOnce(&DbConn) -> crate::error::Result<T> + Send + 'static,
+  T: Send + 'static,
+{
+  Executor::connect(":memory:")
+    .unwrap()
+    .exec(move |conn| {
+      crate::migration::run(conn)?;
+      f(conn)
+    })
+    .await
+    .unwrap()
+}
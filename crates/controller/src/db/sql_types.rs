@@ -0,0 +1,150 @@
+//! SQL types shared by both backends.
+//!
+//! PostgreSQL uses the native `timestamptz` and `jsonb` types.
+//! SQLite stores both as text, timestamps in UTC as `YYYY-MM-DD HH:MM:SS.SSS`,
+//! the format of `strftime('%Y-%m-%d %H:%M:%f')` used by the column defaults and triggers,
+//! so values written by either side compare correctly as strings.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::expression::bound::Bound;
+use diesel::expression::AsExpression;
+use diesel::pg::Pg;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sqlite::Sqlite;
+use serde_json::Value;
+use std::io::Write;
+
+// the other column types, so the schema imports everything from here
+pub use diesel::sql_types::{Bool, Bytea, Int4, Int8, Nullable, Text};
+
+const SQLITE_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[postgres(oid = "1184", array_oid = "1185")]
+#[sqlite_type = "Text"]
+pub struct Timestamptz;
+
+#[derive(Debug, Clone, Copy, Default, QueryId, SqlType)]
+#[postgres(oid = "3802", array_oid = "3807")]
+#[sqlite_type = "Text"]
+pub struct Jsonb;
+
+macro_rules! impl_as_expression {
+  ($ty:ty, $sql_type:ty) => {
+    impl AsExpression<$sql_type> for $ty {
+      type Expression = Bound<$sql_type, Self>;
+
+      fn as_expression(self) -> Self::Expression {
+        Bound::new(self)
+      }
+    }
+
+    impl<'a> AsExpression<$sql_type> for &'a $ty {
+      type Expression = Bound<$sql_type, Self>;
+
+      fn as_expression(self) -> Self::Expression {
+        Bound::new(self)
+      }
+    }
+
+    impl AsExpression<Nullable<$sql_type>> for $ty {
+      type Expression = Bound<Nullable<$sql_type>, Self>;
+
+      fn as_expression(self) -> Self::Expression {
+        Bound::new(self)
+      }
+    }
+
+    impl<'a> AsExpression<Nullable<$sql_type>> for &'a $ty {
+      type Expression = Bound<Nullable<$sql_type>, Self>;
+
+      fn as_expression(self) -> Self::Expression {
+        Bound::new(self)
+      }
+    }
+  };
+}
+
+impl_as_expression!(DateTime<Utc>, Timestamptz);
+impl_as_expression!(Value, Jsonb);
+
+impl FromSql<Timestamptz, Pg> for DateTime<Utc> {
+  fn from_sql(bytes: Option<&<Pg as Backend>::RawValue>) -> deserialize::Result<Self> {
+    <Self as FromSql<diesel::sql_types::Timestamptz, Pg>>::from_sql(bytes)
+  }
+}
+
+impl ToSql<Timestamptz, Pg> for DateTime<Utc> {
+  fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+    <Self as ToSql<diesel::sql_types::Timestamptz, Pg>>::to_sql(self, out)
+  }
+}
+
+impl FromSql<Timestamptz, Sqlite> for DateTime<Utc> {
+  fn from_sql(bytes: Option<&<Sqlite as Backend>::RawValue>) -> deserialize::Result<Self> {
+    let text = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
+    parse_sqlite_timestamp(&text).ok_or_else(|| format!("invalid timestamp: {}", text).into())
+  }
+}
+
+impl ToSql<Timestamptz, Sqlite> for DateTime<Utc> {
+  fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+    let text = self.naive_utc().format(SQLITE_TIMESTAMP_FORMAT).to_string();
+    <String as ToSql<Text, Sqlite>>::to_sql(&text, out)
+  }
+}
+
+impl FromSql<Jsonb, Pg> for Value {
+  fn from_sql(bytes: Option<&<Pg as Backend>::RawValue>) -> deserialize::Result<Self> {
+    <Self as FromSql<diesel::sql_types::Jsonb, Pg>>::from_sql(bytes)
+  }
+}
+
+impl ToSql<Jsonb, Pg> for Value {
+  fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+    <Self as ToSql<diesel::sql_types::Jsonb, Pg>>::to_sql(self, out)
+  }
+}
+
+impl FromSql<Jsonb, Sqlite> for Value {
+  fn from_sql(bytes: Option<&<Sqlite as Backend>::RawValue>) -> deserialize::Result<Self> {
+    let text = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
+    serde_json::from_str(&text).map_err(Into::into)
+  }
+}
+
+impl ToSql<Jsonb, Sqlite> for Value {
+  fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+    let text = serde_json::to_string(self)?;
+    <String as ToSql<Text, Sqlite>>::to_sql(&text, out)
+  }
+}
+
+// also accepts rows written without fractional seconds or as RFC 3339
+fn parse_sqlite_timestamp(text: &str) -> Option<DateTime<Utc>> {
+  NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+    .ok()
+    .map(|t| DateTime::from_utc(t, Utc))
+    .or_else(|| {
+      DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+    })
+}
+
+#[test]
+fn test_parse_sqlite_timestamp() {
+  use chrono::TimeZone;
+  let t = Utc.ymd(2022, 12, 1).and_hms_milli(8, 30, 5, 120);
+  let text = t.naive_utc().format(SQLITE_TIMESTAMP_FORMAT).to_string();
+  // same as `strftime('%Y-%m-%d %H:%M:%f')`
+  assert_eq!(text, "2022-12-01 08:30:05.120");
+  assert_eq!(parse_sqlite_timestamp(&text), Some(t));
+  assert_eq!(
+    parse_sqlite_timestamp("2022-12-01 08:30:05"),
+    Some(Utc.ymd(2022, 12, 1).and_hms(8, 30, 5))
+  );
+  assert_eq!(parse_sqlite_timestamp("invalid"), None);
+}
@@ -1,4 +1,4 @@
-use bs_diesel_utils::executor::ExecutorError;
+use crate::db::ExecutorError;
 use flo_state::RegistryError;
 use thiserror::Error;
 use tonic::Status;
@@ -52,6 +52,12 @@ pub enum Error {
   GameLeaveRejected(flo_net::proto::flo_node::UpdateSlotClientStatusRejectReason),
   #[error("Game node not selected")]
   GameNodeNotSelected,
+  #[error("Game is not running")]
+  GameNotRunning,
+  #[error("Game control request rejected: {0:?}: {1}")]
+  GameControlRejected(flo_net::proto::flo_node::GameControlRejectReason, String),
+  #[error("Game is not hosted on this node")]
+  GameNodeMismatch,
   #[error("Slot update denied")]
   GameSlotUpdateDenied,
   #[error("Game already started")]
@@ -60,6 +66,28 @@ pub enum Error {
   GameNotStarting,
   #[error("This map has no player slot")]
   MapHasNoPlayer,
+  #[error("Map not found")]
+  MapNotFound,
+  #[error("Either a map or a catalog map id is required")]
+  MapRequired,
+  #[error("Map file too large")]
+  MapFileTooLarge,
+  #[error("Invalid map file: {0}")]
+  MapFileInvalid(String),
+  #[error("Matchmaking queue not found")]
+  MatchmakingQueueNotFound,
+  #[error("Invalid matchmaking queue: {0}")]
+  MatchmakingQueueInvalid(String),
+  #[error("Selected maps are not in the map pool of the queue")]
+  MatchmakingMapPoolInvalid,
+  #[error("A match is waiting for your response")]
+  MatchmakingMatchPending,
+  #[error("Unable to start the match game: {0}")]
+  MatchmakingGameStartRejected(String),
+  #[error("Webhook not found")]
+  WebhookNotFound,
+  #[error("Invalid webhook: {0}")]
+  WebhookInvalid(String),
   #[error("Player not in game")]
   PlayerNotInGame,
   #[error("Player already in game")]
@@ -84,14 +112,24 @@ pub enum Error {
   PlayerColorConflict,
   #[error("Invalid player team value")]
   PlayerTeamInvalid,
+  #[error("{0}")]
+  PlayerBanned(crate::player::BanInfo),
+  #[error("Invalid IP address or range: {0}")]
+  IpRangeInvalid(String),
+  #[error("IP ban not found")]
+  IpBanNotFound,
   #[error("Player not belongs to the current API client")]
   PlayerOwnerCheckFailed,
+  #[error("Game not belongs to the current API client")]
+  GameOwnerCheckFailed,
   #[error("Operation timeout: {0}")]
   Timeout(anyhow::Error),
   #[error("net: {0}")]
   Net(#[from] flo_net::error::Error),
   #[error("db error: {0}")]
-  Db(#[from] bs_diesel_utils::result::DbError),
+  Db(#[from] crate::db::DbError),
+  #[error("config: {0}")]
+  Config(#[from] flo_config::error::Error),
   #[error("db migration: {0}")]
   DbMigration(#[from] diesel_migrations::RunMigrationsError),
   #[error("json: {0}")]
@@ -124,9 +162,23 @@ impl From<Error> for Status {
       e @ Error::GameNotFound
       | e @ Error::PlayerNotFound
       | e @ Error::MapHasNoPlayer
+      | e @ Error::MapNotFound
+      | e @ Error::MapRequired
+      | e @ Error::MapFileTooLarge
+      | e @ Error::MapFileInvalid(_)
+      | e @ Error::MatchmakingQueueNotFound
+      | e @ Error::MatchmakingQueueInvalid(_)
+      | e @ Error::WebhookNotFound
+      | e @ Error::WebhookInvalid(_)
       | e @ Error::GameFull
       | e @ Error::GameNotCancellable
+      | e @ Error::GameNodeNotSelected
+      | e @ Error::GameNotRunning
+      | e @ Error::GameControlRejected(..)
+      | e @ Error::IpRangeInvalid(_)
+      | e @ Error::IpBanNotFound
       | e @ Error::JoinTokenExpired => Status::invalid_argument(e.to_string()),
+      e @ Error::PlayerBanned(_) => Status::permission_denied(e.to_string()),
       e @ Error::PlayerTokenExpired => Status::unauthenticated(e.to_string()),
       Error::JsonWebToken(e) => Status::unauthenticated(e.to_string()),
       e => Status::internal(e.to_string()),
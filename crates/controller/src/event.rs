@@ -0,0 +1,254 @@
+//! Streams game and player session events to API clients.
+//!
+//! Events are the frames the controller broadcasts to connected players, published with the
+//! id of the game they belong to. Each subscriber only receives the events of the games created
+//! by players of its API client.
+
+use crate::db::ExecutorRef;
+use crate::error::*;
+use crate::player::state::sender::PlayerFrames;
+use crate::state::Data;
+use flo_net::packet::{Frame, FramePayload};
+use flo_state::*;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::mpsc;
+
+/// Streams that fall behind this many events are closed
+const SUBSCRIBER_BUF_SIZE: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct ApiEvent {
+  pub game_id: i32,
+  /// Set if the event only concerns a single player, e.g. a player session update
+  pub player_id: Option<i32>,
+  pub frame: Frame,
+}
+
+impl ApiEvent {
+  pub fn packet_type_id(&self) -> u8 {
+    u8::from(self.frame.type_id)
+  }
+
+  /// Returns `None` for W3GS frames, which are only sent by nodes
+  pub fn payload(&self) -> Option<&[u8]> {
+    match self.frame.payload {
+      FramePayload::Bytes(ref bytes) => Some(bytes.as_ref()),
+      FramePayload::W3GS { .. } => None,
+    }
+  }
+}
+
+pub struct EventHub {
+  db: ExecutorRef,
+  next_subscriber_id: u64,
+  subscribers: BTreeMap<u64, Subscriber>,
+  // game_id => api_client_id
+  game_owners: HashMap<i32, i32>,
+}
+
+struct Subscriber {
+  api_client_id: i32,
+  tx: mpsc::Sender<ApiEvent>,
+}
+
+impl Actor for EventHub {}
+
+#[async_trait]
+impl Service<Data> for EventHub {
+  type Error = Error;
+
+  async fn create(registry: &mut RegistryRef<Data>) -> Result<Self, Self::Error> {
+    Ok(EventHub {
+      db: registry.data().db.clone(),
+      next_subscriber_id: 0,
+      subscribers: BTreeMap::new(),
+      game_owners: HashMap::new(),
+    })
+  }
+}
+
+impl EventHub {
+  async fn get_game_owner(&mut self, game_id: i32) -> Option<i32> {
+    if let Some(api_client_id) = self.game_owners.get(&game_id) {
+      return Some(*api_client_id);
+    }
+
+    let res = self
+      .db
+      .exec(move |conn| crate::game::db::get_api_client_id(conn, game_id))
+      .await;
+    match res {
+      Ok(api_client_id) => {
+        self.game_owners.insert(game_id, api_client_id);
+        Some(api_client_id)
+      }
+      Err(err) => {
+        tracing::error!(game_id, "get game api client id: {}", err);
+        None
+      }
+    }
+  }
+}
+
+pub struct Subscribe {
+  pub api_client_id: i32,
+}
+
+impl Message for Subscribe {
+  type Result = mpsc::Receiver<ApiEvent>;
+}
+
+#[async_trait]
+impl Handler<Subscribe> for EventHub {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    Subscribe { api_client_id }: Subscribe,
+  ) -> mpsc::Receiver<ApiEvent> {
+    let (tx, rx) = mpsc::channel(SUBSCRIBER_BUF_SIZE);
+    let id = self.next_subscriber_id;
+    self.next_subscriber_id += 1;
+    self
+      .subscribers
+      .insert(id, Subscriber { api_client_id, tx });
+    tracing::debug!(api_client_id, subscriber_id = id, "subscribed");
+    rx
+  }
+}
+
+struct Publish {
+  game_id: i32,
+  player_id: Option<i32>,
+  frames: PlayerFrames,
+}
+
+impl Message for Publish {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<Publish> for EventHub {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    Publish {
+      game_id,
+      player_id,
+      frames,
+    }: Publish,
+  ) {
+    self.subscribers.retain(|_, s| !s.tx.is_closed());
+    if self.subscribers.is_empty() {
+      return;
+    }
+
+    let api_client_id = match self.get_game_owner(game_id).await {
+      Some(id) => id,
+      None => return,
+    };
+
+    let mut remove_list = vec![];
+    for (id, subscriber) in self.subscribers.iter() {
+      if subscriber.api_client_id != api_client_id {
+        continue;
+      }
+      for frame in frames.clone() {
+        let event = ApiEvent {
+          game_id,
+          player_id,
+          frame,
+        };
+        if subscriber.tx.try_send(event).is_err() {
+          tracing::debug!(
+            api_client_id,
+            subscriber_id = *id,
+            "remove lagging subscriber"
+          );
+          remove_list.push(*id);
+          break;
+        }
+      }
+    }
+    for id in remove_list {
+      self.subscribers.remove(&id);
+    }
+  }
+}
+
+struct ForgetGame {
+  game_id: i32,
+}
+
+impl Message for ForgetGame {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<ForgetGame> for EventHub {
+  async fn handle(&mut self, _: &mut Context<Self>, ForgetGame { game_id }: ForgetGame) {
+    self.game_owners.remove(&game_id);
+  }
+}
+
+/// Cloneable handle to publish events
+#[derive(Clone)]
+pub struct EventSender {
+  addr: Addr<EventHub>,
+}
+
+impl EventSender {
+  pub async fn publish<T>(&self, game_id: i32, frames: T)
+  where
+    T: Into<PlayerFrames>,
+  {
+    self.publish_inner(game_id, None, frames.into()).await
+  }
+
+  pub async fn publish_player<T>(&self, game_id: i32, player_id: i32, frames: T)
+  where
+    T: Into<PlayerFrames>,
+  {
+    self
+      .publish_inner(game_id, Some(player_id), frames.into())
+      .await
+  }
+
+  async fn publish_inner(&self, game_id: i32, player_id: Option<i32>, frames: PlayerFrames) {
+    let msg = Publish {
+      game_id,
+      player_id,
+      frames,
+    };
+    if let Err(err) = self.addr.notify(msg).await {
+      tracing::error!(game_id, "publish event: {}", err);
+    }
+  }
+
+  /// Drops the cached owner of a removed game
+  pub async fn forget_game(&self, game_id: i32) {
+    self.addr.notify(ForgetGame { game_id }).await.ok();
+  }
+}
+
+impl From<Addr<EventHub>> for EventSender {
+  fn from(addr: Addr<EventHub>) -> Self {
+    EventSender { addr }
+  }
+}
+
+#[test]
+fn test_api_event_payload() {
+  use flo_net::packet::{FloPacket, PacketTypeId};
+  use flo_net::proto::flo_connect::PacketGameStarting;
+
+  let event = ApiEvent {
+    game_id: 1,
+    player_id: None,
+    frame: PacketGameStarting { game_id: 1 }.encode_as_frame().unwrap(),
+  };
+  assert_eq!(event.packet_type_id(), u8::from(PacketTypeId::GameStarting));
+  let pkt: PacketGameStarting = Frame::new(PacketTypeId::GameStarting, event.payload().unwrap())
+    .decode()
+    .unwrap();
+  assert_eq!(pkt.game_id, 1);
+}
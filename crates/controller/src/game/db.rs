@@ -1,35 +1,39 @@
 use chrono::{DateTime, Utc};
-use diesel::dsl::sql;
 use diesel::prelude::*;
 use s2_grpc_utils::{S2ProtoEnum, S2ProtoPack, S2ProtoUnpack};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
-use crate::db::DbConn;
+use crate::db::{lower, sqlite_last_insert_id, Backend, DbConn};
 use crate::error::*;
 use crate::game::slots::{UsedSlot, UsedSlotInfo};
 use crate::game::state::GameStatusUpdate;
 use crate::game::{
-  Computer, CreateGameSlot, Game, GameEntry, GameStatus, Race, Slot, SlotClientStatus,
-  SlotSettings, SlotStatus, Slots,
+  Computer, CreateGameSlot, Game, GameChatFilterAction, GameChatLogReport, GameChatMessage,
+  GameChatScope, GameEntry, GamePlayerResult, GameResult, GameResultReport, GameStatus, Race, Slot,
+  SlotClientStatus, SlotSettings, SlotStatus, Slots,
 };
 use crate::map::Map;
 use crate::node::{NodeRef, NodeRefColumns, PlayerToken};
 use crate::player::{PlayerRef, PlayerRefColumns};
-use crate::schema::{game, game_used_slot, node, player};
-use diesel::pg::expression::dsl::{all, any};
+use crate::schema::{
+  game, game_chat_message, game_chat_violation, game_player_result, game_result, game_used_slot,
+  node, player,
+};
 
 pub fn get(conn: &DbConn, id: i32) -> Result<GameRowWithRelated> {
-  let row = game::table
-    .find(id)
-    .left_outer_join(node::table)
-    .left_outer_join(player::table)
-    .select(GameRowWithRelated::columns())
-    .first(conn)
-    .optional()?
-    .ok_or_else(|| Error::GameNotFound)?;
-  Ok(row)
+  db_dispatch!(conn, {
+    let row = game::table
+      .find(id)
+      .left_outer_join(node::table)
+      .left_outer_join(player::table)
+      .select(GameRowWithRelated::columns())
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::GameNotFound)?;
+    Ok(row)
+  })
 }
 
 #[derive(Debug, Deserialize, Default, S2ProtoUnpack)]
@@ -68,164 +72,241 @@ impl Default for GameStatusFilter {
 }
 
 pub fn get_entry(conn: &DbConn, id: i32) -> Result<GameEntry> {
-  let q = game::table
-    .find(id)
-    .left_outer_join(node::table)
-    .left_outer_join(player::table)
-    .select(GameEntry::columns());
-
-  let entry: GameEntry = q
-    .first(conn)
-    .optional()?
-    .ok_or_else(|| Error::GameNotFound)?;
+  db_dispatch!(conn, {
+    let q = game::table
+      .find(id)
+      .left_outer_join(node::table)
+      .left_outer_join(player::table)
+      .select(GameEntry::columns());
+
+    let entry: GameEntry = q
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::GameNotFound)?;
 
-  Ok(entry)
+    Ok(entry)
+  })
 }
 
 pub fn query(conn: &DbConn, params: &QueryGameParams) -> Result<QueryGame> {
-  use game::dsl;
+  db_dispatch!(conn, {
+    use game::dsl;
 
-  let take = std::cmp::min(100, params.take.clone().unwrap_or(30));
+    let take = std::cmp::min(100, params.take.clone().unwrap_or(30));
 
-  let mut q = game::table
-    .left_outer_join(node::table)
-    .left_outer_join(player::table)
-    .select(GameEntry::columns())
-    .order(dsl::id.desc())
-    .limit(take + 1)
-    .into_boxed();
+    let mut q = game::table
+      .left_outer_join(node::table)
+      .left_outer_join(player::table)
+      .select(GameEntry::columns())
+      .order(dsl::id.desc())
+      .limit(take + 1)
+      .into_boxed();
 
-  if let Some(ref keyword) = params.keyword {
-    let like = format!("%{}%", keyword.trim());
-    q = q.filter(dsl::name.ilike(like.clone()).or(dsl::map_name.ilike(like)));
-  }
-
-  match params.status {
-    GameStatusFilter::All => q = q.filter(dsl::status.ne(GameStatus::Ended)),
-    GameStatusFilter::Open => q = q.filter(dsl::status.eq(GameStatus::Preparing)),
-    GameStatusFilter::Live => {
+    if let Some(ref keyword) = params.keyword {
+      let like = format!("%{}%", keyword.trim().to_lowercase());
       q = q.filter(
-        dsl::status
-          .eq(GameStatus::Running)
-          .and(dsl::is_private.eq(false)),
-      )
+        lower(dsl::name)
+          .like(like.clone())
+          .or(lower(dsl::map_name).like(like)),
+      );
     }
-    GameStatusFilter::Ended => q = q.filter(dsl::status.eq(GameStatus::Ended)),
-  }
 
-  if let Some(is_private) = params.is_private.clone() {
-    q = q.filter(dsl::is_private.eq(is_private));
-  } else {
-    q = q.filter(dsl::is_private.eq(false));
-  }
+    match params.status {
+      GameStatusFilter::All => q = q.filter(dsl::status.ne(GameStatus::Ended)),
+      GameStatusFilter::Open => q = q.filter(dsl::status.eq(GameStatus::Preparing)),
+      GameStatusFilter::Live => {
+        q = q.filter(
+          dsl::status
+            .eq(GameStatus::Running)
+            .and(dsl::is_private.eq(false)),
+        )
+      }
+      GameStatusFilter::Ended => q = q.filter(dsl::status.eq(GameStatus::Ended)),
+    }
 
-  if let Some(is_live) = params.is_live.clone() {
-    q = q.filter(dsl::is_live.eq(is_live));
-  }
+    if let Some(is_private) = params.is_private.clone() {
+      q = q.filter(dsl::is_private.eq(is_private));
+    } else {
+      q = q.filter(dsl::is_private.eq(false));
+    }
 
-  if let Some(id) = params.since_id.clone() {
-    q = q.filter(dsl::id.lt(id))
-  }
+    if let Some(is_live) = params.is_live.clone() {
+      q = q.filter(dsl::is_live.eq(is_live));
+    }
 
-  if let Some(player_id) = params.player_id.clone() {
-    let subq = game_used_slot::table
-      .select(game_used_slot::dsl::game_id)
-      .filter(game_used_slot::dsl::player_id.eq(player_id));
-    q = q.filter(dsl::id.eq(any(subq)));
-  }
+    if let Some(id) = params.since_id.clone() {
+      q = q.filter(dsl::id.lt(id))
+    }
 
-  let mut games: Vec<GameEntry> = q.load(conn)?;
+    if let Some(player_id) = params.player_id.clone() {
+      let subq = game_used_slot::table
+        .select(game_used_slot::dsl::game_id)
+        .filter(game_used_slot::dsl::player_id.eq(player_id));
+      q = q.filter(dsl::id.eq_any(subq));
+    }
 
-  let has_more = games.len() > take as usize;
-  if has_more {
-    games.truncate(take as usize);
-  }
+    let mut games: Vec<GameEntry> = q.load(conn)?;
+
+    let has_more = games.len() > take as usize;
+    if has_more {
+      games.truncate(take as usize);
+    }
 
-  Ok(QueryGame { games, has_more })
+    Ok(QueryGame { games, has_more })
+  })
 }
 
 pub fn cancel(conn: &DbConn, game_id: i32, created_by: Option<i32>) -> Result<()> {
-  use game::dsl;
+  db_dispatch!(conn, {
+    use game::dsl;
 
-  let mut q = game::table.find(game_id).into_boxed();
-  if let Some(created_by) = created_by {
-    q = q.filter(dsl::created_by.eq(created_by));
-  }
-  let status: GameStatus = q
-    .select(dsl::status)
-    .first(conn)
-    .optional()?
-    .ok_or_else(|| Error::GameNotFound)?;
-  if status != GameStatus::Preparing && status != GameStatus::Created {
-    return Err(Error::GameNotCancellable);
-  }
-  diesel::update(game::table.find(game_id))
-    .set(game::status.eq(GameStatus::Ended))
-    .execute(conn)?;
-  Ok(())
+    let mut q = game::table.find(game_id).into_boxed();
+    if let Some(created_by) = created_by {
+      q = q.filter(dsl::created_by.eq(created_by));
+    }
+    let status: GameStatus = q
+      .select(dsl::status)
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::GameNotFound)?;
+    if status != GameStatus::Preparing && status != GameStatus::Created {
+      return Err(Error::GameNotCancellable);
+    }
+    diesel::update(game::table.find(game_id))
+      .set(game::status.eq(GameStatus::Ended))
+      .execute(conn)?;
+    Ok(())
+  })
+}
+
+/// Checks that the game was created by a player of the API client
+pub fn check_game_api_client_id(conn: &DbConn, api_client_id: i32, game_id: i32) -> Result<()> {
+  db_dispatch!(conn, {
+    let n = game::table
+      .inner_join(player::table)
+      .filter(
+        game::id
+          .eq(game_id)
+          .and(player::api_client_id.eq(api_client_id)),
+      )
+      .count()
+      .get_result::<i64>(conn)?;
+    if n == 0 {
+      return Err(Error::GameOwnerCheckFailed);
+    }
+    Ok(())
+  })
+}
+
+/// Returns the API client of the player who created the game
+pub fn get_api_client_id(conn: &DbConn, game_id: i32) -> Result<i32> {
+  db_dispatch!(conn, {
+    game::table
+      .inner_join(player::table)
+      .filter(game::id.eq(game_id))
+      .select(player::api_client_id)
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::GameNotFound)
+  })
 }
 
-#[derive(Debug, Deserialize, S2ProtoUnpack)]
-#[s2_grpc(message_type = "flo_grpc::controller::CreateGameRequest")]
+#[derive(Debug, Deserialize)]
 pub struct CreateGameParams {
   pub player_id: i32,
   pub name: String,
-  pub map: Map,
+  pub map: Option<Map>,
+  /// Catalog map id, used if `map` is not set
+  pub map_id: Option<i32>,
   pub is_private: bool,
   pub is_live: bool,
 }
 
-/// Creates a game, make the creator as the first player
-pub fn create(conn: &DbConn, params: CreateGameParams) -> Result<Game> {
-  let max_players = params.map.players.len();
-
-  if max_players == 0 {
-    return Err(Error::MapHasNoPlayer);
+// the gRPC request doesn't reference catalog maps
+impl S2ProtoUnpack<flo_grpc::controller::CreateGameRequest> for CreateGameParams {
+  fn unpack(
+    value: flo_grpc::controller::CreateGameRequest,
+  ) -> Result<Self, s2_grpc_utils::result::Error> {
+    Ok(Self {
+      player_id: value.player_id,
+      name: value.name,
+      map: S2ProtoUnpack::unpack(value.map)?,
+      map_id: None,
+      is_private: value.is_private,
+      is_live: value.is_live,
+    })
   }
+}
 
-  let player = crate::player::db::get_ref(conn, params.player_id)?;
-  let mut slots = Slots::new(max_players);
-  slots.join(&player);
+/// Creates a game, make the creator as the first player
+pub fn create(conn: &DbConn, params: CreateGameParams) -> Result<Game> {
+  db_dispatch!(conn, {
+    let (map, map_catalog_id) = crate::map::db::resolve_map(conn, params.map, params.map_id)?;
+    let max_players = map.players.len();
 
-  let meta = Meta {
-    map: params.map,
-    created_by: player.into(),
-  };
+    if max_players == 0 {
+      return Err(Error::MapHasNoPlayer);
+    }
 
-  let meta_value = serde_json::to_value(&meta)?;
-
-  let insert = GameInsert {
-    name: &params.name,
-    map_name: &meta.map.name,
-    is_private: params.is_private,
-    is_live: params.is_live,
-    max_players: max_players as i32,
-    created_by: Some(params.player_id),
-    meta: meta_value,
-    random_seed: rand::random(),
-    locked: false,
-    node_id: None,
-    mask_player_names: false,
-    enable_ping_equalizer: false,
-  };
+    let player = crate::player::db::get_ref(conn, params.player_id)?;
+    let mut slots = Slots::new(max_players);
+    slots.join(&player);
+
+    let meta = Meta {
+      map,
+      created_by: player.into(),
+    };
+
+    let meta_value = serde_json::to_value(&meta)?;
+
+    let insert = GameInsert {
+      name: &params.name,
+      map_name: &meta.map.name,
+      is_private: params.is_private,
+      is_live: params.is_live,
+      max_players: max_players as i32,
+      created_by: Some(params.player_id),
+      meta: meta_value,
+      random_seed: rand::random(),
+      locked: false,
+      node_id: None,
+      mask_player_names: false,
+      enable_ping_equalizer: false,
+      map_catalog_id,
+    };
+
+    let row = conn.transaction(|| -> Result<_> {
+      let id = insert_game(conn, &insert)?;
+      let row = get(conn, id)?;
+      upsert_used_slots(conn, row.id, slots.as_used())?;
+      Ok(row)
+    })?;
+    Ok(row.into_game(meta, slots.into_inner())?)
+  })
+}
 
-  let row = conn.transaction(|| -> Result<_> {
-    let id: i32 = diesel::insert_into(game::table)
-      .values(&insert)
+fn insert_game(conn: &DbConn, insert: &GameInsert) -> Result<i32> {
+  let id = match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(game::table)
+      .values(insert)
       .returning(game::dsl::id)
-      .get_result(conn)?;
-    let row = get(conn, id)?;
-    upsert_used_slots(conn, row.id, slots.as_used())?;
-    Ok(row)
-  })?;
-  Ok(row.into_game(meta, slots.into_inner())?)
+      .get_result(conn)?,
+    Backend::Sqlite(conn) => {
+      diesel::insert_into(game::table)
+        .values(insert)
+        .execute(conn)?;
+      sqlite_last_insert_id(conn)?
+    }
+  };
+  Ok(id)
 }
 
-#[derive(Debug, Deserialize, S2ProtoUnpack)]
-#[s2_grpc(message_type = "flo_grpc::controller::CreateGameAsBotRequest")]
+#[derive(Debug, Deserialize)]
 pub struct CreateGameAsBotParams {
   pub name: String,
-  pub map: Map,
+  pub map: Option<Map>,
+  /// Catalog map id, used if `map` is not set
+  pub map_id: Option<i32>,
   pub is_private: bool,
   pub is_live: bool,
   pub node_id: i32,
@@ -234,6 +315,25 @@ pub struct CreateGameAsBotParams {
   pub enable_ping_equalizer: bool,
 }
 
+// the gRPC request doesn't reference catalog maps
+impl S2ProtoUnpack<flo_grpc::controller::CreateGameAsBotRequest> for CreateGameAsBotParams {
+  fn unpack(
+    value: flo_grpc::controller::CreateGameAsBotRequest,
+  ) -> Result<Self, s2_grpc_utils::result::Error> {
+    Ok(Self {
+      name: value.name,
+      map: S2ProtoUnpack::unpack(value.map)?,
+      map_id: None,
+      is_private: value.is_private,
+      is_live: value.is_live,
+      node_id: value.node_id,
+      slots: S2ProtoUnpack::unpack(value.slots)?,
+      mask_player_names: value.mask_player_names,
+      enable_ping_equalizer: value.enable_ping_equalizer,
+    })
+  }
+}
+
 /// Creates a full game and lock it
 pub fn create_as_bot(
   conn: &DbConn,
@@ -241,127 +341,128 @@ pub fn create_as_bot(
   api_player_id: i32,
   params: CreateGameAsBotParams,
 ) -> Result<Game> {
-  use std::collections::{BTreeMap, BTreeSet};
-  let max_players = params.map.players.len();
-
-  if max_players == 0 {
-    return Err(Error::MapHasNoPlayer);
-  }
+  db_dispatch!(conn, {
+    use std::collections::{BTreeMap, BTreeSet};
+    let (map, map_catalog_id) = crate::map::db::resolve_map(conn, params.map, params.map_id)?;
+    let max_players = map.players.len();
 
-  if params.slots.len() > 24 {
-    return Err(Error::TooManyPlayers);
-  }
-
-  let (player_slots, referee_slots): (Vec<_>, Vec<_>) = params
-    .slots
-    .iter()
-    .enumerate()
-    .filter(|(_idx, s)| s.settings.status == SlotStatus::Occupied)
-    .partition(|s| s.1.settings.team != 24);
-
-  if player_slots.len() > max_players {
-    return Err(Error::TooManyPlayers);
-  }
+    if max_players == 0 {
+      return Err(Error::MapHasNoPlayer);
+    }
 
-  let mut player_ids: Vec<i32> = params
-    .slots
-    .iter()
-    .filter_map(|s| s.player_id.clone())
-    .collect();
+    if params.slots.len() > 24 {
+      return Err(Error::TooManyPlayers);
+    }
 
-  if player_ids.is_empty() {
-    return Err(Error::GameHasNoPlayer);
-  }
+    let (player_slots, referee_slots): (Vec<_>, Vec<_>) = params
+      .slots
+      .iter()
+      .enumerate()
+      .filter(|(_idx, s)| s.settings.status == SlotStatus::Occupied)
+      .partition(|s| s.1.settings.team != 24);
 
-  player_ids.push(api_player_id);
-  player_ids.sort();
-  player_ids.dedup();
+    if player_slots.len() > max_players {
+      return Err(Error::TooManyPlayers);
+    }
 
-  let mut players: BTreeMap<_, _> =
-    crate::player::db::get_client_refs_by_ids(conn, api_client_id, &player_ids)?
-      .into_iter()
-      .map(|p| (p.id, p))
+    let mut player_ids: Vec<i32> = params
+      .slots
+      .iter()
+      .filter_map(|s| s.player_id.clone())
       .collect();
-  let mut slots = vec![];
-  let mut color_set = BTreeSet::new();
 
-  for (i, slot) in player_slots.iter() {
-    if color_set.contains(&slot.settings.color) {
-      return Err(Error::PlayerColorConflict);
+    if player_ids.is_empty() {
+      return Err(Error::GameHasNoPlayer);
     }
 
-    color_set.insert(slot.settings.color);
+    player_ids.push(api_player_id);
+    player_ids.sort();
+    player_ids.dedup();
+
+    let mut players: BTreeMap<_, _> =
+      crate::player::db::get_client_refs_by_ids(conn, api_client_id, &player_ids)?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+    let mut slots = vec![];
+    let mut color_set = BTreeSet::new();
+
+    for (i, slot) in player_slots.iter() {
+      if color_set.contains(&slot.settings.color) {
+        return Err(Error::PlayerColorConflict);
+      }
 
-    if slot.settings.team < 0 || slot.settings.team > 24 {
-      return Err(Error::PlayerTeamInvalid);
-    }
+      color_set.insert(slot.settings.color);
 
-    let player = slot.player_id.clone().and_then(|id| players.remove(&id));
-    if slot.player_id.is_some() && player.is_none() {
-      return Err(Error::PlayerNotFound);
-    }
-    slots.push(UsedSlot {
-      slot_index: *i as i32,
-      settings: slot.settings.clone(),
-      client_status: SlotClientStatus::Pending,
-      player,
-    });
-  }
+      if slot.settings.team < 0 || slot.settings.team > 24 {
+        return Err(Error::PlayerTeamInvalid);
+      }
 
-  for (i, slot) in referee_slots.iter() {
-    let player = slot.player_id.clone().and_then(|id| players.remove(&id));
-    if slot.player_id.is_some() && player.is_none() {
-      return Err(Error::PlayerNotFound);
+      let player = slot.player_id.clone().and_then(|id| players.remove(&id));
+      if slot.player_id.is_some() && player.is_none() {
+        return Err(Error::PlayerNotFound);
+      }
+      slots.push(UsedSlot {
+        slot_index: *i as i32,
+        settings: slot.settings.clone(),
+        client_status: SlotClientStatus::Pending,
+        player,
+      });
     }
-    slots.push(UsedSlot {
-      slot_index: *i as i32,
-      settings: SlotSettings {
-        color: 0,
-        ..slot.settings.clone()
-      },
-      client_status: SlotClientStatus::Pending,
-      player,
-    });
-  }
-
-  let slots = Slots::from_used(max_players, slots);
 
-  let meta = Meta {
-    map: params.map,
-    created_by: players
-      .remove(&api_player_id)
-      .ok_or_else(|| Error::PlayerNotFound)?
-      .into(),
-  };
-
-  let meta_value = serde_json::to_value(&meta)?;
-
-  let insert = GameInsert {
-    name: &params.name,
-    map_name: &meta.map.name,
-    is_private: params.is_private,
-    is_live: params.is_live,
-    max_players: max_players as i32,
-    created_by: Some(api_player_id),
-    meta: meta_value,
-    random_seed: rand::random(),
-    locked: true,
-    node_id: Some(params.node_id),
-    mask_player_names: params.mask_player_names,
-    enable_ping_equalizer: params.enable_ping_equalizer,
-  };
-
-  let row = conn.transaction(|| -> Result<_> {
-    let id: i32 = diesel::insert_into(game::table)
-      .values(&insert)
-      .returning(game::dsl::id)
-      .get_result(conn)?;
-    let row = get(conn, id)?;
-    upsert_used_slots(conn, row.id, slots.as_used())?;
-    Ok(row)
-  })?;
+    for (i, slot) in referee_slots.iter() {
+      let player = slot.player_id.clone().and_then(|id| players.remove(&id));
+      if slot.player_id.is_some() && player.is_none() {
+        return Err(Error::PlayerNotFound);
+      }
+      slots.push(UsedSlot {
+        slot_index: *i as i32,
+        settings: SlotSettings {
+          color: 0,
+          ..slot.settings.clone()
+        },
+        client_status: SlotClientStatus::Pending,
+        player,
+      });
+    }
 
-  Ok(row.into_game(meta, slots.into_inner())?)
+    let slots = Slots::from_used(max_players, slots);
+
+    let meta = Meta {
+      map,
+      created_by: players
+        .remove(&api_player_id)
+        .ok_or_else(|| Error::PlayerNotFound)?
+        .into(),
+    };
+
+    let meta_value = serde_json::to_value(&meta)?;
+
+    let insert = GameInsert {
+      name: &params.name,
+      map_name: &meta.map.name,
+      is_private: params.is_private,
+      is_live: params.is_live,
+      max_players: max_players as i32,
+      created_by: Some(api_player_id),
+      meta: meta_value,
+      random_seed: rand::random(),
+      locked: true,
+      node_id: Some(params.node_id),
+      mask_player_names: params.mask_player_names,
+      enable_ping_equalizer: params.enable_ping_equalizer,
+      map_catalog_id,
+    };
+
+    let row = conn.transaction(|| -> Result<_> {
+      let id = insert_game(conn, &insert)?;
+      let row = get(conn, id)?;
+      upsert_used_slots(conn, row.id, slots.as_used())?;
+      Ok(row)
+    })?;
+
+    Ok(row.into_game(meta, slots.into_inner())?)
+  })
 }
 
 /// Adds a player into a game
@@ -454,39 +555,45 @@ struct InspectId {
 }
 
 fn inspect_id(conn: &DbConn, game_id: i32) -> Result<InspectId> {
-  Ok(
-    game::table
-      .find(game_id)
-      .select((game::status, game::locked))
-      .first(conn)
-      .optional()?
-      .ok_or_else(|| Error::GameNotFound)?,
-  )
+  db_dispatch!(conn, {
+    Ok(
+      game::table
+        .find(game_id)
+        .select((game::status, game::locked))
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| Error::GameNotFound)?,
+    )
+  })
 }
 
 pub fn leave_node(conn: &DbConn, game_id: i32, player_id: i32) -> Result<()> {
-  use game_used_slot::dsl;
-  diesel::update(
-    game_used_slot::table.filter(dsl::game_id.eq(game_id).and(dsl::player_id.eq(player_id))),
-  )
-  .set(dsl::client_status.eq(SlotClientStatus::Left))
-  .execute(conn)?;
-  Ok(())
+  db_dispatch!(conn, {
+    use game_used_slot::dsl;
+    diesel::update(
+      game_used_slot::table.filter(dsl::game_id.eq(game_id).and(dsl::player_id.eq(player_id))),
+    )
+    .set(dsl::client_status.eq(SlotClientStatus::Left))
+    .execute(conn)?;
+    Ok(())
+  })
 }
 
 pub fn get_node_active_player_ids(conn: &DbConn, game_id: i32) -> Result<Vec<i32>> {
-  use game_used_slot::dsl;
-  game_used_slot::table
-    .filter(
-      dsl::game_id
-        .eq(game_id)
-        .and(dsl::player_id.is_not_null())
-        .and(dsl::client_status.ne(all(&[SlotClientStatus::Left] as &[_]))),
-    )
-    .select(dsl::player_id)
-    .load::<Option<i32>>(conn)
-    .map(|rows| rows.into_iter().filter_map(|id| id).collect())
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    use game_used_slot::dsl;
+    game_used_slot::table
+      .filter(
+        dsl::game_id
+          .eq(game_id)
+          .and(dsl::player_id.is_not_null())
+          .and(dsl::client_status.ne(SlotClientStatus::Left)),
+      )
+      .select(dsl::player_id)
+      .load::<Option<i32>>(conn)
+      .map(|rows| rows.into_iter().filter_map(|id| id).collect())
+      .map_err(Into::into)
+  })
 }
 
 #[derive(Debug, Queryable)]
@@ -502,23 +609,25 @@ impl SlotOwnerInfo {
 }
 
 pub fn get_slot_owner_info(conn: &DbConn, game_id: i32, slot_index: i32) -> Result<SlotOwnerInfo> {
-  use game::dsl as g;
-  use game_used_slot::dsl as gus;
-  let rows: Vec<(i32, Option<i32>, Option<i32>)> = game::table
-    .left_join(game_used_slot::table)
-    .select((
-      g::created_by,
-      gus::slot_index.nullable(),
-      gus::player_id.nullable(),
-    ))
-    .filter(g::id.eq(game_id))
-    .load(conn)?;
-  Ok(SlotOwnerInfo {
-    host_player_id: rows.first().ok_or_else(|| Error::GameNotFound)?.0,
-    slot_player_id: rows
-      .iter()
-      .find(|r| r.1 == Some(slot_index))
-      .and_then(|r| r.2),
+  db_dispatch!(conn, {
+    use game::dsl as g;
+    use game_used_slot::dsl as gus;
+    let rows: Vec<(i32, Option<i32>, Option<i32>)> = game::table
+      .left_join(game_used_slot::table)
+      .select((
+        g::created_by,
+        gus::slot_index.nullable(),
+        gus::player_id.nullable(),
+      ))
+      .filter(g::id.eq(game_id))
+      .load(conn)?;
+    Ok(SlotOwnerInfo {
+      host_player_id: rows.first().ok_or_else(|| Error::GameNotFound)?.0,
+      slot_player_id: rows
+        .iter()
+        .find(|r| r.1 == Some(slot_index))
+        .and_then(|r| r.2),
+    })
   })
 }
 
@@ -562,20 +671,40 @@ fn sync_slot_at(conn: &DbConn, game_id: i32, slot_index: i32, slot: &Slot) -> Re
   use game_used_slot::dsl;
 
   if slot.is_used() {
-    diesel::insert_into(game_used_slot::table)
-      .values(&UsedSlotInsert::from_used_slot(
-        game_id,
-        UsedSlot::from((slot_index as usize, slot)),
-      ))
-      .on_conflict((dsl::game_id, dsl::slot_index))
-      .do_update()
-      .set(UsedSlotUpdate::from_slot(slot))
-      .execute(conn)?;
+    let insert =
+      UsedSlotInsert::from_used_slot(game_id, UsedSlot::from((slot_index as usize, slot)));
+    let update = UsedSlotUpdate::from_slot(slot);
+    match conn.backend() {
+      Backend::Pg(conn) => {
+        diesel::insert_into(game_used_slot::table)
+          .values(&insert)
+          .on_conflict((dsl::game_id, dsl::slot_index))
+          .do_update()
+          .set(&update)
+          .execute(conn)?;
+      }
+      Backend::Sqlite(conn) => conn.transaction(|| -> Result<_> {
+        let updated = diesel::update(
+          game_used_slot::table
+            .filter(dsl::game_id.eq(game_id).and(dsl::slot_index.eq(slot_index))),
+        )
+        .set(&update)
+        .execute(conn)?;
+        if updated == 0 {
+          diesel::insert_into(game_used_slot::table)
+            .values(&insert)
+            .execute(conn)?;
+        }
+        Ok(())
+      })?,
+    }
   } else {
-    diesel::delete(
-      game_used_slot::table.filter(dsl::game_id.eq(game_id).and(dsl::slot_index.eq(slot_index))),
-    )
-    .execute(conn)?;
+    db_dispatch!(conn, {
+      diesel::delete(
+        game_used_slot::table.filter(dsl::game_id.eq(game_id).and(dsl::slot_index.eq(slot_index))),
+      )
+      .execute(conn)
+    })?;
   }
 
   Ok(())
@@ -587,93 +716,125 @@ pub fn update_slot_client_status(
   player_id: i32,
   status: SlotClientStatus,
 ) -> Result<()> {
-  use game_used_slot::dsl;
+  db_dispatch!(conn, {
+    use game_used_slot::dsl;
 
-  diesel::update(
-    game_used_slot::table.filter(
-      dsl::game_id
-        .eq(game_id)
-        .and(dsl::player_id.is_not_distinct_from(player_id)),
-    ),
-  )
-  .set(dsl::client_status.eq(status))
-  .execute(conn)?;
+    diesel::update(
+      game_used_slot::table.filter(dsl::game_id.eq(game_id).and(dsl::player_id.eq(player_id))),
+    )
+    .set(dsl::client_status.eq(status))
+    .execute(conn)?;
 
-  Ok(())
+    Ok(())
+  })
 }
 
 pub fn update_status(conn: &DbConn, update: &GameStatusUpdate) -> Result<()> {
-  let game_id = update.game_id;
-  let game_status = GameStatus::from(update.status);
-  conn.transaction(|| {
-    diesel::update(game::table.find(update.game_id))
-      .set(game::dsl::status.eq(game_status))
-      .execute(conn)?;
-
-    match game_status {
-      GameStatus::Running => {
-        diesel::update(
-          game::table.filter(game::id.eq(update.game_id).and(game::started_at.is_null())),
-        )
-        .set(game::dsl::started_at.eq(sql("now()")))
+  db_dispatch!(conn, {
+    let game_id = update.game_id;
+    let game_status = GameStatus::from(update.status);
+    conn.transaction(|| {
+      diesel::update(game::table.find(update.game_id))
+        .set(game::dsl::status.eq(game_status))
         .execute(conn)?;
+
+      match game_status {
+        GameStatus::Running => {
+          diesel::update(
+            game::table.filter(game::id.eq(update.game_id).and(game::started_at.is_null())),
+          )
+          .set(game::dsl::started_at.eq(Utc::now()))
+          .execute(conn)?;
+        }
+        GameStatus::Ended => {
+          diesel::update(
+            game::table.filter(game::id.eq(update.game_id).and(game::ended_at.is_null())),
+          )
+          .set(game::dsl::ended_at.eq(Utc::now()))
+          .execute(conn)?;
+        }
+        _ => {}
       }
-      GameStatus::Ended => {
+
+      for (player_id, status) in &update.updated_player_game_client_status_map {
         diesel::update(
-          game::table.filter(game::id.eq(update.game_id).and(game::ended_at.is_null())),
+          game_used_slot::table.filter(
+            game_used_slot::dsl::game_id
+              .eq(game_id)
+              .and(game_used_slot::player_id.eq(*player_id)),
+          ),
         )
-        .set(game::dsl::ended_at.eq(sql("now()")))
+        .set(game_used_slot::client_status.eq(*status))
         .execute(conn)?;
       }
-      _ => {}
-    }
-
-    for (player_id, status) in &update.updated_player_game_client_status_map {
-      diesel::update(
-        game_used_slot::table.filter(
-          game_used_slot::dsl::game_id
-            .eq(game_id)
-            .and(game_used_slot::player_id.eq(*player_id)),
-        ),
-      )
-      .set(game_used_slot::client_status.eq(*status))
-      .execute(conn)?;
-    }
-    Ok(())
+      Ok(())
+    })
   })
 }
 
 fn upsert_used_slots(conn: &DbConn, game_id: i32, used_slots: Vec<UsedSlot>) -> Result<()> {
-  use diesel::pg::upsert::excluded;
   use game_used_slot::dsl;
   let indices: Vec<i32> = used_slots.iter().map(|slot| slot.slot_index).collect();
   let inserts: Vec<_> = used_slots
     .into_iter()
     .map(|slot| UsedSlotInsert::from_used_slot(game_id, slot))
     .collect();
-  conn.transaction(|| {
-    diesel::delete(
-      game_used_slot::table.filter(
-        dsl::game_id
-          .eq(game_id)
-          .and(dsl::slot_index.ne(all(indices))),
-      ),
-    )
-    .execute(conn)?;
-    diesel::insert_into(game_used_slot::table)
-      .values(&inserts)
-      .on_conflict((dsl::game_id, dsl::slot_index))
-      .do_update()
-      .set((
-        dsl::player_id.eq(excluded(dsl::player_id)),
-        dsl::team.eq(excluded(dsl::team)),
-        dsl::color.eq(excluded(dsl::color)),
-        dsl::computer.eq(excluded(dsl::computer)),
-        dsl::handicap.eq(excluded(dsl::handicap)),
-        dsl::race.eq(excluded(dsl::race)),
-        dsl::client_status.eq(excluded(dsl::client_status)),
-      ))
-      .execute(conn)?;
+  conn.transaction(|| -> Result<_> {
+    db_dispatch!(conn, {
+      diesel::delete(
+        game_used_slot::table.filter(
+          dsl::game_id
+            .eq(game_id)
+            .and(dsl::slot_index.ne_all(&indices)),
+        ),
+      )
+      .execute(conn)
+    })?;
+    match conn.backend() {
+      Backend::Pg(conn) => {
+        use diesel::pg::upsert::excluded;
+        diesel::insert_into(game_used_slot::table)
+          .values(&inserts)
+          .on_conflict((dsl::game_id, dsl::slot_index))
+          .do_update()
+          .set((
+            dsl::player_id.eq(excluded(dsl::player_id)),
+            dsl::team.eq(excluded(dsl::team)),
+            dsl::color.eq(excluded(dsl::color)),
+            dsl::computer.eq(excluded(dsl::computer)),
+            dsl::handicap.eq(excluded(dsl::handicap)),
+            dsl::race.eq(excluded(dsl::race)),
+            dsl::client_status.eq(excluded(dsl::client_status)),
+          ))
+          .execute(conn)?;
+      }
+      Backend::Sqlite(conn) => {
+        for insert in &inserts {
+          let updated = diesel::update(
+            game_used_slot::table.filter(
+              dsl::game_id
+                .eq(game_id)
+                .and(dsl::slot_index.eq(insert.slot_index)),
+            ),
+          )
+          .set((
+            dsl::player_id.eq(insert.player_id),
+            dsl::team.eq(insert.team),
+            dsl::color.eq(insert.color),
+            dsl::computer.eq(insert.computer),
+            dsl::handicap.eq(insert.handicap),
+            dsl::race.eq(insert.race),
+            dsl::client_status.eq(insert.client_status),
+          ))
+          .execute(conn)?;
+          if updated == 0 {
+            diesel::insert_into(game_used_slot::table)
+              .values(insert)
+              .execute(conn)?;
+          }
+        }
+      }
+    }
     Ok(())
   })
 }
@@ -685,39 +846,43 @@ struct GetSlots {
 }
 
 fn get_slots(conn: &DbConn, game_id: i32) -> Result<GetSlots> {
-  use game_used_slot::dsl;
-
-  let (host_player_id, max_players): (i32, i32) = {
-    use game::dsl;
-    game::table
-      .find(game_id)
-      .select((dsl::created_by, dsl::max_players))
-      .first(conn)
-      .optional()?
-      .ok_or_else(|| Error::GameNotFound)?
-  };
+  db_dispatch!(conn, {
+    use game_used_slot::dsl;
 
-  let used_slots: Vec<UsedSlot> = game_used_slot::table
-    .left_outer_join(player::table)
-    .select(UsedSlot::columns())
-    .filter(dsl::game_id.eq(game_id))
-    .load(conn)?;
+    let (host_player_id, max_players): (i32, i32) = {
+      use game::dsl;
+      game::table
+        .find(game_id)
+        .select((dsl::created_by, dsl::max_players))
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| Error::GameNotFound)?
+    };
+
+    let used_slots: Vec<UsedSlot> = game_used_slot::table
+      .left_outer_join(player::table)
+      .select(UsedSlot::columns())
+      .filter(dsl::game_id.eq(game_id))
+      .load(conn)?;
 
-  let slots = Slots::from_used(max_players as usize, used_slots);
-  Ok(GetSlots {
-    host_player_id,
-    slots,
+    let slots = Slots::from_used(max_players as usize, used_slots);
+    Ok(GetSlots {
+      host_player_id,
+      slots,
+    })
   })
 }
 
 fn get_used_slots(conn: &DbConn, game_id: i32) -> Result<Vec<UsedSlot>> {
-  use game_used_slot::dsl;
-  game_used_slot::table
-    .left_outer_join(player::table)
-    .select(UsedSlot::columns())
-    .filter(dsl::game_id.eq(game_id))
-    .load(conn)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    use game_used_slot::dsl;
+    game_used_slot::table
+      .left_outer_join(player::table)
+      .select(UsedSlot::columns())
+      .filter(dsl::game_id.eq(game_id))
+      .load(conn)
+      .map_err(Into::into)
+  })
 }
 
 #[derive(Debug, Queryable)]
@@ -727,35 +892,38 @@ pub struct PlayerActiveSlot {
 }
 
 pub fn get_player_active_slots(conn: &DbConn, player_id: i32) -> Result<Vec<PlayerActiveSlot>> {
-  let rows = game_used_slot::table
-    .left_outer_join(player::table)
-    .inner_join(game::table)
-    .select((game_used_slot::game_id, UsedSlotInfo::columns()))
-    .filter(game::status.eq(any(GameStatus::active_variants())))
-    .filter(game_used_slot::player_id.eq(player_id))
-    .filter(
-      game_used_slot::client_status.ne(all(
-        &[SlotClientStatus::Disconnected, SlotClientStatus::Left] as &[_],
-      )),
-    )
-    .order(game_used_slot::created_at)
-    .load(conn)?;
-  Ok(rows)
+  db_dispatch!(conn, {
+    let rows = game_used_slot::table
+      .left_outer_join(player::table)
+      .inner_join(game::table)
+      .select((game_used_slot::game_id, UsedSlotInfo::columns()))
+      .filter(game::status.eq_any(GameStatus::active_variants()))
+      .filter(game_used_slot::player_id.eq(player_id))
+      .filter(
+        game_used_slot::client_status
+          .ne_all(&[SlotClientStatus::Disconnected, SlotClientStatus::Left] as &[_]),
+      )
+      .order(game_used_slot::created_at)
+      .load(conn)?;
+    Ok(rows)
+  })
 }
 
 pub fn get_full(conn: &DbConn, id: i32) -> Result<Game> {
-  let row: GameRowWithRelated = game::table
-    .find(id)
-    .left_outer_join(node::table)
-    .left_outer_join(player::table)
-    .select(GameRowWithRelated::columns())
-    .first(conn)
-    .optional()?
-    .ok_or_else(|| Error::GameNotFound)?;
-  let meta: Meta = serde_json::from_value(row.meta.clone())?;
-  let used_slots = get_used_slots(conn, id)?;
-  let slots: Vec<Slot> = Slots::from_used(row.max_players as usize, used_slots).into_inner();
-  Ok(row.into_game(meta, slots)?)
+  db_dispatch!(conn, {
+    let row: GameRowWithRelated = game::table
+      .find(id)
+      .left_outer_join(node::table)
+      .left_outer_join(player::table)
+      .select(GameRowWithRelated::columns())
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::GameNotFound)?;
+    let meta: Meta = serde_json::from_value(row.meta.clone())?;
+    let used_slots = get_used_slots(conn, id)?;
+    let slots: Vec<Slot> = Slots::from_used(row.max_players as usize, used_slots).into_inner();
+    Ok(row.into_game(meta, slots)?)
+  })
 }
 
 pub fn get_full_and_node_token(
@@ -763,32 +931,34 @@ pub fn get_full_and_node_token(
   game_id: i32,
   player_id: i32,
 ) -> Result<(Game, Option<PlayerToken>)> {
-  use game_used_slot::dsl as gus;
-  let row: GameRowWithRelated = game::table
-    .find(game_id)
-    .left_outer_join(node::table)
-    .left_outer_join(player::table)
-    .select(GameRowWithRelated::columns())
-    .first(conn)
-    .optional()?
-    .ok_or_else(|| Error::GameNotFound)?;
-  let meta: Meta = serde_json::from_value(row.meta.clone())?;
-  let used_slots = get_used_slots(conn, game_id)?;
-  let player_token: Option<Vec<u8>> = game_used_slot::table
-    .select(gus::node_token)
-    .filter(gus::game_id.eq(game_id).and(gus::player_id.eq(player_id)))
-    .first(conn)
-    .optional()?
-    .ok_or_else(|| Error::PlayerNotInGame)?;
-  let max_players = row.max_players;
-
-  Ok((
-    row.into_game(
-      meta,
-      Slots::from_used(max_players as usize, used_slots).into_inner(),
-    )?,
-    player_token.and_then(|bytes| PlayerToken::from_vec(player_id, bytes)),
-  ))
+  db_dispatch!(conn, {
+    use game_used_slot::dsl as gus;
+    let row: GameRowWithRelated = game::table
+      .find(game_id)
+      .left_outer_join(node::table)
+      .left_outer_join(player::table)
+      .select(GameRowWithRelated::columns())
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::GameNotFound)?;
+    let meta: Meta = serde_json::from_value(row.meta.clone())?;
+    let used_slots = get_used_slots(conn, game_id)?;
+    let player_token: Option<Vec<u8>> = game_used_slot::table
+      .select(gus::node_token)
+      .filter(gus::game_id.eq(game_id).and(gus::player_id.eq(player_id)))
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::PlayerNotInGame)?;
+    let max_players = row.max_players;
+
+    Ok((
+      row.into_game(
+        meta,
+        Slots::from_used(max_players as usize, used_slots).into_inner(),
+      )?,
+      player_token.and_then(|bytes| PlayerToken::from_vec(player_id, bytes)),
+    ))
+  })
 }
 
 #[derive(Debug)]
@@ -803,108 +973,116 @@ pub struct GameStateFromDb {
 /// Loads game players info from database
 /// This is used after server restart to restore in-memory state
 pub fn get_all_active_game_state(conn: &DbConn) -> Result<Vec<GameStateFromDb>> {
-  use game::dsl;
-
-  let rows: Vec<(i32, GameStatus, Option<i32>, i32)> = game::table
-    .left_outer_join(node::table)
-    .filter(dsl::status.eq_any(&[
-      GameStatus::Preparing,
-      GameStatus::Created,
-      GameStatus::Running,
-    ]))
-    .order(dsl::created_at)
-    .select((dsl::id, dsl::status, dsl::node_id, dsl::created_by))
-    .load(conn)?;
-
-  let game_ids: Vec<_> = rows.iter().map(|(id, _, _, _)| *id).collect();
-  let mut game_players_map: HashMap<i32, Vec<(i32, Option<Vec<u8>>)>> = {
-    use game_used_slot::dsl;
-    let rows: Vec<(i32, Option<i32>, Option<Vec<u8>>)> = game_used_slot::table
-      .select((dsl::game_id, dsl::player_id, dsl::node_token))
-      .filter(
-        dsl::game_id
-          .eq(any(game_ids))
-          .and(dsl::player_id.is_not_null())
-          .and(dsl::client_status.ne(all(
-            &[SlotClientStatus::Disconnected, SlotClientStatus::Left] as &[SlotClientStatus],
-          ))),
-      )
+  db_dispatch!(conn, {
+    use game::dsl;
+
+    let rows: Vec<(i32, GameStatus, Option<i32>, i32)> = game::table
+      .left_outer_join(node::table)
+      .filter(dsl::status.eq_any(&[
+        GameStatus::Preparing,
+        GameStatus::Created,
+        GameStatus::Running,
+      ]))
+      .order(dsl::created_at)
+      .select((dsl::id, dsl::status, dsl::node_id, dsl::created_by))
       .load(conn)?;
-    let mut map = HashMap::new();
-    for (game_id, player_id, node_token) in rows {
-      if let Some(player_id) = player_id {
-        map
-          .entry(game_id)
-          .or_insert_with(|| vec![])
-          .push((player_id, node_token))
+
+    let game_ids: Vec<_> = rows.iter().map(|(id, _, _, _)| *id).collect();
+    let mut game_players_map: HashMap<i32, Vec<(i32, Option<Vec<u8>>)>> = {
+      use game_used_slot::dsl;
+      let rows: Vec<(i32, Option<i32>, Option<Vec<u8>>)> = game_used_slot::table
+        .select((dsl::game_id, dsl::player_id, dsl::node_token))
+        .filter(
+          dsl::game_id
+            .eq_any(game_ids)
+            .and(dsl::player_id.is_not_null())
+            .and(dsl::client_status.ne_all(
+              &[SlotClientStatus::Disconnected, SlotClientStatus::Left] as &[SlotClientStatus],
+            )),
+        )
+        .load(conn)?;
+      let mut map = HashMap::new();
+      for (game_id, player_id, node_token) in rows {
+        if let Some(player_id) = player_id {
+          map
+            .entry(game_id)
+            .or_insert_with(|| vec![])
+            .push((player_id, node_token))
+        }
       }
+      map
+    };
+
+    let mut games = Vec::with_capacity(rows.len());
+    for (id, status, node_id, created_by) in rows {
+      let players = game_players_map.remove(&id).unwrap_or_default();
+      games.push(GameStateFromDb {
+        id,
+        status,
+        players,
+        node_id,
+        created_by,
+      });
     }
-    map
-  };
-
-  let mut games = Vec::with_capacity(rows.len());
-  for (id, status, node_id, created_by) in rows {
-    let players = game_players_map.remove(&id).unwrap_or_default();
-    games.push(GameStateFromDb {
-      id,
-      status,
-      players,
-      node_id,
-      created_by,
-    });
-  }
-  Ok(games)
+    Ok(games)
+  })
 }
 
 pub fn get_expired_games(conn: &DbConn) -> Result<Vec<i32>> {
-  let t = Utc::now() - chrono::Duration::minutes(30);
-  game::table
-    .select(game::id)
-    .filter(game::status.eq_any(&[GameStatus::Preparing, GameStatus::Created]))
-    .filter(game::updated_at.lt(t))
-    .load(conn)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    let t = Utc::now() - chrono::Duration::minutes(30);
+    game::table
+      .select(game::id)
+      .filter(game::status.eq_any(&[GameStatus::Preparing, GameStatus::Created]))
+      .filter(game::updated_at.lt(t))
+      .load(conn)
+      .map_err(Into::into)
+  })
 }
 
 pub fn select_node(conn: &DbConn, id: i32, player_id: i32, node_id: Option<i32>) -> Result<()> {
-  use game::dsl;
+  db_dispatch!(conn, {
+    use game::dsl;
 
-  let InspectId { status, locked } = inspect_id(conn, id)?;
+    let InspectId { status, locked } = inspect_id(conn, id)?;
 
-  if locked {
-    return Err(Error::GameSlotUpdateDenied);
-  }
+    if locked {
+      return Err(Error::GameSlotUpdateDenied);
+    }
 
-  if status != GameStatus::Preparing {
-    return Err(Error::GameStarted);
-  }
+    if status != GameStatus::Preparing {
+      return Err(Error::GameStarted);
+    }
 
-  let n: usize = diesel::update(game::table.find(id))
-    .filter(
-      dsl::status
-        .eq(GameStatus::Preparing)
-        .and(game::created_by.eq(player_id)),
-    )
-    .set(dsl::node_id.eq(node_id))
-    .execute(conn)?;
+    let n: usize = diesel::update(game::table.find(id))
+      .filter(
+        dsl::status
+          .eq(GameStatus::Preparing)
+          .and(game::created_by.eq(player_id)),
+      )
+      .set(dsl::node_id.eq(node_id))
+      .execute(conn)?;
 
-  if n != 1 {
-    return Err(Error::GameSlotUpdateDenied);
-  }
+    if n != 1 {
+      return Err(Error::GameSlotUpdateDenied);
+    }
 
-  Ok(())
+    Ok(())
+  })
 }
 
 fn end_game(conn: &DbConn, id: i32, status: GameStatus) -> Result<()> {
-  use game::dsl;
-  conn.transaction(|| -> Result<_> {
-    diesel::update(game::table.find(id))
-      .filter(dsl::status.ne(status))
-      .set((dsl::status.eq(status), dsl::ended_at.eq(sql("now()"))))
-      .execute(conn)?;
+  db_dispatch!(conn, {
+    use game::dsl;
+    conn.transaction(|| -> Result<_> {
+      diesel::update(game::table.find(id))
+        .filter(dsl::status.ne(status))
+        .set((dsl::status.eq(status), dsl::ended_at.eq(Utc::now())))
+        .execute(conn)?;
+      Ok(())
+    })?;
     Ok(())
-  })?;
-  Ok(())
+  })
 }
 
 pub fn terminate_game(conn: &DbConn, id: i32) -> Result<()> {
@@ -918,73 +1096,336 @@ pub fn update_created(
   agreed_version: Option<String>,
   player_tokens: HashMap<i32, PlayerToken>,
 ) -> Result<()> {
-  use game::dsl;
-  conn.transaction(|| {
-    diesel::update(game::table.find(id))
-      .filter(dsl::status.eq(GameStatus::Preparing))
-      .set((
-        dsl::status.eq(GameStatus::Created),
-        dsl::game_version.eq(agreed_version),
-      ))
-      .execute(conn)?;
-    for (player_id, token) in player_tokens {
-      use game_used_slot::dsl as gus;
-      diesel::update(
-        game_used_slot::table.filter(gus::game_id.eq(id).and(gus::player_id.eq(player_id))),
-      )
-      .set(gus::node_token.eq(token.as_slice()))
-      .execute(conn)?;
-    }
-    Ok(())
+  db_dispatch!(conn, {
+    use game::dsl;
+    conn.transaction(|| {
+      diesel::update(game::table.find(id))
+        .filter(dsl::status.eq(GameStatus::Preparing))
+        .set((
+          dsl::status.eq(GameStatus::Created),
+          dsl::game_version.eq(agreed_version),
+        ))
+        .execute(conn)?;
+      for (player_id, token) in player_tokens {
+        use game_used_slot::dsl as gus;
+        diesel::update(
+          game_used_slot::table.filter(gus::game_id.eq(id).and(gus::player_id.eq(player_id))),
+        )
+        .set(gus::node_token.eq(token.as_slice()))
+        .execute(conn)?;
+      }
+      Ok(())
+    })
   })
 }
 
 /// Created -> Preparing
 pub fn update_reset_created(conn: &DbConn, id: i32) -> Result<()> {
-  use game::dsl;
-  use game_used_slot::dsl as gus;
-  conn.transaction(|| {
-    diesel::update(game::table.find(id))
-      .filter(dsl::status.eq(GameStatus::Created))
-      .set(dsl::status.eq(GameStatus::Preparing))
-      .execute(conn)?;
-    diesel::update(game_used_slot::table.filter(gus::game_id.eq(id)))
-      .set(gus::node_token.eq(Option::<Vec<u8>>::None))
-      .execute(conn)?;
-    Ok(())
+  db_dispatch!(conn, {
+    use game::dsl;
+    use game_used_slot::dsl as gus;
+    conn.transaction(|| {
+      diesel::update(game::table.find(id))
+        .filter(dsl::status.eq(GameStatus::Created))
+        .set(dsl::status.eq(GameStatus::Preparing))
+        .execute(conn)?;
+      diesel::update(game_used_slot::table.filter(gus::game_id.eq(id)))
+        .set(gus::node_token.eq(Option::<Vec<u8>>::None))
+        .execute(conn)?;
+      Ok(())
+    })
   })
 }
 
 /// Reset all instance specific states
 /// Should be called after process start
 pub fn reset_instance_state(conn: &DbConn) -> Result<()> {
-  use game::dsl as g;
-  use game_used_slot::dsl as gus;
-  // invalidate active games' slot client status
-  conn.transaction(|| {
-    let active_game_id = game::table
+  db_dispatch!(conn, {
+    use game::dsl as g;
+    use game_used_slot::dsl as gus;
+    // invalidate active games' slot client status
+    conn.transaction(|| {
+      let active_game_id = game::table
+        .select(g::id)
+        .filter(g::status.ne_all(&[GameStatus::Ended, GameStatus::Terminated] as &[_]));
+      diesel::update(game_used_slot::table.filter(gus::game_id.eq_any(active_game_id)))
+        .set(gus::client_status_synced_node_conn_id.eq(Option::<i64>::None))
+        .execute(conn)?;
+      Ok(())
+    })
+  })
+}
+
+pub fn get_node_active_game_ids(conn: &DbConn, node_id: i32) -> Result<Vec<i32>> {
+  db_dispatch!(conn, {
+    use game::dsl as g;
+
+    game::table
+      .inner_join(game_used_slot::table)
       .select(g::id)
-      .filter(g::status.ne(all(&[GameStatus::Ended, GameStatus::Terminated] as &[_])));
-    diesel::update(game_used_slot::table.filter(gus::game_id.eq(any(active_game_id))))
-      .set(gus::client_status_synced_node_conn_id.eq(Option::<i64>::None))
+      .filter(
+        g::status
+          .ne_all(&[GameStatus::Ended, GameStatus::Terminated] as &[_])
+          .and(g::node_id.eq(node_id)),
+      )
+      .load(conn)
+      .map_err(Into::into)
+  })
+}
+
+/// Saves the result reported by the node, returns `false` if the result already exists
+pub fn save_result(conn: &DbConn, node_id: i32, report: GameResultReport) -> Result<bool> {
+  db_dispatch!(conn, {
+    let game_id = report.game_id;
+    conn.transaction(|| -> Result<_> {
+      let game_node_id: Option<i32> = game::table
+        .find(game_id)
+        .select(game::node_id)
+        .first(conn)
+        .optional()?
+        .ok_or(Error::GameNotFound)?;
+      if game_node_id != Some(node_id) {
+        return Err(Error::GameNodeMismatch);
+      }
+
+      let inserted = insert_result(
+        conn,
+        &GameResultInsert {
+          game_id,
+          node_id,
+          duration_ms: report.duration_ms,
+          desyncs: serde_json::to_value(&report.desyncs)?,
+        },
+      )?;
+      if inserted == 0 {
+        return Ok(false);
+      }
+
+      let player_ids: Vec<Option<i32>> = game_used_slot::table
+        .filter(game_used_slot::game_id.eq(game_id))
+        .select(game_used_slot::player_id)
+        .load(conn)?;
+      let inserts: Vec<_> = report
+        .players
+        .iter()
+        .filter(|p| player_ids.contains(&Some(p.player_id)))
+        .map(|p| GamePlayerResultInsert::new(game_id, p))
+        .collect();
+      diesel::insert_into(game_player_result::table)
+        .values(&inserts)
+        .execute(conn)?;
+
+      Ok(true)
+    })
+  })
+}
+
+fn insert_result(conn: &DbConn, insert: &GameResultInsert) -> Result<usize> {
+  let inserted = match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(game_result::table)
+      .values(insert)
+      .on_conflict_do_nothing()
+      .execute(conn)?,
+    Backend::Sqlite(conn) => diesel::insert_or_ignore_into(game_result::table)
+      .values(insert)
+      .execute(conn)?,
+  };
+  Ok(inserted)
+}
+
+pub fn get_result(conn: &DbConn, game_id: i32) -> Result<Option<GameResult>> {
+  db_dispatch!(conn, {
+    use game_result::dsl;
+
+    let row: Option<(i32, i32, Value, DateTime<Utc>)> = game_result::table
+      .filter(dsl::game_id.eq(game_id))
+      .select((
+        dsl::node_id,
+        dsl::duration_ms,
+        dsl::desyncs,
+        dsl::created_at,
+      ))
+      .first(conn)
+      .optional()?;
+    let (node_id, duration_ms, desyncs, created_at) = if let Some(row) = row {
+      row
+    } else {
+      return Ok(None);
+    };
+
+    let players = game_player_result::table
+      .filter(game_player_result::game_id.eq(game_id))
+      .order(game_player_result::id)
+      .select(GamePlayerResult::COLUMNS)
+      .load(conn)?;
+
+    Ok(Some(GameResult {
+      game_id,
+      node_id,
+      duration_ms,
+      players,
+      desyncs: serde_json::from_value(desyncs)?,
+      created_at,
+    }))
+  })
+}
+
+/// Saves a chat log reported by the node of the game,
+/// returns the ids of the players with chat violations in the report
+pub fn save_chat_log(conn: &DbConn, node_id: i32, report: GameChatLogReport) -> Result<Vec<i32>> {
+  db_dispatch!(conn, {
+    let game_id = report.game_id;
+    conn.transaction(|| -> Result<_> {
+      let game_node_id: Option<i32> = game::table
+        .find(game_id)
+        .select(game::node_id)
+        .first(conn)
+        .optional()?
+        .ok_or(Error::GameNotFound)?;
+      if game_node_id != Some(node_id) {
+        return Err(Error::GameNodeMismatch);
+      }
+
+      let player_ids: Vec<Option<i32>> = game_used_slot::table
+        .filter(game_used_slot::game_id.eq(game_id))
+        .select(game_used_slot::player_id)
+        .load(conn)?;
+      let inserts: Vec<_> = report
+        .messages
+        .into_iter()
+        .filter(|m| player_ids.contains(&Some(m.player_id)))
+        .map(|m| {
+          let insert = GameChatMessageInsert::new(game_id, m);
+          GameChatMessageInsert {
+            to_player_id: insert
+              .to_player_id
+              .filter(|id| player_ids.contains(&Some(*id))),
+            ..insert
+          }
+        })
+        .collect();
+      diesel::insert_into(game_chat_message::table)
+        .values(&inserts)
+        .execute(conn)?;
+
+      // counted separately, the log might not contain all filtered messages
+      let violations: Vec<_> = report
+        .violations
+        .into_iter()
+        .filter(|v| v.count > 0 && player_ids.contains(&Some(v.player_id)))
+        .map(|v| GameChatViolationInsert {
+          game_id,
+          player_id: v.player_id,
+          count: v.count as i32,
+        })
+        .collect();
+      for insert in &violations {
+        insert_chat_violation(conn, insert)?;
+      }
+
+      Ok(violations.into_iter().map(|v| v.player_id).collect())
+    })
+  })
+}
+
+// a violation count is reported once per game
+fn insert_chat_violation(conn: &DbConn, insert: &GameChatViolationInsert) -> Result<usize> {
+  let inserted = match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(game_chat_violation::table)
+      .values(insert)
+      .on_conflict_do_nothing()
+      .execute(conn)?,
+    Backend::Sqlite(conn) => diesel::insert_or_ignore_into(game_chat_violation::table)
+      .values(insert)
+      .execute(conn)?,
+  };
+  Ok(inserted)
+}
+
+/// Saves a chat message sent in the flo lobby of a game
+pub fn insert_lobby_chat_message(
+  conn: &DbConn,
+  game_id: i32,
+  player_id: i32,
+  message: String,
+  filter_action: GameChatFilterAction,
+) -> Result<()> {
+  let insert = GameChatMessageInsert {
+    game_id,
+    player_id,
+    scope: GameChatScope::Lobby,
+    to_player_id: None,
+    game_time_ms: 0,
+    sent_at: Utc::now(),
+    message,
+    filter_action,
+  };
+  db_dispatch!(conn, {
+    diesel::insert_into(game_chat_message::table)
+      .values(&insert)
       .execute(conn)?;
     Ok(())
   })
 }
 
-pub fn get_node_active_game_ids(conn: &DbConn, node_id: i32) -> Result<Vec<i32>> {
-  use game::dsl as g;
-
-  game::table
-    .inner_join(game_used_slot::table)
-    .select(g::id)
-    .filter(
-      g::status
-        .ne(all(&[GameStatus::Ended, GameStatus::Terminated] as &[_]))
-        .and(g::node_id.eq(node_id)),
-    )
-    .load(conn)
-    .map_err(Into::into)
+pub struct ListGameChatMessages {
+  pub items: Vec<GameChatMessage>,
+  pub next_id: Option<i32>,
+}
+
+/// Lists the chat messages of a game in the order they were sent
+pub fn list_chat_messages(
+  conn: &DbConn,
+  game_id: i32,
+  player_id: Option<i32>,
+  next_id: Option<i32>,
+) -> Result<ListGameChatMessages> {
+  db_dispatch!(conn, {
+    const PAGE_SIZE: i64 = 500;
+    let mut q = game_chat_message::table
+      .select(GameChatMessage::COLUMNS)
+      .filter(game_chat_message::game_id.eq(game_id))
+      .order(game_chat_message::id)
+      .limit(PAGE_SIZE + 1)
+      .into_boxed();
+
+    if let Some(id) = player_id {
+      q = q.filter(game_chat_message::player_id.eq(id));
+    }
+
+    if let Some(id) = next_id {
+      q = q.filter(game_chat_message::id.ge(id));
+    }
+
+    let mut rows = q.load::<GameChatMessage>(conn)?;
+    let next_id = if rows.len() > PAGE_SIZE as usize {
+      let id = rows.last().map(|row| row.id);
+      rows.truncate(PAGE_SIZE as usize);
+      id
+    } else {
+      None
+    };
+
+    Ok(ListGameChatMessages {
+      items: rows,
+      next_id,
+    })
+  })
+}
+
+/// Counts the chat violations of the player in all games reported since `since`
+pub fn count_chat_violations(conn: &DbConn, player_id: i32, since: DateTime<Utc>) -> Result<i64> {
+  db_dispatch!(conn, {
+    let n = game_chat_violation::table
+      .filter(
+        game_chat_violation::player_id
+          .eq(player_id)
+          .and(game_chat_violation::created_at.ge(since)),
+      )
+      .select(diesel::dsl::sum(game_chat_violation::count))
+      .first::<Option<i64>>(conn)?;
+    Ok(n.unwrap_or(0))
+  })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1105,6 +1546,7 @@ pub struct GameInsert<'a> {
   pub node_id: Option<i32>,
   pub mask_player_names: bool,
   pub enable_ping_equalizer: bool,
+  pub map_catalog_id: Option<i32>,
 }
 
 #[derive(Debug, Insertable)]
@@ -1167,3 +1609,413 @@ impl UsedSlotUpdate {
     }
   }
 }
+
+#[derive(Debug, Insertable)]
+#[table_name = "game_result"]
+struct GameResultInsert {
+  game_id: i32,
+  node_id: i32,
+  duration_ms: i32,
+  desyncs: Value,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "game_chat_message"]
+struct GameChatMessageInsert {
+  game_id: i32,
+  player_id: i32,
+  scope: GameChatScope,
+  to_player_id: Option<i32>,
+  game_time_ms: i32,
+  sent_at: DateTime<Utc>,
+  message: String,
+  filter_action: GameChatFilterAction,
+}
+
+impl GameChatMessageInsert {
+  fn new(game_id: i32, m: flo_net::proto::flo_node::GameChatMessage) -> Self {
+    use chrono::TimeZone;
+    Self {
+      game_id,
+      player_id: m.player_id,
+      scope: GameChatScope::unpack_enum(m.scope()),
+      to_player_id: m.to_player_id,
+      game_time_ms: m.game_time_ms as i32,
+      sent_at: Utc
+        .timestamp_millis_opt(m.timestamp_ms)
+        .single()
+        .unwrap_or_else(Utc::now),
+      filter_action: GameChatFilterAction::unpack_enum(m.filter_action()),
+      message: m.message,
+    }
+  }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "game_chat_violation"]
+struct GameChatViolationInsert {
+  game_id: i32,
+  player_id: i32,
+  count: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "game_player_result"]
+struct GamePlayerResultInsert {
+  game_id: i32,
+  player_id: i32,
+  left_at_ms: Option<i32>,
+  leave_reason: Option<i32>,
+  disconnects: i32,
+  dropped: bool,
+  desynced: bool,
+  lag_count: i32,
+  lag_duration_ms: i32,
+  rtt_min: Option<i32>,
+  rtt_max: Option<i32>,
+  rtt_avg: Option<i32>,
+}
+
+impl GamePlayerResultInsert {
+  fn new(game_id: i32, p: &GamePlayerResult) -> Self {
+    Self {
+      game_id,
+      player_id: p.player_id,
+      left_at_ms: p.left_at_ms,
+      leave_reason: p.leave_reason,
+      disconnects: p.disconnects,
+      dropped: p.dropped,
+      desynced: p.desynced,
+      lag_count: p.lag_count,
+      lag_duration_ms: p.lag_duration_ms,
+      rtt_min: p.rtt_min,
+      rtt_max: p.rtt_max,
+      rtt_avg: p.rtt_avg,
+    }
+  }
+}
+
+#[cfg(test)]
+fn test_map(players: usize) -> Map {
+  use crate::map::{MapPlayer, MapSha1};
+  Map {
+    sha1: MapSha1([1; 20]),
+    checksum: 1,
+    name: "Test Map".to_string(),
+    description: String::new(),
+    author: String::new(),
+    path: "maps/test.w3x".to_string(),
+    width: 64,
+    height: 64,
+    players: (0..players)
+      .map(|i| MapPlayer {
+        name: format!("Player {}", i + 1),
+        r#type: 1,
+        race: 0,
+        flags: 0,
+      })
+      .collect(),
+    forces: vec![],
+  }
+}
+
+#[cfg(test)]
+fn test_create(conn: &DbConn, player_id: i32, players: usize) -> Result<Game> {
+  create(
+    conn,
+    CreateGameParams {
+      player_id,
+      name: "Test Game".to_string(),
+      map: Some(test_map(players)),
+      map_id: None,
+      is_private: false,
+      is_live: false,
+    },
+  )
+}
+
+#[tokio::test]
+async fn test_create_and_add_player() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let alice = crate::lan::get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let bob = crate::lan::get_or_create_player(conn, setup.api_client_id, "Bob".to_string())?;
+    let carol = crate::lan::get_or_create_player(conn, setup.api_client_id, "Carol".to_string())?;
+
+    assert!(matches!(
+      test_create(conn, alice, 0),
+      Err(Error::MapHasNoPlayer)
+    ));
+
+    let game = test_create(conn, alice, 2)?;
+    assert_eq!(game.status, GameStatus::Preparing);
+    assert_eq!(game.max_players, 2);
+    assert_eq!(game.created_by.id, alice);
+    assert_eq!(game.map.players.len(), 2);
+
+    let slots = add_player(conn, game.id, bob)?;
+    let player_ids: Vec<_> = slots
+      .iter()
+      .filter_map(|slot| slot.player.as_ref().map(|p| p.id))
+      .collect();
+    assert_eq!(player_ids, vec![alice, bob]);
+
+    assert!(matches!(
+      add_player(conn, game.id, bob),
+      Err(Error::PlayerAlreadyInGame)
+    ));
+    assert!(matches!(
+      add_player(conn, game.id, carol),
+      Err(Error::GameFull)
+    ));
+
+    let game = get_full(conn, game.id)?;
+    assert_eq!(game.num_players, 2);
+    Ok(())
+  })
+  .await;
+}
+
+#[tokio::test]
+async fn test_update_status_and_save_result() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let alice = crate::lan::get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let bob = crate::lan::get_or_create_player(conn, setup.api_client_id, "Bob".to_string())?;
+    let game = test_create(conn, alice, 2)?;
+    add_player(conn, game.id, bob)?;
+
+    assert!(matches!(
+      select_node(conn, game.id, bob, Some(setup.node_id)),
+      Err(Error::GameSlotUpdateDenied)
+    ));
+    select_node(conn, game.id, alice, Some(setup.node_id))?;
+
+    let mut client_status = HashMap::new();
+    client_status.insert(bob, SlotClientStatus::Loaded);
+    update_status(
+      conn,
+      &GameStatusUpdate {
+        game_id: game.id,
+        status: crate::game::NodeGameStatus::Running,
+        updated_player_game_client_status_map: client_status,
+      },
+    )?;
+    let running = get_full(conn, game.id)?;
+    assert_eq!(running.status, GameStatus::Running);
+    assert!(running.started_at.is_some());
+    assert!(running.ended_at.is_none());
+    assert_eq!(running.slots[1].client_status, SlotClientStatus::Loaded);
+    assert!(matches!(
+      add_player(conn, game.id, bob),
+      Err(Error::GameStarted)
+    ));
+
+    update_status(
+      conn,
+      &GameStatusUpdate {
+        game_id: game.id,
+        status: crate::game::NodeGameStatus::Ended,
+        updated_player_game_client_status_map: HashMap::new(),
+      },
+    )?;
+    let ended = get_full(conn, game.id)?;
+    assert_eq!(ended.status, GameStatus::Ended);
+    assert_eq!(ended.started_at, running.started_at);
+    assert!(ended.ended_at.is_some());
+
+    assert!(get_result(conn, game.id)?.is_none());
+    let player_result = |player_id, leave_reason| GamePlayerResult {
+      player_id,
+      left_at_ms: Some(60_000),
+      leave_reason: Some(leave_reason),
+      disconnects: 0,
+      dropped: false,
+      desynced: player_id == bob,
+      lag_count: 1,
+      lag_duration_ms: 500,
+      rtt_min: Some(10),
+      rtt_max: None,
+      rtt_avg: Some(20),
+    };
+    let report = || GameResultReport {
+      game_id: game.id,
+      duration_ms: 60_000,
+      players: vec![
+        player_result(alice, 9),
+        player_result(bob, 8),
+        // not in the game
+        player_result(bob + 1000, 8),
+      ],
+      desyncs: vec![crate::game::GameDesync {
+        player_id: bob,
+        time: 30_000,
+        tick: 100,
+      }],
+    };
+    assert!(matches!(
+      save_result(conn, setup.node_id + 1, report()),
+      Err(Error::GameNodeMismatch)
+    ));
+    assert!(save_result(conn, setup.node_id, report())?);
+    assert!(!save_result(conn, setup.node_id, report())?);
+
+    let result = get_result(conn, game.id)?.unwrap();
+    assert_eq!(result.node_id, setup.node_id);
+    assert_eq!(result.duration_ms, 60_000);
+    let players: Vec<_> = result
+      .players
+      .iter()
+      .map(|p| (p.player_id, p.leave_reason, p.desynced, p.rtt_max))
+      .collect();
+    assert_eq!(
+      players,
+      vec![(alice, Some(9), false, None), (bob, Some(8), true, None)]
+    );
+    assert_eq!(result.desyncs.len(), 1);
+    assert_eq!(result.desyncs[0].player_id, bob);
+    assert_eq!(result.desyncs[0].tick, 100);
+    Ok(())
+  })
+  .await;
+}
+
+#[tokio::test]
+async fn test_chat_log() {
+  use flo_net::proto::flo_node::{
+    GameChatFilterAction as ProtoFilterAction, GameChatMessage as ProtoMessage,
+    GameChatScope as ProtoScope, GameChatViolationCount,
+  };
+
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let alice = crate::lan::get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let bob = crate::lan::get_or_create_player(conn, setup.api_client_id, "Bob".to_string())?;
+    let carol = crate::lan::get_or_create_player(conn, setup.api_client_id, "Carol".to_string())?;
+    let game = test_create(conn, alice, 2)?;
+    add_player(conn, game.id, bob)?;
+    select_node(conn, game.id, alice, Some(setup.node_id))?;
+
+    let since = Utc::now() - chrono::Duration::minutes(1);
+    insert_lobby_chat_message(
+      conn,
+      game.id,
+      alice,
+      "gl hf".to_string(),
+      GameChatFilterAction::None,
+    )?;
+
+    let message =
+      |player_id, scope: ProtoScope, to_player_id, filter_action: ProtoFilterAction| ProtoMessage {
+        player_id,
+        scope: scope as i32,
+        to_player_id,
+        game_time_ms: 1000,
+        timestamp_ms: Utc::now().timestamp_millis(),
+        message: "hi".to_string(),
+        filter_action: filter_action as i32,
+      };
+    let report = || GameChatLogReport {
+      game_id: game.id,
+      messages: vec![
+        message(alice, ProtoScope::All, None, ProtoFilterAction::None),
+        message(bob, ProtoScope::Allies, None, ProtoFilterAction::Replaced),
+        message(bob, ProtoScope::All, None, ProtoFilterAction::RateLimited),
+        message(
+          bob,
+          ProtoScope::Private,
+          Some(carol),
+          ProtoFilterAction::None,
+        ),
+        message(alice, ProtoScope::All, None, ProtoFilterAction::Muted),
+        // not in the game
+        message(carol, ProtoScope::All, None, ProtoFilterAction::Dropped),
+      ],
+      // includes violations of messages not in the log
+      violations: vec![
+        GameChatViolationCount {
+          player_id: bob,
+          count: 3,
+        },
+        GameChatViolationCount {
+          player_id: carol,
+          count: 1,
+        },
+      ],
+    };
+    assert!(matches!(
+      save_chat_log(conn, setup.node_id + 1, report()),
+      Err(Error::GameNodeMismatch)
+    ));
+    assert_eq!(save_chat_log(conn, setup.node_id, report())?, vec![bob]);
+
+    let list = list_chat_messages(conn, game.id, None, None)?;
+    assert!(list.next_id.is_none());
+    let items: Vec<_> = list
+      .items
+      .iter()
+      .map(|m| (m.player_id, m.scope, m.to_player_id, m.filter_action))
+      .collect();
+    assert_eq!(
+      items,
+      vec![
+        (
+          alice,
+          GameChatScope::Lobby,
+          None,
+          GameChatFilterAction::None
+        ),
+        (alice, GameChatScope::All, None, GameChatFilterAction::None),
+        (
+          bob,
+          GameChatScope::Allies,
+          None,
+          GameChatFilterAction::Replaced
+        ),
+        (
+          bob,
+          GameChatScope::All,
+          None,
+          GameChatFilterAction::RateLimited
+        ),
+        (
+          bob,
+          GameChatScope::Private,
+          None,
+          GameChatFilterAction::None
+        ),
+        (alice, GameChatScope::All, None, GameChatFilterAction::Muted),
+      ]
+    );
+    assert_eq!(list.items[0].message, "gl hf");
+    assert!(list.items[0].sent_at >= since);
+
+    let bob_messages = list_chat_messages(conn, game.id, Some(bob), None)?;
+    assert_eq!(bob_messages.items.len(), 3);
+    let next = list_chat_messages(conn, game.id, None, Some(list.items[4].id))?;
+    assert_eq!(next.items.len(), 2);
+
+    assert_eq!(count_chat_violations(conn, bob, since)?, 3);
+    // reported once per game
+    save_chat_log(
+      conn,
+      setup.node_id,
+      GameChatLogReport {
+        game_id: game.id,
+        messages: vec![],
+        violations: vec![GameChatViolationCount {
+          player_id: bob,
+          count: 1,
+        }],
+      },
+    )?;
+    assert_eq!(count_chat_violations(conn, bob, since)?, 3);
+    assert_eq!(count_chat_violations(conn, alice, since)?, 0);
+    assert_eq!(
+      count_chat_violations(conn, bob, Utc::now() + chrono::Duration::minutes(1))?,
+      0
+    );
+    Ok(())
+  })
+  .await;
+}
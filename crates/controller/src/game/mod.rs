@@ -6,6 +6,7 @@ mod types;
 
 pub mod messages {
   pub use super::state::cancel::CancelGame;
+  pub use super::state::control::GameControl;
   pub use super::state::create::CreateGame;
   pub use super::state::join::PlayerJoin;
   pub use super::state::leave::PlayerLeave;
@@ -2,6 +2,7 @@ use crate::error::*;
 use crate::game::state::GameActor;
 
 use crate::player::state::sender::PlayerFrames;
+use crate::webhook::WebhookEvent;
 
 use flo_net::packet::FloPacket;
 
@@ -35,7 +36,7 @@ impl Handler<CancelGame> for GameActor {
       .players_leave_game(self.players.clone(), game_id)
       .await?;
 
-    let packet_iter = self
+    let packets = self
       .players
       .iter()
       .cloned()
@@ -50,10 +51,20 @@ impl Handler<CancelGame> for GameActor {
 
         Ok((player_id, PlayerFrames::from(frame_left)))
       })
-      .collect::<Result<Vec<_>>>()?
-      .into_iter();
+      .collect::<Result<Vec<_>>>()?;
 
-    self.player_reg.broadcast_map(packet_iter).await?;
+    for (player_id, frames) in &packets {
+      self
+        .events
+        .publish_player(game_id, *player_id, frames.clone())
+        .await;
+    }
+    self.player_reg.broadcast_map(packets).await?;
+
+    self
+      .webhooks
+      .emit(game_id, WebhookEvent::GameCancelled)
+      .await;
 
     Ok(())
   }
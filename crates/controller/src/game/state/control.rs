@@ -0,0 +1,46 @@
+use crate::error::*;
+use crate::game::state::GameActor;
+use crate::game::GameStatus;
+use crate::node::messages::NodeGameControl;
+use crate::node::GameControlAction;
+use crate::state::ActorMapExt;
+
+use flo_state::{async_trait, Context, Handler, Message};
+
+/// Forwards a game control action to the node hosting the game
+pub struct GameControl {
+  pub action: GameControlAction,
+}
+
+impl Message for GameControl {
+  type Result = Result<()>;
+}
+
+#[async_trait]
+impl Handler<GameControl> for GameActor {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    GameControl { action }: GameControl,
+  ) -> Result<()> {
+    let game_id = self.game_id;
+
+    match self.status {
+      GameStatus::Created | GameStatus::Running | GameStatus::Paused => {}
+      _ => return Err(Error::GameNotRunning),
+    }
+
+    let node_id = self
+      .selected_node_id
+      .ok_or_else(|| Error::GameNodeNotSelected)?;
+
+    tracing::info!(game_id, node_id, "game control: {:?}", action);
+
+    self
+      .nodes
+      .send_to(node_id, NodeGameControl { game_id, action })
+      .await?
+      .await
+      .or_cancelled()
+  }
+}
@@ -3,6 +3,8 @@ use crate::game::db::{CreateGameAsBotParams, CreateGameParams};
 use crate::game::state::registry::Register;
 use crate::game::state::GameRegistry;
 use crate::game::{Game, GameStatus};
+use crate::player::PlayerBanType;
+use crate::webhook::WebhookEvent;
 use flo_state::{async_trait, Context, Handler, Message};
 
 pub struct CreateGame {
@@ -23,7 +25,14 @@ impl Handler<CreateGame> for GameRegistry {
     let player_id = params.player_id;
     let game = self
       .db
-      .exec(move |conn| crate::game::db::create(conn, params))
+      .exec(move |conn| {
+        if let Some(ban) =
+          crate::player::db::get_active_ban(conn, &[player_id], PlayerBanType::Game)?
+        {
+          return Err(Error::PlayerBanned(ban));
+        }
+        crate::game::db::create(conn, params)
+      })
       .await?;
 
     self.register(Register {
@@ -39,6 +48,8 @@ impl Handler<CreateGame> for GameRegistry {
       .player_replace_game(player_id, game.clone(), vec![])
       .await?;
 
+    self.webhooks.emit(game.id, WebhookEvent::GameCreated).await;
+
     Ok(game)
   }
 }
@@ -67,6 +78,12 @@ impl Handler<CreateGameAsBot> for GameRegistry {
     let (mut game, player_ids, mute_list_map) = self
       .db
       .exec(move |conn| {
+        let player_ids: Vec<i32> = params.slots.iter().filter_map(|s| s.player_id).collect();
+        if let Some(ban) =
+          crate::player::db::get_active_ban(conn, &player_ids, PlayerBanType::Game)?
+        {
+          return Err(Error::PlayerBanned(ban));
+        }
         let game = crate::game::db::create_as_bot(conn, api_client_id, api_player_id, params)?;
         let player_ids = game.get_player_ids();
         let mute_list_map = crate::player::db::get_mute_list_map(conn, &player_ids)?;
@@ -96,6 +113,8 @@ impl Handler<CreateGameAsBot> for GameRegistry {
       .players_replace_game(player_ids, game.clone(), mute_list_map)
       .await?;
 
+    self.webhooks.emit(game.id, WebhookEvent::GameCreated).await;
+
     Ok(game)
   }
 }
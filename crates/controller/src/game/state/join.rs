@@ -1,6 +1,7 @@
 use crate::error::*;
 use crate::game::state::GameActor;
 use crate::game::Game;
+use crate::player::PlayerBanType;
 use diesel::prelude::*;
 use flo_net::packet::FloPacket;
 use flo_net::proto;
@@ -28,6 +29,11 @@ impl Handler<PlayerJoin> for GameActor {
       .db
       .exec(move |conn| {
         conn.transaction(|| {
+          if let Some(ban) =
+            crate::player::db::get_active_ban(conn, &[player_id], PlayerBanType::Game)?
+          {
+            return Err(Error::PlayerBanned(ban));
+          }
           crate::game::db::add_player(conn, game_id, player_id)?;
           let game = crate::game::db::get_full(conn, game_id)?;
           let mut mute_list_map =
@@ -68,7 +74,8 @@ impl Handler<PlayerJoin> for GameActor {
         }
       }
       .encode_as_frame()?;
-      self.player_reg.broadcast(players, frame).await?;
+      self.player_reg.broadcast(players, frame.clone()).await?;
+      self.events.publish(game.id, frame).await;
     }
 
     Ok(game)
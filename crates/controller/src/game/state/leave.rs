@@ -4,6 +4,7 @@ use crate::game::{GameStatus, SlotClientStatus};
 use crate::node::{messages as node_messages, PlayerLeaveResponse};
 use crate::player::state::sender::PlayerFrames;
 use crate::state::ActorMapExt;
+use crate::webhook::WebhookEvent;
 use diesel::prelude::*;
 use flo_net::packet::FloPacket;
 use flo_net::proto;
@@ -92,6 +93,25 @@ async fn leave_game_lobby(
   )
   .await?;
 
+  if leave.removed_players.contains(&player_id) {
+    state
+      .webhooks
+      .emit(game_id, WebhookEvent::GamePlayerLeft { player_id })
+      .await;
+  }
+
+  if leave.game_ended {
+    state
+      .webhooks
+      .emit(
+        game_id,
+        WebhookEvent::GameEnded {
+          status: GameStatus::Ended,
+        },
+      )
+      .await;
+  }
+
   Ok(PlayerLeaveResult {
     game_ended: leave.game_ended,
   })
@@ -171,8 +191,9 @@ async fn leave_game_abort(
   .encode_as_frame()?;
   state
     .player_reg
-    .broadcast(active_player_ids.clone(), frame)
+    .broadcast(active_player_ids.clone(), frame.clone())
     .await?;
+  state.events.publish(game_id, frame).await;
 
   broadcast(
     state,
@@ -220,6 +241,7 @@ async fn broadcast(
     }
 
     state.player_reg.broadcast_map(frame_map).await?;
+    state.events.publish(game_id, frame_player_leave).await;
   }
   Ok(())
 }
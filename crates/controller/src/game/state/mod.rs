@@ -1,10 +1,12 @@
 pub mod cancel;
+pub mod control;
 pub mod create;
 pub mod join;
 pub mod leave;
 pub mod node;
 pub mod player;
 pub mod registry;
+pub mod result;
 pub mod slot;
 pub mod start;
 pub mod status;
@@ -12,16 +14,18 @@ pub mod status;
 pub use status::{GameSlotClientStatusUpdate, GameStatusUpdate};
 
 use crate::error::*;
+use crate::event::{EventHub, EventSender};
 use crate::game::db::{get_all_active_game_state, get_expired_games};
 use crate::game::{GameStatus, SlotClientStatus};
 use crate::node::{NodeRegistry, PlayerToken};
 use crate::player::state::sender::PlayerRegistryHandle;
 
+use crate::db::ExecutorRef;
 use crate::game::state::cancel::CancelGame;
 use crate::game::state::registry::Remove;
 use crate::player::state::PlayerRegistry;
 use crate::state::{Data, GetActorEntry};
-use bs_diesel_utils::ExecutorRef;
+use crate::webhook::{WebhookDispatcher, WebhookSender};
 use flo_state::*;
 use start::StartGameState;
 use std::collections::BTreeMap;
@@ -35,6 +39,8 @@ pub struct GameRegistry {
   db: ExecutorRef,
   players: PlayerRegistryHandle,
   nodes: Addr<NodeRegistry>,
+  webhooks: WebhookSender,
+  events: EventSender,
   map: BTreeMap<i32, Owner<GameActor>>,
   player_games_map: BTreeMap<i32, Vec<i32>>,
   game_players_map: BTreeMap<i32, Vec<i32>>,
@@ -46,6 +52,8 @@ impl GameRegistry {
     db: ExecutorRef,
     player_packet_sender: PlayerRegistryHandle,
     nodes: Addr<NodeRegistry>,
+    webhooks: WebhookSender,
+    events: EventSender,
   ) -> Result<GameRegistry> {
     let games = db.exec(|conn| get_all_active_game_state(conn)).await?;
     let mut map = BTreeMap::new();
@@ -80,6 +88,8 @@ impl GameRegistry {
           db: db.clone(),
           player_reg: player_packet_sender.clone(),
           nodes: nodes.clone(),
+          webhooks: webhooks.clone(),
+          events: events.clone(),
           status: game.status,
           host_player: game.created_by,
           players,
@@ -95,6 +105,8 @@ impl GameRegistry {
       db: db.clone(),
       players: player_packet_sender.clone(),
       nodes: nodes.clone(),
+      webhooks,
+      events,
       map,
       player_games_map,
       game_players_map,
@@ -147,7 +159,16 @@ impl Service<Data> for GameRegistry {
   async fn create(registry: &mut RegistryRef<Data>) -> Result<Self, Self::Error> {
     let players = registry.resolve::<PlayerRegistry>().await?;
     let nodes = registry.resolve::<NodeRegistry>().await?;
-    Self::init(registry.data().db.clone(), players.into(), nodes).await
+    let webhooks = registry.resolve::<WebhookDispatcher>().await?;
+    let events = registry.resolve::<EventHub>().await?;
+    Self::init(
+      registry.data().db.clone(),
+      players.into(),
+      nodes,
+      webhooks.into(),
+      events.into(),
+    )
+    .await
   }
 }
 
@@ -191,6 +212,8 @@ pub struct GameActor {
   pub db: ExecutorRef,
   pub player_reg: PlayerRegistryHandle,
   pub nodes: Addr<NodeRegistry>,
+  pub webhooks: WebhookSender,
+  pub events: EventSender,
   pub status: GameStatus,
   pub host_player: i32,
   pub players: Vec<i32>,
@@ -1,5 +1,6 @@
 use crate::error::*;
 use crate::game::state::GameActor;
+use crate::webhook::WebhookEvent;
 
 use flo_net::packet::FloPacket;
 use flo_net::proto;
@@ -37,8 +38,14 @@ impl Handler<SelectNode> for GameActor {
     let frame = proto::flo_connect::PacketGameSelectNode { game_id, node_id }.encode_as_frame()?;
     self
       .player_reg
-      .broadcast(self.players.clone(), frame)
+      .broadcast(self.players.clone(), frame.clone())
       .await?;
+    self.events.publish(game_id, frame).await;
+
+    self
+      .webhooks
+      .emit(game_id, WebhookEvent::GameNodeSelected { node_id })
+      .await;
 
     Ok(())
   }
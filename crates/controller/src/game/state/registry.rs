@@ -45,6 +45,8 @@ impl GameRegistry {
         db: self.db.clone(),
         player_reg: self.players.clone(),
         nodes: self.nodes.clone(),
+        webhooks: self.webhooks.clone(),
+        events: self.events.clone(),
         status,
         host_player,
         players,
@@ -72,6 +74,7 @@ impl Handler<Remove> for GameRegistry {
     if let Some(owner) = self.map.remove(&id) {
       self.game_players_map.remove(&id);
       self.game_node_map.remove(&id);
+      self.events.forget_game(id).await;
 
       let addr = ctx.addr();
       ctx.spawn(async move {
@@ -0,0 +1,105 @@
+use crate::error::*;
+use crate::game::state::GameRegistry;
+use crate::game::{db, GameChatLogReport, GameResultReport};
+use crate::player::PlayerBanType;
+use chrono::Utc;
+use flo_state::{async_trait, Context, Handler, Message};
+
+#[derive(Debug)]
+pub struct SaveGameResult {
+  pub node_id: i32,
+  pub report: GameResultReport,
+}
+
+impl Message for SaveGameResult {
+  type Result = Result<()>;
+}
+
+#[async_trait]
+impl Handler<SaveGameResult> for GameRegistry {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    SaveGameResult { node_id, report }: SaveGameResult,
+  ) -> Result<()> {
+    let game_id = report.game_id;
+    let saved = self
+      .db
+      .exec(move |conn| db::save_result(conn, node_id, report))
+      .await?;
+    if saved {
+      tracing::info!(game_id, node_id, "game result saved");
+    } else {
+      tracing::warn!(game_id, node_id, "game result already exists");
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug)]
+pub struct SaveGameChatLog {
+  pub node_id: i32,
+  pub report: GameChatLogReport,
+}
+
+impl Message for SaveGameChatLog {
+  type Result = Result<()>;
+}
+
+#[async_trait]
+impl Handler<SaveGameChatLog> for GameRegistry {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    SaveGameChatLog { node_id, report }: SaveGameChatLog,
+  ) -> Result<()> {
+    let game_id = report.game_id;
+    let len = report.messages.len();
+    let settings = crate::config::settings();
+    let banned_player_ids = self
+      .db
+      .exec(move |conn| -> Result<_> {
+        let player_ids = db::save_chat_log(conn, node_id, report)?;
+        if settings.chat_ban_violations == 0 {
+          return Ok(vec![]);
+        }
+
+        let now = Utc::now();
+        let window_start = now - chrono::Duration::seconds(settings.chat_ban_window_secs as i64);
+        let mut banned_player_ids = vec![];
+        for player_id in player_ids {
+          if crate::player::db::get_active_ban(conn, &[player_id], PlayerBanType::Chat)?.is_some() {
+            continue;
+          }
+          // violations before the last chat ban have been punished already
+          let since =
+            crate::player::db::get_last_ban_created_at(conn, player_id, PlayerBanType::Chat)?
+              .map(|t| t.max(window_start))
+              .unwrap_or(window_start);
+          let violations = db::count_chat_violations(conn, player_id, since)?;
+          if violations >= settings.chat_ban_violations as i64 {
+            crate::player::db::create_ban(
+              conn,
+              player_id,
+              PlayerBanType::Chat,
+              Some(now + chrono::Duration::seconds(settings.chat_ban_duration_secs as i64)),
+              Some("Repeated chat violations".to_string()),
+              None,
+            )?;
+            banned_player_ids.push(player_id);
+          }
+        }
+        Ok(banned_player_ids)
+      })
+      .await?;
+    tracing::info!(game_id, node_id, "game chat log saved: {} messages", len);
+    for player_id in banned_player_ids {
+      tracing::info!(
+        game_id,
+        player_id,
+        "chat banned for repeated chat violations"
+      );
+    }
+    Ok(())
+  }
+}
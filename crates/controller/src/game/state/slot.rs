@@ -68,8 +68,9 @@ impl Handler<UpdateSlot> for GameActor {
       .collect();
     self
       .player_reg
-      .broadcast(players, frames_slot_update)
+      .broadcast(players, frames_slot_update.clone())
       .await?;
+    self.events.publish(game_id, frames_slot_update).await;
 
     Ok(slots)
   }
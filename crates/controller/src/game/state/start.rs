@@ -1,9 +1,11 @@
 use crate::error::*;
 use crate::game::state::GameActor;
 use crate::game::{GameStatus, SlotClientStatus};
+use crate::map::MapSha1;
 use crate::node::messages::NodeCreateGame;
 use crate::player::state::sender::PlayerFrames;
 use crate::state::ActorMapExt;
+use crate::webhook::WebhookEvent;
 use flo_net::packet::FloPacket;
 use flo_net::proto;
 use flo_state::{async_trait, Actor, Addr, Context, Handler, Message};
@@ -52,8 +54,9 @@ impl Handler<StartGameCheck> for GameActor {
     let frame = proto::flo_connect::PacketGameStarting { game_id }.encode_as_frame()?;
     self
       .player_reg
-      .broadcast(self.players.clone(), frame)
+      .broadcast(self.players.clone(), frame.clone())
       .await?;
+    self.events.publish(game_id, frame).await;
 
     Ok(())
   }
@@ -74,6 +77,7 @@ impl GameActor {
 
     let mut pass = true;
     let agreed_version: Option<String>;
+    let agreed_sha1: Option<Vec<u8>>;
     {
       let mut version: Option<&str> = None;
       let mut sha1: Option<&[u8]> = None;
@@ -88,20 +92,37 @@ impl GameActor {
         }
       }
       agreed_version = version.map(ToString::to_string);
+      agreed_sha1 = sha1.map(ToOwned::to_owned);
+    }
+
+    let mut message = "Unable to start the game because the game and map version check failed.";
+
+    // games created from the map catalog must use the uploaded map file
+    if pass {
+      let catalog_sha1 = self
+        .db
+        .exec(move |conn| crate::map::db::get_game_catalog_sha1(conn, game_id))
+        .await?;
+      if let Some(catalog_sha1) = catalog_sha1 {
+        if MapSha1::from_hex_str(&catalog_sha1).map(|sha1| sha1.to_vec()) != agreed_sha1 {
+          pass = false;
+          message = "Unable to start the game because the map file does not match the map catalog.";
+        }
+      }
     }
 
     if !pass {
       let pkt = proto::flo_connect::PacketGameStartReject {
         game_id,
-        message: "Unable to start the game because the game and map version check failed."
-          .to_string(),
+        message: message.to_string(),
         player_client_info_map: map.clone(),
       };
       let frame = pkt.encode_as_frame()?;
       self
         .player_reg
-        .broadcast(self.players.clone(), frame)
+        .broadcast(self.players.clone(), frame.clone())
         .await?;
+      self.events.publish(game_id, frame).await;
 
       tracing::error!(
         game_id = self.game_id,
@@ -222,6 +243,11 @@ impl GameActor {
       .await?;
     self.status = GameStatus::Created;
 
+    self
+      .webhooks
+      .emit(game_id, WebhookEvent::GameStarted { node_id })
+      .await;
+
     Ok(Ok(()))
   }
 }
@@ -274,8 +300,9 @@ impl GameActor {
 
     self
       .player_reg
-      .broadcast(self.players.clone(), frame)
+      .broadcast(self.players.clone(), frame.clone())
       .await?;
+    self.events.publish(game_id, frame).await;
 
     Ok(())
   }
@@ -580,8 +607,9 @@ impl Handler<StartGameCheckAsBot> for GameActor {
     let frame = proto::flo_connect::PacketGameStarting { game_id }.encode_as_frame()?;
     self
       .player_reg
-      .broadcast(self.players.clone(), frame)
+      .broadcast(self.players.clone(), frame.clone())
       .await?;
+    self.events.publish(game_id, frame).await;
 
     Ok(())
   }
@@ -2,6 +2,7 @@ use crate::error::*;
 use crate::game::state::GameActor;
 use crate::game::{db, GameStatus, NodeGameStatus, SlotClientStatus};
 use crate::player::state::sender::PlayerFrames;
+use crate::webhook::WebhookEvent;
 use flo_net::packet::FloPacket;
 use flo_net::proto;
 use flo_state::{async_trait, Context, Handler, Message};
@@ -51,12 +52,21 @@ impl Handler<GameSlotClientStatusUpdate> for GameActor {
     };
     pkt.set_status(status.into_proto_enum());
 
+    let frame = pkt.encode_as_frame()?;
     self
       .player_reg
-      .broadcast(self.players.clone(), pkt.encode_as_frame()?)
+      .broadcast(self.players.clone(), frame.clone())
       .await?;
+    self.events.publish(game_id, frame).await;
 
-    self.player_client_status_map.insert(player_id, status);
+    let prev_status = self.player_client_status_map.insert(player_id, status);
+
+    if status == SlotClientStatus::Left && prev_status != Some(SlotClientStatus::Left) {
+      self
+        .webhooks
+        .emit(game_id, WebhookEvent::GamePlayerLeft { player_id })
+        .await;
+    }
 
     Ok(())
   }
@@ -92,6 +102,7 @@ impl Handler<GameStatusUpdate> for GameActor {
       .await?;
 
     let frame_game_status = message.to_packet().encode_as_frame()?;
+    let prev_status = self.status;
     self.status = GameStatus::from(message.status);
 
     let ended = match self.status {
@@ -110,12 +121,25 @@ impl Handler<GameStatusUpdate> for GameActor {
       .extend(message.updated_player_game_client_status_map);
 
     self.player_reg.broadcast_map(frame_iter).await?;
+    self.events.publish(self.game_id, frame_game_status).await;
 
     if ended {
       self
         .player_reg
         .players_leave_game(self.players.clone(), self.game_id)
         .await?;
+
+      if !matches!(prev_status, GameStatus::Ended | GameStatus::Terminated) {
+        self
+          .webhooks
+          .emit(
+            self.game_id,
+            WebhookEvent::GameEnded {
+              status: self.status,
+            },
+          )
+          .await;
+      }
     }
 
     Ok(self.status)
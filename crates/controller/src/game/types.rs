@@ -1,7 +1,7 @@
 use crate::map::Map;
 use crate::node::{NodeRef, NodeRefColumns};
 use crate::player::{PlayerRef, PlayerRefColumns};
-use crate::schema::{game, game_used_slot};
+use crate::schema::{game, game_chat_message, game_player_result, game_used_slot};
 use bs_diesel_utils::BSDieselEnum;
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
@@ -335,3 +335,196 @@ impl SlotClientStatus {
     }
   }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameResult {
+  pub game_id: i32,
+  pub node_id: i32,
+  pub duration_ms: i32,
+  pub players: Vec<GamePlayerResult>,
+  pub desyncs: Vec<GameDesync>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Queryable)]
+pub struct GamePlayerResult {
+  pub player_id: i32,
+  /// Game time in milliseconds
+  pub left_at_ms: Option<i32>,
+  /// W3GS leave reason, `None` if the player didn't leave the game normally
+  pub leave_reason: Option<i32>,
+  pub disconnects: i32,
+  pub dropped: bool,
+  pub desynced: bool,
+  pub lag_count: i32,
+  pub lag_duration_ms: i32,
+  pub rtt_min: Option<i32>,
+  pub rtt_max: Option<i32>,
+  pub rtt_avg: Option<i32>,
+}
+
+pub(crate) type GamePlayerResultColumns = (
+  game_player_result::dsl::player_id,
+  game_player_result::dsl::left_at_ms,
+  game_player_result::dsl::leave_reason,
+  game_player_result::dsl::disconnects,
+  game_player_result::dsl::dropped,
+  game_player_result::dsl::desynced,
+  game_player_result::dsl::lag_count,
+  game_player_result::dsl::lag_duration_ms,
+  game_player_result::dsl::rtt_min,
+  game_player_result::dsl::rtt_max,
+  game_player_result::dsl::rtt_avg,
+);
+
+impl GamePlayerResult {
+  pub(crate) const COLUMNS: GamePlayerResultColumns = (
+    game_player_result::dsl::player_id,
+    game_player_result::dsl::left_at_ms,
+    game_player_result::dsl::leave_reason,
+    game_player_result::dsl::disconnects,
+    game_player_result::dsl::dropped,
+    game_player_result::dsl::desynced,
+    game_player_result::dsl::lag_count,
+    game_player_result::dsl::lag_duration_ms,
+    game_player_result::dsl::rtt_min,
+    game_player_result::dsl::rtt_max,
+    game_player_result::dsl::rtt_avg,
+  );
+}
+
+impl From<flo_net::proto::flo_node::GamePlayerResult> for GamePlayerResult {
+  fn from(v: flo_net::proto::flo_node::GamePlayerResult) -> Self {
+    Self {
+      player_id: v.player_id,
+      left_at_ms: v.left_at_ms.map(|v| v as i32),
+      leave_reason: v.leave_reason.map(|v| v as i32),
+      disconnects: v.disconnects as i32,
+      dropped: v.dropped,
+      desynced: v.desynced,
+      lag_count: v.lag_count as i32,
+      lag_duration_ms: v.lag_duration_ms as i32,
+      rtt_min: v.rtt_min.map(|v| v as i32),
+      rtt_max: v.rtt_max.map(|v| v as i32),
+      rtt_avg: v.rtt_avg.map(|v| v as i32),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameDesync {
+  pub player_id: i32,
+  pub time: u32,
+  pub tick: u32,
+}
+
+impl From<flo_net::proto::flo_node::GameDesync> for GameDesync {
+  fn from(v: flo_net::proto::flo_node::GameDesync) -> Self {
+    Self {
+      player_id: v.player_id,
+      time: v.time,
+      tick: v.tick,
+    }
+  }
+}
+
+/// Game result reported by the node
+#[derive(Debug)]
+pub struct GameResultReport {
+  pub game_id: i32,
+  pub duration_ms: i32,
+  pub players: Vec<GamePlayerResult>,
+  pub desyncs: Vec<GameDesync>,
+}
+
+impl From<flo_net::proto::flo_node::PacketNodeGameResult> for GameResultReport {
+  fn from(pkt: flo_net::proto::flo_node::PacketNodeGameResult) -> Self {
+    Self {
+      game_id: pkt.game_id,
+      duration_ms: pkt.duration_ms as i32,
+      players: pkt.players.into_iter().map(Into::into).collect(),
+      desyncs: pkt.desyncs.into_iter().map(Into::into).collect(),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, BSDieselEnum, S2ProtoEnum)]
+#[repr(i32)]
+#[s2_grpc(proto_enum_type(flo_net::proto::flo_node::GameChatScope))]
+pub enum GameChatScope {
+  Lobby = 0,
+  All = 1,
+  Allies = 2,
+  Observers = 3,
+  Private = 4,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, BSDieselEnum, S2ProtoEnum)]
+#[repr(i32)]
+#[s2_grpc(proto_enum_type(flo_net::proto::flo_node::GameChatFilterAction))]
+pub enum GameChatFilterAction {
+  None = 0,
+  /// Relayed with the filtered words masked
+  Replaced = 1,
+  Dropped = 2,
+  RateLimited = 3,
+  /// Sent by a muted or chat banned player
+  Muted = 4,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Queryable)]
+pub struct GameChatMessage {
+  pub id: i32,
+  pub player_id: i32,
+  pub scope: GameChatScope,
+  /// Target of private messages
+  pub to_player_id: Option<i32>,
+  pub game_time_ms: i32,
+  pub sent_at: DateTime<Utc>,
+  /// The original text, before filtering
+  pub message: String,
+  pub filter_action: GameChatFilterAction,
+}
+
+pub(crate) type GameChatMessageColumns = (
+  game_chat_message::dsl::id,
+  game_chat_message::dsl::player_id,
+  game_chat_message::dsl::scope,
+  game_chat_message::dsl::to_player_id,
+  game_chat_message::dsl::game_time_ms,
+  game_chat_message::dsl::sent_at,
+  game_chat_message::dsl::message,
+  game_chat_message::dsl::filter_action,
+);
+
+impl GameChatMessage {
+  pub(crate) const COLUMNS: GameChatMessageColumns = (
+    game_chat_message::dsl::id,
+    game_chat_message::dsl::player_id,
+    game_chat_message::dsl::scope,
+    game_chat_message::dsl::to_player_id,
+    game_chat_message::dsl::game_time_ms,
+    game_chat_message::dsl::sent_at,
+    game_chat_message::dsl::message,
+    game_chat_message::dsl::filter_action,
+  );
+}
+
+/// Chat log of a game reported by the node, large logs are split into multiple reports
+#[derive(Debug)]
+pub struct GameChatLogReport {
+  pub game_id: i32,
+  pub messages: Vec<flo_net::proto::flo_node::GameChatMessage>,
+  /// Only set in the first report of a game
+  pub violations: Vec<flo_net::proto::flo_node::GameChatViolationCount>,
+}
+
+impl From<flo_net::proto::flo_node::PacketNodeGameChatLog> for GameChatLogReport {
+  fn from(pkt: flo_net::proto::flo_node::PacketNodeGameChatLog) -> Self {
+    Self {
+      game_id: pkt.game_id,
+      messages: pkt.messages,
+      violations: pkt.violations,
+    }
+  }
+}
@@ -1,4 +1,5 @@
-use crate::config::{ApiRequestExt, GetInterceptor};
+use crate::config::{ApiRequestExt, GetInterceptor, MethodPathService};
+use crate::db::ExecutorError;
 use crate::error::{Error, Result};
 use crate::game::db::{CreateGameAsBotParams, CreateGameParams};
 use crate::game::messages::{CreateGame, PlayerJoin, PlayerLeave};
@@ -11,22 +12,23 @@ use crate::node::messages::ListNode;
 use crate::player::state::ping::GetPlayersPingSnapshot;
 use crate::player::{PlayerBanType, PlayerSource, SourceState};
 use crate::state::{ActorMapExt, ControllerStateRef};
-use bs_diesel_utils::executor::ExecutorError;
 use chrono::{DateTime, Utc};
 use flo_grpc::controller::flo_controller_server::*;
 use flo_grpc::controller::*;
 use s2_grpc_utils::{S2ProtoEnum, S2ProtoPack, S2ProtoUnpack};
-use std::net::{Ipv4Addr, SocketAddrV4};
+use serde_json::json;
+use std::net::SocketAddrV4;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
 
 pub async fn serve(state: ControllerStateRef) -> Result<()> {
-  let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, flo_constants::CONTROLLER_GRPC_PORT);
+  let settings = crate::config::settings();
+  let addr = SocketAddrV4::new(settings.bind_address, settings.grpc_port);
   let server_impl = FloControllerService::new(state.clone());
 
   let interceptor = state.config.send(GetInterceptor).await?;
   let server = FloControllerServer::with_interceptor(server_impl, interceptor);
-  let server = Server::builder().add_service(server);
+  let server = Server::builder().add_service(MethodPathService(server));
   server.serve(addr.into()).await?;
   Ok(())
 }
@@ -39,6 +41,25 @@ impl FloControllerService {
   pub fn new(state: ControllerStateRef) -> Self {
     FloControllerService { state }
   }
+
+  /// Records a privileged call and its outcome in the audit log.
+  /// Failing to write the record doesn't fail the call.
+  async fn audit<T>(
+    &self,
+    api_client_id: i32,
+    method: &'static str,
+    params: serde_json::Value,
+    res: &Result<T, Status>,
+  ) {
+    crate::api_client::db::audit(
+      &self.state.db,
+      api_client_id,
+      method,
+      params,
+      res.as_ref().err(),
+    )
+    .await
+  }
 }
 
 #[tonic::async_trait]
@@ -366,16 +387,26 @@ impl FloController for FloControllerService {
     &self,
     request: Request<ImportMapChecksumsRequest>,
   ) -> Result<Response<ImportMapChecksumsReply>, Status> {
+    let api_client_id = request.get_api_client_id();
     let items =
       Vec::<crate::map::db::ImportItem>::unpack(request.into_inner().items).map_err(Error::from)?;
-    let updated = self
+    let count = items.len();
+    let res = self
       .state
       .db
       .exec(move |conn| crate::map::db::import(conn, items))
       .await
-      .map_err(Error::from)?;
+      .map_err(|err| Status::from(Error::from(err)));
+    self
+      .audit(
+        api_client_id,
+        "ImportMapChecksums",
+        json!({ "items": count, "updated": res.as_ref().ok() }),
+        &res,
+      )
+      .await;
     Ok(Response::new(ImportMapChecksumsReply {
-      updated: updated as u32,
+      updated: res? as u32,
     }))
   }
 
@@ -528,8 +559,11 @@ impl FloController for FloControllerService {
     Ok(Response::new(()))
   }
 
-  async fn reload(&self, _request: Request<()>) -> Result<Response<()>, Status> {
-    self.state.reload().await?;
+  async fn reload(&self, request: Request<()>) -> Result<Response<()>, Status> {
+    let api_client_id = request.get_api_client_id();
+    let res = self.state.reload().await.map_err(Status::from);
+    self.audit(api_client_id, "Reload", json!({}), &res).await;
+    res?;
     Ok(Response::new(()))
   }
 
@@ -547,8 +581,14 @@ impl FloController for FloControllerService {
       })
       .await
       .map_err(Error::from)?;
+    // flo-grpc only has the chat ban type
+    let player_bans: Vec<_> = res
+      .player_bans
+      .into_iter()
+      .filter(|ban| ban.ban_type == PlayerBanType::Chat)
+      .collect();
     Ok(Response::new(ListPlayerBansReply {
-      player_bans: res.player_bans.pack().map_err(Status::internal)?,
+      player_bans: player_bans.pack().map_err(Status::internal)?,
       next_id: res.next_id,
     }))
   }
@@ -565,7 +605,12 @@ impl FloController for FloControllerService {
       .map(|t| DateTime::<Utc>::unpack(t))
       .transpose()
       .map_err(Status::internal)?;
-    self
+    let audit_params = json!({
+      "player_id": params.player_id,
+      "ban_type": params.ban_type,
+      "ban_expires_at": ban_expires_at,
+    });
+    let res = self
       .state
       .db
       .exec(move |conn| {
@@ -573,12 +618,18 @@ impl FloController for FloControllerService {
         crate::player::db::create_ban(
           conn,
           params.player_id,
-          PlayerBanType::unpack_enum(params.ban_type()),
+          PlayerBanType::from(params.ban_type()),
           ban_expires_at,
+          None,
+          Some(api_client_id),
         )
       })
       .await
-      .map_err(Error::from)?;
+      .map_err(|err| Status::from(Error::from(err)));
+    self
+      .audit(api_client_id, "CreatePlayerBan", audit_params, &res)
+      .await;
+    res?;
     Ok(Response::new(()))
   }
 
@@ -587,16 +638,20 @@ impl FloController for FloControllerService {
     request: Request<RemovePlayerBanRequest>,
   ) -> Result<Response<()>, Status> {
     let api_client_id = request.get_api_client_id();
-    let params = request.into_inner();
-    self
+    let id = request.into_inner().id;
+    let res = self
       .state
       .db
       .exec(move |conn| {
-        crate::player::db::check_ban_api_client_id(conn, api_client_id, params.id)?;
-        crate::player::db::remove_ban(conn, params.id)
+        crate::player::db::check_ban_api_client_id(conn, api_client_id, id)?;
+        crate::player::db::remove_ban(conn, id)
       })
       .await
-      .map_err(Error::from)?;
+      .map_err(|err| Status::from(Error::from(err)));
+    self
+      .audit(api_client_id, "RemovePlayerBan", json!({ "id": id }), &res)
+      .await;
+    res?;
     Ok(Response::new(()))
   }
 }
@@ -0,0 +1,166 @@
+//! Self-contained setup for running the controller and a node in the same process
+//! on a local network.
+//!
+//! A single API client and a single node are created on startup.
+//! Players connect with a `lan:<name>` token and are created on their first connection.
+
+use diesel::prelude::*;
+
+use crate::db::DbConn;
+use crate::error::*;
+use crate::player::db::UpsertPlayer;
+use crate::player::PlayerSource;
+use crate::schema::{api_client, node};
+
+pub const LAN_TOKEN_PREFIX: &str = "lan:";
+const LAN_API_CLIENT_NAME: &str = "lan";
+const LAN_NODE_NAME: &str = "LAN";
+const MAX_PLAYER_NAME_LEN: usize = 15;
+
+#[derive(Debug, Clone)]
+pub struct LanConfig {
+  /// Address players use to connect to the node.
+  pub node_ip_addr: String,
+  pub node_secret: String,
+  pub api_client_secret: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LanSetup {
+  pub api_client_id: i32,
+  pub node_id: i32,
+}
+
+/// Creates or updates the API client and the node.
+pub fn setup(conn: &DbConn, config: &LanConfig) -> Result<LanSetup> {
+  db_dispatch!(conn, {
+    conn.transaction(|| -> Result<_> {
+      let api_client_id = match api_client::table
+        .filter(api_client::name.eq(LAN_API_CLIENT_NAME))
+        .select(api_client::id)
+        .first::<i32>(conn)
+        .optional()?
+      {
+        Some(id) => {
+          diesel::update(api_client::table.find(id))
+            .set(api_client::secret_key.eq(&config.api_client_secret))
+            .execute(conn)?;
+          id
+        }
+        None => {
+          diesel::insert_into(api_client::table)
+            .values((
+              api_client::name.eq(LAN_API_CLIENT_NAME),
+              api_client::secret_key.eq(&config.api_client_secret),
+            ))
+            .execute(conn)?;
+          api_client::table
+            .filter(api_client::name.eq(LAN_API_CLIENT_NAME))
+            .select(api_client::id)
+            .first(conn)?
+        }
+      };
+
+      let node_id = match node::table
+        .filter(node::name.eq(LAN_NODE_NAME))
+        .select(node::id)
+        .first::<i32>(conn)
+        .optional()?
+      {
+        Some(id) => {
+          diesel::update(node::table.find(id))
+            .set((
+              node::secret.eq(&config.node_secret),
+              node::ip_addr.eq(&config.node_ip_addr),
+              node::disabled.eq(false),
+            ))
+            .execute(conn)?;
+          id
+        }
+        None => {
+          diesel::insert_into(node::table)
+            .values((
+              node::name.eq(LAN_NODE_NAME),
+              node::location.eq(LAN_NODE_NAME),
+              node::secret.eq(&config.node_secret),
+              node::ip_addr.eq(&config.node_ip_addr),
+            ))
+            .execute(conn)?;
+          node::table
+            .filter(node::name.eq(LAN_NODE_NAME))
+            .select(node::id)
+            .first(conn)?
+        }
+      };
+
+      Ok(LanSetup {
+        api_client_id,
+        node_id,
+      })
+    })
+  })
+}
+
+/// Extracts the player name from a `lan:<name>` token.
+pub fn parse_token(token: &str) -> Option<&str> {
+  let name = token.strip_prefix(LAN_TOKEN_PREFIX)?.trim();
+  if name.is_empty() || name.chars().count() > MAX_PLAYER_NAME_LEN {
+    return None;
+  }
+  Some(name)
+}
+
+/// Returns the id of the player, players are identified by their case-insensitive names.
+pub fn get_or_create_player(conn: &DbConn, api_client_id: i32, name: String) -> Result<i32> {
+  let player = crate::player::db::upsert(
+    conn,
+    &UpsertPlayer {
+      api_client_id,
+      source_id: format!("{}{}", LAN_TOKEN_PREFIX, name.to_lowercase()),
+      name,
+      source: PlayerSource::Api,
+      source_state: None,
+      realm: None,
+    },
+  )?;
+  Ok(player.id)
+}
+
+#[test]
+fn test_parse_token() {
+  assert_eq!(parse_token("lan:Alice"), Some("Alice"));
+  assert_eq!(parse_token("lan: Bob "), Some("Bob"));
+  assert_eq!(parse_token("lan:"), None);
+  assert_eq!(parse_token("lan:ABCDEFGHIJKLMNOP"), None);
+  assert_eq!(parse_token("eyJhbGciOiJIUzI1NiJ9"), None);
+}
+
+/// Creates the LAN API client and node, used as fixtures by database tests
+#[cfg(test)]
+pub(crate) fn setup_test(conn: &DbConn) -> Result<LanSetup> {
+  setup(
+    conn,
+    &LanConfig {
+      node_ip_addr: "192.168.1.2".to_string(),
+      node_secret: "node".to_string(),
+      api_client_secret: "api".to_string(),
+    },
+  )
+}
+
+#[tokio::test]
+async fn test_setup_sqlite() {
+  let (setup, again, alice, alice_again, bob) = crate::db::exec_test(|conn| {
+    let setup = setup_test(conn)?;
+    let again = setup_test(conn)?;
+    let alice = get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let alice_again = get_or_create_player(conn, setup.api_client_id, "alice".to_string())?;
+    let bob = get_or_create_player(conn, setup.api_client_id, "Bob".to_string())?;
+    Ok((setup, again, alice, alice_again, bob))
+  })
+  .await;
+  assert_eq!(setup.api_client_id, again.api_client_id);
+  assert_eq!(setup.node_id, again.node_id);
+  assert_eq!(alice, alice_again);
+  assert_ne!(alice, bob);
+}
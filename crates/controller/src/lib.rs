@@ -1,7 +1,5 @@
-#[cfg(not(debug_assertions))]
 #[macro_use]
 extern crate diesel_migrations;
-#[cfg(not(debug_assertions))]
 pub mod migration;
 
 #[macro_use]
@@ -11,19 +9,24 @@ mod version;
 #[macro_use]
 extern crate diesel;
 
+mod api_client;
 mod db;
 mod schema;
 
 mod client;
 mod config;
 pub mod error;
+pub mod event;
 pub mod game;
 mod grpc;
 pub mod host;
+pub mod lan;
 pub mod map;
+pub mod matchmaking;
 pub mod node;
 pub mod player;
 mod state;
+pub mod webhook;
 
 pub use client::serve as serve_socket;
 pub use grpc::serve as serve_grpc;
@@ -1 +1,10 @@
-
+/// Evaluates `$body` with `$conn` rebound to the concrete connection of its backend,
+/// so the same diesel query compiles for every backend.
+macro_rules! db_dispatch {
+  ($conn:ident, $body:expr) => {
+    match crate::db::DbConnection::backend($conn) {
+      crate::db::Backend::Pg($conn) => $body,
+      crate::db::Backend::Sqlite($conn) => $body,
+    }
+  };
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use flo_w3map::{MapChecksum, W3Map};
+
+use super::{Map, MapForce, MapPlayer, MapSha1};
+use crate::error::*;
+
+/// Uploads are buffered in memory, real world maps are far below this
+pub const MAX_MAP_FILE_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct MapCatalogEntry {
+  pub id: i32,
+  pub map: Map,
+  pub crc32: u32,
+  pub file_size: i32,
+  pub suggested_players: String,
+  pub flags: u32,
+  pub has_preview: bool,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// Metadata extracted from an uploaded map file
+#[derive(Debug)]
+pub struct ParsedMap {
+  pub checksum: MapChecksum,
+  pub path: String,
+  pub name: String,
+  pub description: String,
+  pub author: String,
+  pub suggested_players: String,
+  pub width: u32,
+  pub height: u32,
+  pub flags: u32,
+  pub players: Vec<MapPlayer>,
+  pub forces: Vec<MapForce>,
+  pub preview: Option<Vec<u8>>,
+}
+
+impl ParsedMap {
+  /// Reads map info and computes the checksum
+  ///
+  /// This is CPU bound, call it from a blocking task
+  pub fn parse(path: String, bytes: &[u8]) -> Result<Self> {
+    if bytes.is_empty() {
+      return Err(Error::MapFileInvalid("empty file".to_string()));
+    }
+
+    if bytes.len() > MAX_MAP_FILE_SIZE {
+      return Err(Error::MapFileTooLarge);
+    }
+
+    let (map, checksum) =
+      W3Map::open_memory_with_checksum(bytes).map_err(|e| Error::MapFileInvalid(e.to_string()))?;
+    let (width, height) = map.dimension();
+    let players: Vec<_> = map
+      .get_players()
+      .into_iter()
+      .map(|p| MapPlayer {
+        name: p.name.to_string(),
+        r#type: p.r#type,
+        race: p.race,
+        flags: p.flags,
+      })
+      .collect();
+
+    if players.is_empty() {
+      return Err(Error::MapHasNoPlayer);
+    }
+
+    let forces = map
+      .get_forces()
+      .into_iter()
+      .map(|f| MapForce {
+        name: f.name.to_string(),
+        flags: f.flags,
+        player_set: f.player_set,
+      })
+      .collect();
+    let preview = Some(map.render_preview_png()).filter(|bytes| !bytes.is_empty());
+
+    Ok(Self {
+      path,
+      name: map.name().to_string(),
+      description: map.description().to_string(),
+      author: map.author().to_string(),
+      suggested_players: map.suggested_players().to_string(),
+      width,
+      height,
+      flags: map.flags().bits(),
+      players,
+      forces,
+      preview,
+      checksum,
+    })
+  }
+
+  pub fn sha1(&self) -> String {
+    self.checksum.get_sha1_hex_string()
+  }
+}
+
+impl MapSha1 {
+  pub fn from_hex_str(value: &str) -> Option<Self> {
+    if value.len() != 40 {
+      return None;
+    }
+    let mut bytes = [0_u8; 20];
+    for (i, b) in bytes.iter_mut().enumerate() {
+      *b = u8::from_str_radix(value.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(MapSha1(bytes))
+  }
+}
+
+#[test]
+fn test_sha1_hex() {
+  let sha1 = MapSha1::from_hex_str("00010aff102030405060708090a0b0c0d0e0f07f").unwrap();
+  assert_eq!(
+    sha1.0,
+    [
+      0x00, 0x01, 0x0a, 0xff, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xa0, 0xb0,
+      0xc0, 0xd0, 0xe0, 0xf0, 0x7f,
+    ]
+  );
+  assert!(MapSha1::from_hex_str("0001").is_none());
+  assert!(MapSha1::from_hex_str(&"zz".repeat(20)).is_none());
+}
@@ -1,28 +1,37 @@
+use chrono::{DateTime, Utc};
+use diesel::dsl::sql;
 use diesel::prelude::*;
 use s2_grpc_utils::S2ProtoUnpack;
 use serde::Deserialize;
+use serde_json::Value;
 
-use crate::db::DbConn;
+use super::catalog::{MapCatalogEntry, ParsedMap};
+use super::{Map, MapSha1};
+use crate::db::{lower, sqlite_last_insert_id, Backend, DbConn};
 use crate::error::*;
-use crate::schema::map_checksum;
+use crate::schema::{game, map_catalog, map_checksum};
 
 pub fn search_checksum(conn: &DbConn, sha1: String) -> Result<Option<u32>> {
-  use map_checksum::dsl;
-  let value = map_checksum::table
-    .filter(dsl::sha1.eq(sha1))
-    .select(dsl::checksum)
-    .first::<Vec<u8>>(conn)
-    .optional()?
-    .and_then(|bytes| {
-      if bytes.len() == 4 {
-        let mut b = [0_u8; 4];
-        b.copy_from_slice(&bytes[0..4]);
-        Some(u32::from_le_bytes(b))
-      } else {
-        None
-      }
-    });
-  Ok(value)
+  db_dispatch!(conn, {
+    use map_checksum::dsl;
+    let value = map_checksum::table
+      .filter(dsl::sha1.eq(sha1))
+      .select(dsl::checksum)
+      .first::<Vec<u8>>(conn)
+      .optional()?
+      .and_then(|bytes| u32_from_le_bytes(&bytes));
+    Ok(value)
+  })
+}
+
+fn u32_from_le_bytes(bytes: &[u8]) -> Option<u32> {
+  if bytes.len() == 4 {
+    let mut b = [0_u8; 4];
+    b.copy_from_slice(&bytes[0..4]);
+    Some(u32::from_le_bytes(b))
+  } else {
+    None
+  }
 }
 
 #[derive(Debug, Deserialize, S2ProtoUnpack)]
@@ -33,7 +42,6 @@ pub struct ImportItem {
 }
 
 pub fn import(conn: &DbConn, mut items: Vec<ImportItem>) -> Result<usize> {
-  use diesel::pg::upsert::excluded;
   use map_checksum::dsl;
 
   items.sort_by_cached_key(|i| i.sha1.clone());
@@ -47,13 +55,23 @@ pub fn import(conn: &DbConn, mut items: Vec<ImportItem>) -> Result<usize> {
     })
     .collect();
 
-  diesel::insert_into(map_checksum::table)
-    .values(inserts)
-    .on_conflict(dsl::sha1)
-    .do_update()
-    .set(dsl::checksum.eq(excluded(dsl::checksum)))
-    .execute(conn)
-    .map_err(Into::into)
+  match conn.backend() {
+    Backend::Pg(conn) => {
+      use diesel::pg::upsert::excluded;
+      diesel::insert_into(map_checksum::table)
+        .values(inserts)
+        .on_conflict(dsl::sha1)
+        .do_update()
+        .set(dsl::checksum.eq(excluded(dsl::checksum)))
+        .execute(conn)
+        .map_err(Into::into)
+    }
+    // the conflicting row is deleted and inserted again
+    Backend::Sqlite(conn) => diesel::replace_into(map_checksum::table)
+      .values(inserts)
+      .execute(conn)
+      .map_err(Into::into),
+  }
 }
 
 #[derive(Debug, Insertable)]
@@ -62,3 +80,301 @@ struct Insert<'a> {
   sha1: &'a str,
   checksum: Vec<u8>,
 }
+
+/// Adds a map into the catalog, or returns the existing entry if the same file was uploaded before
+///
+/// The checksum is also imported into `map_checksum`.
+pub fn upsert_catalog(
+  conn: &DbConn,
+  uploaded_by: i32,
+  parsed: ParsedMap,
+) -> Result<MapCatalogEntry> {
+  db_dispatch!(conn, {
+    let sha1 = parsed.sha1();
+    let insert = CatalogInsert {
+      sha1: &sha1,
+      checksum: parsed.checksum.xoro.to_le_bytes().to_vec(),
+      crc32: parsed.checksum.crc32.to_le_bytes().to_vec(),
+      file_size: parsed.checksum.file_size as i32,
+      path: &parsed.path,
+      name: &parsed.name,
+      description: &parsed.description,
+      author: &parsed.author,
+      suggested_players: &parsed.suggested_players,
+      width: parsed.width as i32,
+      height: parsed.height as i32,
+      flags: parsed.flags as i32,
+      players: serde_json::to_value(&parsed.players)?,
+      forces: serde_json::to_value(&parsed.forces)?,
+      preview: parsed.preview.as_ref().map(AsRef::as_ref),
+      uploaded_by,
+    };
+
+    conn.transaction(|| {
+      let id = insert_catalog(conn, &insert)?;
+      import(
+        conn,
+        vec![ImportItem {
+          sha1: sha1.clone(),
+          checksum: parsed.checksum.xoro,
+        }],
+      )?;
+      get_catalog(conn, id)
+    })
+  })
+}
+
+fn insert_catalog(conn: &DbConn, insert: &CatalogInsert) -> Result<i32> {
+  use map_catalog::dsl;
+  let id = match conn.backend() {
+    Backend::Pg(conn) => {
+      use diesel::pg::upsert::excluded;
+      diesel::insert_into(map_catalog::table)
+        .values(insert)
+        .on_conflict(dsl::sha1)
+        .do_update()
+        .set(dsl::path.eq(excluded(dsl::path)))
+        .returning(dsl::id)
+        .get_result(conn)?
+    }
+    Backend::Sqlite(conn) => conn.transaction(|| -> Result<_> {
+      let id = map_catalog::table
+        .filter(dsl::sha1.eq(insert.sha1))
+        .select(dsl::id)
+        .first::<i32>(conn)
+        .optional()?;
+      if let Some(id) = id {
+        diesel::update(map_catalog::table.find(id))
+          .set(dsl::path.eq(insert.path))
+          .execute(conn)?;
+        Ok(id)
+      } else {
+        diesel::insert_into(map_catalog::table)
+          .values(insert)
+          .execute(conn)?;
+        Ok(sqlite_last_insert_id(conn)?)
+      }
+    })?,
+  };
+  Ok(id)
+}
+
+pub fn get_catalog(conn: &DbConn, id: i32) -> Result<MapCatalogEntry> {
+  db_dispatch!(conn, {
+    map_catalog::table
+      .find(id)
+      .select(CatalogRow::columns())
+      .first::<CatalogRow>(conn)
+      .optional()?
+      .ok_or_else(|| Error::MapNotFound)?
+      .into_entry()
+  })
+}
+
+/// Resolves the map of a create game request, a catalog map id takes precedence
+pub fn resolve_map(
+  conn: &DbConn,
+  map: Option<Map>,
+  map_id: Option<i32>,
+) -> Result<(Map, Option<i32>)> {
+  match (map_id, map) {
+    (Some(id), _) => Ok((get_catalog(conn, id)?.map, Some(id))),
+    (None, Some(map)) => Ok((map, None)),
+    (None, None) => Err(Error::MapRequired),
+  }
+}
+
+pub fn get_catalog_preview(conn: &DbConn, id: i32) -> Result<Option<Vec<u8>>> {
+  db_dispatch!(conn, {
+    map_catalog::table
+      .find(id)
+      .select(map_catalog::dsl::preview)
+      .first(conn)
+      .optional()?
+      .ok_or_else(|| Error::MapNotFound)
+  })
+}
+
+/// Returns the catalog sha1 if the game was created from a catalog map
+pub fn get_game_catalog_sha1(conn: &DbConn, game_id: i32) -> Result<Option<String>> {
+  db_dispatch!(conn, {
+    game::table
+      .inner_join(map_catalog::table)
+      .filter(game::dsl::id.eq(game_id))
+      .select(map_catalog::dsl::sha1)
+      .first(conn)
+      .optional()
+      .map_err(Into::into)
+  })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryCatalogParams {
+  pub keyword: Option<String>,
+  pub take: Option<i64>,
+  pub since_id: Option<i32>,
+}
+
+#[derive(Debug)]
+pub struct QueryCatalog {
+  pub maps: Vec<MapCatalogEntry>,
+  pub has_more: bool,
+}
+
+pub fn query_catalog(conn: &DbConn, params: &QueryCatalogParams) -> Result<QueryCatalog> {
+  db_dispatch!(conn, {
+    use map_catalog::dsl;
+
+    let take = std::cmp::min(100, params.take.clone().unwrap_or(30));
+
+    let mut q = map_catalog::table
+      .select(CatalogRow::columns())
+      .order(dsl::id.desc())
+      .limit(take + 1)
+      .into_boxed();
+
+    if let Some(ref keyword) = params.keyword {
+      let like = format!("%{}%", keyword.trim().to_lowercase());
+      q = q.filter(
+        lower(dsl::name)
+          .like(like.clone())
+          .or(lower(dsl::path).like(like)),
+      );
+    }
+
+    if let Some(id) = params.since_id.clone() {
+      q = q.filter(dsl::id.lt(id))
+    }
+
+    let mut rows: Vec<CatalogRow> = q.load(conn)?;
+
+    let has_more = rows.len() > take as usize;
+    if has_more {
+      rows.truncate(take as usize);
+    }
+
+    Ok(QueryCatalog {
+      maps: rows
+        .into_iter()
+        .map(CatalogRow::into_entry)
+        .collect::<Result<_>>()?,
+      has_more,
+    })
+  })
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "map_catalog"]
+struct CatalogInsert<'a> {
+  sha1: &'a str,
+  checksum: Vec<u8>,
+  crc32: Vec<u8>,
+  file_size: i32,
+  path: &'a str,
+  name: &'a str,
+  description: &'a str,
+  author: &'a str,
+  suggested_players: &'a str,
+  width: i32,
+  height: i32,
+  flags: i32,
+  players: Value,
+  forces: Value,
+  preview: Option<&'a [u8]>,
+  uploaded_by: i32,
+}
+
+#[derive(Debug, Queryable)]
+struct CatalogRow {
+  id: i32,
+  sha1: String,
+  checksum: Vec<u8>,
+  crc32: Vec<u8>,
+  file_size: i32,
+  path: String,
+  name: String,
+  description: String,
+  author: String,
+  suggested_players: String,
+  width: i32,
+  height: i32,
+  flags: i32,
+  players: Value,
+  forces: Value,
+  has_preview: bool,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+type CatalogRowColumns = (
+  map_catalog::id,
+  map_catalog::sha1,
+  map_catalog::checksum,
+  map_catalog::crc32,
+  map_catalog::file_size,
+  map_catalog::path,
+  map_catalog::name,
+  map_catalog::description,
+  map_catalog::author,
+  map_catalog::suggested_players,
+  map_catalog::width,
+  map_catalog::height,
+  map_catalog::flags,
+  map_catalog::players,
+  map_catalog::forces,
+  diesel::expression::SqlLiteral<diesel::sql_types::Bool>,
+  map_catalog::created_at,
+  map_catalog::updated_at,
+);
+
+impl CatalogRow {
+  fn columns() -> CatalogRowColumns {
+    (
+      map_catalog::id,
+      map_catalog::sha1,
+      map_catalog::checksum,
+      map_catalog::crc32,
+      map_catalog::file_size,
+      map_catalog::path,
+      map_catalog::name,
+      map_catalog::description,
+      map_catalog::author,
+      map_catalog::suggested_players,
+      map_catalog::width,
+      map_catalog::height,
+      map_catalog::flags,
+      map_catalog::players,
+      map_catalog::forces,
+      sql("preview is not null"),
+      map_catalog::created_at,
+      map_catalog::updated_at,
+    )
+  }
+
+  fn into_entry(self) -> Result<MapCatalogEntry> {
+    Ok(MapCatalogEntry {
+      id: self.id,
+      map: Map {
+        sha1: MapSha1::from_hex_str(&self.sha1)
+          .ok_or_else(|| Error::MapFileInvalid(format!("invalid sha1: {}", self.sha1)))?,
+        checksum: u32_from_le_bytes(&self.checksum)
+          .ok_or_else(|| Error::MapFileInvalid("invalid checksum".to_string()))?,
+        name: self.name,
+        description: self.description,
+        author: self.author,
+        path: self.path,
+        width: self.width as u32,
+        height: self.height as u32,
+        players: serde_json::from_value(self.players)?,
+        forces: serde_json::from_value(self.forces)?,
+      },
+      crc32: u32_from_le_bytes(&self.crc32).unwrap_or_default(),
+      file_size: self.file_size,
+      suggested_players: self.suggested_players,
+      flags: self.flags as u32,
+      has_preview: self.has_preview,
+      created_at: self.created_at,
+      updated_at: self.updated_at,
+    })
+  }
+}
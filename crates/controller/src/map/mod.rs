@@ -1,3 +1,4 @@
+pub mod catalog;
 pub mod db;
 
 use s2_grpc_utils::result::Error as ProtoError;
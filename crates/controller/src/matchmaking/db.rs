@@ -0,0 +1,338 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde_json::Value;
+
+use crate::db::{Backend, DbConn};
+use crate::error::*;
+use crate::matchmaking::{MatchmakingQueue, MatchmakingRating, UpsertMatchmakingQueueParams};
+use crate::player::{PlayerBanType, PlayerSource};
+use crate::schema::{game, map_catalog, matchmaking_queue, matchmaking_rating, player};
+
+pub fn get_all_queues(conn: &DbConn) -> Result<Vec<MatchmakingQueue>> {
+  db_dispatch!(conn, {
+    matchmaking_queue::table
+      .order(matchmaking_queue::id)
+      .load::<Row>(conn)?
+      .into_iter()
+      .map(Row::into_queue)
+      .collect()
+  })
+}
+
+pub fn list_queues(conn: &DbConn, api_client_id: i32) -> Result<Vec<MatchmakingQueue>> {
+  db_dispatch!(conn, {
+    matchmaking_queue::table
+      .filter(matchmaking_queue::api_client_id.eq(api_client_id))
+      .order(matchmaking_queue::id)
+      .load::<Row>(conn)?
+      .into_iter()
+      .map(Row::into_queue)
+      .collect()
+  })
+}
+
+/// Creates or updates the queue with the same name
+pub fn upsert_queue(
+  conn: &DbConn,
+  api_client_id: i32,
+  params: UpsertMatchmakingQueueParams,
+) -> Result<MatchmakingQueue> {
+  db_dispatch!(conn, {
+    if params.team_size < 1 || params.num_teams < 2 {
+      return Err(Error::MatchmakingQueueInvalid(
+        "at least 2 teams with 1 player are required".to_string(),
+      ));
+    }
+
+    if params.map_pool.is_empty() {
+      return Err(Error::MatchmakingQueueInvalid(
+        "map pool is empty".to_string(),
+      ));
+    }
+
+    if params.rating_window_initial < 0
+      || params.rating_window_growth < 0
+      || params.rating_window_max < params.rating_window_initial
+    {
+      return Err(Error::MatchmakingQueueInvalid(
+        "invalid rating window".to_string(),
+      ));
+    }
+
+    if params.max_ping < 0 || params.accept_timeout_secs < 1 || params.decline_ban_secs < 0 {
+      return Err(Error::MatchmakingQueueInvalid(
+        "invalid timeout or ping limit".to_string(),
+      ));
+    }
+
+    let num_players = params.team_size * params.num_teams;
+    let maps: Vec<(i32, Value)> = map_catalog::table
+      .filter(map_catalog::id.eq_any(&params.map_pool[..]))
+      .select((map_catalog::id, map_catalog::players))
+      .load(conn)?;
+    for id in &params.map_pool {
+      let players = maps
+        .iter()
+        .find(|(map_id, _)| map_id == id)
+        .and_then(|(_, players)| players.as_array().map(|v| v.len() as i32))
+        .ok_or_else(|| Error::MatchmakingQueueInvalid(format!("map not found: {}", id)))?;
+      if players < num_players {
+        return Err(Error::MatchmakingQueueInvalid(format!(
+          "map {} has only {} player slots",
+          id, players
+        )));
+      }
+    }
+
+    let insert = QueueInsert {
+      api_client_id,
+      name: &params.name,
+      team_size: params.team_size,
+      num_teams: params.num_teams,
+      map_pool: serde_json::to_value(&params.map_pool)?,
+      default_rating: params.default_rating,
+      rating_window_initial: params.rating_window_initial,
+      rating_window_growth: params.rating_window_growth,
+      rating_window_max: params.rating_window_max,
+      max_ping: params.max_ping,
+      accept_timeout_secs: params.accept_timeout_secs,
+      decline_ban_secs: params.decline_ban_secs,
+      enabled: params.enabled,
+    };
+
+    upsert_queue_row(conn, &insert)?.into_queue()
+  })
+}
+
+fn upsert_queue_row(conn: &DbConn, insert: &QueueInsert) -> Result<Row> {
+  use matchmaking_queue::dsl;
+  let row = match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(matchmaking_queue::table)
+      .values(insert)
+      .on_conflict((dsl::api_client_id, dsl::name))
+      .do_update()
+      .set(insert)
+      .get_result::<Row>(conn)?,
+    Backend::Sqlite(conn) => conn.transaction(|| -> Result<_> {
+      let key = dsl::api_client_id
+        .eq(insert.api_client_id)
+        .and(dsl::name.eq(insert.name));
+      let updated = diesel::update(matchmaking_queue::table.filter(key))
+        .set(insert)
+        .execute(conn)?;
+      if updated == 0 {
+        diesel::insert_into(matchmaking_queue::table)
+          .values(insert)
+          .execute(conn)?;
+      }
+      Ok(matchmaking_queue::table.filter(key).first::<Row>(conn)?)
+    })?,
+  };
+  Ok(row)
+}
+
+/// Sets player ratings of a queue, returns the number of updated players
+pub fn update_ratings(
+  conn: &DbConn,
+  api_client_id: i32,
+  queue_id: i32,
+  items: Vec<MatchmakingRating>,
+) -> Result<usize> {
+  db_dispatch!(conn, {
+    let n: i64 = matchmaking_queue::table
+      .filter(
+        matchmaking_queue::id
+          .eq(queue_id)
+          .and(matchmaking_queue::api_client_id.eq(api_client_id)),
+      )
+      .count()
+      .get_result(conn)?;
+    if n == 0 {
+      return Err(Error::MatchmakingQueueNotFound);
+    }
+
+    let player_ids: Vec<i32> = items.iter().map(|item| item.player_id).collect();
+    let n: i64 = player::table
+      .filter(
+        player::id
+          .eq_any(&player_ids[..])
+          .and(player::api_client_id.eq(api_client_id)),
+      )
+      .count()
+      .get_result(conn)?;
+    if n as usize != player_ids.len() {
+      return Err(Error::PlayerOwnerCheckFailed);
+    }
+
+    let inserts: Vec<_> = items
+      .iter()
+      .map(|item| RatingInsert {
+        queue_id,
+        player_id: item.player_id,
+        rating: item.rating,
+      })
+      .collect();
+
+    upsert_rating_rows(conn, &inserts)
+  })
+}
+
+fn upsert_rating_rows(conn: &DbConn, inserts: &[RatingInsert]) -> Result<usize> {
+  use matchmaking_rating::dsl;
+  let n = match conn.backend() {
+    Backend::Pg(conn) => {
+      use diesel::pg::upsert::excluded;
+      diesel::insert_into(matchmaking_rating::table)
+        .values(inserts)
+        .on_conflict((dsl::queue_id, dsl::player_id))
+        .do_update()
+        .set(dsl::rating.eq(excluded(dsl::rating)))
+        .execute(conn)?
+    }
+    Backend::Sqlite(conn) => conn.transaction(|| -> Result<_> {
+      for insert in inserts {
+        let updated = diesel::update(
+          matchmaking_rating::table.filter(
+            dsl::queue_id
+              .eq(insert.queue_id)
+              .and(dsl::player_id.eq(insert.player_id)),
+          ),
+        )
+        .set(dsl::rating.eq(insert.rating))
+        .execute(conn)?;
+        if updated == 0 {
+          diesel::insert_into(matchmaking_rating::table)
+            .values(insert)
+            .execute(conn)?;
+        }
+      }
+      Ok(inserts.len())
+    })?,
+  };
+  Ok(n)
+}
+
+pub fn get_rating(conn: &DbConn, queue_id: i32, player_id: i32) -> Result<Option<i32>> {
+  db_dispatch!(conn, {
+    use matchmaking_rating::dsl;
+    matchmaking_rating::table
+      .filter(dsl::queue_id.eq(queue_id).and(dsl::player_id.eq(player_id)))
+      .select(dsl::rating)
+      .first(conn)
+      .optional()
+      .map_err(Into::into)
+  })
+}
+
+pub fn ban_players(conn: &DbConn, player_ids: &[i32], ban_expires_at: DateTime<Utc>) -> Result<()> {
+  db_dispatch!(conn, {
+    conn.transaction(|| {
+      for player_id in player_ids {
+        crate::player::db::create_ban(
+          conn,
+          *player_id,
+          PlayerBanType::Matchmaking,
+          Some(ban_expires_at),
+          Some("Did not accept a match".to_string()),
+          None,
+        )?;
+      }
+      Ok(())
+    })
+  })
+}
+
+/// Returns the special player of the api client, see `config::create_api_players`
+pub fn get_api_player_id(conn: &DbConn, api_client_id: i32) -> Result<i32> {
+  db_dispatch!(conn, {
+    player::table
+      .filter(
+        player::api_client_id
+          .eq(api_client_id)
+          .and(player::source.eq(PlayerSource::Api))
+          .and(player::source_id.eq("")),
+      )
+      .select(player::id)
+      .first(conn)
+      .optional()?
+      .ok_or(Error::PlayerNotFound)
+  })
+}
+
+pub fn set_game_queue(conn: &DbConn, game_id: i32, queue_id: i32) -> Result<()> {
+  db_dispatch!(conn, {
+    diesel::update(game::table.find(game_id))
+      .set(game::matchmaking_queue_id.eq(queue_id))
+      .execute(conn)?;
+    Ok(())
+  })
+}
+
+#[derive(Debug, Queryable)]
+struct Row {
+  id: i32,
+  api_client_id: i32,
+  name: String,
+  team_size: i32,
+  num_teams: i32,
+  map_pool: Value,
+  default_rating: i32,
+  rating_window_initial: i32,
+  rating_window_growth: i32,
+  rating_window_max: i32,
+  max_ping: i32,
+  accept_timeout_secs: i32,
+  decline_ban_secs: i32,
+  enabled: bool,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl Row {
+  fn into_queue(self) -> Result<MatchmakingQueue> {
+    Ok(MatchmakingQueue {
+      id: self.id,
+      api_client_id: self.api_client_id,
+      name: self.name,
+      team_size: self.team_size,
+      num_teams: self.num_teams,
+      map_pool: serde_json::from_value(self.map_pool)?,
+      default_rating: self.default_rating,
+      rating_window_initial: self.rating_window_initial,
+      rating_window_growth: self.rating_window_growth,
+      rating_window_max: self.rating_window_max,
+      max_ping: self.max_ping,
+      accept_timeout_secs: self.accept_timeout_secs,
+      decline_ban_secs: self.decline_ban_secs,
+      enabled: self.enabled,
+      created_at: self.created_at,
+      updated_at: self.updated_at,
+    })
+  }
+}
+
+#[derive(Debug, Insertable, AsChangeset)]
+#[table_name = "matchmaking_queue"]
+struct QueueInsert<'a> {
+  api_client_id: i32,
+  name: &'a str,
+  team_size: i32,
+  num_teams: i32,
+  map_pool: Value,
+  default_rating: i32,
+  rating_window_initial: i32,
+  rating_window_growth: i32,
+  rating_window_max: i32,
+  max_ping: i32,
+  accept_timeout_secs: i32,
+  decline_ban_secs: i32,
+  enabled: bool,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "matchmaking_rating"]
+struct RatingInsert {
+  queue_id: i32,
+  player_id: i32,
+  rating: i32,
+}
@@ -0,0 +1,294 @@
+//! Groups queued players into matches.
+//!
+//! Players that waited the longest are matched first. Each player's rating window grows
+//! while waiting, a group is valid if the rating spread fits in the smallest window of the group,
+//! all players share at least one map and there is a node every player can reach within
+//! the ping limit.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The rating window grows by `growth` every `RATING_WINDOW_STEP` of waiting
+pub const RATING_WINDOW_STEP: Duration = Duration::from_secs(10);
+
+/// Used for nodes a player has no ping stats for
+const UNKNOWN_PING: u32 = u32::MAX / 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RatingWindow {
+  pub initial: u32,
+  pub growth: u32,
+  pub max: u32,
+}
+
+impl RatingWindow {
+  pub fn get(&self, waited: Duration) -> u32 {
+    let steps = (waited.as_secs() / RATING_WINDOW_STEP.as_secs()) as u32;
+    self
+      .initial
+      .saturating_add(self.growth.saturating_mul(steps))
+      .min(self.max.max(self.initial))
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchRules {
+  pub team_size: usize,
+  pub num_teams: usize,
+  pub rating_window: RatingWindow,
+  /// 0 = no limit
+  pub max_ping: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+  pub player_id: i32,
+  pub rating: i32,
+  pub waited: Duration,
+  pub maps: Vec<i32>,
+  /// node id -> ping
+  pub pings: BTreeMap<i32, u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Matched {
+  pub teams: Vec<Vec<i32>>,
+  /// Maps in the map pool of every player
+  pub maps: Vec<i32>,
+  pub node_id: i32,
+}
+
+pub fn find_matches(
+  rules: &MatchRules,
+  nodes: &[i32],
+  mut candidates: Vec<Candidate>,
+) -> Vec<Matched> {
+  let group_size = rules.team_size * rules.num_teams;
+  if group_size == 0 || candidates.len() < group_size {
+    return vec![];
+  }
+
+  candidates.sort_by(|a, b| b.waited.cmp(&a.waited).then(a.player_id.cmp(&b.player_id)));
+
+  let mut used = vec![false; candidates.len()];
+  let mut matches = vec![];
+
+  for anchor in 0..candidates.len() {
+    if used[anchor] {
+      continue;
+    }
+
+    let rating = candidates[anchor].rating;
+    let mut others: Vec<usize> = (0..candidates.len())
+      .filter(|i| *i != anchor && !used[*i])
+      .collect();
+    others.sort_by_key(|i| (candidates[*i].rating - rating).abs());
+
+    let mut group = vec![&candidates[anchor]];
+    for i in others {
+      if group.len() == group_size {
+        break;
+      }
+      group.push(&candidates[i]);
+      if !is_compatible(rules, nodes, &group) {
+        group.pop();
+      }
+    }
+
+    if group.len() != group_size {
+      continue;
+    }
+
+    let maps = shared_maps(&group);
+    let node_id = match select_node(rules, nodes, &group) {
+      Some(id) => id,
+      None => continue,
+    };
+    let teams = split_teams(rules, &group);
+    for player_id in teams.iter().flatten() {
+      if let Some(i) = candidates.iter().position(|c| c.player_id == *player_id) {
+        used[i] = true;
+      }
+    }
+    matches.push(Matched {
+      teams,
+      maps,
+      node_id,
+    });
+  }
+
+  matches
+}
+
+fn is_compatible(rules: &MatchRules, nodes: &[i32], group: &[&Candidate]) -> bool {
+  let window = group
+    .iter()
+    .map(|c| rules.rating_window.get(c.waited))
+    .min()
+    .unwrap_or_default();
+  let min = group.iter().map(|c| c.rating).min().unwrap_or_default();
+  let max = group.iter().map(|c| c.rating).max().unwrap_or_default();
+  if (max - min) as u32 > window {
+    return false;
+  }
+
+  !shared_maps(group).is_empty() && select_node(rules, nodes, group).is_some()
+}
+
+fn shared_maps(group: &[&Candidate]) -> Vec<i32> {
+  let mut maps = group.first().map(|c| c.maps.clone()).unwrap_or_default();
+  for c in &group[1..] {
+    maps.retain(|id| c.maps.contains(id));
+  }
+  maps
+}
+
+/// Selects the node with the lowest worst-case ping
+fn select_node(rules: &MatchRules, nodes: &[i32], group: &[&Candidate]) -> Option<i32> {
+  nodes
+    .iter()
+    .map(|node_id| {
+      let ping = group
+        .iter()
+        .map(|c| c.pings.get(node_id).cloned().unwrap_or(UNKNOWN_PING))
+        .max()
+        .unwrap_or(UNKNOWN_PING);
+      (ping, *node_id)
+    })
+    .filter(|(ping, _)| {
+      if rules.max_ping == 0 {
+        true
+      } else {
+        *ping <= rules.max_ping
+      }
+    })
+    .min()
+    .map(|(_, node_id)| node_id)
+}
+
+/// Distributes players to teams in snake order by rating
+fn split_teams(rules: &MatchRules, group: &[&Candidate]) -> Vec<Vec<i32>> {
+  let mut sorted = group.to_vec();
+  sorted.sort_by(|a, b| b.rating.cmp(&a.rating).then(a.player_id.cmp(&b.player_id)));
+
+  let mut teams = vec![Vec::with_capacity(rules.team_size); rules.num_teams];
+  for (round, chunk) in sorted.chunks(rules.num_teams).enumerate() {
+    for (i, c) in chunk.iter().enumerate() {
+      let team = if round % 2 == 0 {
+        i
+      } else {
+        rules.num_teams - 1 - i
+      };
+      teams[team].push(c.player_id);
+    }
+  }
+  teams
+}
+
+#[cfg(test)]
+fn candidate(player_id: i32, rating: i32, waited_secs: u64) -> Candidate {
+  Candidate {
+    player_id,
+    rating,
+    waited: Duration::from_secs(waited_secs),
+    maps: vec![1, 2],
+    pings: vec![(1, 50), (2, 150)].into_iter().collect(),
+  }
+}
+
+#[cfg(test)]
+fn rules(team_size: usize, num_teams: usize) -> MatchRules {
+  MatchRules {
+    team_size,
+    num_teams,
+    rating_window: RatingWindow {
+      initial: 100,
+      growth: 50,
+      max: 300,
+    },
+    max_ping: 200,
+  }
+}
+
+#[test]
+fn test_rating_window() {
+  let window = rules(1, 2).rating_window;
+  assert_eq!(window.get(Duration::from_secs(0)), 100);
+  assert_eq!(window.get(Duration::from_secs(25)), 200);
+  assert_eq!(window.get(Duration::from_secs(3600)), 300);
+}
+
+#[test]
+fn test_find_matches_1v1() {
+  let matches = find_matches(
+    &rules(1, 2),
+    &[1, 2],
+    vec![
+      candidate(1, 1500, 0),
+      candidate(2, 2000, 0),
+      candidate(3, 1550, 5),
+    ],
+  );
+  assert_eq!(
+    matches,
+    vec![Matched {
+      teams: vec![vec![3], vec![1]],
+      maps: vec![1, 2],
+      node_id: 1,
+    }]
+  );
+
+  // the rating window grows while waiting
+  let matches = find_matches(
+    &rules(1, 2),
+    &[1, 2],
+    vec![candidate(1, 1500, 40), candidate(2, 1750, 40)],
+  );
+  assert_eq!(matches.len(), 1);
+}
+
+#[test]
+fn test_find_matches_map_pool() {
+  let mut a = candidate(1, 1500, 0);
+  let mut b = candidate(2, 1500, 0);
+  a.maps = vec![1];
+  b.maps = vec![2];
+  assert!(find_matches(&rules(1, 2), &[1, 2], vec![a.clone(), b.clone()]).is_empty());
+
+  b.maps = vec![1, 2];
+  assert_eq!(
+    find_matches(&rules(1, 2), &[1, 2], vec![a, b])[0].maps,
+    vec![1]
+  );
+}
+
+#[test]
+fn test_find_matches_ping() {
+  let mut a = candidate(1, 1500, 0);
+  let b = candidate(2, 1500, 0);
+  a.pings = vec![(1, 250), (2, 120)].into_iter().collect();
+  assert_eq!(
+    find_matches(&rules(1, 2), &[1, 2], vec![a.clone(), b.clone()])[0].node_id,
+    2
+  );
+
+  a.pings = vec![(1, 250)].into_iter().collect();
+  assert!(find_matches(&rules(1, 2), &[1, 2], vec![a, b]).is_empty());
+}
+
+#[test]
+fn test_find_matches_teams() {
+  let matches = find_matches(
+    &rules(2, 2),
+    &[1],
+    vec![
+      candidate(1, 1600, 0),
+      candidate(2, 1500, 0),
+      candidate(3, 1550, 0),
+      candidate(4, 1450, 0),
+      candidate(5, 1520, 0),
+    ],
+  );
+  assert_eq!(matches.len(), 1);
+  assert_eq!(matches[0].teams, vec![vec![1, 2], vec![3, 5]]);
+}
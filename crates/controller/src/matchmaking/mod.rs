@@ -0,0 +1,11 @@
+pub mod db;
+pub(crate) mod matcher;
+mod state;
+mod types;
+
+pub use state::Matchmaker;
+pub use types::*;
+
+pub mod messages {
+  pub use super::state::{MatchAccept, PlayerDisconnect, QueueEnter, QueueLeave};
+}
@@ -0,0 +1,850 @@
+use crate::db::ExecutorRef;
+use chrono::{DateTime, Utc};
+use flo_net::packet::FloPacket;
+use flo_net::proto::flo_connect::{
+  MatchmakingMatchCancelReason, MatchmakingQueueStatus, PacketMatchmakingMatchCancelled,
+  PacketMatchmakingMatchFound, PacketMatchmakingQueueUpdate,
+};
+use flo_state::*;
+use rand::seq::SliceRandom;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::time::sleep;
+
+use crate::error::*;
+use crate::game::db::CreateGameAsBotParams;
+use crate::game::state::cancel::CancelGame;
+use crate::game::state::create::CreateGameAsBot;
+use crate::game::state::registry::Remove;
+use crate::game::state::start::{StartGameCheckAsBot, StartGameCheckAsBotResult};
+use crate::game::state::GameRegistry;
+use crate::game::{Computer, CreateGameSlot, Race, SlotSettings, SlotStatus};
+use crate::matchmaking::matcher::{find_matches, Candidate};
+use crate::matchmaking::MatchmakingQueue;
+use crate::node::messages::ListNode;
+use crate::node::NodeRegistry;
+use crate::player::state::ping::GetPlayersPingSnapshot;
+use crate::player::state::sender::PlayerRegistryHandle;
+use crate::player::state::PlayerRegistry;
+use crate::player::PlayerBanType;
+use crate::state::{ActorMapExt, Data, Reload};
+
+const MATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+pub struct Matchmaker {
+  db: ExecutorRef,
+  games: Addr<GameRegistry>,
+  nodes: Addr<NodeRegistry>,
+  players: Addr<PlayerRegistry>,
+  player_packet_sender: PlayerRegistryHandle,
+  queues: BTreeMap<i32, MatchmakingQueue>,
+  entries: BTreeMap<i32, QueueEntry>,
+  matches: BTreeMap<i32, PendingMatch>,
+  next_match_id: i32,
+}
+
+#[derive(Debug, Clone)]
+struct QueueEntry {
+  queue_id: i32,
+  player_id: i32,
+  rating: i32,
+  race: Race,
+  maps: Vec<i32>,
+  entered_at: Instant,
+}
+
+#[derive(Debug)]
+struct PendingMatch {
+  queue_id: i32,
+  entries: Vec<QueueEntry>,
+  teams: Vec<Vec<i32>>,
+  map_id: i32,
+  node_id: i32,
+  accepted: BTreeSet<i32>,
+  creating: bool,
+}
+
+impl PendingMatch {
+  fn player_ids(&self) -> Vec<i32> {
+    self.entries.iter().map(|e| e.player_id).collect()
+  }
+}
+
+#[async_trait]
+impl Actor for Matchmaker {
+  async fn started(&mut self, ctx: &mut Context<Self>) {
+    self.handle(ctx, FindMatches).await;
+  }
+}
+
+#[async_trait]
+impl Service<Data> for Matchmaker {
+  type Error = Error;
+
+  async fn create(registry: &mut RegistryRef<Data>) -> Result<Self, Self::Error> {
+    let db = registry.data().db.clone();
+    let games = registry.resolve::<GameRegistry>().await?;
+    let nodes = registry.resolve::<NodeRegistry>().await?;
+    let players = registry.resolve::<PlayerRegistry>().await?;
+    let queues = Self::load_queues(&db).await?;
+    Ok(Matchmaker {
+      db,
+      games,
+      nodes,
+      players: players.clone(),
+      player_packet_sender: players.into(),
+      queues,
+      entries: BTreeMap::new(),
+      matches: BTreeMap::new(),
+      next_match_id: 0,
+    })
+  }
+}
+
+impl Matchmaker {
+  async fn load_queues(db: &ExecutorRef) -> Result<BTreeMap<i32, MatchmakingQueue>> {
+    let queues = db
+      .exec(|conn| crate::matchmaking::db::get_all_queues(conn))
+      .await?;
+    Ok(
+      queues
+        .into_iter()
+        .filter(|q| q.enabled)
+        .map(|q| (q.id, q))
+        .collect(),
+    )
+  }
+
+  async fn send_queue_update(
+    &self,
+    player_ids: Vec<i32>,
+    queue_id: i32,
+    status: MatchmakingQueueStatus,
+    message: String,
+    ban_expires_at: Option<DateTime<Utc>>,
+  ) {
+    let frame = PacketMatchmakingQueueUpdate {
+      queue_id,
+      status: status.into(),
+      message,
+      ban_expires_at: ban_expires_at.map(|t| t.timestamp()),
+    }
+    .encode_as_frame();
+    match frame {
+      Ok(frame) => {
+        if let Err(err) = self.player_packet_sender.broadcast(player_ids, frame).await {
+          tracing::error!("send queue update: {}", err);
+        }
+      }
+      Err(err) => tracing::error!("encode queue update: {}", err),
+    }
+  }
+
+  async fn send_match_cancelled(
+    &self,
+    match_id: i32,
+    player_ids: Vec<i32>,
+    reason: MatchmakingMatchCancelReason,
+  ) {
+    let frame = PacketMatchmakingMatchCancelled {
+      match_id,
+      reason: reason.into(),
+    }
+    .encode_as_frame();
+    match frame {
+      Ok(frame) => {
+        if let Err(err) = self.player_packet_sender.broadcast(player_ids, frame).await {
+          tracing::error!(match_id, "send match cancelled: {}", err);
+        }
+      }
+      Err(err) => tracing::error!(match_id, "encode match cancelled: {}", err),
+    }
+  }
+
+  fn is_in_match(&self, player_id: i32) -> bool {
+    self
+      .matches
+      .values()
+      .any(|m| m.entries.iter().any(|e| e.player_id == player_id))
+  }
+
+  async fn enter(
+    &mut self,
+    player_id: i32,
+    queue_id: i32,
+    race: Race,
+    map_ids: Vec<i32>,
+  ) -> Result<()> {
+    if self.is_in_match(player_id) {
+      return Err(Error::MatchmakingMatchPending);
+    }
+
+    let queue = self
+      .queues
+      .get(&queue_id)
+      .cloned()
+      .ok_or(Error::MatchmakingQueueNotFound)?;
+
+    let maps = if map_ids.is_empty() {
+      queue.map_pool.clone()
+    } else if map_ids.iter().all(|id| queue.map_pool.contains(id)) {
+      map_ids
+    } else {
+      return Err(Error::MatchmakingMapPoolInvalid);
+    };
+
+    let api_client_id = queue.api_client_id;
+    let default_rating = queue.default_rating;
+    let rating = self
+      .db
+      .exec(move |conn| {
+        crate::player::db::check_player_api_client_id(conn, api_client_id, player_id)?;
+        for ban_type in &[PlayerBanType::Matchmaking, PlayerBanType::Game] {
+          if let Some(ban) = crate::player::db::get_active_ban(conn, &[player_id], *ban_type)? {
+            return Err(Error::PlayerBanned(ban));
+          }
+        }
+        if !crate::game::db::get_player_active_slots(conn, player_id)?.is_empty() {
+          return Err(Error::PlayerAlreadyInGame);
+        }
+        Ok(crate::matchmaking::db::get_rating(conn, queue_id, player_id)?.unwrap_or(default_rating))
+      })
+      .await?;
+
+    self.entries.insert(
+      player_id,
+      QueueEntry {
+        queue_id,
+        player_id,
+        rating,
+        race,
+        maps,
+        entered_at: Instant::now(),
+      },
+    );
+
+    Ok(())
+  }
+
+  /// Puts players back into their queue, keeping their wait time
+  async fn requeue(&mut self, entries: Vec<QueueEntry>) {
+    let mut queued = BTreeMap::<i32, Vec<i32>>::new();
+    for entry in entries {
+      if !self.queues.contains_key(&entry.queue_id) {
+        continue;
+      }
+      queued
+        .entry(entry.queue_id)
+        .or_default()
+        .push(entry.player_id);
+      self.entries.insert(entry.player_id, entry);
+    }
+    for (queue_id, player_ids) in queued {
+      self
+        .send_queue_update(
+          player_ids,
+          queue_id,
+          MatchmakingQueueStatus::Queued,
+          String::new(),
+          None,
+        )
+        .await;
+    }
+  }
+
+  async fn ban(&mut self, queue_id: i32, player_ids: Vec<i32>, duration: Duration) {
+    if player_ids.is_empty() || duration.as_secs() == 0 {
+      return;
+    }
+
+    let ban_expires_at = Utc::now()
+      + chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero());
+    let res = self
+      .db
+      .exec({
+        let player_ids = player_ids.clone();
+        move |conn| crate::matchmaking::db::ban_players(conn, &player_ids, ban_expires_at)
+      })
+      .await;
+    if let Err(err) = res {
+      tracing::error!(queue_id, "ban players: {}", err);
+      return;
+    }
+
+    self
+      .send_queue_update(
+        player_ids,
+        queue_id,
+        MatchmakingQueueStatus::Banned,
+        "You did not accept the match.".to_string(),
+        Some(ban_expires_at),
+      )
+      .await;
+  }
+
+  async fn find_matches(&mut self, ctx: &mut Context<Self>) -> Result<()> {
+    if self.entries.is_empty() {
+      return Ok(());
+    }
+
+    let nodes: Vec<i32> = self
+      .nodes
+      .send(ListNode)
+      .await?
+      .into_iter()
+      .map(|node| node.id)
+      .collect();
+    let snapshot = self
+      .players
+      .send(GetPlayersPingSnapshot {
+        players: self.entries.keys().cloned().collect(),
+      })
+      .await?;
+
+    let now = Instant::now();
+    let mut queue_candidates = BTreeMap::<i32, Vec<Candidate>>::new();
+    for entry in self.entries.values() {
+      let pings = snapshot
+        .map
+        .get(&entry.player_id)
+        .map(|map| {
+          map
+            .iter()
+            .filter_map(|(node_id, stats)| stats.avg.or(stats.current).map(|v| (*node_id, v)))
+            .collect()
+        })
+        .unwrap_or_default();
+      queue_candidates
+        .entry(entry.queue_id)
+        .or_default()
+        .push(Candidate {
+          player_id: entry.player_id,
+          rating: entry.rating,
+          waited: now.saturating_duration_since(entry.entered_at),
+          maps: entry.maps.clone(),
+          pings,
+        });
+    }
+
+    for (queue_id, candidates) in queue_candidates {
+      let queue = if let Some(queue) = self.queues.get(&queue_id) {
+        queue.clone()
+      } else {
+        continue;
+      };
+
+      for matched in find_matches(&queue.rules(), &nodes, candidates) {
+        let map_id = match matched.maps.choose(&mut rand::thread_rng()) {
+          Some(id) => *id,
+          None => continue,
+        };
+        let entries: Vec<_> = matched
+          .teams
+          .iter()
+          .flatten()
+          .filter_map(|player_id| self.entries.remove(player_id))
+          .collect();
+
+        self.next_match_id = self.next_match_id.wrapping_add(1);
+        let match_id = self.next_match_id;
+        let player_ids: Vec<_> = entries.iter().map(|e| e.player_id).collect();
+
+        tracing::debug!(
+          match_id,
+          queue_id,
+          map_id,
+          "match found: {:?}",
+          matched.teams
+        );
+
+        self.matches.insert(
+          match_id,
+          PendingMatch {
+            queue_id,
+            entries,
+            teams: matched.teams,
+            map_id,
+            node_id: matched.node_id,
+            accepted: BTreeSet::new(),
+            creating: false,
+          },
+        );
+
+        let addr = ctx.addr();
+        let timeout = Duration::from_secs(queue.accept_timeout_secs as u64);
+        ctx.spawn(async move {
+          sleep(timeout).await;
+          addr.notify(MatchAcceptTimeout { match_id }).await.ok();
+        });
+
+        let frame = PacketMatchmakingMatchFound {
+          match_id,
+          queue_id,
+          map_id,
+          node_id: matched.node_id,
+          accept_timeout_secs: queue.accept_timeout_secs as u32,
+        }
+        .encode_as_frame();
+        match frame {
+          Ok(frame) => {
+            if let Err(err) = self.player_packet_sender.broadcast(player_ids, frame).await {
+              tracing::error!(match_id, "send match found: {}", err);
+            }
+          }
+          Err(err) => tracing::error!(match_id, "encode match found: {}", err),
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Cancels a pending match, bans the declining player
+  /// and puts the other players back into the queue
+  async fn decline(&mut self, match_id: i32, player_id: i32) {
+    let m = if let Some(m) = self.matches.remove(&match_id) {
+      m
+    } else {
+      return;
+    };
+    let decline_ban = self
+      .queues
+      .get(&m.queue_id)
+      .map(|q| Duration::from_secs(q.decline_ban_secs as u64))
+      .unwrap_or_default();
+    self
+      .send_match_cancelled(
+        match_id,
+        m.player_ids(),
+        MatchmakingMatchCancelReason::Declined,
+      )
+      .await;
+    self.ban(m.queue_id, vec![player_id], decline_ban).await;
+    self
+      .requeue(
+        m.entries
+          .into_iter()
+          .filter(|e| e.player_id != player_id)
+          .collect(),
+      )
+      .await;
+  }
+
+  fn create_game(&mut self, ctx: &mut Context<Self>, match_id: i32) {
+    let queues = &self.queues;
+    let (queue, m) = match self
+      .matches
+      .get_mut(&match_id)
+      .and_then(|m| queues.get(&m.queue_id).cloned().map(|q| (q, m)))
+    {
+      Some(v) => v,
+      None => return,
+    };
+    m.creating = true;
+
+    let mut slots = vec![];
+    for (team, player_ids) in m.teams.iter().enumerate() {
+      for player_id in player_ids {
+        let race = m
+          .entries
+          .iter()
+          .find(|e| e.player_id == *player_id)
+          .map(|e| e.race)
+          .unwrap_or(Race::Random);
+        slots.push(CreateGameSlot {
+          player_id: Some(*player_id),
+          settings: SlotSettings {
+            team: team as i32,
+            color: slots.len() as i32,
+            computer: Computer::Easy,
+            handicap: 100,
+            status: SlotStatus::Occupied,
+            race,
+          },
+        });
+      }
+    }
+
+    let params = CreateGameAsBotParams {
+      name: format!("{} #{}", queue.name, match_id),
+      map: None,
+      map_id: Some(m.map_id),
+      is_private: true,
+      is_live: false,
+      node_id: m.node_id,
+      slots,
+      mask_player_names: false,
+      enable_ping_equalizer: false,
+    };
+
+    let db = self.db.clone();
+    let games = self.games.clone();
+    let addr = ctx.addr();
+    ctx.spawn(async move {
+      let result = create_and_start_game(db, games, queue.id, queue.api_client_id, params).await;
+      addr
+        .notify(MatchGameCreated { match_id, result })
+        .await
+        .ok();
+    });
+  }
+}
+
+async fn create_and_start_game(
+  db: ExecutorRef,
+  games: Addr<GameRegistry>,
+  queue_id: i32,
+  api_client_id: i32,
+  params: CreateGameAsBotParams,
+) -> Result<i32> {
+  let api_player_id = db
+    .exec(move |conn| crate::matchmaking::db::get_api_player_id(conn, api_client_id))
+    .await?;
+  let game = games
+    .send(CreateGameAsBot {
+      api_client_id,
+      api_player_id,
+      params,
+    })
+    .await??;
+  let game_id = game.id;
+
+  db.exec(move |conn| crate::matchmaking::db::set_game_queue(conn, game_id, queue_id))
+    .await?;
+
+  let (tx, rx) = oneshot::channel();
+  games.send_to(game_id, StartGameCheckAsBot { tx }).await?;
+  let rejected = match rx.await {
+    Ok(StartGameCheckAsBotResult::Started(_)) => return Ok(game_id),
+    Ok(StartGameCheckAsBotResult::Rejected(pkt)) => {
+      Error::MatchmakingGameStartRejected(pkt.message)
+    }
+    Err(_) => Error::TaskCancelled,
+  };
+
+  games
+    .send_to(game_id, CancelGame { player_id: None })
+    .await
+    .ok();
+  games.send(Remove { game_id }).await.ok();
+
+  Err(rejected)
+}
+
+pub struct QueueEnter {
+  pub player_id: i32,
+  pub queue_id: i32,
+  pub race: Race,
+  pub map_ids: Vec<i32>,
+}
+
+impl Message for QueueEnter {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<QueueEnter> for Matchmaker {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    QueueEnter {
+      player_id,
+      queue_id,
+      race,
+      map_ids,
+    }: QueueEnter,
+  ) {
+    match self.enter(player_id, queue_id, race, map_ids).await {
+      Ok(_) => {
+        self
+          .send_queue_update(
+            vec![player_id],
+            queue_id,
+            MatchmakingQueueStatus::Queued,
+            String::new(),
+            None,
+          )
+          .await
+      }
+      Err(Error::PlayerBanned(ban)) => {
+        self
+          .send_queue_update(
+            vec![player_id],
+            queue_id,
+            MatchmakingQueueStatus::Banned,
+            ban.to_string(),
+            ban.ban_expires_at,
+          )
+          .await
+      }
+      Err(err) => {
+        tracing::debug!(player_id, queue_id, "enter queue: {}", err);
+        self
+          .send_queue_update(
+            vec![player_id],
+            queue_id,
+            MatchmakingQueueStatus::Idle,
+            err.to_string(),
+            None,
+          )
+          .await
+      }
+    }
+  }
+}
+
+pub struct QueueLeave {
+  pub player_id: i32,
+}
+
+impl Message for QueueLeave {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<QueueLeave> for Matchmaker {
+  async fn handle(&mut self, _: &mut Context<Self>, QueueLeave { player_id }: QueueLeave) {
+    if let Some(entry) = self.entries.remove(&player_id) {
+      self
+        .send_queue_update(
+          vec![player_id],
+          entry.queue_id,
+          MatchmakingQueueStatus::Idle,
+          String::new(),
+          None,
+        )
+        .await
+    }
+  }
+}
+
+pub struct MatchAccept {
+  pub player_id: i32,
+  pub match_id: i32,
+  pub accept: bool,
+}
+
+impl Message for MatchAccept {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<MatchAccept> for Matchmaker {
+  async fn handle(
+    &mut self,
+    ctx: &mut Context<Self>,
+    MatchAccept {
+      player_id,
+      match_id,
+      accept,
+    }: MatchAccept,
+  ) {
+    let m = match self.matches.get_mut(&match_id) {
+      Some(m) if !m.creating && m.entries.iter().any(|e| e.player_id == player_id) => m,
+      _ => return,
+    };
+
+    if accept {
+      m.accepted.insert(player_id);
+      if m.accepted.len() == m.entries.len() {
+        tracing::debug!(match_id, "all players accepted");
+        self.create_game(ctx, match_id);
+      }
+      return;
+    }
+
+    tracing::debug!(match_id, player_id, "match declined");
+    self.decline(match_id, player_id).await;
+  }
+}
+
+pub struct PlayerDisconnect {
+  pub player_id: i32,
+}
+
+impl Message for PlayerDisconnect {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<PlayerDisconnect> for Matchmaker {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    PlayerDisconnect { player_id }: PlayerDisconnect,
+  ) {
+    self.entries.remove(&player_id);
+
+    let match_id = self
+      .matches
+      .iter()
+      .find(|(_, m)| !m.creating && m.entries.iter().any(|e| e.player_id == player_id))
+      .map(|(match_id, _)| *match_id);
+    if let Some(match_id) = match_id {
+      tracing::debug!(
+        match_id,
+        player_id,
+        "player disconnected from pending match"
+      );
+      self.decline(match_id, player_id).await;
+    }
+  }
+}
+
+struct MatchAcceptTimeout {
+  match_id: i32,
+}
+
+impl Message for MatchAcceptTimeout {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<MatchAcceptTimeout> for Matchmaker {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    MatchAcceptTimeout { match_id }: MatchAcceptTimeout,
+  ) {
+    if self.matches.get(&match_id).map(|m| m.creating) != Some(false) {
+      return;
+    }
+
+    if let Some(m) = self.matches.remove(&match_id) {
+      tracing::debug!(match_id, "match accept timeout");
+      let decline_ban = self
+        .queues
+        .get(&m.queue_id)
+        .map(|q| Duration::from_secs(q.decline_ban_secs as u64))
+        .unwrap_or_default();
+      self
+        .send_match_cancelled(
+          match_id,
+          m.player_ids(),
+          MatchmakingMatchCancelReason::Timeout,
+        )
+        .await;
+      let accepted_ids = m.accepted;
+      let (accepted, timed_out): (Vec<_>, Vec<_>) = m
+        .entries
+        .into_iter()
+        .partition(|e| accepted_ids.contains(&e.player_id));
+      self
+        .ban(
+          m.queue_id,
+          timed_out.into_iter().map(|e| e.player_id).collect(),
+          decline_ban,
+        )
+        .await;
+      self.requeue(accepted).await;
+    }
+  }
+}
+
+struct MatchGameCreated {
+  match_id: i32,
+  result: Result<i32>,
+}
+
+impl Message for MatchGameCreated {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<MatchGameCreated> for Matchmaker {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    MatchGameCreated { match_id, result }: MatchGameCreated,
+  ) {
+    let m = if let Some(m) = self.matches.remove(&match_id) {
+      m
+    } else {
+      return;
+    };
+    match result {
+      Ok(game_id) => {
+        tracing::info!(
+          match_id,
+          game_id,
+          queue_id = m.queue_id,
+          "match game started"
+        );
+      }
+      Err(err) => {
+        tracing::error!(
+          match_id,
+          queue_id = m.queue_id,
+          "create match game: {}",
+          err
+        );
+        self
+          .send_match_cancelled(
+            match_id,
+            m.player_ids(),
+            MatchmakingMatchCancelReason::GameCreateFailed,
+          )
+          .await;
+        // the players are not at fault, put the ones who accepted back into the queue
+        let accepted_ids = m.accepted;
+        self
+          .requeue(
+            m.entries
+              .into_iter()
+              .filter(|e| accepted_ids.contains(&e.player_id))
+              .collect(),
+          )
+          .await;
+      }
+    }
+  }
+}
+
+struct FindMatches;
+
+impl Message for FindMatches {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<FindMatches> for Matchmaker {
+  async fn handle(&mut self, ctx: &mut Context<Self>, _: FindMatches) {
+    if let Err(err) = self.find_matches(ctx).await {
+      tracing::error!("find matches: {}", err);
+    }
+    let addr = ctx.addr();
+    ctx.spawn(async move {
+      sleep(MATCH_INTERVAL).await;
+      addr.notify(FindMatches).await.ok();
+    });
+  }
+}
+
+#[async_trait]
+impl Handler<Reload> for Matchmaker {
+  async fn handle(&mut self, _: &mut Context<Self>, _: Reload) -> <Reload as Message>::Result {
+    self.queues = Self::load_queues(&self.db).await?;
+
+    let removed: Vec<_> = self
+      .entries
+      .values()
+      .filter(|e| !self.queues.contains_key(&e.queue_id))
+      .map(|e| (e.player_id, e.queue_id))
+      .collect();
+    for (player_id, queue_id) in removed {
+      self.entries.remove(&player_id);
+      self
+        .send_queue_update(
+          vec![player_id],
+          queue_id,
+          MatchmakingQueueStatus::Idle,
+          Error::MatchmakingQueueNotFound.to_string(),
+          None,
+        )
+        .await;
+    }
+
+    Ok(())
+  }
+}
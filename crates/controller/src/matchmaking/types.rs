@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::matchmaking::matcher::{MatchRules, RatingWindow};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchmakingQueue {
+  pub id: i32,
+  pub api_client_id: i32,
+  pub name: String,
+  pub team_size: i32,
+  pub num_teams: i32,
+  pub map_pool: Vec<i32>,
+  pub default_rating: i32,
+  pub rating_window_initial: i32,
+  pub rating_window_growth: i32,
+  pub rating_window_max: i32,
+  pub max_ping: i32,
+  pub accept_timeout_secs: i32,
+  pub decline_ban_secs: i32,
+  pub enabled: bool,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+impl MatchmakingQueue {
+  pub fn num_players(&self) -> usize {
+    (self.team_size * self.num_teams) as usize
+  }
+
+  pub(crate) fn rules(&self) -> MatchRules {
+    MatchRules {
+      team_size: self.team_size as usize,
+      num_teams: self.num_teams as usize,
+      rating_window: RatingWindow {
+        initial: self.rating_window_initial as u32,
+        growth: self.rating_window_growth as u32,
+        max: self.rating_window_max as u32,
+      },
+      max_ping: self.max_ping as u32,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertMatchmakingQueueParams {
+  pub name: String,
+  pub team_size: i32,
+  pub num_teams: i32,
+  pub map_pool: Vec<i32>,
+  pub default_rating: i32,
+  pub rating_window_initial: i32,
+  pub rating_window_growth: i32,
+  pub rating_window_max: i32,
+  pub max_ping: i32,
+  pub accept_timeout_secs: i32,
+  pub decline_ban_secs: i32,
+  pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchmakingRating {
+  pub player_id: i32,
+  pub rating: i32,
+}
@@ -1,9 +1,20 @@
-use crate::db::DbConn;
+use crate::db::{Backend, DbConn};
 use crate::error::*;
 
-embed_migrations!("../../migrations");
+mod pg {
+  embed_migrations!("../../migrations");
+  pub use self::embedded_migrations::run;
+}
+
+mod sqlite {
+  embed_migrations!("../../migrations-sqlite");
+  pub use self::embedded_migrations::run;
+}
 
 pub fn run(conn: &DbConn) -> Result<()> {
-  embedded_migrations::run(conn)?;
+  match conn.backend() {
+    Backend::Pg(conn) => pg::run(conn)?,
+    Backend::Sqlite(conn) => sqlite::run(conn)?,
+  }
   Ok(())
 }
@@ -6,19 +6,55 @@ use crate::node::types::Node;
 use crate::schema::node;
 
 pub fn get_all_nodes(conn: &DbConn) -> Result<Vec<Node>> {
-  use node::dsl;
-  let nodes = node::table
-    .filter(dsl::disabled.eq(false))
-    .order((dsl::location, dsl::name))
-    .load(conn)?;
-  Ok(nodes)
+  db_dispatch!(conn, {
+    use node::dsl;
+    let nodes = node::table
+      .filter(dsl::disabled.eq(false))
+      .order((dsl::location, dsl::name))
+      .load(conn)?;
+    Ok(nodes)
+  })
 }
 
 pub fn get_node(conn: &DbConn, node_id: i32) -> Result<Node> {
-  node::table
-    .find(node_id)
-    .first::<Node>(conn)
-    .optional()?
-    .ok_or_else(|| Error::NodeNotFound)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    node::table
+      .find(node_id)
+      .first::<Node>(conn)
+      .optional()?
+      .ok_or_else(|| Error::NodeNotFound)
+      .map_err(Into::into)
+  })
+}
+
+#[tokio::test]
+async fn test_get_node() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+
+    let node = get_node(conn, setup.node_id)?;
+    assert_eq!(node.ip_addr, "192.168.1.2");
+    assert_eq!(node.secret, "node");
+    assert!(matches!(
+      get_node(conn, setup.node_id + 1),
+      Err(Error::NodeNotFound)
+    ));
+
+    let ids: Vec<_> = get_all_nodes(conn)?
+      .into_iter()
+      .map(|node| node.id)
+      .collect();
+    assert_eq!(ids, vec![setup.node_id]);
+
+    db_dispatch!(conn, {
+      diesel::update(node::table.find(setup.node_id))
+        .set(node::disabled.eq(true))
+        .execute(conn)?;
+    });
+    assert!(get_all_nodes(conn)?.is_empty());
+    // disabled nodes can still be looked up by id
+    assert_eq!(get_node(conn, setup.node_id)?.id, setup.node_id);
+    Ok(())
+  })
+  .await;
 }
@@ -2,11 +2,12 @@ pub mod db;
 mod state;
 mod types;
 
+pub use flo_net::proto::flo_node::packet_controller_game_control::Action as GameControlAction;
 pub use state::conn::NodeConnActor;
 pub use state::request::PlayerLeaveResponse;
 pub use state::NodeRegistry;
 pub use types::*;
 pub mod messages {
-  pub use crate::node::state::conn::{NodeCreateGame, NodePlayerLeave};
+  pub use crate::node::state::conn::{NodeCreateGame, NodeGameControl, NodePlayerLeave};
   pub use crate::node::state::ListNode;
 }
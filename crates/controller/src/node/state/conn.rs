@@ -1,9 +1,10 @@
 use crate::error::*;
+use crate::game::state::result::{SaveGameChatLog, SaveGameResult};
 use crate::game::state::GameRegistry;
 use crate::game::state::{GameSlotClientStatusUpdate, GameStatusUpdate};
-use crate::game::{Game, GameStatus};
+use crate::game::{Game, GameChatLogReport, GameResultReport, GameStatus};
 use crate::node::state::request::{CreatedGameInfo, NodeRequestActor, NodeRequestExt};
-use crate::node::{NodeConnConfig, PlayerLeaveResponse};
+use crate::node::{GameControlAction, NodeConnConfig, PlayerLeaveResponse};
 use crate::state::ActorMapExt;
 use backoff::backoff::Backoff;
 use backoff::ExponentialBackoff;
@@ -274,6 +275,8 @@ impl Handler<IncomingFrame> for NodeConnActor {
       Response(RequestDone),
       GameSlotClientStatusUpdate(GameSlotClientStatusUpdate),
       GameStatusUpdate(Vec<GameStatusUpdate>),
+      GameResult(GameResultReport),
+      GameChatLog(GameChatLogReport),
     }
 
     let parsed = flo_net::try_flo_packet! {
@@ -322,6 +325,28 @@ impl Handler<IncomingFrame> for NodeConnActor {
             )
           )
         }
+        packet: PacketControllerGameControlAccept => {
+          Parsed::Response(
+            RequestDone::new(
+              RequestId::GameControl(GameControlRequestId {
+                game_id: packet.game_id,
+                request_id: packet.request_id,
+              }),
+              Ok(Response::GameControlAccepted)
+            )
+          )
+        }
+        packet: PacketControllerGameControlReject => {
+          Parsed::Response(
+            RequestDone::new(
+              RequestId::GameControl(GameControlRequestId {
+                game_id: packet.game_id,
+                request_id: packet.request_id,
+              }),
+              Err(Error::GameControlRejected(packet.reason(), packet.message))
+            )
+          )
+        }
         packet: PacketClientUpdateSlotClientStatus => {
           Parsed::GameSlotClientStatusUpdate(S2ProtoUnpack::unpack(packet)?)
         }
@@ -331,6 +356,12 @@ impl Handler<IncomingFrame> for NodeConnActor {
         packet: PacketNodeGameStatusUpdateBulk => {
           Parsed::GameStatusUpdate(packet.games.into_iter().map(Into::into).collect())
         }
+        packet: PacketNodeGameResult => {
+          Parsed::GameResult(GameResultReport::from(packet))
+        }
+        packet: PacketNodeGameChatLog => {
+          Parsed::GameChatLog(GameChatLogReport::from(packet))
+        }
       }
     };
 
@@ -375,6 +406,36 @@ impl Handler<IncomingFrame> for NodeConnActor {
           }
         });
       }
+      Parsed::GameResult(report) => {
+        let addr = self.game_reg_addr.clone();
+        let node_id = self.config.id;
+        ctx.spawn(async move {
+          let game_id = report.game_id;
+          let res = addr
+            .send(SaveGameResult { node_id, report })
+            .await
+            .map_err(Error::from)
+            .and_then(std::convert::identity);
+          if let Err(err) = res {
+            tracing::error!(game_id, node_id, "save game result: {}", err);
+          }
+        });
+      }
+      Parsed::GameChatLog(report) => {
+        let addr = self.game_reg_addr.clone();
+        let node_id = self.config.id;
+        ctx.spawn(async move {
+          let game_id = report.game_id;
+          let res = addr
+            .send(SaveGameChatLog { node_id, report })
+            .await
+            .map_err(Error::from)
+            .and_then(std::convert::identity);
+          if let Err(err) = res {
+            tracing::error!(game_id, node_id, "save game chat log: {}", err);
+          }
+        });
+      }
     }
 
     Ok(())
@@ -440,6 +501,35 @@ impl Handler<NodePlayerLeave> for NodeConnActor {
   }
 }
 
+pub struct NodeGameControl {
+  pub game_id: i32,
+  pub action: GameControlAction,
+}
+
+impl Message for NodeGameControl {
+  type Result = Result<FutureReply<Result<()>>>;
+}
+
+#[async_trait]
+impl Handler<NodeGameControl> for NodeConnActor {
+  async fn handle(
+    &mut self,
+    ctx: &mut Context<Self>,
+    NodeGameControl { game_id, action }: NodeGameControl,
+  ) -> Result<FutureReply<Result<()>>> {
+    let addr = self
+      .request_actor
+      .as_ref()
+      .map(|v| v.addr())
+      .ok_or_else(|| Error::NodeNotReady)?;
+    let (tx, rx) = FutureReply::channel();
+    ctx.spawn(async move {
+      tx.send(addr.game_control(game_id, action).await).ok();
+    });
+    Ok(rx)
+  }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum NodeConnStatus {
   Connecting,
@@ -1,6 +1,6 @@
 use crate::error::*;
 use crate::game::{Game, SlotClientStatus, SlotStatus};
-use crate::node::PlayerToken;
+use crate::node::{GameControlAction, PlayerToken};
 use crate::player::PlayerBanType;
 use flo_net::packet::*;
 use flo_net::proto::flo_node::*;
@@ -11,6 +11,7 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::task::Poll;
 use std::time::Duration;
@@ -21,6 +22,8 @@ use tracing_futures::Instrument;
 
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
+static NEXT_GAME_CONTROL_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
 pub struct NodeRequestActor {
   frame_tx: mpsc::Sender<Frame>,
   pending_requests: HashMap<RequestId, PendingRequest>,
@@ -54,12 +57,14 @@ impl Drop for PendingRequest {
 pub enum RequestId {
   CreateGame(i32),
   PlayerLeave(PlayerLeaveRequestId),
+  GameControl(GameControlRequestId),
 }
 
 #[derive(Debug)]
 pub enum Response {
   GameCreated(CreatedGameInfo),
   PlayerLeave(PlayerLeaveResponse),
+  GameControlAccepted,
 }
 
 #[derive(Debug, S2ProtoUnpack)]
@@ -92,6 +97,13 @@ pub struct PlayerLeaveRequestId {
   pub player_id: i32,
 }
 
+/// A game can have multiple pending game control requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GameControlRequestId {
+  pub game_id: i32,
+  pub request_id: u32,
+}
+
 #[derive(Debug)]
 pub enum PlayerLeaveResponse {
   Accepted(SlotClientStatus),
@@ -233,6 +245,7 @@ pub trait NodeRequestExt {
     ban_list_map: BTreeMap<i32, Vec<PlayerBanType>>,
   ) -> Result<CreatedGameInfo>;
   async fn player_force_leave(&self, game_id: i32, player_id: i32) -> Result<PlayerLeaveResponse>;
+  async fn game_control(&self, game_id: i32, action: GameControlAction) -> Result<()>;
 }
 
 #[async_trait]
@@ -323,4 +336,32 @@ impl NodeRequestExt for Addr<NodeRequestActor> {
       }
     }
   }
+
+  async fn game_control(&self, game_id: i32, action: GameControlAction) -> Result<()> {
+    let request_id = NEXT_GAME_CONTROL_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let req_id = RequestId::GameControl(GameControlRequestId {
+      game_id,
+      request_id,
+    });
+
+    let pkt = PacketControllerGameControl {
+      game_id,
+      action: Some(action),
+      request_id,
+    };
+
+    let req = Request {
+      id: req_id,
+      frame: pkt.encode_as_frame()?,
+    };
+
+    let res = self.send(req).await??;
+    match res.await? {
+      Response::GameControlAccepted => Ok(()),
+      other => {
+        tracing::error!(game_id, "unexpected node response: {:?}", other);
+        Err(Error::NodeResponseUnexpected)
+      }
+    }
+  }
 }
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::player::PlayerBanType;
+
+/// An active ban that rejected a player request, the `Display` impl is the player-facing message
+#[derive(Debug, Clone)]
+pub struct BanInfo {
+  /// `None` for IP bans
+  pub ban_type: Option<PlayerBanType>,
+  pub reason: Option<String>,
+  pub ban_expires_at: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for BanInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.ban_type {
+      Some(PlayerBanType::Chat) => write!(f, "You are banned from chatting")?,
+      Some(PlayerBanType::Matchmaking) => write!(f, "You are banned from matchmaking")?,
+      Some(PlayerBanType::Game) => write!(f, "You are banned from playing games")?,
+      None => write!(f, "Your IP address is banned")?,
+    }
+    match self.ban_expires_at {
+      Some(t) => write!(f, " until {}", t.format("%Y-%m-%d %H:%M UTC"))?,
+      None => write!(f, " permanently")?,
+    }
+    match self.reason.as_deref() {
+      Some(reason) if !reason.is_empty() => {
+        write!(f, ". Reason: {}.", reason.trim_end_matches('.'))
+      }
+      _ => write!(f, "."),
+    }
+  }
+}
+
+/// A single IP address or a subnet in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpRange {
+  addr: IpAddr,
+  prefix_len: u8,
+}
+
+impl IpRange {
+  pub fn contains(&self, ip: IpAddr) -> bool {
+    match (self.addr, unmap_ipv4(ip)) {
+      (IpAddr::V4(addr), IpAddr::V4(ip)) => {
+        let mask = mask_v4(self.prefix_len);
+        u32::from(addr) & mask == u32::from(ip) & mask
+      }
+      (IpAddr::V6(addr), IpAddr::V6(ip)) => {
+        let mask = mask_v6(self.prefix_len);
+        u128::from(addr) & mask == u128::from(ip) & mask
+      }
+      _ => false,
+    }
+  }
+}
+
+impl FromStr for IpRange {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let invalid = || Error::IpRangeInvalid(s.to_string());
+    let (addr, prefix_len) = match s.trim().split_once('/') {
+      Some((addr, prefix_len)) => (
+        addr.parse::<IpAddr>().map_err(|_| invalid())?,
+        Some(prefix_len.parse::<u8>().map_err(|_| invalid())?),
+      ),
+      None => (s.trim().parse::<IpAddr>().map_err(|_| invalid())?, None),
+    };
+    let addr = unmap_ipv4(addr);
+    // host bits are cleared so that equal ranges have the same string representation
+    let range = match addr {
+      IpAddr::V4(addr) => {
+        let prefix_len = prefix_len.unwrap_or(32);
+        if prefix_len > 32 {
+          return Err(invalid());
+        }
+        IpRange {
+          addr: Ipv4Addr::from(u32::from(addr) & mask_v4(prefix_len)).into(),
+          prefix_len,
+        }
+      }
+      IpAddr::V6(addr) => {
+        let prefix_len = prefix_len.unwrap_or(128);
+        if prefix_len > 128 {
+          return Err(invalid());
+        }
+        IpRange {
+          addr: Ipv6Addr::from(u128::from(addr) & mask_v6(prefix_len)).into(),
+          prefix_len,
+        }
+      }
+    };
+    Ok(range)
+  }
+}
+
+impl fmt::Display for IpRange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}/{}", self.addr, self.prefix_len)
+  }
+}
+
+fn mask_v4(prefix_len: u8) -> u32 {
+  u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0)
+}
+
+fn mask_v6(prefix_len: u8) -> u128 {
+  u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0)
+}
+
+/// Peers connected to a dual-stack socket have IPv4-mapped addresses
+fn unmap_ipv4(ip: IpAddr) -> IpAddr {
+  match ip {
+    IpAddr::V6(v6) => match v6.segments() {
+      [0, 0, 0, 0, 0, 0xffff, ..] => {
+        let [.., a, b, c, d] = v6.octets();
+        Ipv4Addr::new(a, b, c, d).into()
+      }
+      _ => ip,
+    },
+    ip => ip,
+  }
+}
+
+#[test]
+fn test_ip_range() {
+  let range: IpRange = "192.168.1.20/24".parse().unwrap();
+  assert_eq!(range.to_string(), "192.168.1.0/24");
+  assert!(range.contains("192.168.1.1".parse().unwrap()));
+  assert!(range.contains("::ffff:192.168.1.200".parse().unwrap()));
+  assert!(!range.contains("192.168.2.1".parse().unwrap()));
+
+  let range: IpRange = "10.0.0.1".parse().unwrap();
+  assert_eq!(range.to_string(), "10.0.0.1/32");
+  assert!(range.contains("10.0.0.1".parse().unwrap()));
+  assert!(!range.contains("10.0.0.2".parse().unwrap()));
+
+  let range: IpRange = "0.0.0.0/0".parse().unwrap();
+  assert!(range.contains("8.8.8.8".parse().unwrap()));
+  assert!(!range.contains("2001:db8::1".parse().unwrap()));
+
+  let range: IpRange = "2001:db8::1/32".parse().unwrap();
+  assert_eq!(range.to_string(), "2001:db8::/32");
+  assert!(range.contains("2001:db8:ffff::1".parse().unwrap()));
+
+  assert!("10.0.0.1/33".parse::<IpRange>().is_err());
+  assert!("10.0.0/8".parse::<IpRange>().is_err());
+  assert!("".parse::<IpRange>().is_err());
+}
+
+#[test]
+fn test_ban_info_message() {
+  use chrono::TimeZone;
+  let ban = BanInfo {
+    ban_type: Some(PlayerBanType::Game),
+    reason: Some("Leaving games".to_string()),
+    ban_expires_at: Some(Utc.ymd(2022, 12, 20).and_hms(8, 30, 0)),
+  };
+  assert_eq!(
+    ban.to_string(),
+    "You are banned from playing games until 2022-12-20 08:30 UTC. Reason: Leaving games."
+  );
+  let ban = BanInfo {
+    ban_type: None,
+    reason: None,
+    ban_expires_at: None,
+  };
+  assert_eq!(ban.to_string(), "Your IP address is banned permanently.");
+}
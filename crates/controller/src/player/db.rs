@@ -1,40 +1,50 @@
-use crate::db::DbConn;
+use crate::db::{lower, Backend, DbConn};
 use crate::error::*;
-use crate::player::{Player, PlayerBan, PlayerBanType, PlayerRef, PlayerSource, SourceState};
-use crate::schema::{player, player_ban, player_mute};
+use crate::player::{
+  BanInfo, IpBan, IpRange, Player, PlayerBan, PlayerBanHistory, PlayerBanType, PlayerRef,
+  PlayerSource, SourceState,
+};
+use crate::schema::{ip_ban, player, player_ban, player_ban_history, player_mute};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde_json::Value;
 use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
 
 pub fn get(conn: &DbConn, id: i32) -> Result<Player> {
-  player::table
-    .find(id)
-    .first::<Row>(conn)
-    .optional()?
-    .ok_or_else(|| Error::PlayerNotFound)
-    .map(Into::into)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    player::table
+      .find(id)
+      .first::<Row>(conn)
+      .optional()?
+      .ok_or_else(|| Error::PlayerNotFound)
+      .map(Into::into)
+      .map_err(Into::into)
+  })
 }
 
 pub fn get_ref(conn: &DbConn, id: i32) -> Result<PlayerRef> {
-  use player::dsl;
-  player::table
-    .find(id)
-    .select((dsl::id, dsl::name, dsl::source, dsl::realm))
-    .first::<PlayerRef>(conn)
-    .optional()?
-    .ok_or_else(|| Error::PlayerNotFound)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    use player::dsl;
+    player::table
+      .find(id)
+      .select((dsl::id, dsl::name, dsl::source, dsl::realm))
+      .first::<PlayerRef>(conn)
+      .optional()?
+      .ok_or_else(|| Error::PlayerNotFound)
+      .map_err(Into::into)
+  })
 }
 
 pub fn get_refs_by_ids(conn: &DbConn, ids: &[i32]) -> Result<Vec<PlayerRef>> {
-  use player::dsl;
-  player::table
-    .filter(dsl::id.eq_any(ids))
-    .select((dsl::id, dsl::name, dsl::source, dsl::realm))
-    .load(conn)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    use player::dsl;
+    player::table
+      .filter(dsl::id.eq_any(ids))
+      .select((dsl::id, dsl::name, dsl::source, dsl::realm))
+      .load(conn)
+      .map_err(Into::into)
+  })
 }
 
 pub fn get_client_refs_by_ids(
@@ -42,13 +52,15 @@ pub fn get_client_refs_by_ids(
   api_client_id: i32,
   ids: &[i32],
 ) -> Result<Vec<PlayerRef>> {
-  use player::dsl;
-  player::table
-    .filter(dsl::api_client_id.eq(api_client_id))
-    .filter(dsl::id.eq_any(ids))
-    .select((dsl::id, dsl::name, dsl::source, dsl::realm))
-    .load(conn)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    use player::dsl;
+    player::table
+      .filter(dsl::api_client_id.eq(api_client_id))
+      .filter(dsl::id.eq_any(ids))
+      .select((dsl::id, dsl::name, dsl::source, dsl::realm))
+      .load(conn)
+      .map_err(Into::into)
+  })
 }
 
 pub fn get_player_map_by_api_source_ids(
@@ -56,20 +68,22 @@ pub fn get_player_map_by_api_source_ids(
   api_client_id: i32,
   ids: Vec<String>,
 ) -> Result<HashMap<String, PlayerRef>> {
-  use player::dsl;
-  let pairs = player::table
-    .filter(
-      dsl::source
-        .eq(PlayerSource::Api)
-        .and(dsl::api_client_id.eq(api_client_id)),
-    )
-    .filter(dsl::source_id.eq_any(ids))
-    .select((
-      dsl::source_id,
-      (dsl::id, dsl::name, dsl::source, dsl::realm),
-    ))
-    .load::<(String, PlayerRef)>(conn)?;
-  Ok(pairs.into_iter().collect())
+  db_dispatch!(conn, {
+    use player::dsl;
+    let pairs = player::table
+      .filter(
+        dsl::source
+          .eq(PlayerSource::Api)
+          .and(dsl::api_client_id.eq(api_client_id)),
+      )
+      .filter(dsl::source_id.eq_any(ids))
+      .select((
+        dsl::source_id,
+        (dsl::id, dsl::name, dsl::source, dsl::realm),
+      ))
+      .load::<(String, PlayerRef)>(conn)?;
+    Ok(pairs.into_iter().collect())
+  })
 }
 
 #[derive(Debug, Insertable)]
@@ -90,18 +104,37 @@ pub fn upsert(conn: &DbConn, data: &UpsertPlayer) -> Result<Player> {
     return Err(Error::PlayerSourceIdInvalid);
   }
 
-  diesel::insert_into(player::table)
-    .values(data)
-    .on_conflict((dsl::api_client_id, dsl::source, dsl::source_id))
-    .do_update()
-    .set(Update {
-      name: &data.name,
-      source_state: data.source_state.as_ref(),
-      realm: data.realm.as_ref().map(AsRef::as_ref),
-    })
-    .get_result::<Row>(conn)
-    .map(Into::into)
-    .map_err(Into::into)
+  let update = Update {
+    name: &data.name,
+    source_state: data.source_state.as_ref(),
+    realm: data.realm.as_ref().map(AsRef::as_ref),
+  };
+
+  let row = match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(player::table)
+      .values(data)
+      .on_conflict((dsl::api_client_id, dsl::source, dsl::source_id))
+      .do_update()
+      .set(update)
+      .get_result::<Row>(conn)?,
+    Backend::Sqlite(conn) => conn.transaction(|| -> Result<_> {
+      let key = dsl::api_client_id
+        .eq(data.api_client_id)
+        .and(dsl::source.eq(data.source))
+        .and(dsl::source_id.eq(&data.source_id));
+      let updated = diesel::update(player::table.filter(key))
+        .set(update)
+        .execute(conn)?;
+      if updated == 0 {
+        diesel::insert_into(player::table)
+          .values(data)
+          .execute(conn)?;
+      }
+      Ok(player::table.filter(key).first::<Row>(conn)?)
+    })?,
+  };
+
+  Ok(row.into())
 }
 
 pub fn add_mute(conn: &DbConn, player_id: i32, mute_player_id: i32) -> Result<()> {
@@ -112,49 +145,58 @@ pub fn add_mute(conn: &DbConn, player_id: i32, mute_player_id: i32) -> Result<()
     mute_player_id: i32,
   }
 
-  diesel::insert_into(player_mute::table)
-    .values(&Insert {
-      player_id,
-      mute_player_id,
-    })
-    .on_conflict((player_mute::player_id, player_mute::mute_player_id))
-    .do_nothing()
-    .execute(conn)?;
+  let insert = Insert {
+    player_id,
+    mute_player_id,
+  };
+  match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(player_mute::table)
+      .values(&insert)
+      .on_conflict((player_mute::player_id, player_mute::mute_player_id))
+      .do_nothing()
+      .execute(conn)?,
+    Backend::Sqlite(conn) => diesel::insert_or_ignore_into(player_mute::table)
+      .values(&insert)
+      .execute(conn)?,
+  };
 
   Ok(())
 }
 
 pub fn remove_mute(conn: &DbConn, player_id: i32, mute_player_id: i32) -> Result<()> {
-  diesel::delete(
-    player_mute::table.filter(
-      player_mute::player_id
-        .eq(player_id)
-        .and(player_mute::mute_player_id.eq(mute_player_id)),
-    ),
-  )
-  .execute(conn)?;
+  db_dispatch!(conn, {
+    diesel::delete(
+      player_mute::table.filter(
+        player_mute::player_id
+          .eq(player_id)
+          .and(player_mute::mute_player_id.eq(mute_player_id)),
+      ),
+    )
+    .execute(conn)?;
 
-  Ok(())
+    Ok(())
+  })
 }
 
 pub fn get_mute_list_map(conn: &DbConn, player_ids: &[i32]) -> Result<BTreeMap<i32, Vec<i32>>> {
-  use diesel::pg::expression::dsl::any;
-  let pairs: Vec<(i32, i32)> = player_mute::table
-    .select((player_mute::player_id, player_mute::mute_player_id))
-    .filter(
-      player_mute::player_id
-        .eq(any(player_ids))
-        .and(player_mute::mute_player_id.eq(any(player_ids))),
-    )
-    .load(conn)?;
-  let mut map = BTreeMap::new();
-  for (player_id, mute_player_id) in pairs {
-    map
-      .entry(player_id)
-      .or_insert_with(|| vec![])
-      .push(mute_player_id);
-  }
-  Ok(map)
+  db_dispatch!(conn, {
+    let pairs: Vec<(i32, i32)> = player_mute::table
+      .select((player_mute::player_id, player_mute::mute_player_id))
+      .filter(
+        player_mute::player_id
+          .eq_any(player_ids)
+          .and(player_mute::mute_player_id.eq_any(player_ids)),
+      )
+      .load(conn)?;
+    let mut map = BTreeMap::new();
+    for (player_id, mute_player_id) in pairs {
+      map
+        .entry(player_id)
+        .or_insert_with(|| vec![])
+        .push(mute_player_id);
+    }
+    Ok(map)
+  })
 }
 
 pub struct ListPlayerBan {
@@ -168,45 +210,49 @@ pub fn list_ban(
   query: Option<&str>,
   next_id: Option<i32>,
 ) -> Result<ListPlayerBan> {
-  const PAGE_SIZE: i64 = 100;
-  let mut q = player_ban::table
-    .inner_join(player::table)
-    .select(PlayerBan::COLUMNS)
-    .filter(player::api_client_id.eq(api_client_id))
-    .order(player_ban::id)
-    .limit(PAGE_SIZE + 1)
-    .into_boxed();
-
-  if let Some(v) = query {
-    q = q.filter(player::name.ilike(format!("%{}%", v)));
-  }
-
-  if let Some(id) = next_id {
-    q = q.filter(player_ban::id.ge(id));
-  }
+  db_dispatch!(conn, {
+    const PAGE_SIZE: i64 = 100;
+    let mut q = player_ban::table
+      .inner_join(player::table)
+      .select(PlayerBan::COLUMNS)
+      .filter(player::api_client_id.eq(api_client_id))
+      .order(player_ban::id)
+      .limit(PAGE_SIZE + 1)
+      .into_boxed();
+
+    if let Some(v) = query {
+      q = q.filter(lower(player::name).like(format!("%{}%", v.to_lowercase())));
+    }
 
-  let mut rows = q.load::<PlayerBan>(conn)?;
-  let next_id = if rows.len() > PAGE_SIZE as usize {
-    let id = rows.last().map(|row| row.id);
-    rows.truncate(PAGE_SIZE as usize);
-    id
-  } else {
-    None
-  };
+    if let Some(id) = next_id {
+      q = q.filter(player_ban::id.ge(id));
+    }
 
-  Ok(ListPlayerBan {
-    player_bans: rows,
-    next_id,
+    let mut rows = q.load::<PlayerBan>(conn)?;
+    let next_id = if rows.len() > PAGE_SIZE as usize {
+      let id = rows.last().map(|row| row.id);
+      rows.truncate(PAGE_SIZE as usize);
+      id
+    } else {
+      None
+    };
+
+    Ok(ListPlayerBan {
+      player_bans: rows,
+      next_id,
+    })
   })
 }
 
 pub fn get_ban(conn: &DbConn, id: i32) -> Result<PlayerBan> {
-  player_ban::table
-    .inner_join(player::table)
-    .select(PlayerBan::COLUMNS)
-    .filter(player_ban::id.eq(id))
-    .first(conn)
-    .map_err(Into::into)
+  db_dispatch!(conn, {
+    player_ban::table
+      .inner_join(player::table)
+      .select(PlayerBan::COLUMNS)
+      .filter(player_ban::id.eq(id))
+      .first(conn)
+      .map_err(Into::into)
+  })
 }
 
 pub fn create_ban(
@@ -214,6 +260,8 @@ pub fn create_ban(
   player_id: i32,
   ban_type: PlayerBanType,
   ban_expires_at: Option<DateTime<Utc>>,
+  reason: Option<String>,
+  api_client_id: Option<i32>,
 ) -> Result<()> {
   #[derive(Insertable)]
   #[table_name = "player_ban"]
@@ -221,95 +269,383 @@ pub fn create_ban(
     player_id: i32,
     ban_type: PlayerBanType,
     ban_expires_at: Option<DateTime<Utc>>,
+    reason: Option<String>,
+    api_client_id: Option<i32>,
   }
 
-  diesel::insert_into(player_ban::table)
-    .values(&Insert {
-      player_id,
-      ban_type,
-      ban_expires_at,
+  let insert = Insert {
+    player_id,
+    ban_type,
+    ban_expires_at,
+    reason,
+    api_client_id,
+  };
+  db_dispatch!(conn, {
+    conn.transaction(|| {
+      // the replaced ban is kept in the history
+      let ids: Vec<i32> = player_ban::table
+        .filter(
+          player_ban::player_id
+            .eq(player_id)
+            .and(player_ban::ban_type.eq(ban_type)),
+        )
+        .select(player_ban::id)
+        .load(conn)?;
+      archive_bans(conn, &ids)?;
+      diesel::insert_into(player_ban::table)
+        .values(&insert)
+        .execute(conn)?;
+      Ok(())
     })
-    .on_conflict((player_ban::player_id, player_ban::ban_type))
-    .do_update()
-    .set(player_ban::ban_expires_at.eq(ban_expires_at))
-    .execute(conn)?;
-
-  Ok(())
+  })
 }
 
 pub fn remove_ban_by_type(conn: &DbConn, player_id: i32, ban_type: PlayerBanType) -> Result<()> {
-  diesel::delete(
-    player_ban::table.filter(
-      player_ban::player_id
-        .eq(player_id)
-        .and(player_ban::ban_type.eq(ban_type)),
-    ),
-  )
-  .execute(conn)?;
-
-  Ok(())
+  db_dispatch!(conn, {
+    conn.transaction(|| {
+      let ids: Vec<i32> = player_ban::table
+        .filter(
+          player_ban::player_id
+            .eq(player_id)
+            .and(player_ban::ban_type.eq(ban_type)),
+        )
+        .select(player_ban::id)
+        .load(conn)?;
+      archive_bans(conn, &ids)
+    })
+  })
 }
 
 pub fn remove_ban(conn: &DbConn, id: i32) -> Result<()> {
-  diesel::delete(player_ban::table.find(id)).execute(conn)?;
-  Ok(())
+  conn.transaction(|| archive_bans(conn, &[id]))
 }
 
-pub fn check_ban_api_client_id(conn: &DbConn, api_client_id: i32, id: i32) -> Result<()> {
-  let n = player_ban::table
-    .inner_join(player::table)
-    .filter(
-      player::api_client_id
+/// Moves bans to `player_ban_history`
+fn archive_bans(conn: &DbConn, ids: &[i32]) -> Result<()> {
+  #[derive(Insertable)]
+  #[table_name = "player_ban_history"]
+  struct Insert {
+    player_id: i32,
+    ban_type: PlayerBanType,
+    reason: Option<String>,
+    api_client_id: Option<i32>,
+    ban_expires_at: Option<DateTime<Utc>>,
+    ban_created_at: DateTime<Utc>,
+  }
+
+  if ids.is_empty() {
+    return Ok(());
+  }
+
+  db_dispatch!(conn, {
+    let rows: Vec<(
+      i32,
+      PlayerBanType,
+      Option<String>,
+      Option<i32>,
+      Option<DateTime<Utc>>,
+      DateTime<Utc>,
+    )> = player_ban::table
+      .filter(player_ban::id.eq_any(ids))
+      .select((
+        player_ban::player_id,
+        player_ban::ban_type,
+        player_ban::reason,
+        player_ban::api_client_id,
+        player_ban::ban_expires_at,
+        player_ban::created_at,
+      ))
+      .load(conn)?;
+    for (player_id, ban_type, reason, api_client_id, ban_expires_at, ban_created_at) in rows {
+      diesel::insert_into(player_ban_history::table)
+        .values(&Insert {
+          player_id,
+          ban_type,
+          reason,
+          api_client_id,
+          ban_expires_at,
+          ban_created_at,
+        })
+        .execute(conn)?;
+    }
+    diesel::delete(player_ban::table.filter(player_ban::id.eq_any(ids))).execute(conn)?;
+    Ok(())
+  })
+}
+
+pub struct ListPlayerBanHistory {
+  pub items: Vec<PlayerBanHistory>,
+  pub next_id: Option<i32>,
+}
+
+/// Lists removed and replaced bans of the players of the API client, newest first
+pub fn list_ban_history(
+  conn: &DbConn,
+  api_client_id: i32,
+  player_id: Option<i32>,
+  next_id: Option<i32>,
+) -> Result<ListPlayerBanHistory> {
+  db_dispatch!(conn, {
+    const PAGE_SIZE: i64 = 100;
+    let mut q = player_ban_history::table
+      .inner_join(player::table)
+      .select(PlayerBanHistory::COLUMNS)
+      .filter(player::api_client_id.eq(api_client_id))
+      .order(player_ban_history::id.desc())
+      .limit(PAGE_SIZE + 1)
+      .into_boxed();
+
+    if let Some(id) = player_id {
+      q = q.filter(player_ban_history::player_id.eq(id));
+    }
+
+    if let Some(id) = next_id {
+      q = q.filter(player_ban_history::id.le(id));
+    }
+
+    let mut rows = q.load::<PlayerBanHistory>(conn)?;
+    let next_id = if rows.len() > PAGE_SIZE as usize {
+      let id = rows.last().map(|row| row.id);
+      rows.truncate(PAGE_SIZE as usize);
+      id
+    } else {
+      None
+    };
+
+    Ok(ListPlayerBanHistory {
+      items: rows,
+      next_id,
+    })
+  })
+}
+
+/// Returns the first active ban of `ban_type` of the players
+pub fn get_active_ban(
+  conn: &DbConn,
+  player_ids: &[i32],
+  ban_type: PlayerBanType,
+) -> Result<Option<BanInfo>> {
+  db_dispatch!(conn, {
+    let row: Option<(Option<String>, Option<DateTime<Utc>>)> = player_ban::table
+      .filter(
+        player_ban::player_id
+          .eq_any(player_ids)
+          .and(player_ban::ban_type.eq(ban_type))
+          .and(
+            player_ban::ban_expires_at
+              .gt(Utc::now())
+              .or(player_ban::ban_expires_at.is_null()),
+          ),
+      )
+      .select((player_ban::reason, player_ban::ban_expires_at))
+      .first(conn)
+      .optional()?;
+    Ok(row.map(|(reason, ban_expires_at)| BanInfo {
+      ban_type: Some(ban_type),
+      reason,
+      ban_expires_at,
+    }))
+  })
+}
+
+/// Returns the creation time of the latest ban of `ban_type`, including removed and replaced bans
+pub fn get_last_ban_created_at(
+  conn: &DbConn,
+  player_id: i32,
+  ban_type: PlayerBanType,
+) -> Result<Option<DateTime<Utc>>> {
+  db_dispatch!(conn, {
+    let active: Option<DateTime<Utc>> = player_ban::table
+      .filter(
+        player_ban::player_id
+          .eq(player_id)
+          .and(player_ban::ban_type.eq(ban_type)),
+      )
+      .select(player_ban::created_at)
+      .order(player_ban::created_at.desc())
+      .first(conn)
+      .optional()?;
+    if active.is_some() {
+      return Ok(active);
+    }
+    player_ban_history::table
+      .filter(
+        player_ban_history::player_id
+          .eq(player_id)
+          .and(player_ban_history::ban_type.eq(ban_type)),
+      )
+      .select(player_ban_history::ban_created_at)
+      .order(player_ban_history::ban_created_at.desc())
+      .first(conn)
+      .optional()
+      .map_err(Into::into)
+  })
+}
+
+pub fn list_ip_bans(conn: &DbConn, api_client_id: i32) -> Result<Vec<IpBan>> {
+  db_dispatch!(conn, {
+    ip_ban::table
+      .filter(ip_ban::api_client_id.eq(api_client_id))
+      .select(IpBan::COLUMNS)
+      .order(ip_ban::id)
+      .load(conn)
+      .map_err(Into::into)
+  })
+}
+
+/// Bans an IP address or subnet from connecting as any player of the API client,
+/// replaces the existing ban of the same range
+pub fn create_ip_ban(
+  conn: &DbConn,
+  api_client_id: i32,
+  ip_range: IpRange,
+  reason: Option<String>,
+  ban_expires_at: Option<DateTime<Utc>>,
+) -> Result<IpBan> {
+  #[derive(Insertable)]
+  #[table_name = "ip_ban"]
+  struct Insert {
+    api_client_id: i32,
+    ip_range: String,
+    reason: Option<String>,
+    ban_expires_at: Option<DateTime<Utc>>,
+  }
+
+  let ip_range = ip_range.to_string();
+  db_dispatch!(conn, {
+    conn.transaction(|| {
+      let key = ip_ban::api_client_id
         .eq(api_client_id)
-        .and(player_ban::id.eq(id)),
+        .and(ip_ban::ip_range.eq(&ip_range));
+      diesel::delete(ip_ban::table.filter(key)).execute(conn)?;
+      diesel::insert_into(ip_ban::table)
+        .values(&Insert {
+          api_client_id,
+          ip_range: ip_range.clone(),
+          reason,
+          ban_expires_at,
+        })
+        .execute(conn)?;
+      Ok(
+        ip_ban::table
+          .filter(key)
+          .select(IpBan::COLUMNS)
+          .first(conn)?,
+      )
+    })
+  })
+}
+
+pub fn remove_ip_ban(conn: &DbConn, api_client_id: i32, id: i32) -> Result<()> {
+  db_dispatch!(conn, {
+    let n = diesel::delete(
+      ip_ban::table.filter(
+        ip_ban::id
+          .eq(id)
+          .and(ip_ban::api_client_id.eq(api_client_id)),
+      ),
     )
-    .count()
-    .get_result::<i64>(conn)?;
-  if n == 0 {
-    return Err(Error::PlayerOwnerCheckFailed);
-  }
-  Ok(())
+    .execute(conn)?;
+    if n == 0 {
+      return Err(Error::IpBanNotFound);
+    }
+    Ok(())
+  })
+}
+
+/// Returns the active IP ban matching `ip` issued by the API client of the player
+pub fn get_active_ip_ban(conn: &DbConn, player_id: i32, ip: IpAddr) -> Result<Option<BanInfo>> {
+  db_dispatch!(conn, {
+    let api_client_id: i32 = player::table
+      .find(player_id)
+      .select(player::api_client_id)
+      .first(conn)?;
+    let bans: Vec<IpBan> = ip_ban::table
+      .filter(
+        ip_ban::api_client_id.eq(api_client_id).and(
+          ip_ban::ban_expires_at
+            .gt(Utc::now())
+            .or(ip_ban::ban_expires_at.is_null()),
+        ),
+      )
+      .select(IpBan::COLUMNS)
+      .load(conn)?;
+    for ban in bans {
+      match ban.ip_range.parse::<IpRange>() {
+        Ok(range) if range.contains(ip) => {
+          return Ok(Some(BanInfo {
+            ban_type: None,
+            reason: ban.reason,
+            ban_expires_at: ban.ban_expires_at,
+          }))
+        }
+        Ok(_) => {}
+        Err(err) => tracing::warn!(id = ban.id, "ip ban: {}", err),
+      }
+    }
+    Ok(None)
+  })
+}
+
+pub fn check_ban_api_client_id(conn: &DbConn, api_client_id: i32, id: i32) -> Result<()> {
+  db_dispatch!(conn, {
+    let n = player_ban::table
+      .inner_join(player::table)
+      .filter(
+        player::api_client_id
+          .eq(api_client_id)
+          .and(player_ban::id.eq(id)),
+      )
+      .count()
+      .get_result::<i64>(conn)?;
+    if n == 0 {
+      return Err(Error::PlayerOwnerCheckFailed);
+    }
+    Ok(())
+  })
 }
 
 pub fn get_ban_list_map(
   conn: &DbConn,
   player_ids: &[i32],
 ) -> Result<BTreeMap<i32, Vec<PlayerBanType>>> {
-  use diesel::dsl::sql;
-  use diesel::pg::expression::dsl::any;
-  let pairs: Vec<(i32, PlayerBanType)> = player_ban::table
-    .select((player_ban::player_id, player_ban::ban_type))
-    .filter(
-      player_ban::player_id.eq(any(player_ids)).and(
-        player_ban::ban_expires_at
-          .gt(sql("now()"))
-          .or(player_ban::ban_expires_at.is_null()),
-      ),
-    )
-    .load(conn)?;
-  let mut map = BTreeMap::new();
-  for (player_id, ban_type) in pairs {
-    map
-      .entry(player_id)
-      .or_insert_with(|| vec![])
-      .push(ban_type);
-  }
-  Ok(map)
+  db_dispatch!(conn, {
+    let pairs: Vec<(i32, PlayerBanType)> = player_ban::table
+      .select((player_ban::player_id, player_ban::ban_type))
+      .filter(
+        player_ban::player_id.eq_any(player_ids).and(
+          player_ban::ban_expires_at
+            .gt(Utc::now())
+            .or(player_ban::ban_expires_at.is_null()),
+        ),
+      )
+      .load(conn)?;
+    let mut map = BTreeMap::new();
+    for (player_id, ban_type) in pairs {
+      map
+        .entry(player_id)
+        .or_insert_with(|| vec![])
+        .push(ban_type);
+    }
+    Ok(map)
+  })
 }
 
 pub fn check_player_api_client_id(conn: &DbConn, api_client_id: i32, player_id: i32) -> Result<()> {
-  let n = player::table
-    .filter(
-      player::id
-        .eq(player_id)
-        .and(player::api_client_id.eq(api_client_id)),
-    )
-    .count()
-    .get_result::<i64>(conn)?;
-  if n == 0 {
-    return Err(Error::PlayerOwnerCheckFailed);
-  }
-  Ok(())
+  db_dispatch!(conn, {
+    let n = player::table
+      .filter(
+        player::id
+          .eq(player_id)
+          .and(player::api_client_id.eq(api_client_id)),
+      )
+      .count()
+      .get_result::<i64>(conn)?;
+    if n == 0 {
+      return Err(Error::PlayerOwnerCheckFailed);
+    }
+    Ok(())
+  })
 }
 
 #[derive(Debug, Insertable)]
@@ -373,3 +709,234 @@ impl From<Row> for PlayerRef {
     }
   }
 }
+
+#[tokio::test]
+async fn test_upsert() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let data = |name: &str, source_id: &str| UpsertPlayer {
+      api_client_id: setup.api_client_id,
+      name: name.to_string(),
+      source: PlayerSource::Api,
+      source_id: source_id.to_string(),
+      source_state: None,
+      realm: Some("eu".to_string()),
+    };
+
+    assert!(matches!(
+      upsert(conn, &data("Alice", "")),
+      Err(Error::PlayerSourceIdInvalid)
+    ));
+
+    let alice = upsert(conn, &data("Alice", "1"))?;
+    let renamed = upsert(conn, &data("Alice2", "1"))?;
+    let bob = upsert(conn, &data("Bob", "2"))?;
+    assert_eq!(renamed.id, alice.id);
+    assert_eq!(renamed.name, "Alice2");
+    assert_eq!(renamed.realm.as_deref(), Some("eu"));
+    assert_eq!(renamed.created_at, alice.created_at);
+    assert_ne!(bob.id, alice.id);
+    assert_eq!(get(conn, alice.id)?.name, "Alice2");
+    Ok(())
+  })
+  .await;
+}
+
+#[tokio::test]
+async fn test_mute() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let alice = crate::lan::get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let bob = crate::lan::get_or_create_player(conn, setup.api_client_id, "Bob".to_string())?;
+    let carol = crate::lan::get_or_create_player(conn, setup.api_client_id, "Carol".to_string())?;
+
+    add_mute(conn, alice, bob)?;
+    add_mute(conn, alice, bob)?;
+    add_mute(conn, alice, carol)?;
+    add_mute(conn, carol, alice)?;
+
+    let map = get_mute_list_map(conn, &[alice, bob, carol])?;
+    assert_eq!(map.get(&alice), Some(&vec![bob, carol]));
+    assert_eq!(map.get(&bob), None);
+    assert_eq!(map.get(&carol), Some(&vec![alice]));
+
+    // only mutes between the given players are returned
+    let map = get_mute_list_map(conn, &[alice, bob])?;
+    assert_eq!(map.get(&alice), Some(&vec![bob]));
+    assert_eq!(map.get(&carol), None);
+
+    remove_mute(conn, alice, bob)?;
+    let map = get_mute_list_map(conn, &[alice, bob, carol])?;
+    assert_eq!(map.get(&alice), Some(&vec![carol]));
+    Ok(())
+  })
+  .await;
+}
+
+#[tokio::test]
+async fn test_ban() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let alice = crate::lan::get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let bob = crate::lan::get_or_create_player(conn, setup.api_client_id, "Bob".to_string())?;
+
+    assert!(get_active_ban(conn, &[alice], PlayerBanType::Chat)?.is_none());
+    assert!(get_last_ban_created_at(conn, alice, PlayerBanType::Chat)?.is_none());
+
+    // whole seconds, SQLite timestamps have microsecond precision
+    let expires_at = {
+      use chrono::TimeZone;
+      Utc.timestamp(Utc::now().timestamp() + 3600, 0)
+    };
+    create_ban(
+      conn,
+      alice,
+      PlayerBanType::Chat,
+      Some(expires_at),
+      Some("spam".to_string()),
+      Some(setup.api_client_id),
+    )?;
+    // expired bans are not active
+    create_ban(
+      conn,
+      bob,
+      PlayerBanType::Chat,
+      Some(Utc::now() - chrono::Duration::hours(1)),
+      None,
+      None,
+    )?;
+    create_ban(conn, bob, PlayerBanType::Game, None, None, None)?;
+
+    let ban = get_active_ban(conn, &[bob, alice], PlayerBanType::Chat)?.unwrap();
+    assert_eq!(ban.ban_type, Some(PlayerBanType::Chat));
+    assert_eq!(ban.reason.as_deref(), Some("spam"));
+    assert_eq!(ban.ban_expires_at, Some(expires_at));
+    assert!(get_active_ban(conn, &[bob], PlayerBanType::Chat)?.is_none());
+    assert!(get_active_ban(conn, &[bob], PlayerBanType::Game)?.is_some());
+
+    let map = get_ban_list_map(conn, &[alice, bob])?;
+    assert_eq!(map.get(&alice), Some(&vec![PlayerBanType::Chat]));
+    assert_eq!(map.get(&bob), Some(&vec![PlayerBanType::Game]));
+
+    let list = list_ban(conn, setup.api_client_id, Some("ALI"), None)?;
+    assert_eq!(list.player_bans.len(), 1);
+    assert_eq!(list.player_bans[0].player.id, alice);
+    assert_eq!(list.player_bans[0].api_client_id, Some(setup.api_client_id));
+    assert_eq!(
+      list_ban(conn, setup.api_client_id, None, None)?
+        .player_bans
+        .len(),
+      3
+    );
+    assert!(list_ban(conn, setup.api_client_id + 1, None, None)?
+      .player_bans
+      .is_empty());
+
+    // replaced and removed bans are moved to the history
+    let created_at = get_last_ban_created_at(conn, alice, PlayerBanType::Chat)?.unwrap();
+    create_ban(
+      conn,
+      alice,
+      PlayerBanType::Chat,
+      None,
+      Some("spam again".to_string()),
+      None,
+    )?;
+    let ban = get_active_ban(conn, &[alice], PlayerBanType::Chat)?.unwrap();
+    assert_eq!(ban.reason.as_deref(), Some("spam again"));
+    assert_eq!(ban.ban_expires_at, None);
+    remove_ban_by_type(conn, alice, PlayerBanType::Chat)?;
+    assert!(get_active_ban(conn, &[alice], PlayerBanType::Chat)?.is_none());
+    assert!(get_last_ban_created_at(conn, alice, PlayerBanType::Chat)?.unwrap() >= created_at);
+
+    let history = list_ban_history(conn, setup.api_client_id, Some(alice), None)?;
+    assert!(history.next_id.is_none());
+    let items: Vec<_> = history
+      .items
+      .iter()
+      .map(|item| {
+        (
+          item.reason.as_deref(),
+          item.ban_expires_at,
+          item.api_client_id,
+        )
+      })
+      .collect();
+    assert_eq!(
+      items,
+      vec![
+        (Some("spam again"), None, None),
+        (Some("spam"), Some(expires_at), Some(setup.api_client_id)),
+      ]
+    );
+    assert_eq!(history.items[1].ban_created_at, created_at);
+    assert!(
+      list_ban_history(conn, setup.api_client_id, Some(bob), None)?
+        .items
+        .is_empty()
+    );
+    Ok(())
+  })
+  .await;
+}
+
+#[tokio::test]
+async fn test_ip_ban() {
+  crate::db::exec_test(|conn| {
+    let setup = crate::lan::setup_test(conn)?;
+    let alice = crate::lan::get_or_create_player(conn, setup.api_client_id, "Alice".to_string())?;
+    let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+    let ban = create_ip_ban(
+      conn,
+      setup.api_client_id,
+      "192.168.1.10/24".parse()?,
+      Some("smurfing".to_string()),
+      None,
+    )?;
+    assert_eq!(ban.ip_range, "192.168.1.0/24");
+    create_ip_ban(
+      conn,
+      setup.api_client_id,
+      "10.0.0.1".parse()?,
+      None,
+      Some(Utc::now() - chrono::Duration::hours(1)),
+    )?;
+
+    let info = get_active_ip_ban(conn, alice, ip("192.168.1.20"))?.unwrap();
+    assert_eq!(info.ban_type, None);
+    assert_eq!(info.reason.as_deref(), Some("smurfing"));
+    assert!(get_active_ip_ban(conn, alice, ip("::ffff:192.168.1.20"))?.is_some());
+    assert!(get_active_ip_ban(conn, alice, ip("192.168.2.20"))?.is_none());
+    // expired
+    assert!(get_active_ip_ban(conn, alice, ip("10.0.0.1"))?.is_none());
+
+    // the ban of the same range is replaced
+    let replaced = create_ip_ban(
+      conn,
+      setup.api_client_id,
+      "192.168.1.0/24".parse()?,
+      None,
+      None,
+    )?;
+    assert_ne!(replaced.id, ban.id);
+    let ranges: Vec<_> = list_ip_bans(conn, setup.api_client_id)?
+      .into_iter()
+      .map(|ban| ban.ip_range)
+      .collect();
+    assert_eq!(ranges, vec!["10.0.0.1/32", "192.168.1.0/24"]);
+
+    assert!(matches!(
+      remove_ip_ban(conn, setup.api_client_id + 1, replaced.id),
+      Err(Error::IpBanNotFound)
+    ));
+    remove_ip_ban(conn, setup.api_client_id, replaced.id)?;
+    assert!(matches!(
+      remove_ip_ban(conn, setup.api_client_id, replaced.id),
+      Err(Error::IpBanNotFound)
+    ));
+    assert!(get_active_ip_ban(conn, alice, ip("192.168.1.20"))?.is_none());
+    Ok(())
+  })
+  .await;
+}
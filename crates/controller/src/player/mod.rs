@@ -1,3 +1,4 @@
+mod ban;
 pub mod db;
 pub mod session;
 pub(crate) mod state;
@@ -8,4 +9,5 @@ pub mod message {
   pub use super::state::ping::{GetPlayersPingSnapshot, UpdatePing};
 }
 
+pub use ban::{BanInfo, IpRange};
 pub use types::*;
@@ -4,6 +4,7 @@ pub mod sender;
 
 use crate::client::PlayerSender;
 use crate::error::Error;
+use crate::event::{EventHub, EventSender};
 use crate::state::Data;
 use flo_state::{async_trait, Actor, RegistryRef, Service};
 use flo_types::ping::PingStats;
@@ -11,15 +12,16 @@ use flo_types::ping::PingStats;
 use crate::player::state::sender::PlayerFrames;
 use std::collections::BTreeMap;
 
-#[derive(Debug)]
 pub struct PlayerRegistry {
   registry: BTreeMap<i32, PlayerState>,
+  events: EventSender,
 }
 
 impl PlayerRegistry {
-  pub fn new() -> Self {
+  pub fn new(events: EventSender) -> Self {
     Self {
       registry: Default::default(),
+      events,
     }
   }
 }
@@ -30,8 +32,9 @@ impl Actor for PlayerRegistry {}
 impl Service<Data> for PlayerRegistry {
   type Error = Error;
 
-  async fn create(_registry: &mut RegistryRef<Data>) -> Result<Self, Self::Error> {
-    Ok(PlayerRegistry::new())
+  async fn create(registry: &mut RegistryRef<Data>) -> Result<Self, Self::Error> {
+    let events = registry.resolve::<EventHub>().await?;
+    Ok(PlayerRegistry::new(events.into()))
   }
 }
 
@@ -113,9 +113,15 @@ impl Handler<PlayerReplaceGame> for PlayerRegistry {
     use flo_net::proto::flo_connect::*;
     let game_id = game.id;
 
+    let frame_session_update = get_session_update_packet(Some(game.id)).encode_as_frame()?;
+    self
+      .events
+      .publish_player(game_id, player_id, frame_session_update.clone())
+      .await;
+
     if let Entry::Occupied(mut entry) = self.registry.entry(player_id) {
       let frames = vec![
-        get_session_update_packet(Some(game.id)).encode_as_frame()?,
+        frame_session_update,
         PacketPlayerMuteListUpdate { mute_list }.encode_as_frame()?,
         PacketGameInfo {
           game: Some(game.pack()?),
@@ -163,6 +169,10 @@ impl Handler<PlayersReplaceGame> for PlayerRegistry {
     .encode_as_frame()?;
 
     for player_id in player_ids {
+      self
+        .events
+        .publish_player(game_id, player_id, frame_session_update.clone())
+        .await;
       if let Entry::Occupied(mut entry) = self.registry.entry(player_id) {
         let frames = vec![
           frame_session_update.clone(),
@@ -199,13 +209,15 @@ impl Handler<PlayerLeaveGame> for PlayerRegistry {
     _: &mut Context<Self>,
     PlayerLeaveGame { player_id, game_id }: PlayerLeaveGame,
   ) -> Result<()> {
+    let frame_session_update = get_session_update_packet(None).encode_as_frame()?;
+    self
+      .events
+      .publish_player(game_id, player_id, frame_session_update.clone())
+      .await;
+
     if let Entry::Occupied(mut entry) = self.registry.entry(player_id) {
       if entry.get().game_id == Some(game_id) {
-        if !entry
-          .get_mut()
-          .sender
-          .try_send(get_session_update_packet(None).encode_as_frame()?)
-        {
+        if !entry.get_mut().sender.try_send(frame_session_update) {
           entry.remove();
         } else {
           entry.get_mut().game_id = None;
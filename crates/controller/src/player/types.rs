@@ -4,7 +4,7 @@ use s2_grpc_utils::result::Error as ProtoError;
 use s2_grpc_utils::{S2ProtoEnum, S2ProtoPack, S2ProtoUnpack};
 use serde::{Deserialize, Serialize};
 
-use crate::schema::{player, player_ban};
+use crate::schema::{ip_ban, player, player_ban, player_ban_history};
 
 #[derive(Debug, Serialize, Deserialize, S2ProtoPack, S2ProtoUnpack)]
 #[s2_grpc(message_type = "flo_grpc::player::Player")]
@@ -98,23 +98,33 @@ pub struct BNetState {
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, BSDieselEnum, S2ProtoEnum)]
 #[repr(i32)]
-#[s2_grpc(proto_enum_type(
-  flo_grpc::player::PlayerBanType,
-  flo_net::proto::flo_node::PlayerBanType
-))]
+#[s2_grpc(proto_enum_type(flo_net::proto::flo_node::PlayerBanType))]
 pub enum PlayerBanType {
   Chat = 0,
+  Matchmaking = 1,
+  /// Creating and joining games
+  Game = 2,
 }
 
-#[derive(Debug, Queryable, Serialize, Deserialize, S2ProtoPack, S2ProtoUnpack)]
-#[s2_grpc(message_type = "flo_grpc::player::PlayerBan")]
+// flo-grpc only has the chat ban type
+impl From<flo_grpc::player::PlayerBanType> for PlayerBanType {
+  fn from(value: flo_grpc::player::PlayerBanType) -> Self {
+    match value {
+      flo_grpc::player::PlayerBanType::Chat => PlayerBanType::Chat,
+    }
+  }
+}
+
+#[derive(Debug, Queryable, Serialize, Deserialize)]
 pub struct PlayerBan {
   pub id: i32,
   pub player: PlayerRef,
-  #[s2_grpc(proto_enum)]
   pub ban_type: PlayerBanType,
   pub ban_expires_at: Option<DateTime<Utc>>,
   pub created_at: DateTime<Utc>,
+  pub reason: Option<String>,
+  /// The API client that issued the ban, `None` for bans issued by the controller
+  pub api_client_id: Option<i32>,
 }
 
 pub(crate) type PlayerBanColumns = (
@@ -123,8 +133,22 @@ pub(crate) type PlayerBanColumns = (
   player_ban::ban_type,
   player_ban::ban_expires_at,
   player_ban::created_at,
+  player_ban::reason,
+  player_ban::api_client_id,
 );
 
+impl S2ProtoPack<flo_grpc::player::PlayerBan> for PlayerBan {
+  fn pack(self) -> Result<flo_grpc::player::PlayerBan, ProtoError> {
+    Ok(flo_grpc::player::PlayerBan {
+      id: self.id,
+      player: Some(self.player.pack()?),
+      ban_type: self.ban_type as i32,
+      ban_expires_at: self.ban_expires_at.map(S2ProtoPack::pack).transpose()?,
+      created_at: Some(self.created_at.pack()?),
+    })
+  }
+}
+
 impl PlayerBan {
   pub(crate) const COLUMNS: PlayerBanColumns = (
     player_ban::id,
@@ -132,5 +156,76 @@ impl PlayerBan {
     player_ban::ban_type,
     player_ban::ban_expires_at,
     player_ban::created_at,
+    player_ban::reason,
+    player_ban::api_client_id,
+  );
+}
+
+/// A ban that was removed or replaced
+#[derive(Debug, Queryable, Serialize, Deserialize)]
+pub struct PlayerBanHistory {
+  pub id: i32,
+  pub player: PlayerRef,
+  pub ban_type: PlayerBanType,
+  pub reason: Option<String>,
+  pub api_client_id: Option<i32>,
+  pub ban_expires_at: Option<DateTime<Utc>>,
+  pub ban_created_at: DateTime<Utc>,
+  /// When the ban was removed or replaced
+  pub created_at: DateTime<Utc>,
+}
+
+pub(crate) type PlayerBanHistoryColumns = (
+  player_ban_history::id,
+  PlayerRefColumns,
+  player_ban_history::ban_type,
+  player_ban_history::reason,
+  player_ban_history::api_client_id,
+  player_ban_history::ban_expires_at,
+  player_ban_history::ban_created_at,
+  player_ban_history::created_at,
+);
+
+impl PlayerBanHistory {
+  pub(crate) const COLUMNS: PlayerBanHistoryColumns = (
+    player_ban_history::id,
+    PlayerRef::COLUMNS,
+    player_ban_history::ban_type,
+    player_ban_history::reason,
+    player_ban_history::api_client_id,
+    player_ban_history::ban_expires_at,
+    player_ban_history::ban_created_at,
+    player_ban_history::created_at,
+  );
+}
+
+#[derive(Debug, Queryable, Serialize, Deserialize)]
+pub struct IpBan {
+  pub id: i32,
+  pub api_client_id: i32,
+  /// Normalized CIDR notation, see `IpRange`
+  pub ip_range: String,
+  pub reason: Option<String>,
+  pub ban_expires_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
+
+pub(crate) type IpBanColumns = (
+  ip_ban::id,
+  ip_ban::api_client_id,
+  ip_ban::ip_range,
+  ip_ban::reason,
+  ip_ban::ban_expires_at,
+  ip_ban::created_at,
+);
+
+impl IpBan {
+  pub(crate) const COLUMNS: IpBanColumns = (
+    ip_ban::id,
+    ip_ban::api_client_id,
+    ip_ban::ip_range,
+    ip_ban::reason,
+    ip_ban::ban_expires_at,
+    ip_ban::created_at,
   );
 }
\ No newline at end of file
@@ -1,13 +1,33 @@
 table! {
+    use crate::db::sql_types::*;
+
+    api_audit_log (id) {
+        id -> Int4,
+        api_client_id -> Int4,
+        method -> Text,
+        params -> Jsonb,
+        status_code -> Int4,
+        status_message -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
     api_client (id) {
         id -> Int4,
         name -> Text,
         secret_key -> Text,
         created_at -> Timestamptz,
+        scopes -> Jsonb,
+        rate_limit -> Nullable<Int4>,
     }
 }
 
 table! {
+    use crate::db::sql_types::*;
+
     game (id) {
         id -> Int4,
         name -> Text,
@@ -29,10 +49,76 @@ table! {
         mask_player_names -> Bool,
         game_version -> Nullable<Text>,
         enable_ping_equalizer -> Bool,
+        map_catalog_id -> Nullable<Int4>,
+        matchmaking_queue_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
+    game_chat_message (id) {
+        id -> Int4,
+        game_id -> Int4,
+        player_id -> Int4,
+        scope -> Int4,
+        to_player_id -> Nullable<Int4>,
+        game_time_ms -> Int4,
+        sent_at -> Timestamptz,
+        message -> Text,
+        filter_action -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
+    game_chat_violation (game_id, player_id) {
+        game_id -> Int4,
+        player_id -> Int4,
+        count -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
+    game_player_result (id) {
+        id -> Int4,
+        game_id -> Int4,
+        player_id -> Int4,
+        left_at_ms -> Nullable<Int4>,
+        leave_reason -> Nullable<Int4>,
+        disconnects -> Int4,
+        dropped -> Bool,
+        desynced -> Bool,
+        lag_count -> Int4,
+        lag_duration_ms -> Int4,
+        rtt_min -> Nullable<Int4>,
+        rtt_max -> Nullable<Int4>,
+        rtt_avg -> Nullable<Int4>,
+        created_at -> Timestamptz,
     }
 }
 
 table! {
+    use crate::db::sql_types::*;
+
+    game_result (id) {
+        id -> Int4,
+        game_id -> Int4,
+        node_id -> Int4,
+        duration_ms -> Int4,
+        desyncs -> Jsonb,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
     game_used_slot (id) {
         id -> Int4,
         game_id -> Int4,
@@ -53,6 +139,47 @@ table! {
 }
 
 table! {
+    use crate::db::sql_types::*;
+
+    ip_ban (id) {
+        id -> Int4,
+        api_client_id -> Int4,
+        ip_range -> Text,
+        reason -> Nullable<Text>,
+        ban_expires_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
+    map_catalog (id) {
+        id -> Int4,
+        sha1 -> Text,
+        checksum -> Bytea,
+        crc32 -> Bytea,
+        file_size -> Int4,
+        path -> Text,
+        name -> Text,
+        description -> Text,
+        author -> Text,
+        suggested_players -> Text,
+        width -> Int4,
+        height -> Int4,
+        flags -> Int4,
+        players -> Jsonb,
+        forces -> Jsonb,
+        preview -> Nullable<Bytea>,
+        uploaded_by -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
     map_checksum (id) {
         id -> Int4,
         sha1 -> Text,
@@ -61,6 +188,44 @@ table! {
 }
 
 table! {
+    use crate::db::sql_types::*;
+
+    matchmaking_queue (id) {
+        id -> Int4,
+        api_client_id -> Int4,
+        name -> Text,
+        team_size -> Int4,
+        num_teams -> Int4,
+        map_pool -> Jsonb,
+        default_rating -> Int4,
+        rating_window_initial -> Int4,
+        rating_window_growth -> Int4,
+        rating_window_max -> Int4,
+        max_ping -> Int4,
+        accept_timeout_secs -> Int4,
+        decline_ban_secs -> Int4,
+        enabled -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
+    matchmaking_rating (id) {
+        id -> Int4,
+        queue_id -> Int4,
+        player_id -> Int4,
+        rating -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
     node (id) {
         id -> Int4,
         name -> Text,
@@ -75,6 +240,8 @@ table! {
 }
 
 table! {
+    use crate::db::sql_types::*;
+
     player (id) {
         id -> Int4,
         name -> Text,
@@ -89,16 +256,37 @@ table! {
 }
 
 table! {
+    use crate::db::sql_types::*;
+
     player_ban (id) {
         id -> Int4,
         player_id -> Int4,
         ban_type -> Int4,
         ban_expires_at -> Nullable<Timestamptz>,
         created_at -> Timestamptz,
+        reason -> Nullable<Text>,
+        api_client_id -> Nullable<Int4>,
     }
 }
 
 table! {
+    use crate::db::sql_types::*;
+
+    player_ban_history (id) {
+        id -> Int4,
+        player_id -> Int4,
+        ban_type -> Int4,
+        reason -> Nullable<Text>,
+        api_client_id -> Nullable<Int4>,
+        ban_expires_at -> Nullable<Timestamptz>,
+        ban_created_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
     player_mute (id) {
         id -> Int4,
         player_id -> Int4,
@@ -107,20 +295,87 @@ table! {
     }
 }
 
+table! {
+    use crate::db::sql_types::*;
+
+    webhook (id) {
+        id -> Int4,
+        api_client_id -> Int4,
+        url -> Text,
+        secret -> Text,
+        events -> Jsonb,
+        enabled -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    use crate::db::sql_types::*;
+
+    webhook_delivery (id) {
+        id -> Int4,
+        webhook_id -> Int4,
+        event_type -> Text,
+        game_id -> Int4,
+        payload -> Jsonb,
+        status -> Int4,
+        attempts -> Int4,
+        last_status_code -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamptz,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+joinable!(api_audit_log -> api_client (api_client_id));
+joinable!(game -> map_catalog (map_catalog_id));
+joinable!(game -> matchmaking_queue (matchmaking_queue_id));
 joinable!(game -> node (node_id));
 joinable!(game -> player (created_by));
+joinable!(game_chat_message -> game (game_id));
+joinable!(game_chat_violation -> game (game_id));
+joinable!(game_chat_violation -> player (player_id));
+joinable!(game_player_result -> game (game_id));
+joinable!(game_player_result -> player (player_id));
+joinable!(game_result -> game (game_id));
+joinable!(game_result -> node (node_id));
 joinable!(game_used_slot -> game (game_id));
 joinable!(game_used_slot -> player (player_id));
+joinable!(ip_ban -> api_client (api_client_id));
+joinable!(map_catalog -> api_client (uploaded_by));
+joinable!(matchmaking_queue -> api_client (api_client_id));
+joinable!(matchmaking_rating -> matchmaking_queue (queue_id));
+joinable!(matchmaking_rating -> player (player_id));
 joinable!(player -> api_client (api_client_id));
+joinable!(player_ban -> api_client (api_client_id));
 joinable!(player_ban -> player (player_id));
+joinable!(player_ban_history -> api_client (api_client_id));
+joinable!(player_ban_history -> player (player_id));
+joinable!(webhook -> api_client (api_client_id));
+joinable!(webhook_delivery -> game (game_id));
+joinable!(webhook_delivery -> webhook (webhook_id));
 
 allow_tables_to_appear_in_same_query!(
+    api_audit_log,
     api_client,
     game,
+    game_chat_message,
+    game_chat_violation,
+    game_player_result,
+    game_result,
     game_used_slot,
+    ip_ban,
+    map_catalog,
     map_checksum,
+    matchmaking_queue,
+    matchmaking_rating,
     node,
     player,
     player_ban,
+    player_ban_history,
     player_mute,
+    webhook,
+    webhook_delivery,
 );
@@ -1,13 +1,18 @@
 mod actor_map;
 
-use bs_diesel_utils::{Executor, ExecutorRef};
+use crate::db::{Executor, ExecutorRef};
 use flo_state::{Addr, Message, Registry};
+use parking_lot::Mutex;
 
 use std::sync::Arc;
 
+use crate::api_client::RateLimiter;
 use crate::error::*;
+use crate::event::EventHub;
 use crate::game::state::GameRegistry;
+use crate::lan::{LanConfig, LanSetup};
 
+use crate::matchmaking::Matchmaker;
 use crate::node::NodeRegistry;
 use crate::player::state::PlayerRegistry;
 
@@ -28,25 +33,49 @@ pub struct ControllerState {
   pub players: Addr<PlayerRegistry>,
   pub player_packet_sender: PlayerRegistryHandle,
   pub config: Addr<ConfigStorage>,
+  pub matchmaking: Addr<Matchmaker>,
+  pub events: Addr<EventHub>,
+  pub lan: Option<LanSetup>,
+  /// Lobby chat messages sent by each player
+  pub chat_rate_limiter: Mutex<RateLimiter>,
 }
 
 pub type ControllerStateRef = Arc<ControllerState>;
 
 impl ControllerState {
   pub async fn init() -> Result<Self> {
-    let db = Executor::env().into_ref();
+    crate::config::init_settings()?;
+    let db = Executor::connect(&crate::config::settings().database_url)?.into_ref();
 
-    #[cfg(not(debug_assertions))]
-    {
+    // the diesel cli only manages the PostgreSQL migrations
+    if cfg!(not(debug_assertions)) || db.is_sqlite() {
       db.exec(|conn| crate::migration::run(conn)).await?;
     }
 
+    Self::with_db(db, None).await
+  }
+
+  /// Initializes the controller for a local network, see [`crate::lan`].
+  /// Migrations are always applied.
+  pub async fn init_lan(config: LanConfig) -> Result<Self> {
+    crate::config::init_settings()?;
+    let db = Executor::connect(&crate::config::settings().database_url)?.into_ref();
+    db.exec(|conn| crate::migration::run(conn)).await?;
+    let lan = db
+      .exec(move |conn| crate::lan::setup(conn, &config))
+      .await?;
+    Self::with_db(db, Some(lan)).await
+  }
+
+  async fn with_db(db: ExecutorRef, lan: Option<LanSetup>) -> Result<Self> {
     let registry = Registry::with_data(Data { db: db.clone() });
 
     let nodes = registry.resolve().await?;
     let games = registry.resolve().await?;
     let players = registry.resolve().await?;
     let config = registry.resolve().await?;
+    let matchmaking = registry.resolve().await?;
+    let events = registry.resolve().await?;
 
     Ok(ControllerState {
       db,
@@ -56,12 +85,17 @@ impl ControllerState {
       players: players.clone(),
       player_packet_sender: PlayerRegistryHandle::from(players),
       config,
+      matchmaking,
+      events,
+      lan,
+      chat_rate_limiter: Mutex::new(RateLimiter::default()),
     })
   }
 
   pub async fn reload(&self) -> Result<()> {
     self.config.send(Reload).await??;
     self.nodes.send(Reload).await??;
+    self.matchmaking.send(Reload).await??;
     Ok(())
   }
 
@@ -0,0 +1,410 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde_json::Value;
+
+use crate::db::{sqlite_last_insert_id, Backend, DbConn};
+use crate::error::*;
+use crate::schema::{game, player, webhook, webhook_delivery};
+use crate::webhook::{
+  retry_delay, CreateWebhookParams, Webhook, WebhookDelivery, WebhookDeliveryStatus, WebhookEvent,
+  WebhookEventType, MAX_ATTEMPTS,
+};
+
+pub fn list_webhooks(conn: &DbConn, api_client_id: i32) -> Result<Vec<Webhook>> {
+  db_dispatch!(conn, {
+    webhook::table
+      .filter(webhook::api_client_id.eq(api_client_id))
+      .order(webhook::id)
+      .load::<Row>(conn)?
+      .into_iter()
+      .map(Row::into_webhook)
+      .collect()
+  })
+}
+
+pub fn create_webhook(
+  conn: &DbConn,
+  api_client_id: i32,
+  params: CreateWebhookParams,
+) -> Result<Webhook> {
+  db_dispatch!(conn, {
+    if !(params.url.starts_with("http://") || params.url.starts_with("https://"))
+      || params.url.parse::<hyper::Uri>().is_err()
+    {
+      return Err(Error::WebhookInvalid("invalid url".to_string()));
+    }
+
+    if params.secret.is_empty() {
+      return Err(Error::WebhookInvalid("secret is empty".to_string()));
+    }
+
+    if params.events.is_empty() {
+      return Err(Error::WebhookInvalid("no event subscribed".to_string()));
+    }
+
+    let mut events = Vec::with_capacity(params.events.len());
+    for name in &params.events {
+      let event_type = WebhookEventType::parse(name)
+        .ok_or_else(|| Error::WebhookInvalid(format!("unknown event: {}", name)))?;
+      if !events.contains(&event_type) {
+        events.push(event_type);
+      }
+    }
+
+    let insert = WebhookInsert {
+      api_client_id,
+      url: &params.url,
+      secret: &params.secret,
+      events: serde_json::to_value(&events)?,
+      enabled: params.enabled,
+    };
+
+    insert_webhook_row(conn, &insert)?.into_webhook()
+  })
+}
+
+fn insert_webhook_row(conn: &DbConn, insert: &WebhookInsert) -> Result<Row> {
+  let row = match conn.backend() {
+    Backend::Pg(conn) => diesel::insert_into(webhook::table)
+      .values(insert)
+      .get_result::<Row>(conn)?,
+    Backend::Sqlite(conn) => conn.transaction(|| -> Result<_> {
+      diesel::insert_into(webhook::table)
+        .values(insert)
+        .execute(conn)?;
+      let id = sqlite_last_insert_id(conn)?;
+      Ok(webhook::table.find(id).first::<Row>(conn)?)
+    })?,
+  };
+  Ok(row)
+}
+
+pub fn delete_webhook(conn: &DbConn, api_client_id: i32, id: i32) -> Result<()> {
+  db_dispatch!(conn, {
+    let n = diesel::delete(
+      webhook::table.filter(
+        webhook::id
+          .eq(id)
+          .and(webhook::api_client_id.eq(api_client_id)),
+      ),
+    )
+    .execute(conn)?;
+    if n == 0 {
+      return Err(Error::WebhookNotFound);
+    }
+    Ok(())
+  })
+}
+
+/// Queues the event for every enabled webhook of the game creator's API client
+/// that subscribed to it, returns the number of created deliveries
+pub fn create_deliveries(
+  conn: &DbConn,
+  game_id: i32,
+  event: &WebhookEvent,
+  timestamp: DateTime<Utc>,
+) -> Result<usize> {
+  db_dispatch!(conn, {
+    let api_client_id: i32 = game::table
+      .inner_join(player::table)
+      .filter(game::id.eq(game_id))
+      .select(player::api_client_id)
+      .first(conn)
+      .optional()?
+      .ok_or(Error::GameNotFound)?;
+
+    let event_type = event.event_type();
+    let webhook_ids: Vec<i32> = webhook::table
+      .filter(
+        webhook::api_client_id
+          .eq(api_client_id)
+          .and(webhook::enabled.eq(true)),
+      )
+      .select((webhook::id, webhook::events))
+      .load::<(i32, Value)>(conn)?
+      .into_iter()
+      .filter_map(|(id, events)| {
+        let events: Vec<WebhookEventType> = serde_json::from_value(events).ok()?;
+        if events.contains(&event_type) {
+          Some(id)
+        } else {
+          None
+        }
+      })
+      .collect();
+
+    if webhook_ids.is_empty() {
+      return Ok(0);
+    }
+
+    let payload = event.to_payload(game_id, timestamp);
+    let inserts: Vec<_> = webhook_ids
+      .into_iter()
+      .map(|webhook_id| DeliveryInsert {
+        webhook_id,
+        event_type: event_type.as_str(),
+        game_id,
+        payload: payload.clone(),
+      })
+      .collect();
+
+    diesel::insert_into(webhook_delivery::table)
+      .values(&inserts)
+      .execute(conn)
+      .map_err(Into::into)
+  })
+}
+
+#[derive(Debug, Queryable)]
+pub struct PendingDelivery {
+  pub id: i32,
+  pub event_type: String,
+  pub payload: Value,
+  pub attempts: i32,
+  pub url: String,
+  pub secret: String,
+}
+
+/// Loads pending deliveries of enabled webhooks that are due to be sent
+pub fn get_due_deliveries(
+  conn: &DbConn,
+  exclude_ids: &[i32],
+  limit: i64,
+) -> Result<Vec<PendingDelivery>> {
+  db_dispatch!(conn, {
+    webhook_delivery::table
+      .inner_join(webhook::table)
+      .filter(
+        webhook_delivery::status
+          .eq(WebhookDeliveryStatus::Pending)
+          .and(webhook_delivery::next_attempt_at.le(Utc::now()))
+          .and(webhook::enabled.eq(true))
+          .and(webhook_delivery::id.ne_all(exclude_ids)),
+      )
+      .select((
+        webhook_delivery::id,
+        webhook_delivery::event_type,
+        webhook_delivery::payload,
+        webhook_delivery::attempts,
+        webhook::url,
+        webhook::secret,
+      ))
+      .order(webhook_delivery::next_attempt_at)
+      .limit(limit)
+      .load(conn)
+      .map_err(Into::into)
+  })
+}
+
+/// Outcome of a delivery attempt
+#[derive(Debug)]
+pub enum DeliveryAttempt {
+  /// Got a 2xx response
+  Succeeded { status_code: u16 },
+  /// Got a non-2xx response
+  Rejected { status_code: u16 },
+  /// Request failed or timed out
+  Error(String),
+}
+
+/// Records a delivery attempt and schedules the next attempt if needed,
+/// returns the new status of the delivery
+pub fn update_delivery(
+  conn: &DbConn,
+  id: i32,
+  attempts: i32,
+  attempt: &DeliveryAttempt,
+) -> Result<WebhookDeliveryStatus> {
+  db_dispatch!(conn, {
+    use webhook_delivery::dsl;
+
+    let attempts = attempts + 1;
+    let (status, status_code, error) = match *attempt {
+      DeliveryAttempt::Succeeded { status_code } => {
+        (WebhookDeliveryStatus::Succeeded, Some(status_code), None)
+      }
+      DeliveryAttempt::Rejected { status_code } => (
+        WebhookDeliveryStatus::Pending,
+        Some(status_code),
+        Some(format!("unexpected status code: {}", status_code)),
+      ),
+      DeliveryAttempt::Error(ref err) => (WebhookDeliveryStatus::Pending, None, Some(err.clone())),
+    };
+    let status = if status == WebhookDeliveryStatus::Pending && attempts >= MAX_ATTEMPTS {
+      WebhookDeliveryStatus::Failed
+    } else {
+      status
+    };
+    let next_attempt_at = Utc::now()
+      + chrono::Duration::from_std(retry_delay(attempts))
+        .unwrap_or_else(|_| chrono::Duration::seconds(0));
+
+    diesel::update(webhook_delivery::table.find(id))
+      .set((
+        dsl::status.eq(status),
+        dsl::attempts.eq(attempts),
+        dsl::last_status_code.eq(status_code.map(|v| v as i32)),
+        dsl::last_error.eq(error),
+        dsl::next_attempt_at.eq(next_attempt_at),
+      ))
+      .execute(conn)?;
+
+    Ok(status)
+  })
+}
+
+pub struct ListWebhookDelivery {
+  pub deliveries: Vec<WebhookDelivery>,
+  pub next_id: Option<i32>,
+}
+
+pub fn list_deliveries(
+  conn: &DbConn,
+  api_client_id: i32,
+  webhook_id: Option<i32>,
+  next_id: Option<i32>,
+) -> Result<ListWebhookDelivery> {
+  db_dispatch!(conn, {
+    const PAGE_SIZE: i64 = 100;
+    let mut q = webhook_delivery::table
+      .inner_join(webhook::table)
+      .select(DeliveryRow::COLUMNS)
+      .filter(webhook::api_client_id.eq(api_client_id))
+      .order(webhook_delivery::id.desc())
+      .limit(PAGE_SIZE + 1)
+      .into_boxed();
+
+    if let Some(id) = webhook_id {
+      q = q.filter(webhook_delivery::webhook_id.eq(id));
+    }
+
+    if let Some(id) = next_id {
+      q = q.filter(webhook_delivery::id.le(id));
+    }
+
+    let mut rows = q.load::<DeliveryRow>(conn)?;
+    let next_id = if rows.len() > PAGE_SIZE as usize {
+      let id = rows.last().map(|row| row.id);
+      rows.truncate(PAGE_SIZE as usize);
+      id
+    } else {
+      None
+    };
+
+    Ok(ListWebhookDelivery {
+      deliveries: rows.into_iter().map(DeliveryRow::into_delivery).collect(),
+      next_id,
+    })
+  })
+}
+
+#[derive(Debug, Queryable)]
+struct Row {
+  id: i32,
+  api_client_id: i32,
+  url: String,
+  _secret: String,
+  events: Value,
+  enabled: bool,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+impl Row {
+  fn into_webhook(self) -> Result<Webhook> {
+    let events: Vec<WebhookEventType> = serde_json::from_value(self.events)?;
+    Ok(Webhook {
+      id: self.id,
+      api_client_id: self.api_client_id,
+      url: self.url,
+      events: events.into_iter().map(|t| t.as_str().to_string()).collect(),
+      enabled: self.enabled,
+      created_at: self.created_at,
+      updated_at: self.updated_at,
+    })
+  }
+}
+
+#[derive(Debug, Queryable)]
+struct DeliveryRow {
+  id: i32,
+  webhook_id: i32,
+  event_type: String,
+  game_id: i32,
+  payload: Value,
+  status: WebhookDeliveryStatus,
+  attempts: i32,
+  last_status_code: Option<i32>,
+  last_error: Option<String>,
+  next_attempt_at: DateTime<Utc>,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+}
+
+type DeliveryRowColumns = (
+  webhook_delivery::id,
+  webhook_delivery::webhook_id,
+  webhook_delivery::event_type,
+  webhook_delivery::game_id,
+  webhook_delivery::payload,
+  webhook_delivery::status,
+  webhook_delivery::attempts,
+  webhook_delivery::last_status_code,
+  webhook_delivery::last_error,
+  webhook_delivery::next_attempt_at,
+  webhook_delivery::created_at,
+  webhook_delivery::updated_at,
+);
+
+impl DeliveryRow {
+  const COLUMNS: DeliveryRowColumns = (
+    webhook_delivery::id,
+    webhook_delivery::webhook_id,
+    webhook_delivery::event_type,
+    webhook_delivery::game_id,
+    webhook_delivery::payload,
+    webhook_delivery::status,
+    webhook_delivery::attempts,
+    webhook_delivery::last_status_code,
+    webhook_delivery::last_error,
+    webhook_delivery::next_attempt_at,
+    webhook_delivery::created_at,
+    webhook_delivery::updated_at,
+  );
+
+  fn into_delivery(self) -> WebhookDelivery {
+    WebhookDelivery {
+      id: self.id,
+      webhook_id: self.webhook_id,
+      event_type: self.event_type,
+      game_id: self.game_id,
+      payload: self.payload.to_string(),
+      status: self.status,
+      attempts: self.attempts,
+      last_status_code: self.last_status_code,
+      last_error: self.last_error,
+      next_attempt_at: self.next_attempt_at,
+      created_at: self.created_at,
+      updated_at: self.updated_at,
+    }
+  }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "webhook"]
+struct WebhookInsert<'a> {
+  api_client_id: i32,
+  url: &'a str,
+  secret: &'a str,
+  events: Value,
+  enabled: bool,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "webhook_delivery"]
+struct DeliveryInsert<'a> {
+  webhook_id: i32,
+  event_type: &'a str,
+  game_id: i32,
+  payload: Value,
+}
@@ -0,0 +1,6 @@
+pub mod db;
+mod state;
+mod types;
+
+pub use state::{WebhookDispatcher, WebhookSender};
+pub use types::*;
@@ -0,0 +1,293 @@
+use crate::db::ExecutorRef;
+use chrono::Utc;
+use flo_state::*;
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Method, Request};
+use hyper_tls::HttpsConnector;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tokio::time::sleep;
+
+use crate::error::*;
+use crate::state::Data;
+use crate::webhook::db::{DeliveryAttempt, PendingDelivery};
+use crate::webhook::{
+  sign, WebhookEvent, HEADER_DELIVERY, HEADER_EVENT, HEADER_SIGNATURE, HEADER_TIMESTAMP,
+};
+
+const DELIVER_INTERVAL: Duration = Duration::from_secs(5);
+const DELIVER_BATCH_SIZE: i64 = 100;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+type HttpClient = Client<HttpsConnector<HttpConnector>>;
+
+/// Sends game lifecycle events to the webhooks of API clients.
+///
+/// Events are written to the delivery log first, pending deliveries are picked up
+/// periodically so retries survive controller restarts.
+pub struct WebhookDispatcher {
+  db: ExecutorRef,
+  client: HttpClient,
+  in_flight: BTreeSet<i32>,
+}
+
+#[async_trait]
+impl Actor for WebhookDispatcher {
+  async fn started(&mut self, ctx: &mut Context<Self>) {
+    self.handle(ctx, DeliverDue).await;
+  }
+}
+
+#[async_trait]
+impl Service<Data> for WebhookDispatcher {
+  type Error = Error;
+
+  async fn create(registry: &mut RegistryRef<Data>) -> Result<Self, Self::Error> {
+    Ok(WebhookDispatcher {
+      db: registry.data().db.clone(),
+      client: Client::builder().build(HttpsConnector::new()),
+      in_flight: BTreeSet::new(),
+    })
+  }
+}
+
+impl WebhookDispatcher {
+  async fn deliver_due(&mut self, ctx: &mut Context<Self>) -> Result<()> {
+    let exclude_ids: Vec<i32> = self.in_flight.iter().cloned().collect();
+    let items = self
+      .db
+      .exec(move |conn| {
+        crate::webhook::db::get_due_deliveries(conn, &exclude_ids, DELIVER_BATCH_SIZE)
+      })
+      .await?;
+
+    for item in items {
+      self.in_flight.insert(item.id);
+      let client = self.client.clone();
+      let addr = ctx.addr();
+      ctx.spawn(async move {
+        let id = item.id;
+        let attempts = item.attempts;
+        let attempt = send(&client, &item).await;
+        addr
+          .notify(DeliveryDone {
+            id,
+            attempts,
+            attempt,
+          })
+          .await
+          .ok();
+      });
+    }
+
+    Ok(())
+  }
+}
+
+async fn send(client: &HttpClient, item: &PendingDelivery) -> DeliveryAttempt {
+  let body = item.payload.to_string();
+  let timestamp = Utc::now().timestamp();
+  let req = Request::builder()
+    .method(Method::POST)
+    .uri(item.url.as_str())
+    .header(CONTENT_TYPE, "application/json")
+    .header(HEADER_EVENT, item.event_type.as_str())
+    .header(HEADER_DELIVERY, item.id.to_string())
+    .header(HEADER_TIMESTAMP, timestamp.to_string())
+    .header(
+      HEADER_SIGNATURE,
+      sign(&item.secret, timestamp, body.as_bytes()),
+    )
+    .body(Body::from(body));
+  let req = match req {
+    Ok(req) => req,
+    Err(err) => return DeliveryAttempt::Error(format!("build request: {}", err)),
+  };
+
+  match tokio::time::timeout(REQUEST_TIMEOUT, client.request(req)).await {
+    Ok(Ok(res)) => {
+      let status_code = res.status().as_u16();
+      if res.status().is_success() {
+        DeliveryAttempt::Succeeded { status_code }
+      } else {
+        DeliveryAttempt::Rejected { status_code }
+      }
+    }
+    Ok(Err(err)) => DeliveryAttempt::Error(err.to_string()),
+    Err(_) => DeliveryAttempt::Error("request timeout".to_string()),
+  }
+}
+
+pub struct Emit {
+  pub game_id: i32,
+  pub event: WebhookEvent,
+}
+
+impl Message for Emit {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<Emit> for WebhookDispatcher {
+  async fn handle(&mut self, ctx: &mut Context<Self>, Emit { game_id, event }: Emit) {
+    let timestamp = Utc::now();
+    let res = self
+      .db
+      .exec(move |conn| crate::webhook::db::create_deliveries(conn, game_id, &event, timestamp))
+      .await;
+    match res {
+      Ok(0) => {}
+      Ok(_) => {
+        if let Err(err) = self.deliver_due(ctx).await {
+          tracing::error!(game_id, "deliver webhook events: {}", err);
+        }
+      }
+      Err(err) => {
+        tracing::error!(game_id, "create webhook deliveries: {}", err);
+      }
+    }
+  }
+}
+
+struct DeliverDue;
+
+impl Message for DeliverDue {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<DeliverDue> for WebhookDispatcher {
+  async fn handle(&mut self, ctx: &mut Context<Self>, _: DeliverDue) {
+    if let Err(err) = self.deliver_due(ctx).await {
+      tracing::error!("deliver webhook events: {}", err);
+    }
+    let addr = ctx.addr();
+    ctx.spawn(async move {
+      sleep(DELIVER_INTERVAL).await;
+      addr.notify(DeliverDue).await.ok();
+    });
+  }
+}
+
+struct DeliveryDone {
+  id: i32,
+  attempts: i32,
+  attempt: DeliveryAttempt,
+}
+
+impl Message for DeliveryDone {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<DeliveryDone> for WebhookDispatcher {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    DeliveryDone {
+      id,
+      attempts,
+      attempt,
+    }: DeliveryDone,
+  ) {
+    tracing::debug!(delivery_id = id, "webhook delivery attempt: {:?}", attempt);
+    let res = self
+      .db
+      .exec(move |conn| crate::webhook::db::update_delivery(conn, id, attempts, &attempt))
+      .await;
+    match res {
+      Ok(crate::webhook::WebhookDeliveryStatus::Failed) => {
+        tracing::warn!(delivery_id = id, "webhook delivery failed");
+      }
+      Ok(_) => {}
+      Err(err) => {
+        tracing::error!(delivery_id = id, "update webhook delivery: {}", err);
+      }
+    }
+    self.in_flight.remove(&id);
+  }
+}
+
+/// Cloneable handle to emit webhook events
+#[derive(Clone)]
+pub struct WebhookSender {
+  addr: Addr<WebhookDispatcher>,
+}
+
+impl WebhookSender {
+  pub async fn emit(&self, game_id: i32, event: WebhookEvent) {
+    if let Err(err) = self.addr.notify(Emit { game_id, event }).await {
+      tracing::error!(game_id, "emit webhook event: {}", err);
+    }
+  }
+}
+
+impl From<Addr<WebhookDispatcher>> for WebhookSender {
+  fn from(addr: Addr<WebhookDispatcher>) -> Self {
+    WebhookSender { addr }
+  }
+}
+
+#[tokio::test]
+async fn test_send() {
+  use hyper::service::{make_service_fn, service_fn};
+  use hyper::{Response, Server};
+  use tokio::sync::mpsc;
+
+  let (tx, mut rx) = mpsc::unbounded_channel();
+  let make_svc = make_service_fn(move |_| {
+    let tx = tx.clone();
+    async move {
+      Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
+        let tx = tx.clone();
+        async move {
+          let (parts, body) = req.into_parts();
+          let body = hyper::body::to_bytes(body).await?;
+          let status = if parts.uri.path() == "/ok" { 200 } else { 500 };
+          tx.send((parts.headers, body)).ok();
+          Ok::<_, hyper::Error>(
+            Response::builder()
+              .status(status)
+              .body(Body::empty())
+              .unwrap(),
+          )
+        }
+      }))
+    }
+  });
+  let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+  let addr = server.local_addr();
+  tokio::spawn(server);
+
+  let client: HttpClient = Client::builder().build(HttpsConnector::new());
+  let mut item = PendingDelivery {
+    id: 1,
+    event_type: "game.created".to_string(),
+    payload: WebhookEvent::GameCreated.to_payload(1, Utc::now()),
+    attempts: 0,
+    url: format!("http://{}/ok", addr),
+    secret: "secret".to_string(),
+  };
+
+  let attempt = send(&client, &item).await;
+  assert!(matches!(
+    attempt,
+    DeliveryAttempt::Succeeded { status_code: 200 }
+  ));
+  let (headers, body) = rx.recv().await.unwrap();
+  assert_eq!(headers[HEADER_EVENT], "game.created");
+  assert_eq!(headers[HEADER_DELIVERY], "1");
+  let timestamp: i64 = headers[HEADER_TIMESTAMP].to_str().unwrap().parse().unwrap();
+  assert_eq!(
+    headers[HEADER_SIGNATURE].to_str().unwrap(),
+    sign("secret", timestamp, &body)
+  );
+
+  item.url = format!("http://{}/error", addr);
+  let attempt = send(&client, &item).await;
+  assert!(matches!(
+    attempt,
+    DeliveryAttempt::Rejected { status_code: 500 }
+  ));
+}
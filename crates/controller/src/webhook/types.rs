@@ -0,0 +1,192 @@
+use bs_diesel_utils::BSDieselEnum;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::Duration;
+
+use crate::game::GameStatus;
+
+pub const HEADER_EVENT: &str = "X-Flo-Event";
+pub const HEADER_DELIVERY: &str = "X-Flo-Delivery";
+pub const HEADER_TIMESTAMP: &str = "X-Flo-Timestamp";
+pub const HEADER_SIGNATURE: &str = "X-Flo-Signature";
+
+/// Deliveries are marked as failed after this many attempts
+pub const MAX_ATTEMPTS: i32 = 8;
+const RETRY_DELAY_BASE: Duration = Duration::from_secs(10);
+const RETRY_DELAY_MAX: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEventType {
+  #[serde(rename = "game.created")]
+  GameCreated,
+  #[serde(rename = "game.node_selected")]
+  GameNodeSelected,
+  #[serde(rename = "game.started")]
+  GameStarted,
+  #[serde(rename = "game.player_left")]
+  GamePlayerLeft,
+  #[serde(rename = "game.ended")]
+  GameEnded,
+  #[serde(rename = "game.cancelled")]
+  GameCancelled,
+}
+
+impl WebhookEventType {
+  pub const ALL: &'static [WebhookEventType] = &[
+    Self::GameCreated,
+    Self::GameNodeSelected,
+    Self::GameStarted,
+    Self::GamePlayerLeft,
+    Self::GameEnded,
+    Self::GameCancelled,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match *self {
+      Self::GameCreated => "game.created",
+      Self::GameNodeSelected => "game.node_selected",
+      Self::GameStarted => "game.started",
+      Self::GamePlayerLeft => "game.player_left",
+      Self::GameEnded => "game.ended",
+      Self::GameCancelled => "game.cancelled",
+    }
+  }
+
+  pub fn parse(value: &str) -> Option<Self> {
+    Self::ALL.iter().find(|t| t.as_str() == value).cloned()
+  }
+}
+
+/// Game lifecycle events delivered to the webhooks of the API client that created the game
+#[derive(Debug, Clone)]
+pub enum WebhookEvent {
+  GameCreated,
+  GameNodeSelected { node_id: Option<i32> },
+  GameStarted { node_id: i32 },
+  GamePlayerLeft { player_id: i32 },
+  GameEnded { status: GameStatus },
+  GameCancelled,
+}
+
+impl WebhookEvent {
+  pub fn event_type(&self) -> WebhookEventType {
+    match *self {
+      Self::GameCreated => WebhookEventType::GameCreated,
+      Self::GameNodeSelected { .. } => WebhookEventType::GameNodeSelected,
+      Self::GameStarted { .. } => WebhookEventType::GameStarted,
+      Self::GamePlayerLeft { .. } => WebhookEventType::GamePlayerLeft,
+      Self::GameEnded { .. } => WebhookEventType::GameEnded,
+      Self::GameCancelled => WebhookEventType::GameCancelled,
+    }
+  }
+
+  /// JSON body of the HTTP request
+  pub fn to_payload(&self, game_id: i32, timestamp: DateTime<Utc>) -> Value {
+    let data = match *self {
+      Self::GameCreated | Self::GameCancelled => json!({}),
+      Self::GameNodeSelected { node_id } => json!({ "node_id": node_id }),
+      Self::GameStarted { node_id } => json!({ "node_id": node_id }),
+      Self::GamePlayerLeft { player_id } => json!({ "player_id": player_id }),
+      Self::GameEnded { status } => json!({ "status": status }),
+    };
+    json!({
+      "event": self.event_type(),
+      "game_id": game_id,
+      "timestamp": timestamp,
+      "data": data,
+    })
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Webhook {
+  pub id: i32,
+  pub api_client_id: i32,
+  pub url: String,
+  /// Subscribed event types, see `WebhookEventType`
+  pub events: Vec<String>,
+  pub enabled: bool,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookParams {
+  pub url: String,
+  pub secret: String,
+  pub events: Vec<String>,
+  pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, BSDieselEnum)]
+#[repr(i32)]
+pub enum WebhookDeliveryStatus {
+  Pending = 0,
+  Succeeded = 1,
+  Failed = 2,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+  pub id: i32,
+  pub webhook_id: i32,
+  pub event_type: String,
+  pub game_id: i32,
+  /// JSON encoded request body
+  pub payload: String,
+  pub status: WebhookDeliveryStatus,
+  pub attempts: i32,
+  pub last_status_code: Option<i32>,
+  pub last_error: Option<String>,
+  pub next_attempt_at: DateTime<Utc>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// Computes the value of the `X-Flo-Signature` header:
+/// hex encoded HMAC-SHA256 of `{timestamp}.{body}` keyed with the webhook secret.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+  mac.update(timestamp.to_string().as_bytes());
+  mac.update(b".");
+  mac.update(body);
+  format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delay before the next attempt after `attempts` failed attempts
+pub fn retry_delay(attempts: i32) -> Duration {
+  let exp = attempts.saturating_sub(1).clamp(0, 16) as u32;
+  std::cmp::min(RETRY_DELAY_BASE * 2u32.pow(exp), RETRY_DELAY_MAX)
+}
+
+#[test]
+fn test_sign() {
+  assert_eq!(
+    sign("secret", 1669520000, br#"{"event":"game.created"}"#),
+    "sha256=f6e8bd09c9f7a97911da87d55a67fb583124561e4b68cf6e729968b686018252"
+  );
+}
+
+#[test]
+fn test_retry_delay() {
+  assert_eq!(retry_delay(1), Duration::from_secs(10));
+  assert_eq!(retry_delay(2), Duration::from_secs(20));
+  assert_eq!(retry_delay(4), Duration::from_secs(80));
+  assert_eq!(retry_delay(MAX_ATTEMPTS + 100), RETRY_DELAY_MAX);
+}
+
+#[test]
+fn test_event_type() {
+  for t in WebhookEventType::ALL {
+    assert_eq!(WebhookEventType::parse(t.as_str()), Some(*t));
+    assert_eq!(
+      serde_json::to_value(t).unwrap(),
+      Value::String(t.as_str().to_string())
+    );
+  }
+  assert_eq!(WebhookEventType::parse("game.unknown"), None);
+}
@@ -37,6 +37,20 @@ impl DataStream {
   }
 
   pub async fn into_iter(self, iter_type: ShardIteratorType) -> Result<DataStreamIterator> {
+    self.into_iter_with(|_| iter_type.clone()).await
+  }
+
+  /// Creates an iterator with a per-shard iterator type,
+  /// e.g. to resume each shard from a saved sequence number.
+  pub async fn into_iter_with<F>(self, f: F) -> Result<DataStreamIterator>
+  where
+    F: Fn(&str) -> ShardIteratorType,
+  {
+    let shard_ids = self.list_shard_ids().await?;
+    Ok(self.iter_shards(shard_ids, f))
+  }
+
+  pub async fn list_shard_ids(&self) -> Result<Vec<String>> {
     let shards = self
       .client
       .list_shards(ListShardsInput {
@@ -53,11 +67,19 @@ impl DataStream {
 
     tracing::debug!("shards: {:?}", shard_ids);
 
+    Ok(shard_ids)
+  }
+
+  /// Creates an iterator which only reads the specified shards.
+  pub fn iter_shards<F>(&self, shard_ids: Vec<String>, f: F) -> DataStreamIterator
+  where
+    F: Fn(&str) -> ShardIteratorType,
+  {
     let shards = shard_ids.into_iter()
       .enumerate()
       .map(|(idx, shard_id)| {
         (idx, ShardIterator::new(ShardIteratorConfig {
-          iter_type: iter_type.clone(),
+          iter_type: f(&shard_id),
           source: self.source,
           client: self.client.clone(),
           stream_name: self.stream_name.clone(),
@@ -65,9 +87,9 @@ impl DataStream {
         }))
       }).collect();
 
-    Ok(DataStreamIterator {
+    DataStreamIterator {
       shards
-    })
+    }
   }
 }
 
@@ -244,6 +244,7 @@ impl Scanner {
     self
       .tx
       .send(Item::Chunk(Chunk {
+        shard_id: self.shard_id.clone(),
         max_sequence_number,
         millis_behind_latest,
         game_records: map,
@@ -313,6 +314,7 @@ pub enum Item {
 
 #[derive(Debug)]
 pub struct Chunk {
+  pub shard_id: String,
   pub max_sequence_number: String,
   pub millis_behind_latest: Option<i64>,
   pub game_records: BTreeMap<i32, GameChunk>,
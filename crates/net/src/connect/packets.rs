@@ -35,3 +35,21 @@ packet_type!(RemoveNode, PacketRemoveNode);
 packet_type!(PlayerMuteListUpdate, PacketPlayerMuteListUpdate);
 packet_type!(PlayerMuteAddRequest, PacketPlayerMuteAddRequest);
 packet_type!(PlayerMuteRemoveRequest, PacketPlayerMuteRemoveRequest);
+packet_type!(
+  MatchmakingQueueEnterRequest,
+  PacketMatchmakingQueueEnterRequest
+);
+packet_type!(
+  MatchmakingQueueLeaveRequest,
+  PacketMatchmakingQueueLeaveRequest
+);
+packet_type!(MatchmakingQueueUpdate, PacketMatchmakingQueueUpdate);
+packet_type!(MatchmakingMatchFound, PacketMatchmakingMatchFound);
+packet_type!(
+  MatchmakingMatchAcceptRequest,
+  PacketMatchmakingMatchAcceptRequest
+);
+packet_type!(MatchmakingMatchCancelled, PacketMatchmakingMatchCancelled);
+packet_type!(GameChatRequest, PacketGameChatRequest);
+packet_type!(GameChat, PacketGameChat);
+packet_type!(GameChatReject, PacketGameChatReject);
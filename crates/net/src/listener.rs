@@ -18,7 +18,11 @@ pub struct FloListener {
 
 impl FloListener {
   pub async fn bind_v4(port: u16) -> Result<Self, Error> {
-    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port)).await?;
+    Self::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port)).await
+  }
+
+  pub async fn bind(addr: SocketAddrV4) -> Result<Self, Error> {
+    let listener = TcpListener::bind(addr).await?;
     let local_addr = listener.local_addr()?;
     Ok(FloListener {
       listener,
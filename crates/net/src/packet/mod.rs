@@ -236,6 +236,24 @@ pub enum PacketTypeId {
   PlayerMuteAddRequest,
   #[bin(value = 0x1F)]
   PlayerMuteRemoveRequest,
+  #[bin(value = 0x20)]
+  MatchmakingQueueEnterRequest,
+  #[bin(value = 0x21)]
+  MatchmakingQueueLeaveRequest,
+  #[bin(value = 0x22)]
+  MatchmakingQueueUpdate,
+  #[bin(value = 0x23)]
+  MatchmakingMatchFound,
+  #[bin(value = 0x24)]
+  MatchmakingMatchAcceptRequest,
+  #[bin(value = 0x25)]
+  MatchmakingMatchCancelled,
+  #[bin(value = 0x26)]
+  GameChatRequest,
+  #[bin(value = 0x27)]
+  GameChat,
+  #[bin(value = 0x28)]
+  GameChatReject,
 
   // Lobby <-> Node
   #[bin(value = 0x30)]
@@ -258,6 +276,12 @@ pub enum PacketTypeId {
   ControllerUpdateSlotStatusReject,
   #[bin(value = 0x39)]
   ControllerQueryGameStatus,
+  #[bin(value = 0x3A)]
+  ControllerGameControl,
+  #[bin(value = 0x3B)]
+  ControllerGameControlAccept,
+  #[bin(value = 0x3C)]
+  ControllerGameControlReject,
 
   // Client <-> Node
   #[bin(value = 0x40)]
@@ -282,6 +306,10 @@ pub enum PacketTypeId {
   NodeGameStatusUpdate,
   #[bin(value = 0x51)]
   NodeGameStatusUpdateBulk,
+  #[bin(value = 0x52)]
+  NodeGameResult,
+  #[bin(value = 0x53)]
+  NodeGameChatLog,
 
   // Client <-> Observer
   #[bin(value = 0x60)]
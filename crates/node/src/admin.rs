@@ -0,0 +1,269 @@
+//! Admin HTTP API for inspecting and controlling live games, served on the node HTTP port.
+//!
+//! Requests are authenticated by the `x-flo-secret` header, which must match `admin_secret`
+//! in the node config. The API is disabled if `admin_secret` is not set.
+//!
+//! - `GET /admin/games`
+//! - `GET /admin/games/{game_id}`
+//! - `POST /admin/games/{game_id}/terminate`
+//! - `POST /admin/games/{game_id}/message`, `{"message": "..."}`
+//! - `POST /admin/games/{game_id}/players/{player_id}/kick`
+//! - `POST /admin/games/{game_id}/players/{player_id}/delay`, `{"delay_ms": 50}`,
+//!   `null` or `0` removes the delay
+
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+
+use crate::error::*;
+use crate::game::GameSessionHandle;
+use crate::state::GlobalStateRef;
+
+const HEADER_SECRET: &str = "x-flo-secret";
+
+pub async fn handle(state: GlobalStateRef, req: Request<Body>) -> Response<Body> {
+  let admin_secret = crate::config::get().admin_secret.clone();
+  if admin_secret.is_empty() {
+    return ApiError::new(StatusCode::NOT_FOUND, "admin api disabled").into_response();
+  }
+
+  let authorized = req
+    .headers()
+    .get(HEADER_SECRET)
+    .map(|v| bool::from(v.as_bytes().ct_eq(admin_secret.as_bytes())))
+    .unwrap_or(false);
+  if !authorized {
+    return ApiError::new(StatusCode::UNAUTHORIZED, "invalid secret").into_response();
+  }
+
+  let route = match Route::parse(req.method(), req.uri().path()) {
+    Some(route) => route,
+    None => return ApiError::new(StatusCode::NOT_FOUND, "not found").into_response(),
+  };
+
+  match handle_route(&state, route, req.into_body()).await {
+    Ok(res) => res,
+    Err(err) => err.into_response(),
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum Route {
+  ListGames,
+  GetGame(i32),
+  TerminateGame(i32),
+  BroadcastMessage(i32),
+  KickPlayer { game_id: i32, player_id: i32 },
+  SetPlayerDelay { game_id: i32, player_id: i32 },
+}
+
+impl Route {
+  fn parse(method: &Method, path: &str) -> Option<Self> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let route = match (method, &segments[..]) {
+      (&Method::GET, ["admin", "games"]) => Route::ListGames,
+      (&Method::GET, ["admin", "games", id]) => Route::GetGame(id.parse().ok()?),
+      (&Method::POST, ["admin", "games", id, "terminate"]) => {
+        Route::TerminateGame(id.parse().ok()?)
+      }
+      (&Method::POST, ["admin", "games", id, "message"]) => {
+        Route::BroadcastMessage(id.parse().ok()?)
+      }
+      (&Method::POST, ["admin", "games", game_id, "players", player_id, "kick"]) => {
+        Route::KickPlayer {
+          game_id: game_id.parse().ok()?,
+          player_id: player_id.parse().ok()?,
+        }
+      }
+      (&Method::POST, ["admin", "games", game_id, "players", player_id, "delay"]) => {
+        Route::SetPlayerDelay {
+          game_id: game_id.parse().ok()?,
+          player_id: player_id.parse().ok()?,
+        }
+      }
+      _ => return None,
+    };
+    Some(route)
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct BroadcastMessageBody {
+  message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetPlayerDelayBody {
+  delay_ms: Option<u64>,
+}
+
+async fn handle_route(
+  state: &GlobalStateRef,
+  route: Route,
+  body: Body,
+) -> Result<Response<Body>, ApiError> {
+  match route {
+    Route::ListGames => {
+      let mut games = vec![];
+      for game in state.list_games() {
+        // the game may have ended after being listed
+        if let Ok(info) = game.get_info().await {
+          games.push(info);
+        }
+      }
+      games.sort_by_key(|game| game.game_id);
+      Ok(json_response(&games))
+    }
+    Route::GetGame(game_id) => {
+      let info = get_game(state, game_id)?.get_info().await?;
+      Ok(json_response(&info))
+    }
+    Route::TerminateGame(game_id) => {
+      get_game(state, game_id)?.terminate().await?;
+      Ok(empty_response())
+    }
+    Route::BroadcastMessage(game_id) => {
+      let body: BroadcastMessageBody = parse_body(body).await?;
+      if body.message.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "message is empty"));
+      }
+      get_game(state, game_id)?
+        .broadcast_message(body.message)
+        .await?;
+      Ok(empty_response())
+    }
+    Route::KickPlayer { game_id, player_id } => {
+      get_game(state, game_id)?.kick_player(player_id).await?;
+      Ok(empty_response())
+    }
+    Route::SetPlayerDelay { game_id, player_id } => {
+      let body: SetPlayerDelayBody = parse_body(body).await?;
+      let delay = parse_delay(body.delay_ms)?;
+      get_game(state, game_id)?
+        .set_player_delay(player_id, delay)
+        .await?;
+      Ok(empty_response())
+    }
+  }
+}
+
+fn get_game(state: &GlobalStateRef, game_id: i32) -> Result<GameSessionHandle, ApiError> {
+  state
+    .get_game(game_id)
+    .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "game not found"))
+}
+
+/// Accepts the same range as the `!delay` command
+fn parse_delay(delay_ms: Option<u64>) -> Result<Option<Duration>, ApiError> {
+  let config = crate::config::get();
+  let (min, max) = (config.game.min_delay_ms, config.game.max_delay_ms);
+  match delay_ms {
+    None | Some(0) => Ok(None),
+    Some(ms) if ms < min || ms > max => Err(ApiError::new(
+      StatusCode::BAD_REQUEST,
+      format!("invalid delay, range {} - {}", min, max),
+    )),
+    Some(ms) => Ok(Some(Duration::from_millis(ms))),
+  }
+}
+
+async fn parse_body<T: serde::de::DeserializeOwned>(body: Body) -> Result<T, ApiError> {
+  let bytes = hyper::body::to_bytes(body)
+    .await
+    .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, err.to_string()))?;
+  serde_json::from_slice(&bytes)
+    .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, format!("invalid body: {}", err)))
+}
+
+fn json_response<T: Serialize>(value: &T) -> Response<Body> {
+  match serde_json::to_vec(value) {
+    Ok(bytes) => Response::builder()
+      .status(StatusCode::OK)
+      .header(CONTENT_TYPE, "application/json")
+      .body(Body::from(bytes))
+      .unwrap(),
+    Err(err) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+  }
+}
+
+fn empty_response() -> Response<Body> {
+  Response::builder()
+    .status(StatusCode::NO_CONTENT)
+    .body(Body::empty())
+    .unwrap()
+}
+
+#[derive(Debug)]
+struct ApiError {
+  status: StatusCode,
+  message: String,
+}
+
+impl ApiError {
+  fn new<T: Into<String>>(status: StatusCode, message: T) -> Self {
+    ApiError {
+      status,
+      message: message.into(),
+    }
+  }
+
+  fn into_response(self) -> Response<Body> {
+    #[derive(Serialize)]
+    struct ErrorBody {
+      error: String,
+    }
+
+    let body = serde_json::to_vec(&ErrorBody {
+      error: self.message,
+    })
+    .unwrap_or_default();
+    Response::builder()
+      .status(self.status)
+      .header(CONTENT_TYPE, "application/json")
+      .body(Body::from(body))
+      .unwrap()
+  }
+}
+
+impl From<Error> for ApiError {
+  fn from(err: Error) -> Self {
+    let status = match err {
+      Error::PlayerNotFoundInGame => StatusCode::NOT_FOUND,
+      Error::PlayerAlreadyLeft | Error::PingEqualizerEnabled => StatusCode::CONFLICT,
+      // the game ended while processing the request
+      Error::Cancelled => StatusCode::GONE,
+      _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    ApiError::new(status, err.to_string())
+  }
+}
+
+#[test]
+fn test_parse_route() {
+  assert_eq!(
+    Route::parse(&Method::GET, "/admin/games"),
+    Some(Route::ListGames)
+  );
+  assert_eq!(
+    Route::parse(&Method::GET, "/admin/games/42"),
+    Some(Route::GetGame(42))
+  );
+  assert_eq!(
+    Route::parse(&Method::POST, "/admin/games/42/players/7/delay"),
+    Some(Route::SetPlayerDelay {
+      game_id: 42,
+      player_id: 7
+    })
+  );
+  assert_eq!(
+    Route::parse(&Method::GET, "/admin/games/42/terminate"),
+    None
+  );
+  assert_eq!(Route::parse(&Method::GET, "/admin/games/abc"), None);
+  assert_eq!(
+    Route::parse(&Method::POST, "/admin/games/1/players/x/kick"),
+    None
+  );
+}
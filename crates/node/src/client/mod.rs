@@ -1,6 +1,6 @@
 use futures::stream::StreamExt;
 
-use flo_constants::NODE_CLIENT_PORT;
+use flo_constants::NODE_CLIENT_PORT_OFFSET;
 use flo_net::listener::FloListener;
 use flo_net::proto::flo_node::*;
 use flo_net::stream::FloStream;
@@ -10,7 +10,7 @@ use crate::state::{GlobalState, GlobalStateRef, PlayerToken};
 use flo_w3gs::constants::LeaveReason;
 
 pub async fn serve_client(state: GlobalStateRef) -> Result<()> {
-  let mut listener = FloListener::bind_v4(NODE_CLIENT_PORT).await?;
+  let mut listener = FloListener::bind(crate::config::socket_addr(NODE_CLIENT_PORT_OFFSET)).await?;
 
   while let Some(incoming) = listener.incoming().next().await {
     if let Ok(mut stream) = incoming {
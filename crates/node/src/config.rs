@@ -0,0 +1,61 @@
+//! Node settings loaded from `flo-node.toml` and environment variables, see [`NodeConfig`].
+
+use arc_swap::ArcSwap;
+use flo_config::NodeConfig;
+use flo_observer::record::ObserverRecordSource;
+use once_cell::sync::OnceCell;
+use std::net::SocketAddrV4;
+use std::sync::Arc;
+
+use crate::error::*;
+
+static CONFIG: OnceCell<ArcSwap<NodeConfig>> = OnceCell::new();
+
+/// Loads and validates the config, called before serving so errors are reported on startup.
+pub fn init() -> Result<()> {
+  let config = Arc::new(load()?);
+  CONFIG
+    .get_or_init(|| ArcSwap::new(config.clone()))
+    .store(config);
+  Ok(())
+}
+
+pub fn get() -> Arc<NodeConfig> {
+  CONFIG
+    .get_or_init(|| ArcSwap::from_pointee(NodeConfig::load().expect("load node config")))
+    .load_full()
+}
+
+/// Reloads the config file, see `NodeConfig::reload` for the applied settings.
+pub fn reload() -> Result<()> {
+  let new = load()?;
+  let (config, ignored) = get().reload(&new);
+  if !ignored.is_empty() {
+    tracing::warn!("restart required to apply: {}", ignored.join(", "));
+  }
+  CONFIG
+    .get_or_init(|| ArcSwap::from_pointee(config.clone()))
+    .store(Arc::new(config));
+  Ok(())
+}
+
+pub fn socket_addr(port_offset: u16) -> SocketAddrV4 {
+  let config = get();
+  SocketAddrV4::new(config.bind_address, config.port + port_offset)
+}
+
+fn load() -> Result<NodeConfig> {
+  let config = NodeConfig::load()?;
+  config.validate()?;
+  config
+    .observer
+    .source
+    .parse::<ObserverRecordSource>()
+    .map_err(|_| {
+      flo_config::error::Error::Invalid(format!(
+        "unknown observer source: {}",
+        config.observer.source
+      ))
+    })?;
+  Ok(config)
+}
@@ -5,7 +5,7 @@ use std::sync::Arc;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tracing_futures::Instrument;
 
-use flo_constants::NODE_CONTROLLER_PORT;
+use flo_constants::NODE_CONTROLLER_PORT_OFFSET;
 use flo_net::listener::FloListener;
 use flo_net::packet::Frame;
 use flo_net::proto::flo_node::*;
@@ -49,7 +49,8 @@ impl ControllerServer {
   }
 
   pub async fn serve(&mut self) -> Result<()> {
-    let mut listener = FloListener::bind_v4(NODE_CONTROLLER_PORT).await?;
+    let mut listener =
+      FloListener::bind(crate::config::socket_addr(NODE_CONTROLLER_PORT_OFFSET)).await?;
 
     while let Some(incoming) = listener.incoming().next().await {
       if let Ok(stream) = incoming {
@@ -68,7 +69,7 @@ impl ControllerServer {
 
     let connect: PacketControllerConnect = stream.recv_timeout(RECV_TIMEOUT).await?;
 
-    if connect.secret != crate::env::Env::get().secret_key {
+    if connect.secret != crate::config::get().secret_key {
       stream
         .send(PacketControllerConnectReject {
           reason: ControllerConnectRejectReason::InvalidSecretKey.into(),
@@ -185,6 +186,9 @@ async fn handle_frame(state: &Arc<State>, mut frame: Frame) -> Result<()> {
         let frame = state.g_state.handle_controller_update_slot_client_status(pkt).await?;
         flo_log::result_ok!("update slot status", tx.send(frame).await);
       }
+      pkt: PacketControllerGameControl => {
+        state.g_state.handle_controller_game_control(ControllerServerHandle::new(state.clone()), pkt);
+      }
     }
   }
   Ok(())
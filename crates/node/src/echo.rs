@@ -1,13 +1,12 @@
 use crate::error::Result;
-use std::net::{Ipv4Addr, SocketAddrV4};
 use tokio::net::UdpSocket;
 const ALLOWED_ECHO_DATAGRAM_LEN: &[usize] = &[4, 8];
 const MAX_RECV_BUF: usize = 8;
 
-use flo_constants::NODE_ECHO_PORT;
+use flo_constants::NODE_ECHO_PORT_OFFSET;
 
 pub async fn serve_echo() -> Result<()> {
-  let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, NODE_ECHO_PORT)).await?;
+  let socket = UdpSocket::bind(crate::config::socket_addr(NODE_ECHO_PORT_OFFSET)).await?;
 
   let mut recv_buf = [0_u8; MAX_RECV_BUF];
 
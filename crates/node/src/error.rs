@@ -21,6 +21,8 @@ pub enum Error {
   PlayerChannelBroken,
   #[error("player already left")]
   PlayerAlreadyLeft,
+  #[error("ping equalizer is enabled")]
+  PingEqualizerEnabled,
   #[error("invalid player slot client status: {0:?}")]
   InvalidPlayerSlotClientStatus(SlotClientStatus),
   #[error("invalid slot id")]
@@ -45,6 +47,8 @@ pub enum Error {
   Proto(#[from] s2_grpc_utils::result::Error),
   #[error("http: {0}")]
   Http(#[from] hyper::Error),
+  #[error("config: {0}")]
+  Config(#[from] flo_config::error::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
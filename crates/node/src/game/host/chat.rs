@@ -0,0 +1,213 @@
+use flo_net::proto::flo_node::{
+  GameChatFilterAction, GameChatMessage, GameChatViolationCount, PacketNodeGameChatLog,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Messages after this are not logged, to bound the memory used by spamming players,
+/// violations are still counted
+const MAX_LOGGED_MESSAGES: usize = 5000;
+/// Keeps the encoded packets below the frame size limit,
+/// W3GS chat messages are at most 255 bytes long
+const MESSAGES_PER_PACKET: usize = 100;
+
+/// Collects the chat messages of a game and the filter violations of each player,
+/// reported to the controller after the game ended.
+#[derive(Debug)]
+pub struct ChatLogRecorder {
+  game_id: i32,
+  messages: Vec<GameChatMessage>,
+  violations: BTreeMap<i32, u32>,
+}
+
+impl ChatLogRecorder {
+  pub fn new(game_id: i32) -> Self {
+    Self {
+      game_id,
+      messages: vec![],
+      violations: BTreeMap::new(),
+    }
+  }
+
+  pub fn record(&mut self, message: GameChatMessage) {
+    if self.messages.len() < MAX_LOGGED_MESSAGES {
+      self.messages.push(message);
+    }
+  }
+
+  /// Returns the number of violations of the player in this game
+  pub fn record_violation(&mut self, player_id: i32) -> u32 {
+    let count = self.violations.entry(player_id).or_default();
+    *count += 1;
+    *count
+  }
+
+  /// The violation counts are sent in the first packet
+  pub fn to_packets(&self) -> Vec<PacketNodeGameChatLog> {
+    let mut packets: Vec<_> = self
+      .messages
+      .chunks(MESSAGES_PER_PACKET)
+      .map(|messages| PacketNodeGameChatLog {
+        game_id: self.game_id,
+        messages: messages.to_vec(),
+        violations: vec![],
+      })
+      .collect();
+    if self.violations.is_empty() {
+      return packets;
+    }
+    if packets.is_empty() {
+      packets.push(PacketNodeGameChatLog {
+        game_id: self.game_id,
+        ..Default::default()
+      });
+    }
+    packets[0].violations = self
+      .violations
+      .iter()
+      .map(|(&player_id, &count)| GameChatViolationCount { player_id, count })
+      .collect();
+    packets
+  }
+}
+
+/// Sliding window limit of messages sent by each player
+#[derive(Debug, Default)]
+pub struct ChatRateLimiter {
+  sent: BTreeMap<i32, VecDeque<Instant>>,
+}
+
+impl ChatRateLimiter {
+  /// Returns `false` if the player has sent `limit` messages within `window`,
+  /// a `limit` of `0` disables the check
+  pub fn check(&mut self, player_id: i32, limit: u32, window: Duration, now: Instant) -> bool {
+    if limit == 0 {
+      return true;
+    }
+    let sent = self.sent.entry(player_id).or_default();
+    while let Some(t) = sent.front() {
+      if now.saturating_duration_since(*t) >= window {
+        sent.pop_front();
+      } else {
+        break;
+      }
+    }
+    if sent.len() >= limit as usize {
+      return false;
+    }
+    sent.push_back(now);
+    true
+  }
+}
+
+/// Returns the text with the filtered words masked by `*`,
+/// or `None` if no word matched. Words are matched case-insensitively.
+pub fn filter_words(words: &[String], text: &str) -> Option<String> {
+  let mut filtered = false;
+  let mut out = String::with_capacity(text.len());
+  let mut pos = 0;
+  while pos < text.len() {
+    let rest = &text[pos..];
+    let matched = words
+      .iter()
+      .filter_map(|word| match_len(rest, word))
+      .max()
+      .unwrap_or(0);
+    if matched > 0 {
+      out.extend(std::iter::repeat('*').take(rest[..matched].chars().count()));
+      pos += matched;
+      filtered = true;
+    } else {
+      let c = rest.chars().next().unwrap_or_default();
+      out.push(c);
+      pos += c.len_utf8();
+    }
+  }
+  if filtered {
+    Some(out)
+  } else {
+    None
+  }
+}
+
+/// Returns the byte length of `word` matched at the start of `text`
+fn match_len(text: &str, word: &str) -> Option<usize> {
+  if word.is_empty() {
+    return None;
+  }
+  let mut chars = text.char_indices();
+  let mut len = 0;
+  for wc in word.chars() {
+    let (idx, tc) = chars.next()?;
+    if !tc.to_lowercase().eq(wc.to_lowercase()) {
+      return None;
+    }
+    len = idx + tc.len_utf8();
+  }
+  Some(len)
+}
+
+impl GameChatFilterAction {
+  /// Filtered and rate limited messages count toward automatic mutes and chat bans
+  pub fn is_violation(self) -> bool {
+    match self {
+      GameChatFilterAction::Replaced
+      | GameChatFilterAction::Dropped
+      | GameChatFilterAction::RateLimited => true,
+      GameChatFilterAction::None | GameChatFilterAction::Muted => false,
+    }
+  }
+}
+
+#[test]
+fn test_filter_words() {
+  let words: Vec<String> = vec!["foo".into(), "FooBar".into(), "ü".into()];
+  assert_eq!(filter_words(&words, "hello"), None);
+  assert_eq!(filter_words(&words, "FOO bar"), Some("*** bar".to_string()));
+  assert_eq!(
+    filter_words(&words, "xfoobarx"),
+    Some("x******x".to_string())
+  );
+  assert_eq!(filter_words(&words, "Über"), Some("*ber".to_string()));
+  assert_eq!(filter_words(&["".to_string()], "text"), None);
+}
+
+#[test]
+fn test_chat_rate_limiter() {
+  let mut r = ChatRateLimiter::default();
+  let window = Duration::from_secs(10);
+  let t = Instant::now();
+  assert!(r.check(1, 2, window, t));
+  assert!(r.check(1, 2, window, t + Duration::from_secs(1)));
+  assert!(!r.check(1, 2, window, t + Duration::from_secs(2)));
+  assert!(r.check(2, 2, window, t + Duration::from_secs(2)));
+  assert!(r.check(1, 2, window, t + Duration::from_secs(10)));
+  assert!(r.check(1, 0, window, t + Duration::from_secs(10)));
+}
+
+#[test]
+fn test_chat_log_recorder() {
+  let mut r = ChatLogRecorder::new(1);
+  assert!(r.to_packets().is_empty());
+  assert_eq!(r.record_violation(2), 1);
+  assert_eq!(r.record_violation(2), 2);
+  let packets = r.to_packets();
+  assert_eq!(packets.len(), 1);
+  assert!(packets[0].messages.is_empty());
+  assert_eq!(packets[0].violations.len(), 1);
+  for _ in 0..(MESSAGES_PER_PACKET + 1) {
+    r.record(GameChatMessage {
+      player_id: 2,
+      message: "hi".to_string(),
+      ..Default::default()
+    });
+  }
+  let packets = r.to_packets();
+  assert_eq!(packets.len(), 2);
+  assert_eq!(packets[0].game_id, 1);
+  assert_eq!(packets[0].messages.len(), MESSAGES_PER_PACKET);
+  assert_eq!(packets[0].violations[0].player_id, 2);
+  assert_eq!(packets[0].violations[0].count, 2);
+  assert_eq!(packets[1].messages.len(), 1);
+  assert!(packets[1].violations.is_empty());
+}
@@ -1,7 +1,9 @@
+use super::chat::{filter_words, ChatLogRecorder, ChatRateLimiter};
 use super::clock::ActionTickStream;
 use super::delay::{DelayedFrame, DelayedFrameStream};
 use super::delay_equalizer::DelayEqualizer;
-use super::player::{PlayerDispatchInfo, PlayerSendError};
+use super::player::{PlayerDispatchInfo, PlayerRTTSnapshot, PlayerSendError};
+use super::result::GameResultRecorder;
 use super::sync::SyncMap;
 use super::{broadcast, GameHostOptions};
 use crate::error::*;
@@ -13,14 +15,19 @@ use crate::game::{
   SlotClientStatusUpdateSource,
 };
 use crate::observer::ObserverPublisherHandle;
+use flo_config::ChatFilterAction;
 use flo_net::packet::{Frame, PacketTypeId};
 use flo_net::ping::{PingMsg, PingStream};
+use flo_net::proto::flo_node::{
+  GameChatFilterAction, GameChatMessage, GameChatScope, PacketNodeGameChatLog, PacketNodeGameResult,
+};
 use flo_net::w3gs::{W3GSFrameExt, W3GSMetadata, W3GSPacket, W3GSPacketTypeId};
 use flo_observer::record::{RTTStats, RTTStatsItem};
+use flo_util::binary::IntoCStringLossy;
 use flo_util::chat::{parse_chat_command, ChatCommand};
 use flo_w3gs::action::{IncomingAction, IncomingAction2, OutgoingKeepAlive};
 use flo_w3gs::protocol::action::{OutgoingAction, PlayerAction, TimeSlot};
-use flo_w3gs::protocol::chat::ChatToHost;
+use flo_w3gs::protocol::chat::{ChatFromHost, ChatMessage, ChatToHost, MessageScope};
 use flo_w3gs::protocol::constants::LeaveReason;
 use flo_w3gs::protocol::lag::{LagPlayer, StartLag, StopLag};
 use flo_w3gs::protocol::leave::LeaveReq;
@@ -29,9 +36,10 @@ use flo_w3gs::protocol::packet::*;
 use futures::stream::StreamExt;
 use parking_lot::Mutex;
 use s2_grpc_utils::S2ProtoEnum;
+use serde::Serialize;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tokio::sync::{oneshot, watch, Notify};
@@ -53,6 +61,54 @@ pub enum Cmd {
     player_id: i32,
     leave_reason: Option<LeaveReason>,
   },
+  GetInfo {
+    tx: oneshot::Sender<DispatcherInfo>,
+  },
+  KickPlayer {
+    player_id: i32,
+    tx: oneshot::Sender<Result<()>>,
+  },
+  SetPlayerDelay {
+    player_id: i32,
+    delay: Option<Duration>,
+    tx: oneshot::Sender<Result<()>>,
+  },
+  BroadcastMessage {
+    message: String,
+  },
+  Terminate {
+    tx: oneshot::Sender<Result<()>>,
+  },
+  EndGame {
+    winner_player_ids: Vec<i32>,
+    tx: oneshot::Sender<Result<()>>,
+  },
+  SetPingEqualizer {
+    enabled: bool,
+  },
+}
+
+/// Live state of a game, for the admin API
+#[derive(Debug, Serialize)]
+pub struct DispatcherInfo {
+  pub started: bool,
+  pub paused: bool,
+  pub game_time_ms: u32,
+  pub ping_equalizer: bool,
+  /// Players who haven't left the game
+  pub players: Vec<PlayerDispatchSnapshot>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerDispatchSnapshot {
+  pub player_id: i32,
+  pub connected: bool,
+  pub rtt: Option<PlayerRTTSnapshot>,
+  pub delay_ms: Option<u64>,
+  pub lagging: bool,
+  pub lag_duration_ms: u32,
+  pub last_ack_received: Option<u32>,
+  pub pending_ack_len: usize,
 }
 
 enum PeerMsg {
@@ -91,6 +147,7 @@ pub struct Dispatcher {
   ct: CancellationToken,
   cmd_tx: Sender<Cmd>,
   start_notify: Arc<Notify>,
+  shared: Arc<Mutex<Shared>>,
 }
 
 impl Drop for Dispatcher {
@@ -124,6 +181,7 @@ impl Dispatcher {
       ct.clone(),
     );
 
+    let shared = state.shared.clone();
     let mut start_messages = vec![];
 
     if enabled_ping_equalizer {
@@ -166,6 +224,7 @@ impl Dispatcher {
       game_id,
       cmd_tx,
       start_notify,
+      shared,
     }
   }
 
@@ -174,6 +233,14 @@ impl Dispatcher {
     self.start_notify.notify_one();
   }
 
+  pub fn game_result(&self) -> PacketNodeGameResult {
+    self.shared.lock().game_result()
+  }
+
+  pub fn chat_log(&self) -> Vec<PacketNodeGameChatLog> {
+    self.shared.lock().chat_log.to_packets()
+  }
+
   pub async fn register_player_stream(&self, stream: PlayerStream) -> Result<PlayerStreamHandle> {
     let (tx, rx) = oneshot::channel();
     self
@@ -200,6 +267,85 @@ impl Dispatcher {
     Ok(())
   }
 
+  pub async fn get_info(&self) -> Result<DispatcherInfo> {
+    let (tx, rx) = oneshot::channel();
+    self
+      .cmd_tx
+      .send(Cmd::GetInfo { tx })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    rx.await.map_err(|_| Error::Cancelled)
+  }
+
+  /// Removes the player as if the player left the game
+  pub async fn kick_player(&self, player_id: i32) -> Result<()> {
+    let (tx, rx) = oneshot::channel();
+    self
+      .cmd_tx
+      .send(Cmd::KickPlayer { player_id, tx })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    rx.await.map_err(|_| Error::Cancelled)?
+  }
+
+  pub async fn set_player_delay(&self, player_id: i32, delay: Option<Duration>) -> Result<()> {
+    let (tx, rx) = oneshot::channel();
+    self
+      .cmd_tx
+      .send(Cmd::SetPlayerDelay {
+        player_id,
+        delay,
+        tx,
+      })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    rx.await.map_err(|_| Error::Cancelled)?
+  }
+
+  pub async fn broadcast_message(&self, message: String) -> Result<()> {
+    self
+      .cmd_tx
+      .send(Cmd::BroadcastMessage { message })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    Ok(())
+  }
+
+  /// Removes all players, the game ends after every player left
+  pub async fn terminate(&self) -> Result<()> {
+    let (tx, rx) = oneshot::channel();
+    self
+      .cmd_tx
+      .send(Cmd::Terminate { tx })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    rx.await.map_err(|_| Error::Cancelled)?
+  }
+
+  /// Removes all players, recording the winners as won and the others as lost.
+  /// If `winner_player_ids` is empty, the game is recorded as a draw.
+  pub async fn end_game(&self, winner_player_ids: Vec<i32>) -> Result<()> {
+    let (tx, rx) = oneshot::channel();
+    self
+      .cmd_tx
+      .send(Cmd::EndGame {
+        winner_player_ids,
+        tx,
+      })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    rx.await.map_err(|_| Error::Cancelled)?
+  }
+
+  pub async fn set_ping_equalizer(&self, enabled: bool) -> Result<()> {
+    self
+      .cmd_tx
+      .send(Cmd::SetPingEqualizer { enabled })
+      .await
+      .map_err(|_| Error::Cancelled)?;
+    Ok(())
+  }
+
   async fn serve(
     mut state: State,
     mut rx: Receiver<Cmd>,
@@ -267,7 +413,7 @@ impl Dispatcher {
         }
       }
 
-      let mut tick_stream = ActionTickStream::new(*crate::constants::GAME_DEFAULT_STEP_MS);
+      let mut tick_stream = ActionTickStream::new(crate::config::get().game.step_ms);
       let pause_timeout = sleep(Duration::from_secs(0));
       tokio::pin!(pause_timeout);
 
@@ -373,6 +519,13 @@ impl Dispatcher {
   }
 }
 
+#[derive(Debug)]
+enum ChatVerdict {
+  Relay,
+  Replace(String),
+  Drop,
+}
+
 #[derive(Debug)]
 enum ActionMsg {
   PlayerAction(PlayerAction),
@@ -390,6 +543,7 @@ struct State {
   game_player_id_lookup: BTreeMap<u8, i32>,
   _player_name_lookup: BTreeMap<i32, String>,
   chat_banned_player_ids: Vec<i32>,
+  chat_rate_limiter: ChatRateLimiter,
   left_players: BTreeSet<i32>,
 }
 
@@ -438,6 +592,7 @@ impl State {
           }
         })
         .collect(),
+      chat_rate_limiter: ChatRateLimiter::default(),
       left_players: BTreeSet::new(),
     }
   }
@@ -472,11 +627,130 @@ impl State {
           tracing::error!(game_id = self.game_id, player_id, "send shutdown: {}", err);
         }
       }
+      Cmd::GetInfo { tx } => {
+        let paused = *self.status_rx.borrow() == DispatchStatus::Paused;
+        tx.send(self.shared.lock().get_info(paused)).ok();
+      }
+      Cmd::KickPlayer { player_id, tx } => {
+        tx.send(self.kick_player(player_id, action_tx, out_tx).await)
+          .ok();
+      }
+      Cmd::SetPlayerDelay {
+        player_id,
+        delay,
+        tx,
+      } => {
+        tx.send(self.shared.lock().set_player_delay(player_id, delay))
+          .ok();
+      }
+      Cmd::BroadcastMessage { message } => {
+        self.shared.lock().broadcast_message(message);
+      }
+      Cmd::Terminate { tx } => {
+        tx.send(self.terminate(action_tx, out_tx).await).ok();
+      }
+      Cmd::EndGame {
+        winner_player_ids,
+        tx,
+      } => {
+        tx.send(self.end_game(winner_player_ids, action_tx, out_tx).await)
+          .ok();
+      }
+      Cmd::SetPingEqualizer { enabled } => {
+        self.shared.lock().set_ping_equalizer(enabled);
+      }
     }
 
     Ok(())
   }
 
+  async fn kick_player(
+    &mut self,
+    player_id: i32,
+    action_tx: &mut Sender<ActionMsg>,
+    out_tx: &mut GameEventSender,
+  ) -> Result<()> {
+    if self.left_players.contains(&player_id) {
+      return Err(Error::PlayerAlreadyLeft);
+    }
+
+    {
+      let mut guard = self.shared.lock();
+      let name = guard
+        .get_player(player_id)
+        .ok_or_else(|| Error::PlayerNotFoundInGame)?
+        .player_name()
+        .to_string();
+      guard.broadcast_message(format!("{} has been removed by the server.", name));
+    }
+
+    tracing::info!(game_id = self.game_id, player_id, "kick player");
+
+    self
+      .handle_player_leave(
+        player_id,
+        Some(LeaveReason::LeaveDisconnect),
+        action_tx,
+        out_tx,
+      )
+      .await
+  }
+
+  async fn terminate(
+    &mut self,
+    action_tx: &mut Sender<ActionMsg>,
+    out_tx: &mut GameEventSender,
+  ) -> Result<()> {
+    let player_ids: Vec<i32> = {
+      let mut guard = self.shared.lock();
+      guard.broadcast_message("The game has been terminated by the server.");
+      guard.map.keys().cloned().collect()
+    };
+
+    tracing::info!(game_id = self.game_id, "terminate");
+
+    for player_id in player_ids {
+      if !self.left_players.contains(&player_id) {
+        self
+          .handle_player_leave(
+            player_id,
+            Some(LeaveReason::LeaveDisconnect),
+            action_tx,
+            out_tx,
+          )
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  async fn end_game(
+    &mut self,
+    winner_player_ids: Vec<i32>,
+    action_tx: &mut Sender<ActionMsg>,
+    out_tx: &mut GameEventSender,
+  ) -> Result<()> {
+    let leave_reasons = {
+      let mut guard = self.shared.lock();
+      let player_ids: Vec<i32> = guard.map.keys().cloned().collect();
+      let leave_reasons =
+        end_game_leave_reasons(&guard.active_players, &player_ids, &winner_player_ids)?;
+      guard.broadcast_message("The game has been ended by the server.");
+      leave_reasons
+    };
+
+    tracing::info!(game_id = self.game_id, ?winner_player_ids, "end game");
+
+    for (player_id, leave_reason) in leave_reasons {
+      if !self.left_players.contains(&player_id) {
+        self
+          .handle_player_leave(player_id, Some(leave_reason), action_tx, out_tx)
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
   async fn register_stream(
     &mut self,
     stream: PlayerStream,
@@ -864,11 +1138,29 @@ impl State {
       }
     }
 
-    if self.chat_banned_player_ids.contains(&player_id) && chat.is_in_game_chat() {
-      return Ok(());
-    }
+    let packet = match self.moderate_chat(player_id, &chat) {
+      ChatVerdict::Relay => {
+        packet.header.type_id = PacketTypeId::ChatFromHost;
+        packet
+      }
+      ChatVerdict::Replace(text) => {
+        let message = match chat.message {
+          ChatMessage::Scoped { scope, .. } => ChatMessage::Scoped {
+            scope,
+            message: text.into_c_string_lossy(),
+          },
+          _ => ChatMessage::Chat(text.into_c_string_lossy()),
+        };
+        Packet::simple(ChatFromHost(ChatToHost {
+          to_players_len: chat.to_players_len,
+          to_players: chat.to_players.clone(),
+          from_player: chat.from_player,
+          message,
+        }))?
+      }
+      ChatVerdict::Drop => return Ok(()),
+    };
 
-    packet.header.type_id = PacketTypeId::ChatFromHost;
     {
       let mut guard = self.shared.lock();
       guard.obs.push_w3gs(self.game_id, packet.clone());
@@ -896,6 +1188,97 @@ impl State {
     Ok(())
   }
 
+  /// Applies mutes, rate limits and word filters to a chat message and records it in the chat log
+  fn moderate_chat(&mut self, player_id: i32, chat: &ChatToHost) -> ChatVerdict {
+    let (scope, text) = match chat.message {
+      ChatMessage::Chat(ref text) => (GameChatScope::Lobby, text),
+      ChatMessage::Scoped { scope, ref message } => (
+        match scope {
+          MessageScope::All => GameChatScope::All,
+          MessageScope::Allies => GameChatScope::Allies,
+          MessageScope::Observers => GameChatScope::Observers,
+          MessageScope::Player(_) => GameChatScope::Private,
+        },
+        message,
+      ),
+      _ => return ChatVerdict::Relay,
+    };
+    let text = text.to_string_lossy().into_owned();
+
+    let config = crate::config::get();
+    let (filter_action, verdict) =
+      if self.chat_banned_player_ids.contains(&player_id) && chat.is_in_game_chat() {
+        (GameChatFilterAction::Muted, ChatVerdict::Drop)
+      } else if !self.chat_rate_limiter.check(
+        player_id,
+        config.chat.rate_limit_messages,
+        Duration::from_secs(config.chat.rate_limit_window_secs as u64),
+        Instant::now(),
+      ) {
+        (GameChatFilterAction::RateLimited, ChatVerdict::Drop)
+      } else if let Some(filtered) = filter_words(&config.chat.filter_words, &text) {
+        match config.chat.filter_action {
+          ChatFilterAction::Replace => (
+            GameChatFilterAction::Replaced,
+            ChatVerdict::Replace(filtered),
+          ),
+          ChatFilterAction::Drop => (GameChatFilterAction::Dropped, ChatVerdict::Drop),
+        }
+      } else {
+        (GameChatFilterAction::None, ChatVerdict::Relay)
+      };
+
+    let to_player_id = if scope == GameChatScope::Private {
+      chat
+        .to_players
+        .first()
+        .and_then(|id| self.game_player_id_lookup.get(id).cloned())
+    } else {
+      None
+    };
+
+    let mut guard = self.shared.lock();
+    let game_time_ms = guard.sync.time();
+    guard.chat_log.record(GameChatMessage {
+      player_id,
+      scope: scope.into(),
+      to_player_id,
+      game_time_ms,
+      timestamp_ms: SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default(),
+      message: text,
+      filter_action: filter_action.into(),
+    });
+
+    if filter_action.is_violation() {
+      match filter_action {
+        GameChatFilterAction::RateLimited => {
+          guard.private_message(player_id, "You are sending messages too fast.")
+        }
+        GameChatFilterAction::Dropped => guard.private_message(
+          player_id,
+          "Your message has been blocked by the chat filter.",
+        ),
+        _ => {}
+      }
+      let violations = guard.chat_log.record_violation(player_id);
+      if config.chat.mute_after_violations > 0
+        && violations >= config.chat.mute_after_violations
+        && !self.chat_banned_player_ids.contains(&player_id)
+      {
+        self.chat_banned_player_ids.push(player_id);
+        guard.private_message(
+          player_id,
+          "You have been muted for repeated chat violations.",
+        );
+      }
+    }
+
+    verdict
+  }
+
   async fn handle_command(
     &self,
     action_tx: &mut Sender<ActionMsg>,
@@ -928,7 +1311,11 @@ impl State {
       }
       "delay" => {
         if let Some(Some((ms,))) = cmd.parse_arguments::<Option<(u16,)>>().ok() {
-          let [min, max] = crate::constants::GAME_DELAY_RANGE;
+          let config = crate::config::get();
+          let [min, max] = [
+            Duration::from_millis(config.game.min_delay_ms),
+            Duration::from_millis(config.game.max_delay_ms),
+          ];
 
           if ms == 0 {
             let mut guard = self.shared.lock();
@@ -1085,6 +1472,36 @@ impl State {
   }
 }
 
+/// Returns the leave reason of each player when the game is ended by the server,
+/// the game is a draw if there are no winners
+fn end_game_leave_reasons(
+  active_players: &BTreeSet<i32>,
+  player_ids: &[i32],
+  winner_player_ids: &[i32],
+) -> Result<Vec<(i32, LeaveReason)>> {
+  if winner_player_ids
+    .iter()
+    .any(|player_id| !active_players.contains(player_id))
+  {
+    return Err(Error::PlayerNotFoundInGame);
+  }
+  Ok(
+    player_ids
+      .iter()
+      .map(|player_id| {
+        let leave_reason = if winner_player_ids.is_empty() {
+          LeaveReason::LeaveDraw
+        } else if winner_player_ids.contains(player_id) {
+          LeaveReason::LeaveWon
+        } else {
+          LeaveReason::LeaveLost
+        };
+        (*player_id, leave_reason)
+      })
+      .collect(),
+  )
+}
+
 #[derive(Debug)]
 struct Shared {
   game_id: i32,
@@ -1097,6 +1514,8 @@ struct Shared {
   obs: ObserverPublisherHandle,
   active_players: BTreeSet<i32>,
   delay_equalizer: Option<DelayEqualizer>,
+  result: GameResultRecorder,
+  chat_log: ChatLogRecorder,
 }
 
 impl Shared {
@@ -1109,6 +1528,13 @@ impl Shared {
     let sync = SyncMap::new(slots.iter().map(|s| s.player.player_id).collect());
     let mut slot_id_lookup = BTreeMap::new();
     let mut active_players = BTreeSet::new();
+    let result = GameResultRecorder::new(
+      game_id,
+      slots
+        .iter()
+        .filter(|slot| slot.settings.team != 24)
+        .map(|slot| slot.player.player_id),
+    );
     Self {
       game_id,
       started: false,
@@ -1130,6 +1556,8 @@ impl Shared {
       obs,
       active_players,
       delay_equalizer,
+      result,
+      chat_log: ChatLogRecorder::new(game_id),
     }
   }
 
@@ -1141,6 +1569,80 @@ impl Shared {
     self.map.get_mut(&player_id)
   }
 
+  fn get_info(&self, paused: bool) -> DispatcherInfo {
+    DispatcherInfo {
+      started: self.started,
+      paused,
+      game_time_ms: self.sync.time(),
+      ping_equalizer: self.delay_equalizer.is_some(),
+      players: self
+        .map
+        .iter()
+        .map(|(player_id, info)| PlayerDispatchSnapshot {
+          player_id: *player_id,
+          connected: info.stream_id().is_some(),
+          rtt: info.rtt(),
+          delay_ms: info.delay().map(|v| v.as_millis() as u64),
+          lagging: self.lagging_player_ids.contains(player_id),
+          lag_duration_ms: info.lag_duration_ms(),
+          last_ack_received: info.ack_queue().last_ack_received(),
+          pending_ack_len: info.ack_queue().pending_ack_len(),
+        })
+        .collect(),
+    }
+  }
+
+  fn set_player_delay(&mut self, player_id: i32, delay: Option<Duration>) -> Result<()> {
+    // the equalizer would override the value on the next pong
+    if self.delay_equalizer.is_some() {
+      return Err(Error::PingEqualizerEnabled);
+    }
+    let player = self
+      .get_player(player_id)
+      .ok_or_else(|| Error::PlayerNotFoundInGame)?;
+    player.set_delay(delay)?;
+    let msg = match delay {
+      Some(delay) => format!(
+        "Set delay for {}: {}ms",
+        player.player_name(),
+        delay.as_millis()
+      ),
+      None => format!("Removed delay for {}", player.player_name()),
+    };
+    self.broadcast_message(msg);
+    Ok(())
+  }
+
+  fn set_ping_equalizer(&mut self, enabled: bool) {
+    if enabled == self.delay_equalizer.is_some() {
+      return;
+    }
+
+    if enabled {
+      self.delay_equalizer = Some(DelayEqualizer::new(self.active_players.len()));
+      self.broadcast_message("Ping equalizer has been enabled by the server.");
+    } else {
+      self.delay_equalizer = None;
+      for (player_id, player) in self.map.iter_mut() {
+        if let Err(err) = player.set_delay(None) {
+          tracing::error!(player_id, "remove delay: {}", err);
+        }
+      }
+      self.broadcast_message("Ping equalizer has been disabled by the server.");
+    }
+  }
+
+  fn game_result(&mut self) -> PacketNodeGameResult {
+    self.result.set_time(self.sync.time());
+    for (player_id, info) in &self.map {
+      self.result.record_rtt(*player_id, info.rtt());
+      self
+        .result
+        .record_lag_duration(*player_id, info.lag_duration_ms());
+    }
+    self.result.to_packet()
+  }
+
   #[must_use]
   pub fn dispatch_action_tick(&mut self, mut tick: Tick) -> Result<DispatchResult> {
     let time_increment_ms = tick.time_increment_ms;
@@ -1228,6 +1730,11 @@ impl Shared {
   }
 
   fn handle_lag(&mut self, add_player_ids: Vec<i32>) -> Result<bool> {
+    for player_id in &add_player_ids {
+      if !self.lagging_player_ids.contains(player_id) {
+        self.result.record_lag_start(*player_id);
+      }
+    }
     self.lagging_player_ids.extend(add_player_ids);
     self.obs.push_start_lag(
       self.game_id,
@@ -1296,6 +1803,7 @@ impl Shared {
         self.slot_id_lookup.get(&id).cloned().map(|slot| (slot, 0))
       };
       if let Some((slot, lag_duration_ms)) = info {
+        self.result.record_lag_duration(id, lag_duration_ms);
         self.obs.push_end_lag(self.game_id, id);
         self.lagging_player_ids.remove(&id);
         stop_lag_players.push(id);
@@ -1362,6 +1870,7 @@ impl Shared {
     }) {
       if self.started {
         tracing::warn!(game_id = self.game_id, player_id, "player disconnected");
+        self.result.record_disconnect(player_id);
         stream.close();
         // don't need to check `lagging_player_ids`
         // because disconnect does not change lag status
@@ -1401,6 +1910,13 @@ impl Shared {
 
     tracing::info!(game_id = self.game_id, player_id, "remove player");
 
+    self.result.set_time(self.sync.time());
+    self.result.record_rtt(player_id, player.rtt());
+    self
+      .result
+      .record_lag_duration(player_id, player.lag_duration_ms());
+    self.result.record_left(player_id, reason);
+
     for p in self.map.values_mut() {
       p.remove_lag_slot(player.slot_player_id());
     }
@@ -1525,6 +2041,7 @@ impl Shared {
         player_id = *drop_player_id,
         "lagging player dropped."
       );
+      self.result.record_dropped(*drop_player_id);
       self.remove_player_and_broadcast(*drop_player_id, None)?;
     }
     self.lagging_player_ids.clear();
@@ -1590,6 +2107,9 @@ impl Shared {
           item.tick
         );
         tracing::warn!("{}", self.sync.debug_pending());
+        self
+          .result
+          .record_desync(item.player_id, item.time, item.tick);
 
         if let Some(name) = self.map.get(&item.player_id).map(|v| v.player_name()) {
           targets.push((
@@ -1967,3 +2487,30 @@ enum ClosePlayerStreamResult {
   ClosedLagging,
   Skipped,
 }
+
+#[test]
+fn test_end_game_leave_reasons() {
+  let active_players: BTreeSet<i32> = vec![1, 2, 3].into_iter().collect();
+  let player_ids = vec![1, 2, 3];
+
+  assert_eq!(
+    end_game_leave_reasons(&active_players, &player_ids, &[1, 3]).unwrap(),
+    vec![
+      (1, LeaveReason::LeaveWon),
+      (2, LeaveReason::LeaveLost),
+      (3, LeaveReason::LeaveWon)
+    ]
+  );
+  assert_eq!(
+    end_game_leave_reasons(&active_players, &player_ids, &[]).unwrap(),
+    vec![
+      (1, LeaveReason::LeaveDraw),
+      (2, LeaveReason::LeaveDraw),
+      (3, LeaveReason::LeaveDraw)
+    ]
+  );
+  assert!(matches!(
+    end_game_leave_reasons(&active_players, &player_ids, &[1, 4]),
+    Err(Error::PlayerNotFoundInGame)
+  ));
+}
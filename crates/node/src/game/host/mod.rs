@@ -1,6 +1,7 @@
 use s2_grpc_utils::S2ProtoEnum;
 
 use dispatch::Dispatcher;
+pub use dispatch::{DispatcherInfo, PlayerDispatchSnapshot};
 use flo_net::packet::*;
 pub use sync::AckError;
 
@@ -9,13 +10,16 @@ use crate::game::host::stream::{PlayerStream, PlayerStreamHandle};
 use crate::game::{GameEventSender, NodeGameStatusSnapshot, PlayerSlot};
 use crate::observer::ObserverPublisherHandle;
 use flo_w3gs::constants::LeaveReason;
+use std::time::Duration;
 
 mod broadcast;
+mod chat;
 mod clock;
 mod delay;
 mod delay_equalizer;
 mod dispatch;
 mod player;
+mod result;
 pub mod stream;
 mod sync;
 
@@ -49,6 +53,14 @@ impl GameHost {
     self.dispatcher.start();
   }
 
+  pub fn game_result(&self) -> flo_net::proto::flo_node::PacketNodeGameResult {
+    self.dispatcher.game_result()
+  }
+
+  pub fn chat_log(&self) -> Vec<flo_net::proto::flo_node::PacketNodeGameChatLog> {
+    self.dispatcher.chat_log()
+  }
+
   pub async fn register_player_stream(
     &mut self,
     mut stream: PlayerStream,
@@ -86,4 +98,32 @@ impl GameHost {
       .notify_player_shutdown(player_id, leave_reason)
       .await
   }
+
+  pub async fn get_info(&self) -> Result<DispatcherInfo> {
+    self.dispatcher.get_info().await
+  }
+
+  pub async fn kick_player(&self, player_id: i32) -> Result<()> {
+    self.dispatcher.kick_player(player_id).await
+  }
+
+  pub async fn set_player_delay(&self, player_id: i32, delay: Option<Duration>) -> Result<()> {
+    self.dispatcher.set_player_delay(player_id, delay).await
+  }
+
+  pub async fn broadcast_message(&self, message: String) -> Result<()> {
+    self.dispatcher.broadcast_message(message).await
+  }
+
+  pub async fn terminate(&self) -> Result<()> {
+    self.dispatcher.terminate().await
+  }
+
+  pub async fn end_game(&self, winner_player_ids: Vec<i32>) -> Result<()> {
+    self.dispatcher.end_game(winner_player_ids).await
+  }
+
+  pub async fn set_ping_equalizer(&self, enabled: bool) -> Result<()> {
+    self.dispatcher.set_ping_equalizer(enabled).await
+  }
 }
@@ -4,6 +4,7 @@ use crate::game::{PlayerBanType, PlayerSlot};
 use flo_net::packet::Frame;
 use flo_net::w3gs::{W3GSAckQueue, W3GSFrameExt, W3GSMetadata, W3GSPacket};
 use flo_w3gs::protocol::chat::ChatFromHost;
+use serde::Serialize;
 use std::collections::BTreeSet;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc::error::TrySendError;
@@ -173,6 +174,10 @@ impl PlayerDispatchInfo {
     self.lag_duration_ms
   }
 
+  pub fn lag_duration_ms(&self) -> u32 {
+    self.lag_duration_ms
+  }
+
   pub fn end_lag(&mut self) -> u32 {
     if let Some(start) = self.lag_start.take() {
       self.lag_duration_ms = self.lag_duration_ms.saturating_add(std::cmp::min(
@@ -290,7 +295,7 @@ pub enum PlayerSendError {
   AckQueueFull,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct PlayerRTTSnapshot {
   pub ticks: u16,
   pub avg: f32,
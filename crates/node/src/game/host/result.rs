@@ -0,0 +1,154 @@
+use flo_net::proto::flo_node::{GameDesync, GamePlayerResult, PacketNodeGameResult};
+use flo_w3gs::protocol::constants::LeaveReason;
+use std::collections::BTreeMap;
+
+use super::player::PlayerRTTSnapshot;
+
+/// Collects the per-player outcome of a game,
+/// reported to the controller after the game ended.
+#[derive(Debug)]
+pub struct GameResultRecorder {
+  game_id: i32,
+  players: BTreeMap<i32, PlayerResult>,
+  desyncs: Vec<GameDesync>,
+  time: u32,
+}
+
+#[derive(Debug, Default)]
+struct PlayerResult {
+  left_at_ms: Option<u32>,
+  leave_reason: Option<LeaveReason>,
+  disconnects: u32,
+  dropped: bool,
+  desynced: bool,
+  lag_count: u32,
+  lag_duration_ms: u32,
+  rtt: Option<PlayerRTTSnapshot>,
+}
+
+impl GameResultRecorder {
+  pub fn new<I: IntoIterator<Item = i32>>(game_id: i32, player_ids: I) -> Self {
+    Self {
+      game_id,
+      players: player_ids
+        .into_iter()
+        .map(|id| (id, PlayerResult::default()))
+        .collect(),
+      desyncs: vec![],
+      time: 0,
+    }
+  }
+
+  pub fn set_time(&mut self, time: u32) {
+    self.time = time;
+  }
+
+  pub fn record_left(&mut self, player_id: i32, reason: Option<LeaveReason>) {
+    let time = self.time;
+    if let Some(p) = self.players.get_mut(&player_id) {
+      if p.left_at_ms.is_none() {
+        p.left_at_ms = Some(time);
+        p.leave_reason = reason;
+      }
+    }
+  }
+
+  pub fn record_disconnect(&mut self, player_id: i32) {
+    if let Some(p) = self.players.get_mut(&player_id) {
+      p.disconnects += 1;
+    }
+  }
+
+  pub fn record_dropped(&mut self, player_id: i32) {
+    if let Some(p) = self.players.get_mut(&player_id) {
+      p.dropped = true;
+    }
+  }
+
+  pub fn record_desync(&mut self, player_id: i32, time: u32, tick: u32) {
+    if let Some(p) = self.players.get_mut(&player_id) {
+      p.desynced = true;
+    }
+    self.desyncs.push(GameDesync {
+      player_id,
+      time,
+      tick,
+    });
+  }
+
+  pub fn record_lag_start(&mut self, player_id: i32) {
+    if let Some(p) = self.players.get_mut(&player_id) {
+      p.lag_count += 1;
+    }
+  }
+
+  /// `total_ms` is the accumulated lag duration of the player
+  pub fn record_lag_duration(&mut self, player_id: i32, total_ms: u32) {
+    if let Some(p) = self.players.get_mut(&player_id) {
+      p.lag_duration_ms = std::cmp::max(p.lag_duration_ms, total_ms);
+    }
+  }
+
+  pub fn record_rtt(&mut self, player_id: i32, rtt: Option<PlayerRTTSnapshot>) {
+    if let Some(p) = self.players.get_mut(&player_id) {
+      if rtt.is_some() {
+        p.rtt = rtt;
+      }
+    }
+  }
+
+  pub fn to_packet(&self) -> PacketNodeGameResult {
+    PacketNodeGameResult {
+      game_id: self.game_id,
+      duration_ms: self.time,
+      players: self
+        .players
+        .iter()
+        .map(|(player_id, p)| GamePlayerResult {
+          player_id: *player_id,
+          left_at_ms: p.left_at_ms,
+          leave_reason: p.leave_reason.map(u32::from),
+          disconnects: p.disconnects,
+          dropped: p.dropped,
+          desynced: p.desynced,
+          lag_count: p.lag_count,
+          lag_duration_ms: p.lag_duration_ms,
+          rtt_min: p.rtt.as_ref().map(|v| v.min as u32),
+          rtt_max: p.rtt.as_ref().map(|v| v.max as u32),
+          rtt_avg: p.rtt.as_ref().map(|v| v.avg as u32),
+        })
+        .collect(),
+      desyncs: self.desyncs.clone(),
+    }
+  }
+}
+
+#[test]
+fn test_game_result_recorder() {
+  let mut r = GameResultRecorder::new(1, vec![1, 2]);
+  r.set_time(1000);
+  r.record_lag_start(2);
+  r.record_lag_duration(2, 300);
+  r.record_disconnect(2);
+  r.record_dropped(2);
+  r.record_left(2, None);
+  r.set_time(5000);
+  r.record_left(2, Some(LeaveReason::LeaveLost));
+  r.record_left(1, Some(LeaveReason::LeaveWon));
+  r.record_desync(3, 100, 1);
+
+  let pkt = r.to_packet();
+  assert_eq!(pkt.duration_ms, 5000);
+  assert_eq!(pkt.desyncs.len(), 1);
+  assert_eq!(pkt.players[0].left_at_ms, Some(5000));
+  assert_eq!(
+    pkt.players[0].leave_reason,
+    Some(u32::from(LeaveReason::LeaveWon))
+  );
+  assert_eq!(pkt.players[1].left_at_ms, Some(1000));
+  assert_eq!(pkt.players[1].leave_reason, None);
+  assert!(pkt.players[1].dropped);
+  assert_eq!(pkt.players[1].lag_count, 1);
+  assert_eq!(pkt.players[1].lag_duration_ms, 300);
+  assert_eq!(pkt.players[1].disconnects, 1);
+}
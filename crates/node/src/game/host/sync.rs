@@ -11,6 +11,7 @@ pub struct SyncMap {
   pending_tick: BTreeMap<u32, usize>,
   pending_slab: Slab<Pending>,
   desync_buf: Vec<PlayerDesync>,
+  lagging_threshold_ms: u32,
 }
 
 impl SyncMap {
@@ -25,6 +26,7 @@ impl SyncMap {
       pending_tick: BTreeMap::new(),
       pending_slab: Slab::new(),
       desync_buf: vec![],
+      lagging_threshold_ms: crate::config::get().game.lagging_threshold_ms,
     }
   }
 
@@ -52,9 +54,7 @@ impl SyncMap {
   fn check_timeout(&mut self, time_increment: u16) -> Option<Vec<PlayerTimeout>> {
     for id in self.pending_tick.values() {
       let item = &mut self.pending_slab[*id];
-      if (self.time + time_increment as u32) - item.time
-        > crate::constants::GAME_PLAYER_LAGGING_THRESHOLD_MS
-      {
+      if (self.time + time_increment as u32) - item.time > self.lagging_threshold_ms {
         return Some(
           self
             .players
@@ -395,7 +395,7 @@ fn test_sync_map() {
     acks.push_back((SIZE - tick + 1, player_id, end));
   }
 
-  let threshold_ticks = crate::constants::GAME_PLAYER_LAGGING_THRESHOLD_MS / 5;
+  let threshold_ticks = map.lagging_threshold_ms / 5;
   let mut offset = 0;
   for tick in 0..(SIZE + 1/* timeout uss 1 iteration */) {
     let timeout = map.clock(5);
@@ -1,9 +1,11 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::lock::Mutex;
 use futures::FutureExt;
 use s2_grpc_utils::{S2ProtoEnum, S2ProtoUnpack};
+use serde::Serialize;
 use tokio::sync::mpsc::Sender;
 use tracing_futures::Instrument;
 
@@ -16,6 +18,7 @@ pub use flo_types::node::*;
 use host::stream::PlayerStreamHandle;
 pub use host::AckError;
 use host::GameHost;
+use host::PlayerDispatchSnapshot;
 
 use crate::controller::ControllerServerHandle;
 use crate::error::*;
@@ -153,10 +156,96 @@ impl GameSession {
   }
 }
 
+/// Game state reported by the admin API
+#[derive(Debug, Serialize)]
+pub struct GameInfo {
+  pub game_id: i32,
+  pub status: NodeGameStatus,
+  pub started: bool,
+  pub paused: bool,
+  pub game_time_ms: u32,
+  pub ping_equalizer: bool,
+  pub players: Vec<GamePlayerInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GamePlayerInfo {
+  pub player_id: i32,
+  pub name: String,
+  pub team: i32,
+  pub client_status: SlotClientStatus,
+  /// `None` if the player has left
+  pub dispatch: Option<PlayerDispatchSnapshot>,
+}
+
 #[derive(Debug, Clone)]
 pub struct GameSessionHandle(Arc<Mutex<State>>);
 
 impl GameSessionHandle {
+  pub async fn get_info(&self) -> Result<GameInfo> {
+    let guard = self.0.lock().await;
+    let mut info = guard.host.get_info().await?;
+    Ok(GameInfo {
+      game_id: guard.game_id,
+      status: guard.status,
+      started: info.started,
+      paused: info.paused,
+      game_time_ms: info.game_time_ms,
+      ping_equalizer: info.ping_equalizer,
+      players: guard
+        .player_slots
+        .values()
+        .map(|slot| {
+          let player_id = slot.player.player_id;
+          GamePlayerInfo {
+            player_id,
+            name: slot.player.name.clone(),
+            team: slot.settings.team,
+            client_status: slot.client_status,
+            dispatch: info
+              .players
+              .iter()
+              .position(|p| p.player_id == player_id)
+              .map(|idx| info.players.swap_remove(idx)),
+          }
+        })
+        .collect(),
+    })
+  }
+
+  pub async fn kick_player(&self, player_id: i32) -> Result<()> {
+    let guard = self.0.lock().await;
+    if !guard.player_slots.contains_key(&player_id) {
+      return Err(Error::PlayerNotFoundInGame);
+    }
+    guard.host.kick_player(player_id).await
+  }
+
+  pub async fn set_player_delay(&self, player_id: i32, delay: Option<Duration>) -> Result<()> {
+    let guard = self.0.lock().await;
+    guard.host.set_player_delay(player_id, delay).await
+  }
+
+  pub async fn broadcast_message(&self, message: String) -> Result<()> {
+    let guard = self.0.lock().await;
+    guard.host.broadcast_message(message).await
+  }
+
+  pub async fn terminate(&self) -> Result<()> {
+    let guard = self.0.lock().await;
+    guard.host.terminate().await
+  }
+
+  pub async fn end_game(&self, winner_player_ids: Vec<i32>) -> Result<()> {
+    let guard = self.0.lock().await;
+    guard.host.end_game(winner_player_ids).await
+  }
+
+  pub async fn set_ping_equalizer(&self, enabled: bool) -> Result<()> {
+    let guard = self.0.lock().await;
+    guard.host.set_ping_equalizer(enabled).await
+  }
+
   pub async fn register_player_stream(
     &self,
     player_id: i32,
@@ -535,6 +624,8 @@ impl State {
       self.status = NodeGameStatus::Ended;
       tracing::debug!("all player left, end game");
       self.obs.push_game_end(self.game_id);
+      self.report_game_result().await;
+      self.report_chat_log().await;
       self
         .g_event_sender
         .send(GlobalEvent::GameEnded(self.game_id))
@@ -546,6 +637,35 @@ impl State {
     }
   }
 
+  async fn report_game_result(&mut self) {
+    let frame = match self.host.game_result().encode_as_frame() {
+      Ok(frame) => frame,
+      Err(err) => {
+        tracing::error!("encode game result: {}", err);
+        return;
+      }
+    };
+    if self.ctrl.send(frame).await.is_err() {
+      tracing::error!("report game result: controller channel closed");
+    }
+  }
+
+  async fn report_chat_log(&mut self) {
+    for chat_log in self.host.chat_log() {
+      let frame = match chat_log.encode_as_frame() {
+        Ok(frame) => frame,
+        Err(err) => {
+          tracing::error!("encode chat log: {}", err);
+          return;
+        }
+      };
+      if self.ctrl.send(frame).await.is_err() {
+        tracing::error!("report chat log: controller channel closed");
+        return;
+      }
+    }
+  }
+
   async fn check_game_all_joined(&mut self) {
     if self
       .player_slots
@@ -651,4 +771,6 @@ impl<'a> From<&'a State> for NodeGameStatusSnapshot {
 #[repr(i32)]
 pub enum PlayerBanType {
   Chat = 0,
+  Matchmaking = 1,
+  Game = 2,
 }
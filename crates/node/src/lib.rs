@@ -1,7 +1,8 @@
+mod admin;
 mod client;
+mod config;
 mod controller;
 mod echo;
-mod env;
 mod game;
 mod metrics;
 mod state;
@@ -22,6 +23,8 @@ use crate::state::GlobalState;
 use state::event::{handle_global_events, FloNodeEventContext, GlobalEvent};
 
 pub async fn serve() -> Result<()> {
+  config::init()?;
+
   let (event_sender, event_receiver) = GlobalEvent::channel(30);
   let state = GlobalState::new(event_sender).into_ref();
   let mut ctrl = controller::ControllerServer::new(state.clone());
@@ -30,7 +33,7 @@ pub async fn serve() -> Result<()> {
   tokio::try_join!(
     ctrl.serve(),
     serve_client(state.clone()),
-    serve_metrics(),
+    serve_metrics(state.clone()),
     serve_echo(),
     handle_global_events(
       FloNodeEventContext {
@@ -42,3 +45,8 @@ pub async fn serve() -> Result<()> {
   )
   .map(|_| ())
 }
+
+/// Reloads the config file, see [`flo_config::NodeConfig`].
+pub fn reload_config() -> Result<()> {
+  config::reload()
+}
@@ -2,6 +2,7 @@ use once_cell::sync::Lazy;
 use prometheus::{register_int_gauge, Encoder, IntGauge, TextEncoder};
 
 use crate::error::*;
+use crate::state::GlobalStateRef;
 use hyper::header::CONTENT_TYPE;
 
 pub static GAME_SESSIONS: Lazy<IntGauge> =
@@ -21,12 +22,19 @@ pub static PLAYER_TOKENS: Lazy<IntGauge> = Lazy::new(|| {
   .unwrap()
 });
 
-pub async fn serve_metrics() -> Result<()> {
+pub async fn serve_metrics(state: GlobalStateRef) -> Result<()> {
   use hyper::service::{make_service_fn, service_fn};
   use hyper::{Body, Request, Response, Server};
-  use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+  use std::net::SocketAddr;
+
+  async fn serve_req(
+    state: GlobalStateRef,
+    req: Request<Body>,
+  ) -> Result<Response<Body>, hyper::Error> {
+    if req.uri().path().starts_with("/admin/") {
+      return Ok(crate::admin::handle(state, req).await);
+    }
 
-  async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
     if req.uri().path() == "/version" {
       let response = Response::builder()
         .status(200)
@@ -51,13 +59,13 @@ pub async fn serve_metrics() -> Result<()> {
     Ok(response)
   }
 
-  let addr = SocketAddr::from(SocketAddrV4::new(
-    Ipv4Addr::UNSPECIFIED,
-    flo_constants::NODE_HTTP_PORT,
+  let addr = SocketAddr::from(crate::config::socket_addr(
+    flo_constants::NODE_HTTP_PORT_OFFSET,
   ));
 
-  let server = Server::bind(&addr).serve(make_service_fn(|_| async {
-    Ok::<_, hyper::Error>(service_fn(serve_req))
+  let server = Server::bind(&addr).serve(make_service_fn(move |_| {
+    let state = state.clone();
+    async move { Ok::<_, hyper::Error>(service_fn(move |req| serve_req(state.clone(), req))) }
   }));
   server.await?;
 
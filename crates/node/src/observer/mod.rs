@@ -19,6 +19,7 @@ const BUFFER_TIMEOUT: Duration = Duration::from_secs(15 * 60);
 pub struct ObserverPublisher {
   ct: CancellationToken,
   tx: Sender<Cmd>,
+  disabled: bool,
 }
 
 impl Drop for ObserverPublisher {
@@ -31,17 +32,22 @@ impl ObserverPublisher {
   pub fn new() -> Self {
     let (tx, rx) = channel(crate::constants::OBS_CHANNEL_SIZE);
     let ct = CancellationToken::new();
-    let bm = BufferMap::new();
-
-    tokio::spawn(Handler::new(ct.clone(), rx, bm.clone()).run());
-    tokio::spawn(Pusher::new(ct.clone(), bm.clone()).run());
+    let disabled = crate::config::get().observer.disabled;
+
+    if disabled {
+      tracing::warn!("observer pushing disabled by config.");
+    } else {
+      let bm = BufferMap::new();
+      tokio::spawn(Handler::new(ct.clone(), rx, bm.clone()).run());
+      tokio::spawn(Pusher::new(ct.clone(), bm.clone()).run());
+    }
 
-    Self { ct, tx }
+    Self { ct, tx, disabled }
   }
 
   pub fn handle(&self) -> ObserverPublisherHandle {
     ObserverPublisherHandle {
-      broken: Cell::new(false),
+      broken: Cell::new(self.disabled),
       tx: self.tx.clone(),
     }
   }
@@ -12,8 +12,9 @@ use std::sync::Arc;
 
 use flo_net::packet::{FloPacket, Frame, OptionalFieldExt};
 use flo_net::proto::flo_node::{
-  ControllerCreateGameRejectReason, Game, PacketControllerCreateGame,
-  PacketControllerCreateGameAccept, PacketControllerCreateGameReject,
+  ControllerCreateGameRejectReason, Game, GameControlRejectReason, PacketControllerCreateGame,
+  PacketControllerCreateGameAccept, PacketControllerCreateGameReject, PacketControllerGameControl,
+  PacketControllerGameControlAccept, PacketControllerGameControlReject,
   PacketControllerUpdateSlotStatus, PacketControllerUpdateSlotStatusAccept,
   PacketControllerUpdateSlotStatusReject,
 };
@@ -56,6 +57,10 @@ impl GlobalState {
     self.games.get(id)
   }
 
+  pub fn list_games(&self) -> Vec<GameSessionHandle> {
+    self.games.list()
+  }
+
   pub fn end_game(&self, id: i32) {
     self.players.remove_game(id);
     self.games.remove(id);
@@ -227,6 +232,99 @@ impl GlobalState {
       },
     }
   }
+
+  /// Runs the action in a new task, ending a game waits for the game to shut down,
+  /// the reply is sent to the controller when done
+  pub fn handle_controller_game_control(
+    &self,
+    ctrl: ControllerServerHandle,
+    packet: PacketControllerGameControl,
+  ) {
+    let game_id = packet.game_id;
+    let game = self.games.get(game_id);
+    tokio::spawn(async move {
+      match Self::game_control(game, packet).await {
+        Ok(frame) => {
+          if ctrl.send(frame).await.is_err() {
+            tracing::debug!(game_id, "game control: controller disconnected");
+          }
+        }
+        Err(err) => tracing::error!(game_id, "game control: {}", err),
+      }
+    });
+  }
+
+  async fn game_control(
+    game: Option<GameSessionHandle>,
+    packet: PacketControllerGameControl,
+  ) -> Result<Frame> {
+    use flo_net::proto::flo_node::packet_controller_game_control::Action;
+    let game_id = packet.game_id;
+    let request_id = packet.request_id;
+
+    let reject = |reason: GameControlRejectReason, message: String| {
+      PacketControllerGameControlReject {
+        game_id,
+        reason: reason.into(),
+        message,
+        request_id,
+      }
+      .encode_as_frame()
+    };
+
+    let game = match game {
+      Some(game) => game,
+      None => {
+        return Ok(reject(
+          GameControlRejectReason::GameNotFound,
+          "game not found".to_string(),
+        )?)
+      }
+    };
+
+    let res = match packet.action {
+      Some(Action::EndGame(action)) => game.end_game(action.winner_player_ids).await,
+      Some(Action::KickPlayer(action)) => game.kick_player(action.player_id).await,
+      Some(Action::BroadcastMessage(action)) => {
+        if action.message.trim().is_empty() {
+          return Ok(reject(
+            GameControlRejectReason::Invalid,
+            "message is empty".to_string(),
+          )?);
+        }
+        game.broadcast_message(action.message).await
+      }
+      Some(Action::SetPingEqualizer(action)) => game.set_ping_equalizer(action.enabled).await,
+      None => {
+        return Ok(reject(
+          GameControlRejectReason::Invalid,
+          "action is required".to_string(),
+        )?)
+      }
+    };
+
+    match res {
+      Ok(_) => Ok(
+        PacketControllerGameControlAccept {
+          game_id,
+          request_id,
+        }
+        .encode_as_frame()?,
+      ),
+      Err(err) => {
+        let reason = match err {
+          Error::PlayerNotFoundInGame => GameControlRejectReason::PlayerNotFound,
+          Error::PlayerAlreadyLeft => GameControlRejectReason::Invalid,
+          Error::Cancelled => GameControlRejectReason::GameNotFound,
+          ref err => {
+            tracing::error!(game_id, "game control: {}", err);
+            GameControlRejectReason::Unknown
+          }
+        };
+        Ok(reject(reason, err.to_string())?)
+      }
+    }
+  }
 }
 
 #[derive(Debug)]
@@ -354,6 +452,10 @@ impl GameRegistry {
     self.map.get(&game_id).map(|r| r.value().handle())
   }
 
+  fn list(&self) -> Vec<GameSessionHandle> {
+    self.map.iter().map(|r| r.value().handle()).collect()
+  }
+
   fn remove(&self, id: i32) {
     if let Some(_) = self.map.remove(&id) {
       metrics::GAME_SESSIONS.dec();
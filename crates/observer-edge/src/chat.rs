@@ -0,0 +1,188 @@
+use crate::game::event::{GameUpdateEvent, GameUpdateEventData};
+use crate::game::ChatScope;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Players hidden from the public chat feed, by game.
+#[derive(Debug, Default)]
+pub struct ChatFilter {
+  hidden: Mutex<BTreeMap<i32, BTreeSet<i32>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChatFeedOptions {
+  /// Chat events are held back for this long, other events are sent immediately.
+  pub delay: Option<Duration>,
+  /// Include messages sent to observers or to a single player.
+  pub include_private_scopes: bool,
+  /// Include messages from hidden players.
+  pub include_hidden: bool,
+}
+
+impl ChatFilter {
+  pub fn new() -> Arc<Self> {
+    Arc::new(Self::default())
+  }
+
+  pub fn set_player_hidden(&self, game_id: i32, player_id: i32, hidden: bool) {
+    let mut map = self.hidden.lock().unwrap();
+    if hidden {
+      map.entry(game_id).or_default().insert(player_id);
+    } else if let Some(set) = map.get_mut(&game_id) {
+      set.remove(&player_id);
+      if set.is_empty() {
+        map.remove(&game_id);
+      }
+    }
+  }
+
+  pub fn is_player_hidden(&self, game_id: i32, player_id: i32) -> bool {
+    self
+      .hidden
+      .lock()
+      .unwrap()
+      .get(&game_id)
+      .map(|set| set.contains(&player_id))
+      .unwrap_or_default()
+  }
+
+  pub fn remove_game(&self, game_id: i32) {
+    self.hidden.lock().unwrap().remove(&game_id);
+  }
+
+  /// Filters and delays the chat events of a game update stream.
+  ///
+  /// Hidden players are checked when a message is released,
+  /// so hiding a player also drops the messages still being delayed.
+  pub fn filter_events<S>(
+    self: &Arc<Self>,
+    options: ChatFeedOptions,
+    mut events: S,
+  ) -> impl Stream<Item = GameUpdateEvent>
+  where
+    S: Stream<Item = GameUpdateEvent> + Send + Unpin + 'static,
+  {
+    let filter = self.clone();
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+      let mut delayed: VecDeque<(Instant, GameUpdateEvent)> = VecDeque::new();
+      let mut source_ended = false;
+
+      loop {
+        let release_at = delayed.front().map(|(t, _)| *t);
+        tokio::select! {
+          _ = tx.closed() => break,
+          next = events.next(), if !source_ended => {
+            let event = match next {
+              Some(event) => event,
+              None => {
+                source_ended = true;
+                continue;
+              }
+            };
+            let scope = match event.data {
+              GameUpdateEventData::Chat(ref chat) => Some(chat.scope),
+              _ => None,
+            };
+            match scope {
+              Some(scope) => {
+                if !options.include_private_scopes && !scope.is_public() {
+                  continue;
+                }
+                match options.delay {
+                  Some(delay) => delayed.push_back((Instant::now() + delay, event)),
+                  None => {
+                    if filter.should_send(&options, &event) && tx.send(event).is_err() {
+                      break;
+                    }
+                  }
+                }
+              }
+              None => {
+                if tx.send(event).is_err() {
+                  break;
+                }
+              }
+            }
+          }
+          _ = sleep_until(release_at), if release_at.is_some() => {
+            if let Some((_, event)) = delayed.pop_front() {
+              if filter.should_send(&options, &event) && tx.send(event).is_err() {
+                break;
+              }
+            }
+          }
+        }
+
+        if source_ended && delayed.is_empty() {
+          break;
+        }
+      }
+    });
+
+    UnboundedReceiverStream::new(rx)
+  }
+
+  fn should_send(&self, options: &ChatFeedOptions, event: &GameUpdateEvent) -> bool {
+    match event.data {
+      GameUpdateEventData::Chat(ref chat) => {
+        options.include_hidden || !self.is_player_hidden(event.game_id, chat.player_id)
+      }
+      _ => true,
+    }
+  }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+  match deadline {
+    Some(deadline) => tokio::time::sleep_until(deadline).await,
+    None => futures::future::pending().await,
+  }
+}
+
+#[tokio::test]
+async fn test_filter_events() {
+  use crate::game::event::GameUpdateEventDataChat;
+
+  let chat = |player_id: i32, scope: ChatScope| {
+    GameUpdateEvent::chat(
+      1,
+      GameUpdateEventDataChat {
+        time: 0,
+        player_id,
+        scope,
+        message: format!("{}", player_id),
+      },
+    )
+  };
+  let events = vec![
+    chat(1, ChatScope::All),
+    chat(2, ChatScope::Observers),
+    chat(3, ChatScope::Allies),
+    GameUpdateEvent::player_left(1, 0, 1, crate::game::PlayerLeaveReason::LeaveLost),
+  ];
+
+  let filter = ChatFilter::new();
+  filter.set_player_hidden(1, 3, true);
+  let stream = filter.filter_events(
+    ChatFeedOptions {
+      delay: Some(Duration::from_millis(10)),
+      ..Default::default()
+    },
+    tokio_stream::iter(events),
+  );
+  let items: Vec<_> = stream
+    .map(|event| match event.data {
+      GameUpdateEventData::Chat(chat) => Some(chat.player_id),
+      _ => None,
+    })
+    .collect()
+    .await;
+  assert_eq!(items, vec![None, Some(1)]);
+}
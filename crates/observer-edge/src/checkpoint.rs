@@ -0,0 +1,458 @@
+use crate::error::Result;
+use bytes::Buf;
+use chrono::Utc;
+use flo_observer::record::GameRecordData;
+use flo_observer_fs::GameDataWriter;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::mpsc;
+
+const CHECKPOINT_FOLDER: &str = "checkpoints";
+const CHECKPOINT_FILENAME: &str = "checkpoint.json";
+const CHECKPOINT_TEMP_FILENAME: &str = "_checkpoint.json";
+
+/// Persists shard sequence numbers and the records of in-progress games,
+/// so a restarted edge can resume the data stream where it left off.
+///
+/// Game records are stored with `GameDataWriter`, one folder per game,
+/// in a folder owned by the checkpointer, see `checkpoint_dir`.
+/// The saved sequence number of a shard only advances after the records
+/// of all chunks up to it have been flushed to disk.
+pub struct Checkpointer {
+  dir: PathBuf,
+  interval: Duration,
+  rx: mpsc::UnboundedReceiver<Msg>,
+  shards: BTreeMap<String, String>,
+  games: BTreeMap<i32, CheckpointGame>,
+  removed: BTreeSet<i32>,
+  dirty: bool,
+}
+
+struct CheckpointGame {
+  initial_arrival_time: f64,
+  writer: GameDataWriter,
+  dirty: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Restored {
+  pub shards: BTreeMap<String, String>,
+  pub games: Vec<RestoredGame>,
+}
+
+#[derive(Debug)]
+pub struct RestoredGame {
+  pub game_id: i32,
+  pub initial_arrival_time: f64,
+  pub next_record_id: u32,
+  pub records: Vec<GameRecordData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointFile {
+  saved_at: i64,
+  shards: BTreeMap<String, String>,
+  games: Vec<CheckpointFileGame>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointFileGame {
+  game_id: i32,
+  initial_arrival_time: f64,
+}
+
+impl Checkpointer {
+  /// Loads the last checkpoint from `dir`.
+  /// Checkpoints older than `max_age` are discarded because
+  /// the saved sequence numbers may have expired.
+  pub async fn load(
+    dir: PathBuf,
+    interval: Duration,
+    max_age: Duration,
+  ) -> Result<(Self, CheckpointHandle, Restored)> {
+    let mut restored = Restored::default();
+    let mut games = BTreeMap::new();
+
+    fs::create_dir_all(&dir).await?;
+    let file = match fs::read(dir.join(CHECKPOINT_FILENAME)).await {
+      Ok(bytes) => Some(serde_json::from_slice::<CheckpointFile>(&bytes)?),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+      Err(err) => return Err(err.into()),
+    };
+
+    let file = file.filter(|file| {
+      let age = Utc::now().timestamp_millis() - file.saved_at;
+      if age > max_age.as_millis() as i64 {
+        tracing::warn!("checkpoint discarded: saved {}s ago", age / 1000);
+        false
+      } else {
+        true
+      }
+    });
+
+    if let Some(file) = file {
+      restored.shards = file.shards;
+      for CheckpointFileGame {
+        game_id,
+        initial_arrival_time,
+      } in file.games
+      {
+        match restore_game(&dir, game_id).await {
+          Ok((writer, records)) => {
+            restored.games.push(RestoredGame {
+              game_id,
+              initial_arrival_time,
+              next_record_id: writer.next_record_id(),
+              records,
+            });
+            games.insert(
+              game_id,
+              CheckpointGame {
+                initial_arrival_time,
+                writer,
+                dirty: false,
+              },
+            );
+          }
+          Err(err) => {
+            tracing::error!(game_id, "restore game: {}", err);
+          }
+        }
+      }
+    }
+
+    remove_untracked_game_dirs(&dir, &games).await?;
+
+    tracing::info!(
+      "checkpoint loaded: {} shards, {} games",
+      restored.shards.len(),
+      restored.games.len()
+    );
+
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    Ok((
+      Self {
+        dir,
+        interval,
+        rx,
+        shards: restored.shards.clone(),
+        games,
+        removed: BTreeSet::new(),
+        dirty: false,
+      },
+      CheckpointHandle { tx },
+      restored,
+    ))
+  }
+
+  pub async fn serve(mut self) {
+    let mut interval = tokio::time::interval(self.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+      tokio::select! {
+        msg = self.rx.recv() => {
+          match msg {
+            Some(msg) => self.handle_msg(msg).await,
+            None => break,
+          }
+        }
+        _ = interval.tick() => {
+          if let Err(err) = self.save().await {
+            tracing::error!("save checkpoint: {}", err);
+          }
+        }
+      }
+    }
+
+    if let Err(err) = self.save().await {
+      tracing::error!("save checkpoint: {}", err);
+    }
+  }
+
+  async fn handle_msg(&mut self, msg: Msg) {
+    match msg {
+      Msg::Records {
+        game_id,
+        initial_arrival_time,
+        min_seq_id,
+        records,
+      } => {
+        if let Err(err) = self
+          .write_records(game_id, initial_arrival_time, min_seq_id, records)
+          .await
+        {
+          tracing::error!(game_id, "write records: {}", err);
+          self.games.remove(&game_id);
+          self.removed.insert(game_id);
+        }
+      }
+      Msg::SequenceNumber {
+        shard_id,
+        sequence_number,
+      } => {
+        self.shards.insert(shard_id, sequence_number);
+        self.dirty = true;
+      }
+      Msg::RemoveGame(game_id) => {
+        if self.games.remove(&game_id).is_some() {
+          self.removed.insert(game_id);
+          self.dirty = true;
+        }
+      }
+    }
+  }
+
+  async fn write_records(
+    &mut self,
+    game_id: i32,
+    initial_arrival_time: f64,
+    min_seq_id: u32,
+    records: Vec<GameRecordData>,
+  ) -> Result<()> {
+    if self.removed.contains(&game_id) {
+      return Ok(());
+    }
+
+    if !self.games.contains_key(&game_id) {
+      let writer = GameDataWriter::create_or_recover_in(&self.dir, game_id).await?;
+      self.games.insert(
+        game_id,
+        CheckpointGame {
+          initial_arrival_time,
+          writer,
+          dirty: false,
+        },
+      );
+      self.dirty = true;
+    }
+
+    let game = match self.games.get_mut(&game_id) {
+      Some(game) => game,
+      None => return Ok(()),
+    };
+
+    let next_record_id = game.writer.next_record_id();
+    if min_seq_id > next_record_id {
+      tracing::warn!(
+        game_id,
+        "records skipped: expected {}, got {}",
+        next_record_id,
+        min_seq_id
+      );
+      return Ok(());
+    }
+
+    let skip = (next_record_id - min_seq_id) as usize;
+    for record in records.into_iter().skip(skip) {
+      game.writer.write_record(record).await?;
+      game.dirty = true;
+    }
+    Ok(())
+  }
+
+  async fn save(&mut self) -> Result<()> {
+    if !self.dirty && !self.games.values().any(|game| game.dirty) {
+      return Ok(());
+    }
+
+    for game in self.games.values_mut() {
+      if game.dirty {
+        game.writer.flush_state().await?;
+        game.dirty = false;
+      }
+    }
+
+    let file = CheckpointFile {
+      saved_at: Utc::now().timestamp_millis(),
+      shards: self.shards.clone(),
+      games: self
+        .games
+        .iter()
+        .map(|(game_id, game)| CheckpointFileGame {
+          game_id: *game_id,
+          initial_arrival_time: game.initial_arrival_time,
+        })
+        .collect(),
+    };
+    let temp_path = self.dir.join(CHECKPOINT_TEMP_FILENAME);
+    fs::write(&temp_path, serde_json::to_vec(&file)?).await?;
+    fs::rename(temp_path, self.dir.join(CHECKPOINT_FILENAME)).await?;
+    self.dirty = false;
+
+    for game_id in std::mem::replace(&mut self.removed, BTreeSet::new()) {
+      remove_game_dir(&self.dir, game_id).await;
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Clone)]
+pub struct CheckpointHandle {
+  tx: mpsc::UnboundedSender<Msg>,
+}
+
+impl CheckpointHandle {
+  pub fn add_records(
+    &self,
+    game_id: i32,
+    initial_arrival_time: f64,
+    min_seq_id: u32,
+    records: Vec<GameRecordData>,
+  ) {
+    self
+      .tx
+      .send(Msg::Records {
+        game_id,
+        initial_arrival_time,
+        min_seq_id,
+        records,
+      })
+      .ok();
+  }
+
+  pub fn set_sequence_number(&self, shard_id: String, sequence_number: String) {
+    self
+      .tx
+      .send(Msg::SequenceNumber {
+        shard_id,
+        sequence_number,
+      })
+      .ok();
+  }
+
+  pub fn remove_game(&self, game_id: i32) {
+    self.tx.send(Msg::RemoveGame(game_id)).ok();
+  }
+}
+
+#[derive(Debug)]
+enum Msg {
+  Records {
+    game_id: i32,
+    initial_arrival_time: f64,
+    min_seq_id: u32,
+    records: Vec<GameRecordData>,
+  },
+  SequenceNumber {
+    shard_id: String,
+    sequence_number: String,
+  },
+  RemoveGame(i32),
+}
+
+/// Folder of the checkpoints of an instance, instances sharing the data folder
+/// must have different ids. Everything in it is owned by the checkpointer.
+pub fn checkpoint_dir(instance_id: &str) -> PathBuf {
+  GameDataWriter::data_folder()
+    .join(CHECKPOINT_FOLDER)
+    .join(instance_id)
+}
+
+async fn restore_game(dir: &Path, game_id: i32) -> Result<(GameDataWriter, Vec<GameRecordData>)> {
+  let mut writer = GameDataWriter::recover_in(dir, game_id).await?;
+  let mut buf = writer.build_initial_part().await?;
+  let mut records = vec![];
+  while buf.has_remaining() {
+    records.push(GameRecordData::decode(&mut buf)?);
+  }
+  Ok((writer, records))
+}
+
+async fn remove_untracked_game_dirs(
+  dir: &Path,
+  games: &BTreeMap<i32, CheckpointGame>,
+) -> Result<()> {
+  let mut stream = fs::read_dir(dir).await?;
+  while let Some(entry) = stream.next_entry().await? {
+    if !entry.file_type().await?.is_dir() {
+      continue;
+    }
+    let game_id = match entry
+      .file_name()
+      .to_str()
+      .and_then(|v| v.parse::<i32>().ok())
+    {
+      Some(v) => v,
+      None => continue,
+    };
+    if !games.contains_key(&game_id) {
+      remove_game_dir(dir, game_id).await;
+    }
+  }
+  Ok(())
+}
+
+async fn remove_game_dir(dir: &Path, game_id: i32) {
+  if let Err(err) = fs::remove_dir_all(dir.join(game_id.to_string())).await {
+    if err.kind() != std::io::ErrorKind::NotFound {
+      tracing::warn!(game_id, "remove game data: {}", err);
+    }
+  }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_checkpoint_records() {
+  let dir = checkpoint_dir("test_checkpoint_records");
+  let game_id = i32::MAX;
+  fs::remove_dir_all(&dir).await.ok();
+  fs::create_dir_all(&dir).await.unwrap();
+
+  let (_tx, rx) = mpsc::unbounded_channel();
+  let mut checkpointer = Checkpointer {
+    dir: dir.clone(),
+    interval: Duration::from_secs(1),
+    rx,
+    shards: BTreeMap::new(),
+    games: BTreeMap::new(),
+    removed: BTreeSet::new(),
+    dirty: false,
+  };
+
+  let records = |range: std::ops::Range<i32>| range.map(GameRecordData::StopLag).collect();
+  checkpointer
+    .write_records(game_id, 1., 0, records(0..10))
+    .await
+    .unwrap();
+  // replayed after resuming from the saved sequence number
+  checkpointer
+    .write_records(game_id, 1., 5, records(5..20))
+    .await
+    .unwrap();
+  checkpointer.save().await.unwrap();
+
+  let (writer, restored) = restore_game(&dir, game_id).await.unwrap();
+  assert_eq!(writer.next_record_id(), 20);
+  assert_eq!(restored.len(), 20);
+  for (i, record) in restored.into_iter().enumerate() {
+    assert!(matches!(record, GameRecordData::StopLag(v) if v == i as i32));
+  }
+
+  // another instance only removes the game folders in its own folder
+  let other_dir = checkpoint_dir("test_checkpoint_records_other");
+  fs::remove_dir_all(&other_dir).await.ok();
+  let (_, _, restored) = Checkpointer::load(
+    other_dir.clone(),
+    Duration::from_secs(1),
+    Duration::from_secs(60),
+  )
+  .await
+  .unwrap();
+  assert!(restored.games.is_empty());
+  assert!(fs::metadata(dir.join(game_id.to_string())).await.is_ok());
+
+  let (_, _, restored) =
+    Checkpointer::load(dir.clone(), Duration::from_secs(1), Duration::from_secs(60))
+      .await
+      .unwrap();
+  assert_eq!(restored.games.len(), 1);
+  assert_eq!(restored.games[0].next_record_id, 20);
+
+  fs::remove_dir_all(&dir).await.ok();
+  fs::remove_dir_all(&other_dir).await.ok();
+}
@@ -0,0 +1,157 @@
+use super::{ClusterBackend, ClusterState, InstanceInfo, ShardOwner};
+use crate::error::Result;
+use crate::index::GameHistory;
+use flo_state::async_trait;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::fs;
+
+const STATE_FILENAME: &str = "cluster.json";
+const LOCK_FILENAME: &str = "cluster.lock";
+const HISTORY_FOLDER: &str = "history";
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+// a lock older than this was left by a crashed instance
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(10);
+
+/// Stores the cluster state in a directory shared by all instances.
+///
+/// Updates are serialized with a lock file created exclusively.
+/// Finished games are stored as one file per game, outside of the locked state.
+#[derive(Debug)]
+pub struct FsClusterBackend {
+  dir: PathBuf,
+}
+
+impl FsClusterBackend {
+  pub async fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+    let dir = dir.as_ref().to_owned();
+    fs::create_dir_all(dir.join(HISTORY_FOLDER)).await?;
+    Ok(Self { dir })
+  }
+
+  fn history_path(&self, game_id: i32) -> PathBuf {
+    self
+      .dir
+      .join(HISTORY_FOLDER)
+      .join(format!("{}.json", game_id))
+  }
+
+  async fn update<F, T>(&self, f: F) -> Result<T>
+  where
+    F: FnOnce(&mut ClusterState) -> T,
+  {
+    let _lock = self.lock().await?;
+    let path = self.dir.join(STATE_FILENAME);
+    let mut state = match fs::read(&path).await {
+      Ok(bytes) => serde_json::from_slice(&bytes)?,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => ClusterState::default(),
+      Err(err) => return Err(err.into()),
+    };
+    let r = f(&mut state);
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, serde_json::to_vec(&state)?).await?;
+    fs::rename(temp_path, path).await?;
+    Ok(r)
+  }
+
+  async fn lock(&self) -> Result<LockGuard> {
+    let path = self.dir.join(LOCK_FILENAME);
+    loop {
+      match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .await
+      {
+        Ok(_) => return Ok(LockGuard { path }),
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+          let stale = fs::metadata(&path)
+            .await
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .map(|age| age > LOCK_STALE_AFTER)
+            .unwrap_or_default();
+          if stale {
+            tracing::warn!("removing stale cluster lock");
+            fs::remove_file(&path).await.ok();
+          } else {
+            tokio::time::sleep(LOCK_RETRY_INTERVAL).await;
+          }
+        }
+        Err(err) => return Err(err.into()),
+      }
+    }
+  }
+}
+
+struct LockGuard {
+  path: PathBuf,
+}
+
+impl Drop for LockGuard {
+  fn drop(&mut self) {
+    std::fs::remove_file(&self.path).ok();
+  }
+}
+
+#[async_trait]
+impl ClusterBackend for FsClusterBackend {
+  async fn put_instance(&self, info: InstanceInfo) -> Result<()> {
+    self.update(|state| state.put_instance(info)).await
+  }
+
+  async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+    self.update(|state| state.list_instances()).await
+  }
+
+  async fn acquire_shard(&self, shard_id: &str, instance_id: &str) -> Result<ShardOwner> {
+    self
+      .update(|state| state.acquire_shard(shard_id, instance_id))
+      .await
+  }
+
+  async fn put_history(&self, history: &GameHistory) -> Result<()> {
+    let path = self.history_path(history.entry.game.id);
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, serde_json::to_vec(history)?).await?;
+    fs::rename(temp_path, path).await?;
+    Ok(())
+  }
+
+  async fn list_history_ids(&self) -> Result<Vec<i32>> {
+    let mut ids = vec![];
+    let mut stream = fs::read_dir(self.dir.join(HISTORY_FOLDER)).await?;
+    while let Some(entry) = stream.next_entry().await? {
+      let path = entry.path();
+      if path.extension().and_then(|v| v.to_str()) != Some("json") {
+        continue;
+      }
+      if let Some(game_id) = path
+        .file_stem()
+        .and_then(|v| v.to_str())
+        .and_then(|v| v.parse().ok())
+      {
+        ids.push(game_id);
+      }
+    }
+    Ok(ids)
+  }
+
+  async fn get_history(&self, game_id: i32) -> Result<Option<GameHistory>> {
+    match fs::read(self.history_path(game_id)).await {
+      Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  async fn revoke_token(&self, jti: &str, exp: i64) -> Result<()> {
+    self.update(|state| state.revoke_token(jti, exp)).await
+  }
+
+  async fn list_revoked_tokens(&self) -> Result<BTreeMap<String, i64>> {
+    self.update(|state| state.list_revoked_tokens()).await
+  }
+}
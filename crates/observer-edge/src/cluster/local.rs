@@ -0,0 +1,67 @@
+use super::{ClusterBackend, ClusterState, InstanceInfo, ShardOwner};
+use crate::error::Result;
+use crate::index::GameHistory;
+use flo_state::async_trait;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// In-process backend, for instances running in the same process.
+#[derive(Debug, Default)]
+pub struct LocalClusterBackend {
+  state: Mutex<ClusterState>,
+  history: Mutex<BTreeMap<i32, GameHistory>>,
+}
+
+impl LocalClusterBackend {
+  pub fn new() -> Arc<Self> {
+    Arc::new(Self::default())
+  }
+}
+
+#[async_trait]
+impl ClusterBackend for LocalClusterBackend {
+  async fn put_instance(&self, info: InstanceInfo) -> Result<()> {
+    self.state.lock().unwrap().put_instance(info);
+    Ok(())
+  }
+
+  async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+    Ok(self.state.lock().unwrap().list_instances())
+  }
+
+  async fn acquire_shard(&self, shard_id: &str, instance_id: &str) -> Result<ShardOwner> {
+    Ok(
+      self
+        .state
+        .lock()
+        .unwrap()
+        .acquire_shard(shard_id, instance_id),
+    )
+  }
+
+  async fn put_history(&self, history: &GameHistory) -> Result<()> {
+    self
+      .history
+      .lock()
+      .unwrap()
+      .insert(history.entry.game.id, history.clone());
+    Ok(())
+  }
+
+  async fn list_history_ids(&self) -> Result<Vec<i32>> {
+    Ok(self.history.lock().unwrap().keys().cloned().collect())
+  }
+
+  async fn get_history(&self, game_id: i32) -> Result<Option<GameHistory>> {
+    Ok(self.history.lock().unwrap().get(&game_id).cloned())
+  }
+
+  async fn revoke_token(&self, jti: &str, exp: i64) -> Result<()> {
+    self.state.lock().unwrap().revoke_token(jti, exp);
+    Ok(())
+  }
+
+  async fn list_revoked_tokens(&self) -> Result<BTreeMap<String, i64>> {
+    Ok(self.state.lock().unwrap().list_revoked_tokens())
+  }
+}
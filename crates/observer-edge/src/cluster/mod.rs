@@ -0,0 +1,475 @@
+//! Coordinates multiple edge instances consuming the same data stream.
+//!
+//! Records are partitioned by game id, so every game lives on a single shard.
+//! Each instance owns a subset of the shards and publishes the games it holds.
+//! Finished games are stored in the backend and indexed by every instance.
+//! Revoked observer tokens are shared the same way.
+//! Shards owned by an expired instance are taken over by the others.
+//!
+//! Shards are not released by a live instance, an instance joining later
+//! only picks up shards left by lost instances.
+//! Instances publish the last sequence number they handled for each of their shards,
+//! a taken over shard resumes after it.
+
+mod fs;
+mod local;
+
+pub use self::fs::FsClusterBackend;
+pub use self::local::LocalClusterBackend;
+
+use crate::dispatcher::{
+  AddIterator, Dispatcher, IndexGames, ListGameIds, ListGames, ListIndexedGameIds,
+  ListShardSequenceNumbers,
+};
+use crate::error::Result;
+use crate::game::event::GameListUpdateEvent;
+use crate::game::snapshot::GameSnapshot;
+use crate::index::{self, GameHistory};
+use crate::token::TokenRegistry;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use flo_kinesis::data_stream::DataStream;
+use flo_kinesis::iterator::ShardIteratorType;
+use flo_state::{async_trait, Addr};
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const INSTANCE_TTL: Duration = Duration::from_secs(30);
+
+#[async_trait]
+pub trait ClusterBackend: Send + Sync + 'static {
+  /// Publishes the state of an instance.
+  /// The instance is considered lost after `expires_at`.
+  async fn put_instance(&self, info: InstanceInfo) -> Result<()>;
+  /// Lists instances that haven't expired.
+  async fn list_instances(&self) -> Result<Vec<InstanceInfo>>;
+  /// Takes the shard if it has no owner or its owner expired.
+  async fn acquire_shard(&self, shard_id: &str, instance_id: &str) -> Result<ShardOwner>;
+  /// Stores a finished game.
+  async fn put_history(&self, history: &GameHistory) -> Result<()>;
+  /// Lists the ids of the stored finished games.
+  async fn list_history_ids(&self) -> Result<Vec<i32>>;
+  async fn get_history(&self, game_id: i32) -> Result<Option<GameHistory>>;
+  /// Stores a revoked token id until `exp`.
+  async fn revoke_token(&self, jti: &str, exp: i64) -> Result<()>;
+  /// Lists the revoked token ids that haven't expired, with their expiration timestamps.
+  async fn list_revoked_tokens(&self) -> Result<BTreeMap<String, i64>>;
+}
+
+/// Owner of a shard after `acquire_shard`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardOwner {
+  pub instance_id: String,
+  /// Last sequence number published for the shard by its previous owners.
+  pub sequence_number: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+pub struct InstanceInfo {
+  pub id: String,
+  pub graphql_url: Option<String>,
+  pub stream_host: Option<String>,
+  pub shards: Vec<String>,
+  pub games: Vec<i32>,
+  #[graphql(skip)]
+  #[serde(default)]
+  pub snapshots: Vec<GameSnapshot>,
+  /// shard id -> last handled sequence number
+  #[graphql(skip)]
+  #[serde(default)]
+  pub sequence_numbers: BTreeMap<String, String>,
+  pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct ClusterConfig {
+  pub backend: Arc<dyn ClusterBackend>,
+  pub instance_id: String,
+  /// Url of the GraphQL service embedding this instance.
+  pub graphql_url: Option<String>,
+  /// Host clients use to connect to the stream server of this instance.
+  pub stream_host: Option<String>,
+}
+
+impl ClusterConfig {
+  /// Returns the instance holding the game, if it's not this instance.
+  pub async fn find_remote_instance(&self, game_id: i32) -> Result<Option<InstanceInfo>> {
+    Ok(
+      self
+        .backend
+        .list_instances()
+        .await?
+        .into_iter()
+        .find(|instance| instance.id != self.instance_id && instance.games.contains(&game_id)),
+    )
+  }
+
+  /// Lists the games held by the other instances, as of their last heartbeat.
+  pub async fn list_remote_games(&self) -> Result<Vec<GameSnapshot>> {
+    Ok(
+      self
+        .backend
+        .list_instances()
+        .await?
+        .into_iter()
+        .filter(|instance| instance.id != self.instance_id)
+        .flat_map(|instance| instance.snapshots)
+        .collect(),
+    )
+  }
+
+  /// Emits list update events for the games held by the other instances,
+  /// by comparing their snapshots with `games` on every heartbeat.
+  pub fn watch_remote_games(
+    &self,
+    games: BTreeMap<i32, GameSnapshot>,
+  ) -> impl Stream<Item = GameListUpdateEvent> {
+    let interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    stream::unfold(
+      (self.clone(), games, interval),
+      |(config, mut games, mut interval)| async move {
+        interval.tick().await;
+        let events = match config.list_remote_games().await {
+          Ok(snapshots) => diff_games(&mut games, snapshots),
+          Err(err) => {
+            tracing::error!("list remote games: {}", err);
+            vec![]
+          }
+        };
+        Some((stream::iter(events), (config, games, interval)))
+      },
+    )
+    .flatten()
+  }
+}
+
+fn diff_games(
+  games: &mut BTreeMap<i32, GameSnapshot>,
+  snapshots: Vec<GameSnapshot>,
+) -> Vec<GameListUpdateEvent> {
+  let mut events = vec![];
+  let mut next = BTreeMap::new();
+  for snapshot in snapshots {
+    if next.contains_key(&snapshot.id) {
+      continue;
+    }
+    match games.remove(&snapshot.id) {
+      Some(prev) => {
+        if let (None, Some(ended_at)) = (prev.ended_at, snapshot.ended_at) {
+          events.push(GameListUpdateEvent::ended(snapshot.id, ended_at));
+        }
+      }
+      None => events.push(GameListUpdateEvent::add(snapshot.clone())),
+    }
+    next.insert(snapshot.id, snapshot);
+  }
+  events.extend(
+    games
+      .keys()
+      .map(|game_id| GameListUpdateEvent::removed(*game_id)),
+  );
+  *games = next;
+  events
+}
+
+/// State shared by the backends.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ClusterState {
+  instances: BTreeMap<String, InstanceInfo>,
+  // shard id -> instance id
+  shard_owners: BTreeMap<String, String>,
+  // shard id -> sequence number, kept after the owner expired
+  #[serde(default)]
+  shard_sequence_numbers: BTreeMap<String, String>,
+  // jti -> exp
+  #[serde(default)]
+  revoked_tokens: BTreeMap<String, i64>,
+}
+
+impl ClusterState {
+  fn put_instance(&mut self, info: InstanceInfo) {
+    self.remove_expired();
+    for (shard_id, sequence_number) in &info.sequence_numbers {
+      if self.shard_owners.get(shard_id) == Some(&info.id) {
+        self
+          .shard_sequence_numbers
+          .insert(shard_id.clone(), sequence_number.clone());
+      }
+    }
+    self.instances.insert(info.id.clone(), info);
+  }
+
+  fn list_instances(&mut self) -> Vec<InstanceInfo> {
+    self.remove_expired();
+    self.instances.values().cloned().collect()
+  }
+
+  fn acquire_shard(&mut self, shard_id: &str, instance_id: &str) -> ShardOwner {
+    self.remove_expired();
+    let instance_id = match self.shard_owners.get(shard_id) {
+      Some(owner) if self.instances.contains_key(owner) => owner.clone(),
+      _ => {
+        self
+          .shard_owners
+          .insert(shard_id.to_string(), instance_id.to_string());
+        instance_id.to_string()
+      }
+    };
+    ShardOwner {
+      instance_id,
+      sequence_number: self.shard_sequence_numbers.get(shard_id).cloned(),
+    }
+  }
+
+  fn revoke_token(&mut self, jti: &str, exp: i64) {
+    self.remove_expired();
+    self.revoked_tokens.insert(jti.to_string(), exp);
+  }
+
+  fn list_revoked_tokens(&mut self) -> BTreeMap<String, i64> {
+    self.remove_expired();
+    self.revoked_tokens.clone()
+  }
+
+  fn remove_expired(&mut self) {
+    let now = Utc::now();
+    self.instances.retain(|_, info| info.expires_at > now);
+    let now = now.timestamp();
+    self.revoked_tokens.retain(|_, exp| *exp > now);
+  }
+}
+
+/// Publishes the state of this instance and acquires orphaned shards.
+pub(crate) struct ClusterMember {
+  config: ClusterConfig,
+  dispatcher: Addr<Dispatcher>,
+  tokens: Arc<TokenRegistry>,
+  data_stream: DataStream,
+  shards: BTreeSet<String>,
+  resume: BTreeMap<String, String>,
+  iter_type: ShardIteratorType,
+}
+
+impl ClusterMember {
+  /// `resume` holds the sequence numbers saved by the checkpoint of this instance,
+  /// they take precedence over the ones published to the cluster
+  /// because they match the restored games.
+  pub fn new(
+    config: ClusterConfig,
+    dispatcher: Addr<Dispatcher>,
+    tokens: Arc<TokenRegistry>,
+    data_stream: DataStream,
+    resume: BTreeMap<String, String>,
+    iter_type: ShardIteratorType,
+  ) -> Self {
+    Self {
+      config,
+      dispatcher,
+      tokens,
+      data_stream,
+      shards: BTreeSet::new(),
+      resume,
+      iter_type,
+    }
+  }
+
+  pub async fn serve(mut self) {
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    // register before acquiring shards,
+    // so instances started together can see each other
+    if let Err(err) = self.heartbeat().await {
+      tracing::error!("cluster heartbeat: {}", err);
+    }
+    interval.tick().await;
+
+    loop {
+      interval.tick().await;
+      if let Err(err) = self.heartbeat().await {
+        tracing::error!("cluster heartbeat: {}", err);
+        continue;
+      }
+      if let Err(err) = self.acquire_shards().await {
+        tracing::error!("cluster acquire shards: {}", err);
+      }
+      if let Err(err) = self.sync_history().await {
+        tracing::error!("cluster sync history: {}", err);
+      }
+      match self.config.backend.list_revoked_tokens().await {
+        Ok(revoked) => self.tokens.add_revoked(revoked),
+        Err(err) => tracing::error!("cluster list revoked tokens: {}", err),
+      }
+    }
+  }
+
+  async fn heartbeat(&mut self) -> Result<()> {
+    let games = self.dispatcher.send(ListGameIds).await?;
+    let snapshots = self.dispatcher.send(ListGames).await?;
+    let sequence_numbers = self.dispatcher.send(ListShardSequenceNumbers).await?;
+    self
+      .config
+      .backend
+      .put_instance(InstanceInfo {
+        id: self.config.instance_id.clone(),
+        graphql_url: self.config.graphql_url.clone(),
+        stream_host: self.config.stream_host.clone(),
+        shards: self.shards.iter().cloned().collect(),
+        games,
+        snapshots,
+        sequence_numbers,
+        expires_at: Utc::now() + chrono::Duration::from_std(INSTANCE_TTL).unwrap(),
+      })
+      .await
+  }
+
+  async fn acquire_shards(&mut self) -> Result<()> {
+    let instances = self.config.backend.list_instances().await?;
+    let shard_ids = self.data_stream.list_shard_ids().await?;
+    let quota = (shard_ids.len() + instances.len().max(1) - 1) / instances.len().max(1);
+
+    let mut acquired = vec![];
+    for shard_id in shard_ids {
+      if self.shards.len() + acquired.len() >= quota {
+        break;
+      }
+      if self.shards.contains(&shard_id) {
+        continue;
+      }
+      let owner = self
+        .config
+        .backend
+        .acquire_shard(&shard_id, &self.config.instance_id)
+        .await?;
+      if owner.instance_id == self.config.instance_id {
+        if let Some(sequence_number) = owner.sequence_number {
+          self
+            .resume
+            .entry(shard_id.clone())
+            .or_insert(sequence_number);
+        }
+        acquired.push(shard_id);
+      }
+    }
+
+    if acquired.is_empty() {
+      return Ok(());
+    }
+
+    tracing::info!("shards acquired: {:?}", acquired);
+
+    let resume = &self.resume;
+    let iter_type = &self.iter_type;
+    let iter =
+      self
+        .data_stream
+        .iter_shards(acquired.clone(), |shard_id| match resume.get(shard_id) {
+          Some(sequence_number) => ShardIteratorType::AfterSequenceNumber(sequence_number.clone()),
+          None => iter_type.clone(),
+        });
+    self.dispatcher.send(AddIterator(iter)).await?;
+    self.shards.extend(acquired);
+    Ok(())
+  }
+
+  // Stores the games finished by this instance and indexes the ones finished by the others
+  async fn sync_history(&mut self) -> Result<()> {
+    let local: BTreeSet<i32> = self
+      .dispatcher
+      .send(ListIndexedGameIds)
+      .await?
+      .into_iter()
+      .collect();
+    let stored: BTreeSet<i32> = self
+      .config
+      .backend
+      .list_history_ids()
+      .await?
+      .into_iter()
+      .collect();
+
+    for game_id in local.difference(&stored) {
+      // the index file is written in the background, retried on the next heartbeat if missing
+      if let Some(history) = index::read_history(*game_id).await? {
+        self.config.backend.put_history(&history).await?;
+      }
+    }
+
+    let mut games = vec![];
+    for game_id in stored.difference(&local) {
+      if let Some(history) = self.config.backend.get_history(*game_id).await? {
+        games.push(history);
+      }
+    }
+    if !games.is_empty() {
+      self.dispatcher.send(IndexGames(games)).await?;
+    }
+    Ok(())
+  }
+}
+
+#[tokio::test]
+async fn test_cluster_state() {
+  let backend = LocalClusterBackend::new();
+  let instance = |id: &str, ttl_secs: i64| InstanceInfo {
+    id: id.to_string(),
+    graphql_url: None,
+    stream_host: None,
+    shards: vec![],
+    games: vec![1],
+    snapshots: vec![],
+    sequence_numbers: BTreeMap::new(),
+    expires_at: Utc::now() + chrono::Duration::seconds(ttl_secs),
+  };
+
+  backend.put_instance(instance("a", 30)).await.unwrap();
+  backend.put_instance(instance("b", 30)).await.unwrap();
+  assert_eq!(
+    backend.acquire_shard("0", "a").await.unwrap().instance_id,
+    "a"
+  );
+  assert_eq!(
+    backend.acquire_shard("0", "b").await.unwrap().instance_id,
+    "a"
+  );
+
+  // b doesn't own shard 0
+  let mut b = instance("b", 30);
+  b.sequence_numbers.insert("0".to_string(), "1".to_string());
+  backend.put_instance(b).await.unwrap();
+  let mut a = instance("a", 30);
+  a.sequence_numbers.insert("0".to_string(), "2".to_string());
+  backend.put_instance(a).await.unwrap();
+
+  let config = ClusterConfig {
+    backend: backend.clone(),
+    instance_id: "b".to_string(),
+    graphql_url: None,
+    stream_host: None,
+  };
+  assert_eq!(
+    config.find_remote_instance(1).await.unwrap().unwrap().id,
+    "a"
+  );
+
+  // a is lost
+  backend.put_instance(instance("a", -1)).await.unwrap();
+  assert_eq!(backend.list_instances().await.unwrap().len(), 1);
+  assert_eq!(
+    backend.acquire_shard("0", "b").await.unwrap(),
+    ShardOwner {
+      instance_id: "b".to_string(),
+      sequence_number: Some("2".to_string()),
+    }
+  );
+  assert!(config.find_remote_instance(1).await.unwrap().is_none());
+
+  backend.revoke_token("1", i64::MAX).await.unwrap();
+  backend.revoke_token("2", 0).await.unwrap();
+  let revoked = backend.list_revoked_tokens().await.unwrap();
+  assert_eq!(revoked.keys().collect::<Vec<_>>(), vec!["1"]);
+}
@@ -26,12 +26,19 @@ impl Controller {
   }
 
   pub async fn fetch_game(&self, game_id: i32) -> Result<Game> {
+    Ok(Game::unpack(self.fetch_game_proto(game_id).await?)?)
+  }
+
+  pub async fn fetch_game_proto(&self, game_id: i32) -> Result<flo_grpc::game::Game> {
     use flo_grpc::controller::GetGameRequest;
     let res = self.client.clone().get_game(GetGameRequest {
       game_id
     }).await;
     match res {
-      Ok(res) => Ok(Game::unpack(res.into_inner().game)?),
+      Ok(res) => res
+        .into_inner()
+        .game
+        .ok_or_else(|| Error::GameNotFound(game_id)),
       Err(status) => {
         if status.code() == tonic::Code::InvalidArgument {
           Err(Error::InvalidGameId(game_id))
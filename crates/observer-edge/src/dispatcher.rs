@@ -1,10 +1,12 @@
 use crate::broadcast::BroadcastReceiver;
+use crate::checkpoint::RestoredGame;
 use crate::constants::FLO_STATS_MAX_IN_MEMORY_GAMES;
 use crate::error::{Error, Result};
 use crate::game::event::{GameListUpdateEvent, GameUpdateEvent};
 use crate::game::snapshot::{GameSnapshot, GameSnapshotMap, GameSnapshotWithStats};
 use crate::game::stream::GameStreamMap;
 use crate::game::{Game, GameHandler, GameMeta};
+use crate::index::{GameHistory, GameHistoryPage, GameHistoryQuery, GameIndex};
 use crate::server::peer::GameStreamServer;
 use crate::services::Services;
 use backoff::backoff::Backoff;
@@ -15,6 +17,7 @@ use flo_net::observer::GameInfo;
 use flo_observer::record::GameRecordData;
 use flo_state::{async_trait, Actor, Addr, Context, Handler, Message};
 use lru::LruCache;
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tokio_stream::StreamExt;
 
@@ -24,16 +27,21 @@ pub struct Dispatcher {
   inactive_cache: LruCache<i32, ()>,
   snapshots: GameSnapshotMap,
   streams: GameStreamMap,
+  index: GameIndex,
+  // shard id -> last handled sequence number
+  sequence_numbers: BTreeMap<String, String>,
 }
 
 impl Dispatcher {
-  pub fn new(services: Services) -> Self {
+  pub fn new(services: Services, index: GameIndex) -> Self {
     Self {
       services,
       slots: LruCache::new(*FLO_STATS_MAX_IN_MEMORY_GAMES),
       inactive_cache: LruCache::new(*FLO_STATS_MAX_IN_MEMORY_GAMES),
       snapshots: GameSnapshotMap::new(),
       streams: GameStreamMap::new(),
+      index,
+      sequence_numbers: BTreeMap::new(),
     }
   }
 
@@ -46,7 +54,11 @@ impl Dispatcher {
   }
 
   fn handle_chunk(&mut self, ctx: &mut Context<Self>, chunk: Chunk) {
-    for (game_id, game_chunk) in chunk.game_records {
+    for (game_id, mut game_chunk) in chunk.game_records {
+      if let Some(handler) = self.slots.peek(&game_id) {
+        handler.skip_replayed_records(&mut game_chunk);
+      }
+
       self.streams.dispatch_game_records(game_id, &game_chunk);
 
       if self.inactive_cache.get(&game_id).is_some() {
@@ -62,12 +74,22 @@ impl Dispatcher {
           } else {
             false
           };
+          let checkpoint_records = self
+            .services
+            .checkpoint
+            .as_ref()
+            .map(|_| (game_chunk.min_seq_id, game_chunk.records.clone()));
           if let Err(err) = handler.handle_chunk(game_chunk, &mut self.snapshots) {
             should_remove = true;
             tracing::error!(game_id, "handle records: {}", err);
           } else {
+            if let (Some(checkpoint), Some((min_seq_id, records))) =
+              (self.services.checkpoint.as_ref(), checkpoint_records)
+            {
+              checkpoint.add_records(game_id, handler.initial_arrival_time(), min_seq_id, records);
+            }
             if is_last_chunk {
-              Self::upload_archive(self.services.clone(), handler);
+              Self::finish_game(self.services.clone(), &mut self.index, handler);
             }
           }
         }
@@ -91,7 +113,8 @@ impl Dispatcher {
               if let Some((game_id, mut removed)) = self.slots.pop_lru() {
                 tracing::info!(game_id, "expired");
                 self.snapshots.remove_game(game_id);
-                Self::upload_archive(self.services.clone(), &mut removed);
+                self.services.chat_filter.remove_game(game_id);
+                Self::finish_game(self.services.clone(), &mut self.index, &mut removed);
               }
             }
             self.inactive_cache.put(game_id, ());
@@ -103,14 +126,25 @@ impl Dispatcher {
             game_chunk.approximate_arrival_timestamp,
           );
 
+          let checkpoint_records = self
+            .services
+            .checkpoint
+            .as_ref()
+            .map(|_| game_chunk.records.clone());
           if let Err(err) = handler.handle_chunk(game_chunk, &mut self.snapshots) {
             tracing::error!(game_id, "handle initial records: {}", err);
           } else {
+            if let (Some(checkpoint), Some(records)) =
+              (self.services.checkpoint.as_ref(), checkpoint_records)
+            {
+              checkpoint.add_records(game_id, handler.initial_arrival_time(), 0, records);
+            }
             if self.slots.len() == self.slots.cap() {
               if let Some((game_id, mut removed)) = self.slots.pop_lru() {
                 tracing::info!(game_id, "expired");
                 self.snapshots.remove_game(game_id);
-                Self::upload_archive(self.services.clone(), &mut removed);
+                self.services.chat_filter.remove_game(game_id);
+                Self::finish_game(self.services.clone(), &mut self.index, &mut removed);
               }
             }
             self.slots.put(game_id, handler);
@@ -120,9 +154,44 @@ impl Dispatcher {
       }
       if should_remove {
         if let Some(mut removed) = self.slots.pop(&game_id) {
-          Self::upload_archive(self.services.clone(), &mut removed);
+          Self::finish_game(self.services.clone(), &mut self.index, &mut removed);
         }
         self.snapshots.remove_game(game_id);
+        self.services.chat_filter.remove_game(game_id);
+      }
+    }
+
+    self
+      .sequence_numbers
+      .insert(chunk.shard_id.clone(), chunk.max_sequence_number.clone());
+
+    if let Some(checkpoint) = self.services.checkpoint.as_ref() {
+      checkpoint.set_sequence_number(chunk.shard_id, chunk.max_sequence_number);
+    }
+  }
+
+  fn restore_games(&mut self, ctx: &mut Context<Self>, games: Vec<RestoredGame>) {
+    for game in games {
+      let game_id = game.game_id;
+      match GameHandler::restore(
+        self.services.clone(),
+        game_id,
+        game.initial_arrival_time,
+        game.next_record_id,
+        game.records,
+        &mut self.snapshots,
+      ) {
+        Ok(handler) => {
+          tracing::info!(game_id, "restored");
+          self.slots.put(game_id, handler);
+          ctx.spawn(Self::fetch_game(self.services.clone(), ctx.addr(), game_id));
+        }
+        Err(err) => {
+          tracing::error!(game_id, "restore: {}", err);
+          if let Some(checkpoint) = self.services.checkpoint.as_ref() {
+            checkpoint.remove_game(game_id);
+          }
+        }
       }
     }
   }
@@ -170,29 +239,44 @@ impl Dispatcher {
     }
   }
 
-  fn upload_archive(services: Services, handler: &mut GameHandler) {
+  // Game ended or removed from memory, it won't be restored from the checkpoint
+  fn finish_game(services: Services, index: &mut GameIndex, handler: &mut GameHandler) {
+    let game_id = handler.id();
+    if let Some(checkpoint) = services.checkpoint.as_ref() {
+      checkpoint.remove_game(game_id);
+    }
+    let archived = Self::upload_archive(services, handler);
+    if !index.contains(game_id) {
+      match handler.make_history(archived) {
+        Ok(history) => index.insert(history),
+        Err(err) => tracing::warn!(game_id, "game not indexed: {}", err),
+      }
+    }
+  }
+
+  fn upload_archive(services: Services, handler: &mut GameHandler) -> bool {
     let archiver = if let Some(handle) = services.archiver.clone() {
       handle
     } else {
-      return
+      return false;
     };
     let game_id = handler.id();
     match handler.make_archive() {
       Ok(Some(archive)) => {
         if !archiver.add_archive(archive) {
           tracing::warn!(game_id, "archive upload cancelled");
+          false
+        } else {
+          true
         }
-      },
-      Ok(None) => {},
+      }
+      Ok(None) => false,
       Err(e) => {
-        tracing::error!(
-          game_id = handler.id(),
-          "archive: {}",
-          e
-        );
+        tracing::error!(game_id = handler.id(), "archive: {}", e);
+        false
       }
     }
-  } 
+  }
 }
 
 #[async_trait]
@@ -225,6 +309,19 @@ impl Handler<AddIterator> for Dispatcher {
   }
 }
 
+pub struct RestoreGames(pub Vec<RestoredGame>);
+
+impl Message for RestoreGames {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<RestoreGames> for Dispatcher {
+  async fn handle(&mut self, ctx: &mut Context<Self>, RestoreGames(games): RestoreGames) {
+    self.restore_games(ctx, games);
+  }
+}
+
 pub struct ListGames;
 
 impl Message for ListGames {
@@ -238,6 +335,86 @@ impl Handler<ListGames> for Dispatcher {
   }
 }
 
+/// Ids of the games held in memory.
+pub struct ListGameIds;
+
+impl Message for ListGameIds {
+  type Result = Vec<i32>;
+}
+
+#[async_trait]
+impl Handler<ListGameIds> for Dispatcher {
+  async fn handle(&mut self, _: &mut Context<Self>, _: ListGameIds) -> Vec<i32> {
+    self.slots.iter().map(|(game_id, _)| *game_id).collect()
+  }
+}
+
+pub struct ListShardSequenceNumbers;
+
+impl Message for ListShardSequenceNumbers {
+  type Result = BTreeMap<String, String>;
+}
+
+#[async_trait]
+impl Handler<ListShardSequenceNumbers> for Dispatcher {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    _: ListShardSequenceNumbers,
+  ) -> BTreeMap<String, String> {
+    self.sequence_numbers.clone()
+  }
+}
+
+/// Ids of the finished games in the index.
+pub struct ListIndexedGameIds;
+
+impl Message for ListIndexedGameIds {
+  type Result = Vec<i32>;
+}
+
+#[async_trait]
+impl Handler<ListIndexedGameIds> for Dispatcher {
+  async fn handle(&mut self, _: &mut Context<Self>, _: ListIndexedGameIds) -> Vec<i32> {
+    self.index.ids()
+  }
+}
+
+/// Adds games finished by other cluster instances to the index.
+pub struct IndexGames(pub Vec<GameHistory>);
+
+impl Message for IndexGames {
+  type Result = ();
+}
+
+#[async_trait]
+impl Handler<IndexGames> for Dispatcher {
+  async fn handle(&mut self, _: &mut Context<Self>, IndexGames(games): IndexGames) {
+    for history in games {
+      if !self.index.contains(history.entry.game.id) {
+        self.index.insert(history);
+      }
+    }
+  }
+}
+
+pub struct QueryGameHistory(pub GameHistoryQuery);
+
+impl Message for QueryGameHistory {
+  type Result = GameHistoryPage;
+}
+
+#[async_trait]
+impl Handler<QueryGameHistory> for Dispatcher {
+  async fn handle(
+    &mut self,
+    _: &mut Context<Self>,
+    QueryGameHistory(query): QueryGameHistory,
+  ) -> GameHistoryPage {
+    self.index.query(&query)
+  }
+}
+
 pub struct GetGame {
   pub game_id: i32,
 }
@@ -422,7 +599,8 @@ async fn test_dispatcher() -> anyhow::Result<()> {
   flo_log_subscriber::init();
 
   let services = Services::from_env();
-  let d = Dispatcher::new(services).start();
+  let index = GameIndex::load().await?;
+  let d = Dispatcher::new(services, index).start();
   let ds = DataStream::from_env();
   let it = ShardIteratorType::at_timestamp_backward(Duration::from_secs(3600));
   d.send(AddIterator(ds.into_iter(it).await?)).await?;
@@ -8,12 +8,17 @@ pub struct Env {
   pub controller_secret: String,
   pub record_source: ObserverRecordSource,
   pub record_backscan_secs: u64,
+  pub checkpoint_interval_secs: u64,
   pub jwt_secret_base64: String,
   pub aws_s3_region: Option<String>,
   pub aws_s3_bucket: Option<String>,
   pub aws_access_key_id: Option<String>,
   pub aws_secret_access_key: Option<String>,
   pub admin_secret: Option<String>,
+  pub cluster_dir: Option<String>,
+  pub cluster_instance_id: Option<String>,
+  pub cluster_graphql_url: Option<String>,
+  pub cluster_stream_host: Option<String>,
 }
 
 pub static ENV: Lazy<Env> = Lazy::new(|| {
@@ -29,10 +34,18 @@ pub static ENV: Lazy<Env> = Lazy::new(|| {
       .ok()
       .and_then(|v| v.parse().ok())
       .unwrap_or(3600),
+    checkpoint_interval_secs: std::env::var("OBSERVER_CHECKPOINT_INTERVAL_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(5),
     aws_s3_region: env::var("AWS_S3_REGION").ok(),
     aws_s3_bucket: env::var("AWS_S3_BUCKET").ok(),
     aws_access_key_id: env::var("AWS_ACCESS_KEY_ID").ok(),
     aws_secret_access_key: env::var("AWS_SECRET_ACCESS_KEY").ok(),
     admin_secret: env::var("ADMIN_SECRET").ok(),
+    cluster_dir: env::var("OBSERVER_CLUSTER_DIR").ok(),
+    cluster_instance_id: env::var("OBSERVER_CLUSTER_INSTANCE_ID").ok(),
+    cluster_graphql_url: env::var("OBSERVER_CLUSTER_GRAPHQL_URL").ok(),
+    cluster_stream_host: env::var("OBSERVER_CLUSTER_STREAM_HOST").ok(),
   }
 });
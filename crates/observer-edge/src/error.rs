@@ -14,6 +14,12 @@ pub enum Error {
     range: [u32; 2],
     len: usize,
   },
+  #[error("game is private: {0}")]
+  PrivateGame(i32),
+  #[error("archiver disabled")]
+  ArchiverDisabled,
+  #[error("game archive not found: {0}")]
+  ArchiveNotFound(i32),
   #[error("game version unknown")]
   GameVersionUnknown,
   #[error("peer lagged: {0} events dropped")]
@@ -24,6 +30,12 @@ pub enum Error {
   Kinesis(#[from] flo_kinesis::error::Error),
   #[error("w3gs: {0}")]
   W3GS(#[from] flo_w3gs::error::Error),
+  #[error("observer fs: {0}")]
+  ObserverFs(#[from] flo_observer_fs::error::Error),
+  #[error("decode game record: {0}")]
+  DecodeGameRecord(#[from] flo_observer::record::RecordError),
+  #[error("json: {0}")]
+  Json(#[from] serde_json::Error),
   #[error("io: {0}")]
   Io(#[from] std::io::Error),
   #[error("actor: {0}")]
@@ -32,6 +44,8 @@ pub enum Error {
   Proto(#[from] s2_grpc_utils::result::Error),
   #[error("net: {0}")]
   Net(#[from] flo_net::error::Error),
+  #[error("replay: {0}")]
+  Replay(#[from] flo_replay::error::Error),
   #[error("observer archiver: {0}")]
   ObserverArchiver(#[from] flo_observer_archiver::error::Error),
 }
@@ -0,0 +1,271 @@
+use super::objects::{self, ObjectKind};
+use super::Game;
+use async_graphql::SimpleObject;
+use flo_w3gs::actions::Action as W3GSAction;
+use flo_w3gs::protocol::action::PlayerAction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const HOTKEY_GROUPS: usize = 10;
+
+/// Per-player counters derived from decoded actions.
+///
+/// Objects are counted when ordered, cancelled orders are not subtracted.
+#[derive(Debug)]
+pub struct ActionCounters {
+  player_slots: Vec<Option<PlayerCounters>>,
+}
+
+#[derive(Debug)]
+struct PlayerCounters {
+  player_id: i32,
+  objects: BTreeMap<String, u32>,
+  gold_sent: u32,
+  lumber_sent: u32,
+  gold_received: u32,
+  lumber_received: u32,
+  hotkey_assigned: [u32; HOTKEY_GROUPS],
+  hotkey_selected: [u32; HOTKEY_GROUPS],
+}
+
+impl ActionCounters {
+  pub fn new(game: &Game) -> Self {
+    let player_slots = game
+      .slots
+      .iter()
+      .map(|slot| {
+        let player = slot.player.as_ref()?;
+        if slot.settings.team == 24 {
+          return None;
+        }
+        Some(PlayerCounters {
+          player_id: player.id,
+          objects: BTreeMap::new(),
+          gold_sent: 0,
+          lumber_sent: 0,
+          gold_received: 0,
+          lumber_received: 0,
+          hotkey_assigned: [0; HOTKEY_GROUPS],
+          hotkey_selected: [0; HOTKEY_GROUPS],
+        })
+      })
+      .collect();
+    Self { player_slots }
+  }
+
+  pub fn put_actions(&mut self, actions: &[PlayerAction]) {
+    for action in actions {
+      let slot = action.player_id.saturating_sub(1) as usize;
+      if !matches!(self.player_slots.get(slot), Some(Some(_))) {
+        continue;
+      }
+      for item in action.actions() {
+        // the remaining bytes can't be decoded after an unknown action
+        let item = match item {
+          Ok(item) => item,
+          Err(_) => break,
+        };
+        self.put_action(slot, item);
+      }
+    }
+  }
+
+  fn put_action(&mut self, slot: usize, action: W3GSAction) {
+    let item_id = match action {
+      W3GSAction::UnitBuildingAbility(ref v) => Some(v.item_id),
+      W3GSAction::UnitBuildingAbilityTargeted(ref v) => Some(v.item_id),
+      W3GSAction::UnitBuildingAbilityTargetedId(ref v) => Some(v.item_id),
+      W3GSAction::AssignGroupHotkey(ref v) => {
+        if let Some(item) = self.player_mut(slot) {
+          if let Some(count) = item.hotkey_assigned.get_mut(v.group_number as usize) {
+            *count += 1;
+          }
+        }
+        None
+      }
+      W3GSAction::SelectGroupHotkey(ref v) => {
+        if let Some(item) = self.player_mut(slot) {
+          if let Some(count) = item.hotkey_selected.get_mut(v.group_number as usize) {
+            *count += 1;
+          }
+        }
+        None
+      }
+      W3GSAction::TransferResources(ref v) => {
+        if let Some(item) = self.player_mut(slot) {
+          item.gold_sent += v.gold_to_transfer;
+          item.lumber_sent += v.lumber_to_transfer;
+        }
+        if let Some(item) = self.player_mut(v.player_slot_number as usize) {
+          item.gold_received += v.gold_to_transfer;
+          item.lumber_received += v.lumber_to_transfer;
+        }
+        None
+      }
+      _ => None,
+    };
+
+    let id = match item_id.and_then(objects::object_id) {
+      Some(id) => id,
+      None => return,
+    };
+    if objects::lookup(&id).is_some() {
+      if let Some(item) = self.player_mut(slot) {
+        *item.objects.entry(id).or_default() += 1;
+      }
+    }
+  }
+
+  fn player_mut(&mut self, slot: usize) -> Option<&mut PlayerCounters> {
+    self.player_slots.get_mut(slot).and_then(Option::as_mut)
+  }
+
+  pub fn make_snapshot(&self, time: u32) -> ActionCounterStats {
+    ActionCounterStats {
+      time,
+      data: self
+        .player_slots
+        .iter()
+        .filter_map(|item| {
+          let item = item.as_ref()?;
+          let mut v = PlayerActionCounters {
+            player_id: item.player_id,
+            units: vec![],
+            heroes: vec![],
+            buildings: vec![],
+            hero_abilities: vec![],
+            items: vec![],
+            gold_sent: item.gold_sent,
+            lumber_sent: item.lumber_sent,
+            gold_received: item.gold_received,
+            lumber_received: item.lumber_received,
+            hotkeys: (0..HOTKEY_GROUPS)
+              .filter(|i| item.hotkey_assigned[*i] > 0 || item.hotkey_selected[*i] > 0)
+              .map(|i| HotkeyCount {
+                group: i as u8,
+                assigned: item.hotkey_assigned[i],
+                selected: item.hotkey_selected[i],
+              })
+              .collect(),
+          };
+          for (id, count) in &item.objects {
+            let info = match objects::lookup(id) {
+              Some(info) => info,
+              None => continue,
+            };
+            let list = match info.kind {
+              ObjectKind::Unit => &mut v.units,
+              ObjectKind::Hero => &mut v.heroes,
+              ObjectKind::Building => &mut v.buildings,
+              ObjectKind::HeroAbility => &mut v.hero_abilities,
+              ObjectKind::Item => &mut v.items,
+            };
+            list.push(ObjectCount {
+              id: id.clone(),
+              name: info.name.to_string(),
+              count: *count,
+            });
+          }
+          Some(v)
+        })
+        .collect(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, SimpleObject, Serialize, Deserialize)]
+pub struct ActionCounterStats {
+  pub time: u32,
+  pub data: Vec<PlayerActionCounters>,
+}
+
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+pub struct PlayerActionCounters {
+  pub player_id: i32,
+  pub units: Vec<ObjectCount>,
+  pub heroes: Vec<ObjectCount>,
+  pub buildings: Vec<ObjectCount>,
+  /// Hero abilities learned
+  pub hero_abilities: Vec<ObjectCount>,
+  /// Items bought
+  pub items: Vec<ObjectCount>,
+  pub gold_sent: u32,
+  pub lumber_sent: u32,
+  pub gold_received: u32,
+  pub lumber_received: u32,
+  pub hotkeys: Vec<HotkeyCount>,
+}
+
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+pub struct ObjectCount {
+  pub id: String,
+  pub name: String,
+  pub count: u32,
+}
+
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+pub struct HotkeyCount {
+  pub group: u8,
+  pub assigned: u32,
+  pub selected: u32,
+}
+
+#[test]
+fn test_action_counters() {
+  use bytes::{BufMut, Bytes, BytesMut};
+
+  let mut counters = ActionCounters {
+    player_slots: (0..2)
+      .map(|i| {
+        Some(PlayerCounters {
+          player_id: i + 100,
+          objects: BTreeMap::new(),
+          gold_sent: 0,
+          lumber_sent: 0,
+          gold_received: 0,
+          lumber_received: 0,
+          hotkey_assigned: [0; HOTKEY_GROUPS],
+          hotkey_selected: [0; HOTKEY_GROUPS],
+        })
+      })
+      .collect(),
+  };
+
+  let mut buf = BytesMut::new();
+  // train a footman
+  buf.put_u8(0x10);
+  buf.put_u16_le(0);
+  buf.put_slice(b"oofh");
+  buf.put_u32_le(0xFFFFFFFF);
+  buf.put_u32_le(0xFFFFFFFF);
+  // select group 1
+  buf.put_u8(0x18);
+  buf.put_u8(1);
+  buf.put_u8(0);
+  // send 100 gold to slot 1
+  buf.put_u8(0x51);
+  buf.put_u8(1);
+  buf.put_u32_le(100);
+  buf.put_u32_le(0);
+  // right click, not counted
+  buf.put_u8(0x10);
+  buf.put_u16_le(0);
+  buf.put_u32_le(0x000D0003);
+  buf.put_u32_le(0xFFFFFFFF);
+  buf.put_u32_le(0xFFFFFFFF);
+
+  counters.put_actions(&[PlayerAction {
+    player_id: 1,
+    data: Bytes::from(buf),
+  }]);
+
+  let snapshot = counters.make_snapshot(0);
+  let a = &snapshot.data[0];
+  assert_eq!(a.units.len(), 1);
+  assert_eq!(a.units[0].name, "Footman");
+  assert_eq!(a.units[0].count, 1);
+  assert_eq!(a.hotkeys.len(), 1);
+  assert_eq!(a.hotkeys[0].selected, 1);
+  assert_eq!(a.gold_sent, 100);
+  assert_eq!(snapshot.data[1].gold_received, 100);
+}
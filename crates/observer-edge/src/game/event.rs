@@ -1,7 +1,8 @@
 use crate::game::{
+  counters::ActionCounterStats,
   snapshot::GameSnapshot,
   stats::{ActionStats, PingStats},
-  PlayerLeaveReason,
+  ChatScope, PlayerLeaveReason,
 };
 use async_graphql::{SimpleObject, Union};
 use chrono::{DateTime, Utc};
@@ -44,12 +45,26 @@ impl GameUpdateEvent {
     }
   }
 
+  pub fn action_counters(game_id: i32, item: ActionCounterStats) -> Self {
+    GameUpdateEvent {
+      game_id,
+      data: GameUpdateEventData::ActionCounters(item),
+    }
+  }
+
   pub fn player_left(game_id: i32, time: u32, player_id: i32, reason: PlayerLeaveReason) -> Self {
     GameUpdateEvent {
       game_id,
       data: GameUpdateEventData::PlayerLeft(GameUpdateEventDataPlayerLeft { time, player_id, reason }),
     }
   }
+
+  pub fn chat(game_id: i32, data: GameUpdateEventDataChat) -> Self {
+    GameUpdateEvent {
+      game_id,
+      data: GameUpdateEventData::Chat(data),
+    }
+  }
 }
 
 #[derive(Clone, Union)]
@@ -58,7 +73,9 @@ pub enum GameUpdateEventData {
   Removed(GameUpdateEventDataRemoved),
   PingStats(PingStats),
   ActionStats(ActionStats),
+  ActionCounters(ActionCounterStats),
   PlayerLeft(GameUpdateEventDataPlayerLeft),
+  Chat(GameUpdateEventDataChat),
 }
 
 #[derive(Clone, SimpleObject)]
@@ -79,6 +96,14 @@ pub struct GameUpdateEventDataPlayerLeft {
   pub reason: PlayerLeaveReason,
 }
 
+#[derive(Clone, SimpleObject)]
+pub struct GameUpdateEventDataChat {
+  pub time: u32,
+  pub player_id: i32,
+  pub scope: ChatScope,
+  pub message: String,
+}
+
 #[derive(Clone, Union)]
 pub enum GameListUpdateEvent {
   Added(GameListUpdateEventAdded),
@@ -1,11 +1,15 @@
+pub mod counters;
 pub mod event;
+mod objects;
 pub mod snapshot;
 pub mod stats;
 pub mod stream;
 
+use self::event::GameUpdateEventDataChat;
 use self::snapshot::{GameSnapshot, GameSnapshotMap, GameSnapshotWithStats};
 use self::stats::GameStats;
 use crate::error::{Error, Result};
+use crate::index::{GameHistory, GameIndexEntry};
 use crate::services::Services;
 use async_graphql::{Enum, SimpleObject};
 use bytes::{Bytes, BytesMut};
@@ -19,6 +23,7 @@ use flo_w3gs::action::PlayerAction;
 use flo_w3gs::protocol;
 use flo_w3gs::protocol::constants::PacketTypeId;
 use s2_grpc_utils::{S2ProtoEnum, S2ProtoUnpack};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::io::Write;
 use std::time::Duration;
@@ -81,6 +86,21 @@ impl GameHandler {
     }
   }
 
+  /// Rebuilds a handler from records persisted by the checkpointer.
+  pub fn restore(
+    services: Services,
+    game_id: i32,
+    initial_arrival_time: f64,
+    next_record_id: u32,
+    records: Vec<GameRecordData>,
+    snapshot_map: &mut GameSnapshotMap,
+  ) -> Result<Self> {
+    let mut handler = Self::new(services, game_id, initial_arrival_time);
+    handler.handle_records(initial_arrival_time, records, snapshot_map)?;
+    handler.next_record_id = next_record_id;
+    Ok(handler)
+  }
+
   pub fn id(&self) -> i32 {
     self.meta.id
   }
@@ -101,6 +121,7 @@ impl GameHandler {
               DeferredOp::PushAction(time_increment_ms, actions) => {
                 if let Some(item) = stats.put_actions(time_increment_ms, &actions) {
                   snapshot_map.insert_game_action_stats(game_id, item);
+                  snapshot_map.insert_game_action_counters(game_id, stats.make_action_counters());
                 }
               }
               DeferredOp::PushRTTStats(item) => {
@@ -109,6 +130,14 @@ impl GameHandler {
               DeferredOp::PushPlayerLeft { time, slot, reason } => {
                 insert_game_player_left(&game, &mut self.meta, snapshot_map, time, slot, reason);
               }
+              DeferredOp::PushChat {
+                time,
+                slot,
+                scope,
+                message,
+              } => {
+                insert_game_chat(&game, snapshot_map, time, slot, scope, message);
+              }
             }
           }
         }
@@ -140,6 +169,18 @@ impl GameHandler {
     }
   }
 
+  pub fn make_history(&self, archived: bool) -> Result<GameHistory> {
+    let GameSnapshotWithStats { game, stats } = self.make_snapshot_with_stats()?;
+    Ok(GameHistory {
+      entry: GameIndexEntry {
+        game,
+        duration_millis: self.meta.duration.map(|d| d.as_millis() as i64),
+        archived,
+      },
+      stats,
+    })
+  }
+
   pub fn make_game_info(&self) -> Result<(GameMeta, GameInfo)> {
     use flo_net::observer::{Map, PlayerInfo, Slot, SlotSettings};
     let game = self.game.get()?;
@@ -245,6 +286,16 @@ impl GameHandler {
     Ok(())
   }
 
+  /// After resuming from a checkpoint, shards are re-read from the saved sequence numbers,
+  /// which can deliver records this handler has already seen.
+  pub fn skip_replayed_records(&self, chunk: &mut GameChunk) {
+    if chunk.min_seq_id < self.next_record_id && chunk.max_seq_id >= self.next_record_id {
+      let n = (self.next_record_id - chunk.min_seq_id) as usize;
+      chunk.records.drain(..n);
+      chunk.min_seq_id = self.next_record_id;
+    }
+  }
+
   pub fn records(&self) -> &[GameRecordData] {
     &self.records
   }
@@ -308,6 +359,25 @@ impl GameHandler {
               })
             }
           }
+          PacketTypeId::ChatToHost | PacketTypeId::ChatFromHost => {
+            let payload: protocol::chat::ChatToHost =
+              if packet.type_id() == PacketTypeId::ChatToHost {
+                packet.decode_simple()?
+              } else {
+                packet.decode_simple::<protocol::chat::ChatFromHost>()?.0
+              };
+            if let protocol::chat::ChatMessage::Scoped { scope, message } = payload.message {
+              if payload.from_player != 0 {
+                self.game.push_chat(
+                  self.game.time(),
+                  (payload.from_player - 1) as usize,
+                  ChatScope::from(scope),
+                  message.to_string_lossy().to_string(),
+                  snapshot_map,
+                )?;
+              }
+            }
+          }
           _ => {}
         },
         GameRecordData::StartLag(_) => {}
@@ -403,8 +473,9 @@ impl FetchGameState {
         Ok(())
       }
       FetchGameState::Loaded { ref mut stats, .. } => {
-        if let Some(stats) = stats.put_actions(time_increment_ms, actions) {
-          snapshot_map.insert_game_action_stats(id, stats);
+        if let Some(item) = stats.put_actions(time_increment_ms, actions) {
+          snapshot_map.insert_game_action_stats(id, item);
+          snapshot_map.insert_game_action_counters(id, stats.make_action_counters());
         }
         Ok(())
       }
@@ -446,6 +517,32 @@ impl FetchGameState {
       FetchGameState::Failed(ref e) => Err(Error::GameNotReady(e.to_string())),
     }
   }
+
+  fn push_chat(
+    &mut self,
+    time: u32,
+    slot: usize,
+    scope: ChatScope,
+    message: String,
+    snapshot_map: &mut GameSnapshotMap,
+  ) -> Result<()> {
+    match self {
+      FetchGameState::Loading { ref mut deferred } => {
+        deferred.push(DeferredOp::PushChat {
+          time,
+          slot,
+          scope,
+          message,
+        });
+        Ok(())
+      }
+      FetchGameState::Loaded { ref game, .. } => {
+        insert_game_chat(game, snapshot_map, time, slot, scope, message);
+        Ok(())
+      }
+      FetchGameState::Failed(ref e) => Err(Error::GameNotReady(e.to_string())),
+    }
+  }
 }
 
 fn insert_game_player_left(
@@ -471,6 +568,34 @@ fn insert_game_player_left(
   }
 }
 
+fn insert_game_chat(
+  game: &Game,
+  snapshot_map: &mut GameSnapshotMap,
+  time: u32,
+  slot: usize,
+  scope: ChatScope,
+  message: String,
+) {
+  let game_id = game.id;
+  let player_id = game
+    .slots
+    .get(slot)
+    .and_then(|slot| slot.player.as_ref().map(|player| player.id));
+  if let Some(player_id) = player_id {
+    snapshot_map.insert_game_chat(
+      game_id,
+      GameUpdateEventDataChat {
+        time,
+        player_id,
+        scope,
+        message,
+      },
+    );
+  } else {
+    tracing::error!(game_id, "invalid chat player slot: {}", slot);
+  }
+}
+
 enum DeferredOp {
   PushAction(u16, Vec<PlayerAction>),
   PushRTTStats(RTTStats),
@@ -479,6 +604,12 @@ enum DeferredOp {
     slot: usize,
     reason: PlayerLeaveReason,
   },
+  PushChat {
+    time: u32,
+    slot: usize,
+    scope: ChatScope,
+    message: String,
+  },
 }
 
 #[derive(Debug, S2ProtoUnpack)]
@@ -539,7 +670,7 @@ pub struct Player {
   pub name: String,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, S2ProtoEnum, Enum)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, S2ProtoEnum, Enum, Serialize, Deserialize)]
 #[s2_grpc(proto_enum_type(flo_grpc::game::Race, flo_net::proto::flo_common::Race))]
 pub enum Race {
   Human,
@@ -549,7 +680,7 @@ pub enum Race {
   Random,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Enum)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Enum, Serialize, Deserialize)]
 pub enum PlayerLeaveReason {
   LeaveDisconnect,
   LeaveLost,
@@ -573,3 +704,32 @@ impl From<protocol::leave::LeaveReason> for PlayerLeaveReason {
     }
   }
 }
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Enum)]
+pub enum ChatScope {
+  All,
+  Allies,
+  Observers,
+  Private,
+}
+
+impl ChatScope {
+  /// Messages visible in the public chat feed.
+  pub fn is_public(&self) -> bool {
+    match self {
+      ChatScope::All | ChatScope::Allies => true,
+      ChatScope::Observers | ChatScope::Private => false,
+    }
+  }
+}
+
+impl From<protocol::chat::MessageScope> for ChatScope {
+  fn from(v: protocol::chat::MessageScope) -> Self {
+    match v {
+      protocol::chat::MessageScope::All => Self::All,
+      protocol::chat::MessageScope::Allies => Self::Allies,
+      protocol::chat::MessageScope::Observers => Self::Observers,
+      protocol::chat::MessageScope::Player(_) => Self::Private,
+    }
+  }
+}
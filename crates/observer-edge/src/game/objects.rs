@@ -0,0 +1,326 @@
+//! Names of the standard melee objects, keyed by object id.
+
+use async_graphql::Enum;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Enum, Serialize, Deserialize)]
+pub enum ObjectKind {
+  Unit,
+  Hero,
+  Building,
+  HeroAbility,
+  Item,
+}
+
+#[derive(Debug)]
+pub struct ObjectInfo {
+  pub kind: ObjectKind,
+  pub name: &'static str,
+}
+
+/// Converts an item id from an action to the object id string, e.g. `hfoo`.
+///
+/// Returns `None` for order ids of the basic commands, e.g. move or attack.
+pub fn object_id(item_id: u32) -> Option<String> {
+  let bytes = item_id.to_be_bytes();
+  if bytes.iter().all(|b| b.is_ascii_alphanumeric()) {
+    Some(bytes.iter().map(|b| *b as char).collect())
+  } else {
+    None
+  }
+}
+
+pub fn lookup(id: &str) -> Option<&'static ObjectInfo> {
+  OBJECTS.get(id)
+}
+
+static OBJECTS: Lazy<HashMap<&'static str, ObjectInfo>> = Lazy::new(|| {
+  TABLE
+    .iter()
+    .map(|(id, kind, name)| {
+      (
+        *id,
+        ObjectInfo {
+          kind: *kind,
+          name: *name,
+        },
+      )
+    })
+    .collect()
+});
+
+const TABLE: &[(&str, ObjectKind, &str)] = &[
+  // Human
+  ("hpea", ObjectKind::Unit, "Peasant"),
+  ("hfoo", ObjectKind::Unit, "Footman"),
+  ("hkni", ObjectKind::Unit, "Knight"),
+  ("hrif", ObjectKind::Unit, "Rifleman"),
+  ("hmtm", ObjectKind::Unit, "Mortar Team"),
+  ("hgyr", ObjectKind::Unit, "Flying Machine"),
+  ("hgry", ObjectKind::Unit, "Gryphon Rider"),
+  ("hmpr", ObjectKind::Unit, "Priest"),
+  ("hsor", ObjectKind::Unit, "Sorceress"),
+  ("hmtt", ObjectKind::Unit, "Siege Engine"),
+  ("hspt", ObjectKind::Unit, "Spell Breaker"),
+  ("hdhw", ObjectKind::Unit, "Dragonhawk Rider"),
+  ("Hamg", ObjectKind::Hero, "Archmage"),
+  ("Hmkg", ObjectKind::Hero, "Mountain King"),
+  ("Hpal", ObjectKind::Hero, "Paladin"),
+  ("Hblm", ObjectKind::Hero, "Blood Mage"),
+  ("htow", ObjectKind::Building, "Town Hall"),
+  ("hkee", ObjectKind::Building, "Keep"),
+  ("hcas", ObjectKind::Building, "Castle"),
+  ("hhou", ObjectKind::Building, "Farm"),
+  ("hbar", ObjectKind::Building, "Barracks"),
+  ("hbla", ObjectKind::Building, "Blacksmith"),
+  ("hlum", ObjectKind::Building, "Lumber Mill"),
+  ("hwtw", ObjectKind::Building, "Scout Tower"),
+  ("hgtw", ObjectKind::Building, "Guard Tower"),
+  ("hctw", ObjectKind::Building, "Cannon Tower"),
+  ("hatw", ObjectKind::Building, "Arcane Tower"),
+  ("halt", ObjectKind::Building, "Altar of Kings"),
+  ("harm", ObjectKind::Building, "Workshop"),
+  ("hars", ObjectKind::Building, "Arcane Sanctum"),
+  ("hgra", ObjectKind::Building, "Gryphon Aviary"),
+  ("hvlt", ObjectKind::Building, "Arcane Vault"),
+  ("AHbz", ObjectKind::HeroAbility, "Blizzard"),
+  ("AHwe", ObjectKind::HeroAbility, "Summon Water Elemental"),
+  ("AHab", ObjectKind::HeroAbility, "Brilliance Aura"),
+  ("AHmt", ObjectKind::HeroAbility, "Mass Teleport"),
+  ("AHtb", ObjectKind::HeroAbility, "Storm Bolt"),
+  ("AHtc", ObjectKind::HeroAbility, "Thunder Clap"),
+  ("AHbh", ObjectKind::HeroAbility, "Bash"),
+  ("AHav", ObjectKind::HeroAbility, "Avatar"),
+  ("AHhb", ObjectKind::HeroAbility, "Holy Light"),
+  ("AHds", ObjectKind::HeroAbility, "Divine Shield"),
+  ("AHad", ObjectKind::HeroAbility, "Devotion Aura"),
+  ("AHre", ObjectKind::HeroAbility, "Resurrection"),
+  ("AHfs", ObjectKind::HeroAbility, "Flame Strike"),
+  ("AHbn", ObjectKind::HeroAbility, "Banish"),
+  ("AHdr", ObjectKind::HeroAbility, "Siphon Mana"),
+  ("AHpx", ObjectKind::HeroAbility, "Phoenix"),
+  // Orc
+  ("opeo", ObjectKind::Unit, "Peon"),
+  ("ogru", ObjectKind::Unit, "Grunt"),
+  ("ohun", ObjectKind::Unit, "Troll Headhunter"),
+  ("otbk", ObjectKind::Unit, "Troll Berserker"),
+  ("ocat", ObjectKind::Unit, "Demolisher"),
+  ("oshm", ObjectKind::Unit, "Shaman"),
+  ("odoc", ObjectKind::Unit, "Troll Witch Doctor"),
+  ("ospw", ObjectKind::Unit, "Spirit Walker"),
+  ("orai", ObjectKind::Unit, "Raider"),
+  ("okod", ObjectKind::Unit, "Kodo Beast"),
+  ("owyv", ObjectKind::Unit, "Wind Rider"),
+  ("otbr", ObjectKind::Unit, "Troll Batrider"),
+  ("otau", ObjectKind::Unit, "Tauren"),
+  ("Obla", ObjectKind::Hero, "Blademaster"),
+  ("Ofar", ObjectKind::Hero, "Far Seer"),
+  ("Otch", ObjectKind::Hero, "Tauren Chieftain"),
+  ("Oshd", ObjectKind::Hero, "Shadow Hunter"),
+  ("ogre", ObjectKind::Building, "Great Hall"),
+  ("ostr", ObjectKind::Building, "Stronghold"),
+  ("ofrt", ObjectKind::Building, "Fortress"),
+  ("otrb", ObjectKind::Building, "Orc Burrow"),
+  ("obar", ObjectKind::Building, "Barracks"),
+  ("ofor", ObjectKind::Building, "War Mill"),
+  ("oalt", ObjectKind::Building, "Altar of Storms"),
+  ("obea", ObjectKind::Building, "Beastiary"),
+  ("osld", ObjectKind::Building, "Spirit Lodge"),
+  ("otto", ObjectKind::Building, "Tauren Totem"),
+  ("ovln", ObjectKind::Building, "Voodoo Lounge"),
+  ("owtw", ObjectKind::Building, "Watch Tower"),
+  ("AOwk", ObjectKind::HeroAbility, "Wind Walk"),
+  ("AOmi", ObjectKind::HeroAbility, "Mirror Image"),
+  ("AOcr", ObjectKind::HeroAbility, "Critical Strike"),
+  ("AOww", ObjectKind::HeroAbility, "Bladestorm"),
+  ("AOcl", ObjectKind::HeroAbility, "Chain Lightning"),
+  ("AOfs", ObjectKind::HeroAbility, "Far Sight"),
+  ("AOsf", ObjectKind::HeroAbility, "Feral Spirit"),
+  ("AOeq", ObjectKind::HeroAbility, "Earthquake"),
+  ("AOsh", ObjectKind::HeroAbility, "Shockwave"),
+  ("AOws", ObjectKind::HeroAbility, "War Stomp"),
+  ("AOae", ObjectKind::HeroAbility, "Endurance Aura"),
+  ("AOre", ObjectKind::HeroAbility, "Reincarnation"),
+  ("AOhw", ObjectKind::HeroAbility, "Healing Wave"),
+  ("AOhx", ObjectKind::HeroAbility, "Hex"),
+  ("AOsw", ObjectKind::HeroAbility, "Serpent Ward"),
+  ("AOvd", ObjectKind::HeroAbility, "Big Bad Voodoo"),
+  // Night Elf
+  ("ewsp", ObjectKind::Unit, "Wisp"),
+  ("earc", ObjectKind::Unit, "Archer"),
+  ("esen", ObjectKind::Unit, "Huntress"),
+  ("ebal", ObjectKind::Unit, "Glaive Thrower"),
+  ("edry", ObjectKind::Unit, "Dryad"),
+  ("edoc", ObjectKind::Unit, "Druid of the Claw"),
+  ("emtg", ObjectKind::Unit, "Mountain Giant"),
+  ("ehip", ObjectKind::Unit, "Hippogryph"),
+  ("ehpr", ObjectKind::Unit, "Hippogryph Rider"),
+  ("edot", ObjectKind::Unit, "Druid of the Talon"),
+  ("efdr", ObjectKind::Unit, "Faerie Dragon"),
+  ("echm", ObjectKind::Unit, "Chimaera"),
+  ("Edem", ObjectKind::Hero, "Demon Hunter"),
+  ("Ekee", ObjectKind::Hero, "Keeper of the Grove"),
+  ("Emoo", ObjectKind::Hero, "Priestess of the Moon"),
+  ("Ewar", ObjectKind::Hero, "Warden"),
+  ("etol", ObjectKind::Building, "Tree of Life"),
+  ("etoa", ObjectKind::Building, "Tree of Ages"),
+  ("etoe", ObjectKind::Building, "Tree of Eternity"),
+  ("emow", ObjectKind::Building, "Moon Well"),
+  ("eaom", ObjectKind::Building, "Ancient of War"),
+  ("eate", ObjectKind::Building, "Altar of Elders"),
+  ("etrp", ObjectKind::Building, "Ancient Protector"),
+  ("eaoe", ObjectKind::Building, "Ancient of Lore"),
+  ("eaow", ObjectKind::Building, "Ancient of Wind"),
+  ("edob", ObjectKind::Building, "Hunter's Hall"),
+  ("eden", ObjectKind::Building, "Ancient of Wonders"),
+  ("edos", ObjectKind::Building, "Chimaera Roost"),
+  ("AEmb", ObjectKind::HeroAbility, "Mana Burn"),
+  ("AEim", ObjectKind::HeroAbility, "Immolation"),
+  ("AEev", ObjectKind::HeroAbility, "Evasion"),
+  ("AEme", ObjectKind::HeroAbility, "Metamorphosis"),
+  ("AEer", ObjectKind::HeroAbility, "Entangling Roots"),
+  ("AEfn", ObjectKind::HeroAbility, "Force of Nature"),
+  ("AEah", ObjectKind::HeroAbility, "Thorns Aura"),
+  ("AEtq", ObjectKind::HeroAbility, "Tranquility"),
+  ("AHfa", ObjectKind::HeroAbility, "Searing Arrows"),
+  ("AEst", ObjectKind::HeroAbility, "Scout"),
+  ("AEar", ObjectKind::HeroAbility, "Trueshot Aura"),
+  ("AEsf", ObjectKind::HeroAbility, "Starfall"),
+  ("AEbl", ObjectKind::HeroAbility, "Blink"),
+  ("AEfk", ObjectKind::HeroAbility, "Fan of Knives"),
+  ("AEsh", ObjectKind::HeroAbility, "Shadow Strike"),
+  ("AEsv", ObjectKind::HeroAbility, "Spirit of Vengeance"),
+  // Undead
+  ("uaco", ObjectKind::Unit, "Acolyte"),
+  ("ugho", ObjectKind::Unit, "Ghoul"),
+  ("ucry", ObjectKind::Unit, "Crypt Fiend"),
+  ("ugar", ObjectKind::Unit, "Gargoyle"),
+  ("uabo", ObjectKind::Unit, "Abomination"),
+  ("umtw", ObjectKind::Unit, "Meat Wagon"),
+  ("unec", ObjectKind::Unit, "Necromancer"),
+  ("uban", ObjectKind::Unit, "Banshee"),
+  ("uobs", ObjectKind::Unit, "Obsidian Statue"),
+  ("ubsp", ObjectKind::Unit, "Destroyer"),
+  ("ufro", ObjectKind::Unit, "Frost Wyrm"),
+  ("ushd", ObjectKind::Unit, "Shade"),
+  ("Udea", ObjectKind::Hero, "Death Knight"),
+  ("Udre", ObjectKind::Hero, "Dreadlord"),
+  ("Ulic", ObjectKind::Hero, "Lich"),
+  ("Ucrl", ObjectKind::Hero, "Crypt Lord"),
+  ("unpl", ObjectKind::Building, "Necropolis"),
+  ("unp1", ObjectKind::Building, "Halls of the Dead"),
+  ("unp2", ObjectKind::Building, "Black Citadel"),
+  ("uzig", ObjectKind::Building, "Ziggurat"),
+  ("uzg1", ObjectKind::Building, "Spirit Tower"),
+  ("uzg2", ObjectKind::Building, "Nerubian Tower"),
+  ("uaod", ObjectKind::Building, "Altar of Darkness"),
+  ("usep", ObjectKind::Building, "Crypt"),
+  ("ugrv", ObjectKind::Building, "Graveyard"),
+  ("utod", ObjectKind::Building, "Temple of the Damned"),
+  ("uslh", ObjectKind::Building, "Slaughterhouse"),
+  ("utom", ObjectKind::Building, "Tomb of Relics"),
+  ("ubon", ObjectKind::Building, "Boneyard"),
+  ("ugol", ObjectKind::Building, "Haunted Gold Mine"),
+  ("usap", ObjectKind::Building, "Sacrificial Pit"),
+  ("AUdc", ObjectKind::HeroAbility, "Death Coil"),
+  ("AUdp", ObjectKind::HeroAbility, "Death Pact"),
+  ("AUau", ObjectKind::HeroAbility, "Unholy Aura"),
+  ("AUan", ObjectKind::HeroAbility, "Animate Dead"),
+  ("AUcs", ObjectKind::HeroAbility, "Carrion Swarm"),
+  ("AUsl", ObjectKind::HeroAbility, "Sleep"),
+  ("AUav", ObjectKind::HeroAbility, "Vampiric Aura"),
+  ("AUin", ObjectKind::HeroAbility, "Inferno"),
+  ("AUfn", ObjectKind::HeroAbility, "Frost Nova"),
+  ("AUfu", ObjectKind::HeroAbility, "Frost Armor"),
+  ("AUdr", ObjectKind::HeroAbility, "Dark Ritual"),
+  ("AUdd", ObjectKind::HeroAbility, "Death and Decay"),
+  ("AUim", ObjectKind::HeroAbility, "Impale"),
+  ("AUts", ObjectKind::HeroAbility, "Spiked Carapace"),
+  ("AUcb", ObjectKind::HeroAbility, "Carrion Beetles"),
+  ("AUls", ObjectKind::HeroAbility, "Locust Swarm"),
+  // Neutral
+  ("Nalc", ObjectKind::Hero, "Goblin Alchemist"),
+  ("Nngs", ObjectKind::Hero, "Naga Sea Witch"),
+  ("Ntin", ObjectKind::Hero, "Goblin Tinker"),
+  ("Nbst", ObjectKind::Hero, "Beastmaster"),
+  ("Npbm", ObjectKind::Hero, "Pandaren Brewmaster"),
+  ("Nbrn", ObjectKind::Hero, "Dark Ranger"),
+  ("Nplh", ObjectKind::Hero, "Pit Lord"),
+  ("Nfir", ObjectKind::Hero, "Firelord"),
+  ("ANhs", ObjectKind::HeroAbility, "Healing Spray"),
+  ("ANab", ObjectKind::HeroAbility, "Acid Bomb"),
+  ("ANcr", ObjectKind::HeroAbility, "Chemical Rage"),
+  ("ANtm", ObjectKind::HeroAbility, "Transmute"),
+  ("ANfl", ObjectKind::HeroAbility, "Forked Lightning"),
+  ("ANfa", ObjectKind::HeroAbility, "Frost Arrows"),
+  ("ANms", ObjectKind::HeroAbility, "Mana Shield"),
+  ("ANto", ObjectKind::HeroAbility, "Tornado"),
+  ("ANsy", ObjectKind::HeroAbility, "Pocket Factory"),
+  ("ANcs", ObjectKind::HeroAbility, "Cluster Rockets"),
+  ("ANeg", ObjectKind::HeroAbility, "Engineering Upgrade"),
+  ("ANrg", ObjectKind::HeroAbility, "Robo-Goblin"),
+  ("ANsg", ObjectKind::HeroAbility, "Summon Bear"),
+  ("ANsq", ObjectKind::HeroAbility, "Summon Quilbeast"),
+  ("ANsw", ObjectKind::HeroAbility, "Summon Hawk"),
+  ("ANst", ObjectKind::HeroAbility, "Stampede"),
+  ("ANbf", ObjectKind::HeroAbility, "Breath of Fire"),
+  ("ANdh", ObjectKind::HeroAbility, "Drunken Haze"),
+  ("ANdb", ObjectKind::HeroAbility, "Drunken Brawler"),
+  ("ANef", ObjectKind::HeroAbility, "Storm, Earth, and Fire"),
+  ("ANsi", ObjectKind::HeroAbility, "Silence"),
+  ("ANba", ObjectKind::HeroAbility, "Black Arrow"),
+  ("ANdr", ObjectKind::HeroAbility, "Life Drain"),
+  ("ANch", ObjectKind::HeroAbility, "Charm"),
+  ("ANrf", ObjectKind::HeroAbility, "Rain of Fire"),
+  ("ANht", ObjectKind::HeroAbility, "Howl of Terror"),
+  ("ANca", ObjectKind::HeroAbility, "Cleaving Attack"),
+  ("ANdo", ObjectKind::HeroAbility, "Doom"),
+  ("ANia", ObjectKind::HeroAbility, "Incinerate"),
+  ("ANso", ObjectKind::HeroAbility, "Soul Burn"),
+  ("ANlm", ObjectKind::HeroAbility, "Summon Lava Spawn"),
+  ("ANvc", ObjectKind::HeroAbility, "Volcano"),
+  // Items
+  ("phea", ObjectKind::Item, "Potion of Healing"),
+  ("pman", ObjectKind::Item, "Potion of Mana"),
+  ("pghe", ObjectKind::Item, "Potion of Greater Healing"),
+  ("pgma", ObjectKind::Item, "Potion of Greater Mana"),
+  ("plcl", ObjectKind::Item, "Lesser Clarity Potion"),
+  ("pinv", ObjectKind::Item, "Potion of Invisibility"),
+  ("hslv", ObjectKind::Item, "Healing Salve"),
+  ("stwp", ObjectKind::Item, "Scroll of Town Portal"),
+  ("shea", ObjectKind::Item, "Scroll of Healing"),
+  ("spro", ObjectKind::Item, "Scroll of Protection"),
+  ("sreg", ObjectKind::Item, "Scroll of Regeneration"),
+  ("shas", ObjectKind::Item, "Scroll of Speed"),
+  ("dust", ObjectKind::Item, "Dust of Appearance"),
+  ("bspd", ObjectKind::Item, "Boots of Speed"),
+  ("cnob", ObjectKind::Item, "Circlet of Nobility"),
+  ("ankh", ObjectKind::Item, "Ankh of Reincarnation"),
+  ("tret", ObjectKind::Item, "Tome of Retraining"),
+  ("ssan", ObjectKind::Item, "Staff of Sanctuary"),
+  ("stel", ObjectKind::Item, "Staff of Teleportation"),
+  ("ssil", ObjectKind::Item, "Staff of Silence"),
+  ("spre", ObjectKind::Item, "Staff of Preservation"),
+  ("sneg", ObjectKind::Item, "Staff of Negation"),
+  ("wneg", ObjectKind::Item, "Wand of Negation"),
+  ("moon", ObjectKind::Item, "Moonstone"),
+  ("mcri", ObjectKind::Item, "Mechanical Critter"),
+  ("ofir", ObjectKind::Item, "Orb of Fire"),
+  ("ofro", ObjectKind::Item, "Orb of Frost"),
+  ("oven", ObjectKind::Item, "Orb of Venom"),
+  ("ocor", ObjectKind::Item, "Orb of Corruption"),
+  ("wild", ObjectKind::Item, "Amulet of the Wild"),
+  ("skul", ObjectKind::Item, "Sacrificial Skull"),
+];
+
+#[test]
+fn test_object_id() {
+  let item_id = u32::from_le_bytes(*b"oofh");
+  let id = object_id(item_id).unwrap();
+  assert_eq!(id, "hfoo");
+  assert_eq!(lookup(&id).unwrap().kind, ObjectKind::Unit);
+  // right click
+  assert_eq!(object_id(0x000D0003), None);
+}
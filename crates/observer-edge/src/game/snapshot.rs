@@ -1,3 +1,4 @@
+use super::counters::ActionCounterStats;
 use super::event::*;
 use super::stats::{ActionStats, GameStatsSnapshot, PingStats};
 use super::{Game, Race};
@@ -6,6 +7,7 @@ use crate::broadcast::{BroadcastReceiver, BroadcastSender};
 use crate::error::{Error, Result};
 use async_graphql::SimpleObject;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 pub struct GameSnapshotMap {
@@ -86,6 +88,14 @@ impl GameSnapshotMap {
     })
   }
 
+  pub fn insert_game_action_counters(&mut self, game_id: i32, item: ActionCounterStats) {
+    self.send_game_update_event(game_id, || GameUpdateEvent::action_counters(game_id, item))
+  }
+
+  pub fn insert_game_chat(&mut self, game_id: i32, item: GameUpdateEventDataChat) {
+    self.send_game_update_event(game_id, || GameUpdateEvent::chat(game_id, item))
+  }
+
   pub fn subscribe_game_updates(&mut self, game_id: i32) -> BroadcastReceiver<GameUpdateEvent> {
     match self
       .tx_map_game_update
@@ -141,7 +151,7 @@ impl GameSnapshotMap {
   }
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct GameSnapshot {
   pub id: i32,
   pub game_name: String,
@@ -208,7 +218,7 @@ impl GameSnapshot {
   }
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct Player {
   pub id: i32,
   pub name: String,
@@ -1,7 +1,9 @@
 use async_graphql::{SimpleObject};
 use flo_observer::record;
 use flo_w3gs::protocol::action::PlayerAction;
+use serde::{Deserialize, Serialize};
 
+use super::counters::{ActionCounterStats, ActionCounters};
 use super::Game;
 
 const APM_COLLECT_INTERVAL_MS: u32 = 15 * 1000;
@@ -13,6 +15,7 @@ pub struct GameStats {
   ping: Vec<PingStats>,
   action: Vec<ActionStats>,
   apm_collect: ApmCollect,
+  counters: ActionCounters,
 }
 
 impl GameStats {
@@ -23,6 +26,7 @@ impl GameStats {
       ping: vec![],
       action: vec![],
       apm_collect: ApmCollect::new(game),
+      counters: ActionCounters::new(game),
     }
   }
 
@@ -49,6 +53,7 @@ impl GameStats {
 
   pub fn put_actions(&mut self, time_increment: u16, actions: &[PlayerAction]) -> Option<ActionStats> {
     self.time += time_increment as u32;
+    self.counters.put_actions(actions);
     if let Some(item) = self.apm_collect.try_collect(self.time, actions) {
       self.action.push(item.clone());
       Some(item)
@@ -57,10 +62,15 @@ impl GameStats {
     }
   }
 
+  pub fn make_action_counters(&self) -> ActionCounterStats {
+    self.counters.make_snapshot(self.time)
+  }
+
   pub fn make_snapshot(&self) -> GameStatsSnapshot {
     GameStatsSnapshot {
       ping: self.ping.clone(),
       action: self.action.clone(),
+      counters: self.make_action_counters(),
     }
   }
 }
@@ -133,20 +143,22 @@ impl ApmCollect {
 }
 
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct GameStatsSnapshot {
   pub ping: Vec<PingStats>,
   pub action: Vec<ActionStats>,
+  #[serde(default)]
+  pub counters: ActionCounterStats,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct PingStats 
 {
   pub time: u32,
   pub data: Vec<Ping>,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct Ping {
   pub player_id: i32,
   pub min: u16,
@@ -155,14 +167,14 @@ pub struct Ping {
   pub ticks: u16,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct ActionStats 
 {
   pub time: u32,
   pub data: Vec<Action>,
 }
 
-#[derive(Debug, Clone, SimpleObject)]
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
 pub struct Action {
   pub player_id: i32,
   pub apm: f32,
@@ -0,0 +1,273 @@
+use crate::error::{Error, Result};
+use crate::game::snapshot::GameSnapshot;
+use crate::game::stats::GameStatsSnapshot;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use flo_observer_fs::GameDataWriter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+const INDEX_FOLDER: &str = "index";
+const MAX_TAKE: usize = 100;
+const DEFAULT_TAKE: usize = 30;
+
+/// Index of finished games, keyed by game id.
+///
+/// Each game is stored as a json file with its final snapshot and stats.
+/// Only the snapshots are kept in memory, stats are read on demand.
+pub struct GameIndex {
+  entries: BTreeMap<i32, GameIndexEntry>,
+}
+
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+pub struct GameIndexEntry {
+  pub game: GameSnapshot,
+  pub duration_millis: Option<i64>,
+  /// The archive was uploaded, using the game id as the object key.
+  pub archived: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+pub struct GameHistory {
+  pub entry: GameIndexEntry,
+  pub stats: GameStatsSnapshot,
+}
+
+#[derive(Debug, Default)]
+pub struct GameHistoryQuery {
+  pub player_name: Option<String>,
+  pub map_name: Option<String>,
+  pub node_id: Option<i32>,
+  pub started_after: Option<DateTime<Utc>>,
+  pub started_before: Option<DateTime<Utc>>,
+  pub include_private: bool,
+  pub take: Option<usize>,
+  pub since_id: Option<i32>,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct GameHistoryPage {
+  pub games: Vec<GameIndexEntry>,
+  pub has_more: bool,
+}
+
+impl GameIndex {
+  pub async fn load() -> Result<Self> {
+    let dir = index_dir();
+    fs::create_dir_all(&dir).await?;
+
+    let mut entries = BTreeMap::new();
+    let mut stream = fs::read_dir(&dir).await?;
+    while let Some(entry) = stream.next_entry().await? {
+      let path = entry.path();
+      if path.extension().and_then(|v| v.to_str()) != Some("json") {
+        continue;
+      }
+      let history = match read_history_file(&path).await {
+        Ok(v) => v,
+        Err(err) => {
+          tracing::error!("load index entry {}: {}", path.display(), err);
+          continue;
+        }
+      };
+      entries.insert(history.entry.game.id, history.entry);
+    }
+
+    tracing::info!("game index loaded: {} games", entries.len());
+
+    Ok(Self { entries })
+  }
+
+  pub fn contains(&self, game_id: i32) -> bool {
+    self.entries.contains_key(&game_id)
+  }
+
+  pub fn ids(&self) -> Vec<i32> {
+    self.entries.keys().cloned().collect()
+  }
+
+  pub fn insert(&mut self, history: GameHistory) {
+    let game_id = history.entry.game.id;
+    self.entries.insert(game_id, history.entry.clone());
+    tokio::spawn(async move {
+      if let Err(err) = write_history(&history).await {
+        tracing::error!(game_id, "write index entry: {}", err);
+      }
+    });
+  }
+
+  pub fn query(&self, params: &GameHistoryQuery) -> GameHistoryPage {
+    let take = std::cmp::min(MAX_TAKE, params.take.unwrap_or(DEFAULT_TAKE));
+    let player_name = params.player_name.as_ref().map(|v| v.trim().to_lowercase());
+    let map_name = params.map_name.as_ref().map(|v| v.trim().to_lowercase());
+
+    let upper = params.since_id.unwrap_or(i32::MAX);
+    let mut games: Vec<_> = self
+      .entries
+      .range(..upper)
+      .rev()
+      .map(|(_, entry)| entry)
+      .filter(|entry| {
+        let game = &entry.game;
+        if game.is_private && !params.include_private {
+          return false;
+        }
+        if let Some(ref name) = player_name {
+          if !game
+            .players
+            .iter()
+            .any(|p| p.name.to_lowercase().contains(name))
+          {
+            return false;
+          }
+        }
+        if let Some(ref name) = map_name {
+          if !game.map_name.to_lowercase().contains(name) {
+            return false;
+          }
+        }
+        if let Some(node_id) = params.node_id {
+          if game.node_id != node_id {
+            return false;
+          }
+        }
+        if let Some(t) = params.started_after {
+          if game.started_at < t {
+            return false;
+          }
+        }
+        if let Some(t) = params.started_before {
+          if game.started_at >= t {
+            return false;
+          }
+        }
+        true
+      })
+      .take(take + 1)
+      .cloned()
+      .collect();
+
+    let has_more = games.len() > take;
+    if has_more {
+      games.truncate(take);
+    }
+
+    GameHistoryPage { games, has_more }
+  }
+}
+
+pub async fn read_history(game_id: i32) -> Result<Option<GameHistory>> {
+  match read_history_file(&history_path(game_id)).await {
+    Ok(history) => Ok(Some(history)),
+    Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(err) => Err(err),
+  }
+}
+
+async fn read_history_file(path: &Path) -> Result<GameHistory> {
+  let bytes = fs::read(path).await?;
+  Ok(serde_json::from_slice(&bytes)?)
+}
+
+async fn write_history(history: &GameHistory) -> Result<()> {
+  let path = history_path(history.entry.game.id);
+  let temp_path = path.with_extension("tmp");
+  fs::write(&temp_path, serde_json::to_vec(history)?).await?;
+  fs::rename(temp_path, path).await?;
+  Ok(())
+}
+
+fn index_dir() -> PathBuf {
+  GameDataWriter::data_folder().join(INDEX_FOLDER)
+}
+
+fn history_path(game_id: i32) -> PathBuf {
+  index_dir().join(format!("{}.json", game_id))
+}
+
+#[test]
+fn test_game_index_query() {
+  use crate::game::snapshot::Player;
+  use crate::game::Race;
+  use chrono::TimeZone;
+
+  let entry = |id: i32, player: &str, node_id: i32, is_private: bool| GameIndexEntry {
+    game: GameSnapshot {
+      id,
+      game_name: format!("game {}", id),
+      map_name: if id % 2 == 0 {
+        "Echo Isles"
+      } else {
+        "Twisted Meadows"
+      }
+      .to_string(),
+      map_path: String::new(),
+      map_sha1: vec![],
+      map_checksum: 0,
+      node_id,
+      node_name: String::new(),
+      started_at: Utc.timestamp(id as i64 * 60, 0),
+      ended_at: None,
+      players: vec![Player {
+        id,
+        name: player.to_string(),
+        race: Race::Human,
+        team: 0,
+        left_at: None,
+        leave_reason: None,
+      }],
+      random_seed: 0,
+      game_version: None,
+      mask_player_names: false,
+      is_private,
+      is_live: false,
+    },
+    duration_millis: None,
+    archived: true,
+  };
+
+  let mut index = GameIndex {
+    entries: BTreeMap::new(),
+  };
+  for id in 1..=10 {
+    let player = if id <= 5 { "Alice" } else { "Bob" };
+    index
+      .entries
+      .insert(id, entry(id, player, id % 3, id == 10));
+  }
+
+  let ids = |page: GameHistoryPage| page.games.iter().map(|e| e.game.id).collect::<Vec<_>>();
+
+  let page = index.query(&GameHistoryQuery {
+    take: Some(3),
+    ..Default::default()
+  });
+  assert!(page.has_more);
+  assert_eq!(ids(page), vec![9, 8, 7]);
+
+  let page = index.query(&GameHistoryQuery {
+    take: Some(3),
+    since_id: Some(7),
+    include_private: true,
+    ..Default::default()
+  });
+  assert_eq!(ids(page), vec![6, 5, 4]);
+
+  let page = index.query(&GameHistoryQuery {
+    player_name: Some("alice".to_string()),
+    map_name: Some("echo".to_string()),
+    ..Default::default()
+  });
+  assert!(!page.has_more);
+  assert_eq!(ids(page), vec![4, 2]);
+
+  let page = index.query(&GameHistoryQuery {
+    node_id: Some(0),
+    started_after: Some(Utc.timestamp(4 * 60, 0)),
+    started_before: Some(Utc.timestamp(9 * 60, 0)),
+    ..Default::default()
+  });
+  assert_eq!(ids(page), vec![6]);
+}
@@ -1,39 +1,83 @@
 mod broadcast;
+pub mod chat;
+mod checkpoint;
+pub mod cluster;
 mod constants;
 mod controller;
 mod dispatcher;
 mod env;
-mod error;
+pub mod error;
 pub mod game;
+pub mod index;
+mod replay;
 mod server;
 mod services;
+mod token;
 mod version;
 
 use crate::broadcast::BroadcastReceiver;
+use crate::chat::{ChatFeedOptions, ChatFilter};
+use crate::checkpoint::{checkpoint_dir, Checkpointer};
+use crate::cluster::{ClusterConfig, ClusterMember, FsClusterBackend, InstanceInfo};
 use crate::env::Env;
 use dispatcher::{
-  AddIterator, Dispatcher, GetGame, ListGames, SubscribeGameListUpdate, SubscribeGameUpdate,
+  AddIterator, Dispatcher, GetGame, ListGames, QueryGameHistory, RestoreGames,
+  SubscribeGameListUpdate, SubscribeGameUpdate,
 };
-use error::Result;
+use error::{Error, Result};
 use flo_kinesis::{data_stream::DataStream, iterator::ShardIteratorType};
-use flo_observer_archiver::{Archiver, ArchiverOptions};
+use flo_observer_archiver::{Archiver, ArchiverOptions, Fetcher};
 use flo_state::{Actor, Addr, Owner};
+use futures::stream::{self, BoxStream, StreamExt};
 use game::event::{GameListUpdateEvent, GameUpdateEvent};
 use game::snapshot::{GameSnapshot, GameSnapshotWithStats};
+use index::{GameHistory, GameHistoryPage, GameHistoryQuery, GameIndex};
+use replay::ReplayService;
 use server::StreamServer;
 use services::Services;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
 use std::time::Duration;
+use token::TokenRegistry;
+use tokio_stream::Stream;
+
+pub use replay::Replay;
 
 pub struct FloObserverEdge {
   dispatcher: Owner<Dispatcher>,
   stream_server: StreamServer,
   archiver: Option<Archiver>,
+  checkpointer: Option<Checkpointer>,
+  replays: Option<Arc<ReplayService>>,
+  tokens: Arc<TokenRegistry>,
+  chat_filter: Arc<ChatFilter>,
+  cluster: Option<ClusterConfig>,
+  cluster_member: Option<ClusterMember>,
 }
 
 impl FloObserverEdge {
+  /// Joins a cluster using `OBSERVER_CLUSTER_DIR` as the shared directory if it's set.
   pub async fn from_env() -> Result<Self> {
+    let cluster = match env::ENV.cluster_dir {
+      Some(ref dir) => Some(ClusterConfig {
+        backend: Arc::new(FsClusterBackend::new(dir).await?),
+        instance_id: env::ENV
+          .cluster_instance_id
+          .clone()
+          .expect("env OBSERVER_CLUSTER_INSTANCE_ID"),
+        graphql_url: env::ENV.cluster_graphql_url.clone(),
+        stream_host: env::ENV.cluster_stream_host.clone(),
+      }),
+      None => None,
+    };
+    Self::with_cluster(cluster).await
+  }
+
+  /// Consumes only the shards acquired through the cluster backend if `cluster` is set,
+  /// otherwise consumes all shards.
+  pub async fn with_cluster(cluster: Option<ClusterConfig>) -> Result<Self> {
     let mut services = Services::from_env();
-    let archiver = match &*env::ENV {
+    let (archiver, replays) = match &*env::ENV {
       Env {
         aws_s3_bucket: Some(ref aws_s3_bucket),
         aws_access_key_id: Some(ref aws_access_key_id),
@@ -41,39 +85,78 @@ impl FloObserverEdge {
         aws_s3_region: Some(ref aws_s3_region),
         ..
       } => {
-        let opts = ArchiverOptions {
+        let opts = || ArchiverOptions {
           aws_s3_bucket: aws_s3_bucket.clone(),
           aws_access_key_id: aws_access_key_id.clone(),
           aws_secret_access_key: aws_secret_access_key.clone(),
           aws_s3_region: aws_s3_region.clone(),
         };
-        let (archiver, handle) = Archiver::new(opts)?;
+        let (archiver, handle) = Archiver::new(opts())?;
         services.archiver.replace(handle);
-        Some(archiver)
+        let replays = ReplayService::new(services.controller.clone(), Fetcher::new(opts())?);
+        (Some(archiver), Some(Arc::new(replays)))
       }
       _ => {
         tracing::debug!("archiver disabled.");
-        None
+        (None, None)
       }
     };
-    let dispatcher = Dispatcher::new(services).start();
+    let (checkpointer, restored) = if env::ENV.checkpoint_interval_secs > 0 {
+      let instance_id = cluster.as_ref().map(|c| c.instance_id.as_str());
+      let (checkpointer, handle, restored) = Checkpointer::load(
+        checkpoint_dir(instance_id.unwrap_or("default")),
+        Duration::from_secs(env::ENV.checkpoint_interval_secs),
+        Duration::from_secs(env::ENV.record_backscan_secs),
+      )
+      .await?;
+      services.checkpoint.replace(handle);
+      (Some(checkpointer), restored)
+    } else {
+      tracing::debug!("checkpoint disabled.");
+      (None, Default::default())
+    };
+    let chat_filter = services.chat_filter.clone();
+    let index = GameIndex::load().await?;
+    let dispatcher = Dispatcher::new(services, index).start();
+
+    dispatcher.send(RestoreGames(restored.games)).await?;
 
     let data_stream = DataStream::from_env();
     let iter_type = ShardIteratorType::at_timestamp_backward(Duration::from_secs(
       crate::env::ENV.record_backscan_secs,
     ));
 
-    tracing::debug!("creating iterator...");
+    let tokens = TokenRegistry::load().await?;
 
-    let iter = data_stream.into_iter(iter_type).await?;
+    let shards = restored.shards;
+    let cluster_member = if let Some(ref cluster) = cluster {
+      Some(ClusterMember::new(
+        cluster.clone(),
+        dispatcher.addr(),
+        tokens.clone(),
+        data_stream,
+        shards,
+        iter_type,
+      ))
+    } else {
+      tracing::debug!("creating iterator...");
 
-    tracing::debug!("iterator created.");
+      let iter = data_stream
+        .into_iter_with(|shard_id| match shards.get(shard_id) {
+          Some(sequence_number) => ShardIteratorType::AfterSequenceNumber(sequence_number.clone()),
+          None => iter_type.clone(),
+        })
+        .await?;
 
-    dispatcher.send(AddIterator(iter)).await?;
+      tracing::debug!("iterator created.");
 
-    tracing::debug!("iterator added.");
+      dispatcher.send(AddIterator(iter)).await?;
 
-    let stream_server = StreamServer::new(dispatcher.addr()).await?;
+      tracing::debug!("iterator added.");
+      None
+    };
+
+    let stream_server = StreamServer::new(dispatcher.addr(), tokens.clone()).await?;
 
     tracing::debug!(
       "server listening on {}",
@@ -84,10 +167,24 @@ impl FloObserverEdge {
       dispatcher,
       stream_server,
       archiver,
+      checkpointer,
+      replays,
+      tokens,
+      chat_filter,
+      cluster,
+      cluster_member,
     })
   }
 
   pub async fn serve(self) -> Result<()> {
+    if let Some(checkpointer) = self.checkpointer {
+      tokio::spawn(checkpointer.serve());
+    }
+
+    if let Some(member) = self.cluster_member {
+      tokio::spawn(member.serve());
+    }
+
     if let Some(archiver) = self.archiver {
       tokio::pin! {
         let f1 = self.stream_server.serve();
@@ -104,33 +201,159 @@ impl FloObserverEdge {
   }
 
   pub fn handle(&self) -> FloObserverEdgeHandle {
-    FloObserverEdgeHandle(self.dispatcher.addr())
+    FloObserverEdgeHandle {
+      dispatcher: self.dispatcher.addr(),
+      replays: self.replays.clone(),
+      tokens: self.tokens.clone(),
+      chat_filter: self.chat_filter.clone(),
+      cluster: self.cluster.clone(),
+    }
   }
 }
 
 #[derive(Clone)]
-pub struct FloObserverEdgeHandle(Addr<Dispatcher>);
+pub struct FloObserverEdgeHandle {
+  dispatcher: Addr<Dispatcher>,
+  replays: Option<Arc<ReplayService>>,
+  tokens: Arc<TokenRegistry>,
+  chat_filter: Arc<ChatFilter>,
+  cluster: Option<ClusterConfig>,
+}
 
 impl FloObserverEdgeHandle {
+  /// Lists the games of all cluster instances, remote games are as of their last heartbeat.
   pub async fn list_games(&self) -> Result<Vec<GameSnapshot>> {
-    self.0.send(ListGames).await.map_err(Into::into)
+    let mut games = self.dispatcher.send(ListGames).await?;
+    if let Some(ref cluster) = self.cluster {
+      let local: BTreeSet<i32> = games.iter().map(|game| game.id).collect();
+      games.extend(
+        cluster
+          .list_remote_games()
+          .await?
+          .into_iter()
+          .filter(|game| !local.contains(&game.id)),
+      );
+    }
+    Ok(games)
   }
 
   pub async fn get_game(&self, game_id: i32) -> Result<GameSnapshot> {
-    let game = self.0.send(GetGame { game_id }).await??;
+    let game = self.dispatcher.send(GetGame { game_id }).await??;
     Ok(game)
   }
 
+  /// Games finished by other cluster instances are indexed on the next heartbeat.
+  pub async fn query_game_history(&self, query: GameHistoryQuery) -> Result<GameHistoryPage> {
+    self
+      .dispatcher
+      .send(QueryGameHistory(query))
+      .await
+      .map_err(Into::into)
+  }
+
+  pub async fn get_game_history(&self, game_id: i32) -> Result<Option<GameHistory>> {
+    index::read_history(game_id).await
+  }
+
+  /// Subscribes to the game list updates of all cluster instances,
+  /// updates of remote games are emitted on every heartbeat.
   pub async fn subscribe_game_list_updates(
     &self,
-  ) -> Result<(Vec<GameSnapshot>, BroadcastReceiver<GameListUpdateEvent>)> {
-    self.0.send(SubscribeGameListUpdate).await?
+  ) -> Result<(Vec<GameSnapshot>, BoxStream<'static, GameListUpdateEvent>)> {
+    let (mut snapshots, rx) = self.dispatcher.send(SubscribeGameListUpdate).await??;
+    let events = rx.into_stream();
+    match self.cluster {
+      Some(ref cluster) => {
+        let local: BTreeSet<i32> = snapshots.iter().map(|game| game.id).collect();
+        let remote: BTreeMap<i32, GameSnapshot> = cluster
+          .list_remote_games()
+          .await?
+          .into_iter()
+          .filter(|game| !local.contains(&game.id))
+          .map(|game| (game.id, game))
+          .collect();
+        snapshots.extend(remote.values().cloned());
+        let remote_events = cluster.watch_remote_games(remote);
+        Ok((snapshots, stream::select(events, remote_events).boxed()))
+      }
+      None => Ok((snapshots, events.boxed())),
+    }
   }
 
   pub async fn subscribe_game_updates(
     &self,
     game_id: i32,
   ) -> Result<(GameSnapshotWithStats, BroadcastReceiver<GameUpdateEvent>)> {
-    self.0.send(SubscribeGameUpdate { game_id }).await?
+    self
+      .dispatcher
+      .send(SubscribeGameUpdate { game_id })
+      .await?
+  }
+
+  /// Subscribes to game updates, with chat events filtered and delayed by `options`.
+  pub async fn subscribe_game_updates_with_chat(
+    &self,
+    game_id: i32,
+    options: ChatFeedOptions,
+  ) -> Result<(GameSnapshotWithStats, impl Stream<Item = GameUpdateEvent>)> {
+    let (snapshot, rx) = self.subscribe_game_updates(game_id).await?;
+    let events = self
+      .chat_filter
+      .filter_events(options, Box::pin(rx.into_stream()));
+    Ok((snapshot, events))
+  }
+
+  /// Hides or shows the messages of a player in the public chat feed.
+  pub fn set_chat_player_hidden(&self, game_id: i32, player_id: i32, hidden: bool) {
+    self
+      .chat_filter
+      .set_player_hidden(game_id, player_id, hidden)
+  }
+
+  /// Returns the instance holding the game if it's held by another cluster instance.
+  pub async fn find_remote_instance(&self, game_id: i32) -> Result<Option<InstanceInfo>> {
+    match self.cluster {
+      Some(ref cluster) => cluster.find_remote_instance(game_id).await,
+      None => Ok(None),
+    }
+  }
+
+  pub async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+    match self.cluster {
+      Some(ref cluster) => cluster.backend.list_instances().await,
+      None => Ok(vec![]),
+    }
+  }
+
+  /// Host of this instance's stream server, when running in a cluster.
+  pub fn stream_host(&self) -> Option<&str> {
+    self
+      .cluster
+      .as_ref()
+      .and_then(|cluster| cluster.stream_host.as_deref())
+  }
+
+  /// Rejects new connections using the token, `exp` is the token's expiration timestamp.
+  /// Other cluster instances reject it after their next heartbeat.
+  pub async fn revoke_observer_token(&self, jti: String, exp: i64) -> Result<()> {
+    if let Some(ref cluster) = self.cluster {
+      cluster.backend.revoke_token(&jti, exp).await?;
+    }
+    self.tokens.revoke(jti, exp).await
+  }
+
+  pub async fn get_replay(
+    &self,
+    game_id: i32,
+    include_chats: bool,
+    allow_private: bool,
+  ) -> Result<Replay> {
+    let replays = self
+      .replays
+      .as_ref()
+      .ok_or_else(|| Error::ArchiverDisabled)?;
+    replays
+      .get_replay(game_id, include_chats, allow_private)
+      .await
   }
 }
@@ -0,0 +1,321 @@
+use crate::controller::Controller;
+use crate::error::{Error, Result};
+use bytes::Bytes;
+use flo_observer_archiver::Fetcher;
+use flo_replay::{generate_replay, GenerateReplayOptions};
+use flo_types::observer::GameInfo;
+use lru::LruCache;
+use s2_grpc_utils::S2ProtoUnpack;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+/// Total size of the cached replays
+const CACHE_MAX_BYTES: usize = 128 * 1024 * 1024;
+
+/// Generates `.w3g` replays from game archives.
+pub struct ReplayService {
+  controller: Controller,
+  fetcher: Fetcher,
+  cache: ReplayCache,
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+  pub filename: String,
+  pub data: Bytes,
+}
+
+impl ReplayService {
+  pub fn new(controller: Controller, fetcher: Fetcher) -> Self {
+    Self {
+      controller,
+      fetcher,
+      cache: ReplayCache::new(CACHE_MAX_BYTES),
+    }
+  }
+
+  pub async fn get_replay(
+    &self,
+    game_id: i32,
+    include_chats: bool,
+    allow_private: bool,
+  ) -> Result<Replay> {
+    let game = self.controller.fetch_game_proto(game_id).await?;
+    if game.is_private && !allow_private {
+      return Err(Error::PrivateGame(game_id));
+    }
+
+    let filename = format!("flo_{}.w3g", game_id);
+
+    let data = self
+      .cache
+      .get_or_build(game_id, include_chats, async {
+        let mask_player_names = game.mask_player_names;
+        let game = GameInfo::unpack(game)?;
+
+        let archive = self.fetcher.fetch(game_id).await.map_err(|err| match err {
+          flo_observer_archiver::error::Error::GetArchivedObject(
+            rusoto_core::RusotoError::Service(rusoto_s3::GetObjectError::NoSuchKey(_)),
+          ) => Error::ArchiveNotFound(game_id),
+          err => err.into(),
+        })?;
+        if archive.is_empty() {
+          return Err(Error::ArchiveNotFound(game_id));
+        }
+
+        build_replay(game, archive, mask_player_names, include_chats).await
+      })
+      .await?;
+
+    Ok(Replay { filename, data })
+  }
+}
+
+type ReplayCacheKey = (i32, bool);
+
+/// Generated replays keyed by game id and whether chats are included,
+/// least recently used replays are evicted when the total size exceeds `max_bytes`
+struct ReplayCache {
+  max_bytes: usize,
+  state: Mutex<ReplayCacheState>,
+}
+
+struct ReplayCacheState {
+  replays: LruCache<ReplayCacheKey, Bytes>,
+  bytes: usize,
+  // held while building, concurrent requests for the same replay wait for the first one
+  building: HashMap<ReplayCacheKey, Arc<tokio::sync::Mutex<()>>>,
+}
+
+impl ReplayCache {
+  fn new(max_bytes: usize) -> Self {
+    Self {
+      max_bytes,
+      state: Mutex::new(ReplayCacheState {
+        replays: LruCache::unbounded(),
+        bytes: 0,
+        building: HashMap::new(),
+      }),
+    }
+  }
+
+  /// Returns the cached replay, or awaits `build` and caches its result,
+  /// only one request builds a replay at a time
+  async fn get_or_build<F>(&self, game_id: i32, include_chats: bool, build: F) -> Result<Bytes>
+  where
+    F: Future<Output = Result<Bytes>>,
+  {
+    let key = (game_id, include_chats);
+    let lock = {
+      let mut state = self.state.lock().unwrap();
+      if let Some(data) = state.replays.get(&key).cloned() {
+        return Ok(data);
+      }
+      state.building.entry(key).or_default().clone()
+    };
+
+    let _guard = lock.lock().await;
+    // built by the request we waited for
+    let cached = self.state.lock().unwrap().replays.get(&key).cloned();
+    let res = match cached {
+      Some(data) => Ok(data),
+      None => build.await,
+    };
+
+    let mut state = self.state.lock().unwrap();
+    // no other request is waiting
+    if Arc::strong_count(&lock) == 2 {
+      state.building.remove(&key);
+    }
+    if let Ok(ref data) = res {
+      if cached.is_none() && data.len() <= self.max_bytes {
+        state.bytes += data.len();
+        if let Some(replaced) = state.replays.put(key, data.clone()) {
+          state.bytes -= replaced.len();
+        }
+        while state.bytes > self.max_bytes {
+          match state.replays.pop_lru() {
+            Some((_, evicted)) => state.bytes -= evicted.len(),
+            None => break,
+          }
+        }
+      }
+    }
+    res
+  }
+}
+
+/// Generates a `.w3g` replay from a game archive
+async fn build_replay(
+  mut game: GameInfo,
+  archive: Bytes,
+  mask_player_names: bool,
+  include_chats: bool,
+) -> Result<Bytes> {
+  if mask_player_names {
+    for (i, slot) in game.slots.iter_mut().enumerate() {
+      if let Some(player) = slot.player.as_mut() {
+        player.name = format!("Player {}", i + 1);
+      }
+    }
+  }
+
+  let game_id = game.id;
+  let mut w = Cursor::new(vec![]);
+  generate_replay(
+    GenerateReplayOptions {
+      game,
+      archive,
+      include_chats,
+    },
+    &mut w,
+  )
+  .await?;
+  let data = Bytes::from(w.into_inner());
+
+  tracing::info!(
+    game_id,
+    include_chats,
+    "replay generated: {} bytes",
+    data.len()
+  );
+
+  Ok(data)
+}
+
+#[cfg(test)]
+async fn build_test_archive(game_id: i32) -> Bytes {
+  use flo_observer::record::GameRecordData;
+  use flo_observer_fs::GameDataWriter;
+
+  tokio::fs::remove_dir_all(GameDataWriter::data_folder().join(game_id.to_string()))
+    .await
+    .ok();
+  let mut writer = GameDataWriter::create_or_recover(game_id).await.unwrap();
+  for tick in 0..1000 {
+    writer
+      .write_record(GameRecordData::TickChecksum {
+        tick,
+        checksum: tick * 7,
+      })
+      .await
+      .unwrap();
+  }
+  writer.write_record(GameRecordData::GameEnd).await.unwrap();
+  let path = writer.build_archive(true).await.unwrap();
+  let archive = tokio::fs::read(&path).await.unwrap();
+  tokio::fs::remove_dir_all(writer.data_dir()).await.ok();
+  Bytes::from(archive)
+}
+
+#[cfg(test)]
+fn test_game_info(game_id: i32) -> GameInfo {
+  use flo_types::observer::{Map, PlayerInfo, Slot, SlotSettings, SlotStatus};
+
+  GameInfo {
+    id: game_id,
+    name: "test".to_string(),
+    map: Map {
+      sha1: vec![0; 20],
+      checksum: 0,
+      path: "Maps\\test.w3x".to_string(),
+    },
+    slots: (0..2)
+      .map(|i| Slot {
+        player: Some(PlayerInfo {
+          id: i + 1,
+          name: format!("player{}", i + 1),
+        }),
+        settings: SlotSettings {
+          team: i,
+          color: i,
+          status: SlotStatus::Occupied,
+          ..Default::default()
+        },
+      })
+      .collect(),
+    random_seed: 0,
+    game_version: "1.34.0.00000".to_string(),
+    start_time_millis: 0,
+  }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_replay_cache() {
+  let game_id = i32::MAX - 1;
+  let archive = build_test_archive(game_id).await;
+  let cache = ReplayCache::new(CACHE_MAX_BYTES);
+
+  let data = cache
+    .get_or_build(
+      game_id,
+      false,
+      build_replay(test_game_info(game_id), archive.clone(), false, false),
+    )
+    .await
+    .unwrap();
+  assert!(!data.is_empty());
+
+  // cache hits don't build the replay again
+  let cached = cache
+    .get_or_build(game_id, false, async {
+      Err(Error::ArchiveNotFound(game_id))
+    })
+    .await
+    .unwrap();
+  assert_eq!(cached, data);
+
+  // replays with chats are cached separately
+  assert!(cache
+    .get_or_build(game_id, true, async {
+      Err(Error::ArchiveNotFound(game_id))
+    })
+    .await
+    .is_err());
+
+  let masked = build_replay(test_game_info(game_id), archive, true, false)
+    .await
+    .unwrap();
+  assert_ne!(masked, data);
+}
+
+#[tokio::test]
+async fn test_replay_cache_single_flight() {
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::time::Duration;
+
+  let cache = ReplayCache::new(CACHE_MAX_BYTES);
+  let builds = AtomicUsize::new(0);
+  let build = || async {
+    builds.fetch_add(1, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    Ok(Bytes::from_static(b"replay"))
+  };
+  let (a, b) = tokio::join!(
+    cache.get_or_build(1, false, build()),
+    cache.get_or_build(1, false, build())
+  );
+  assert_eq!(a.unwrap(), b.unwrap());
+  assert_eq!(builds.load(Ordering::SeqCst), 1);
+  assert!(cache.state.lock().unwrap().building.is_empty());
+}
+
+#[tokio::test]
+async fn test_replay_cache_max_bytes() {
+  let cache = ReplayCache::new(10);
+  let replay = |len: usize| async move { Ok(Bytes::from(vec![0; len])) };
+  cache.get_or_build(1, false, replay(4)).await.unwrap();
+  cache.get_or_build(2, false, replay(4)).await.unwrap();
+  // evicts the least recently used replay
+  cache.get_or_build(3, false, replay(4)).await.unwrap();
+  // larger than the cache, not cached
+  cache.get_or_build(4, false, replay(11)).await.unwrap();
+
+  let state = cache.state.lock().unwrap();
+  assert_eq!(state.bytes, 8);
+  let mut keys: Vec<_> = state.replays.iter().map(|(k, _)| k.0).collect();
+  keys.sort_unstable();
+  assert_eq!(keys, vec![2, 3]);
+}
@@ -4,23 +4,27 @@ mod send_queue;
 use crate::dispatcher::{CreateGameStreamServer, GetGameInfo};
 use crate::error::Error;
 use crate::error::Result;
+use crate::token::{AcquireViewer, TokenRegistry, ViewerGuard};
 use crate::Dispatcher;
 use flo_net::{listener::FloListener, observer::ObserverConnectRejectReason, stream::FloStream};
 use flo_state::Addr;
+use std::sync::Arc;
 use std::time::SystemTime;
 use tokio_stream::StreamExt;
 
 pub struct StreamServer {
   listener: FloListener,
   dispatcher: Addr<Dispatcher>,
+  tokens: Arc<TokenRegistry>,
 }
 
 impl StreamServer {
-  pub async fn new(dispatcher: Addr<Dispatcher>) -> Result<Self> {
+  pub async fn new(dispatcher: Addr<Dispatcher>, tokens: Arc<TokenRegistry>) -> Result<Self> {
     let listener = FloListener::bind_v4(flo_constants::OBSERVER_SOCKET_PORT).await?;
     Ok(Self {
       listener,
       dispatcher,
+      tokens,
     })
   }
 
@@ -28,6 +32,7 @@ impl StreamServer {
     while let Some(transport) = self.listener.incoming().try_next().await? {
       let handler = Handler {
         dispatcher: self.dispatcher.clone(),
+        tokens: self.tokens.clone(),
         transport,
       };
       tokio::spawn(async move {
@@ -42,6 +47,7 @@ impl StreamServer {
 
 struct Handler {
   dispatcher: Addr<Dispatcher>,
+  tokens: Arc<TokenRegistry>,
   transport: FloStream,
 }
 
@@ -63,6 +69,7 @@ impl Handler {
       .await??;
 
     server.run(self.transport).await?;
+    drop(accepted.viewer);
 
     Ok(())
   }
@@ -79,6 +86,21 @@ impl Handler {
         return Ok(None);
       }
     };
+    let viewer = match self.tokens.acquire_viewer(&token) {
+      AcquireViewer::Acquired(v) => v,
+      AcquireViewer::Revoked => {
+        self
+          .reject(ObserverConnectRejectReason::TokenRevoked, None)
+          .await?;
+        return Ok(None);
+      }
+      AcquireViewer::TooManyViewers => {
+        self
+          .reject(ObserverConnectRejectReason::TooManyViewers, None)
+          .await?;
+        return Ok(None);
+      }
+    };
     let (meta, game) = match self
       .dispatcher
       .send(GetGameInfo {
@@ -134,6 +156,7 @@ impl Handler {
     Ok(Some(Accepted {
       game_id: token.game_id,
       delay_secs: token.delay_secs,
+      viewer,
     }))
   }
 
@@ -161,4 +184,5 @@ impl Handler {
 struct Accepted {
   game_id: i32,
   delay_secs: Option<i64>,
+  viewer: ViewerGuard,
 }
@@ -1,10 +1,15 @@
+use crate::chat::ChatFilter;
+use crate::checkpoint::CheckpointHandle;
 use crate::controller::Controller;
 use flo_observer_archiver::ArchiverHandle;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Services {
   pub controller: Controller,
   pub archiver: Option<ArchiverHandle>,
+  pub checkpoint: Option<CheckpointHandle>,
+  pub chat_filter: Arc<ChatFilter>,
 }
 
 impl Services {
@@ -12,6 +17,8 @@ impl Services {
     Self {
       controller: Controller::from_env(),
       archiver: None,
+      checkpoint: None,
+      chat_filter: ChatFilter::new(),
     }
   }
 }
@@ -0,0 +1,167 @@
+use crate::error::Result;
+use chrono::Utc;
+use flo_observer::token::ObserverToken;
+use flo_observer_fs::GameDataWriter;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+
+const REVOKED_TOKENS_FILENAME: &str = "revoked_tokens.json";
+
+/// Tracks revoked observer tokens and the number of viewers connected with each token.
+///
+/// Revoked token ids are kept until the token expires.
+/// In a cluster, tokens revoked by other instances are added on every heartbeat.
+#[derive(Debug)]
+pub struct TokenRegistry {
+  state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  // jti -> exp
+  revoked: BTreeMap<String, i64>,
+  // jti -> number of connected viewers
+  viewers: BTreeMap<String, u32>,
+}
+
+pub enum AcquireViewer {
+  Acquired(ViewerGuard),
+  Revoked,
+  TooManyViewers,
+}
+
+impl TokenRegistry {
+  pub async fn load() -> Result<Arc<Self>> {
+    let revoked = match fs::read(revoked_tokens_path()).await {
+      Ok(bytes) => serde_json::from_slice(&bytes)?,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+      Err(err) => return Err(err.into()),
+    };
+    let mut state = State {
+      revoked,
+      ..Default::default()
+    };
+    state.remove_expired();
+    Ok(Arc::new(Self {
+      state: Mutex::new(state),
+    }))
+  }
+
+  pub async fn revoke(&self, jti: String, exp: i64) -> Result<()> {
+    let bytes = {
+      let mut state = self.state.lock().unwrap();
+      state.remove_expired();
+      state.revoked.insert(jti, exp);
+      serde_json::to_vec(&state.revoked)?
+    };
+    let path = revoked_tokens_path();
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, bytes).await?;
+    fs::rename(temp_path, path).await?;
+    Ok(())
+  }
+
+  /// Adds token ids revoked elsewhere, `revoked` maps token ids to expiration timestamps.
+  pub fn add_revoked(&self, revoked: BTreeMap<String, i64>) {
+    let mut state = self.state.lock().unwrap();
+    state.revoked.extend(revoked);
+    state.remove_expired();
+  }
+
+  pub fn acquire_viewer(self: &Arc<Self>, token: &ObserverToken) -> AcquireViewer {
+    let mut state = self.state.lock().unwrap();
+    if state.revoked.contains_key(&token.jti) {
+      return AcquireViewer::Revoked;
+    }
+    let count = state.viewers.entry(token.jti.clone()).or_default();
+    if let Some(max) = token.max_viewers {
+      if *count >= max {
+        return AcquireViewer::TooManyViewers;
+      }
+    }
+    *count += 1;
+    AcquireViewer::Acquired(ViewerGuard {
+      registry: self.clone(),
+      jti: token.jti.clone(),
+    })
+  }
+}
+
+impl State {
+  fn remove_expired(&mut self) {
+    let now = Utc::now().timestamp();
+    self.revoked.retain(|_, exp| *exp > now);
+  }
+}
+
+/// Decrements the token's viewer count on drop.
+#[derive(Debug)]
+pub struct ViewerGuard {
+  registry: Arc<TokenRegistry>,
+  jti: String,
+}
+
+impl Drop for ViewerGuard {
+  fn drop(&mut self) {
+    let mut state = self.registry.state.lock().unwrap();
+    let remove = match state.viewers.get_mut(&self.jti) {
+      Some(count) => {
+        *count = count.saturating_sub(1);
+        *count == 0
+      }
+      None => false,
+    };
+    if remove {
+      state.viewers.remove(&self.jti);
+    }
+  }
+}
+
+fn revoked_tokens_path() -> PathBuf {
+  GameDataWriter::data_folder().join(REVOKED_TOKENS_FILENAME)
+}
+
+#[test]
+fn test_acquire_viewer() {
+  let registry = Arc::new(TokenRegistry {
+    state: Mutex::new(State::default()),
+  });
+  let token = ObserverToken {
+    sub: "flo-observer".to_string(),
+    iss: "flo".to_string(),
+    aud: "flo-observer-edge".to_string(),
+    jti: "1".to_string(),
+    game_id: 1,
+    delay_secs: None,
+    max_viewers: Some(2),
+    exp: 0,
+  };
+
+  let a = registry.acquire_viewer(&token);
+  let b = registry.acquire_viewer(&token);
+  assert!(matches!(a, AcquireViewer::Acquired(_)));
+  assert!(matches!(b, AcquireViewer::Acquired(_)));
+  assert!(matches!(
+    registry.acquire_viewer(&token),
+    AcquireViewer::TooManyViewers
+  ));
+  drop(a);
+  assert!(matches!(
+    registry.acquire_viewer(&token),
+    AcquireViewer::Acquired(_)
+  ));
+
+  registry
+    .state
+    .lock()
+    .unwrap()
+    .revoked
+    .insert(token.jti.clone(), i64::MAX);
+  drop(b);
+  assert!(matches!(
+    registry.acquire_viewer(&token),
+    AcquireViewer::Revoked
+  ));
+}
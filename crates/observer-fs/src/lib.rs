@@ -45,11 +45,16 @@ impl GameDataWriter {
   }
 
   pub async fn create_or_recover(game_id: i32) -> Result<Self> {
-    let dir = DATA_FOLDER.join(game_id.to_string());
+    Self::create_or_recover_in(&DATA_FOLDER, game_id).await
+  }
+
+  /// Same as `create_or_recover`, but stores the game in `folder` instead of the data folder
+  pub async fn create_or_recover_in(folder: &Path, game_id: i32) -> Result<Self> {
+    let dir = folder.join(game_id.to_string());
     let path = dir.join(CHUNK_TEMP_FILENAME);
 
     match fs::metadata(&path).await {
-      Ok(_) => return Self::recover(game_id).await,
+      Ok(_) => return Self::recover_in(folder, game_id).await,
       Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
       Err(err) => return Err(err.into()),
     }
@@ -68,7 +73,11 @@ impl GameDataWriter {
   }
 
   pub async fn recover(game_id: i32) -> Result<Self> {
-    let r = GameDataReader::open(game_id).await?;
+    Self::recover_in(&DATA_FOLDER, game_id).await
+  }
+
+  pub async fn recover_in(folder: &Path, game_id: i32) -> Result<Self> {
+    let r = GameDataReader::open_in(folder, game_id).await?;
     let path = r.dir.join(CHUNK_TEMP_FILENAME);
     let chunk_file = File::create(path).await?;
     Ok(Self {
@@ -201,7 +210,11 @@ pub struct GameDataReader {
 
 impl GameDataReader {
   pub async fn open(game_id: i32) -> Result<Self> {
-    let dir = DATA_FOLDER.join(game_id.to_string());
+    Self::open_in(&DATA_FOLDER, game_id).await
+  }
+
+  pub async fn open_in(folder: &Path, game_id: i32) -> Result<Self> {
+    let dir = folder.join(game_id.to_string());
     let mut chunk_buf = BytesMut::with_capacity(MAX_CHUNK_SIZE);
     let mut stream = fs::read_dir(&dir).await?;
     let mut max_chunk_id: Option<usize> = None;
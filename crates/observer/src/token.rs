@@ -7,41 +7,115 @@ use serde::{Deserialize, Serialize};
 use crate::error::*;
 
 // 15mins
-const TOKEN_EXPIRATION_SECS: i64 = 60 * 15;
+pub const TOKEN_EXPIRATION_SECS: i64 = 60 * 15;
+// 7 days
+pub const TOKEN_MAX_EXPIRATION_SECS: i64 = 60 * 60 * 24 * 7;
 const TOKEN_SUB: &str = "flo-observer";
+const TOKEN_AUD: &str = "flo-observer-edge";
+const TOKEN_DEFAULT_ISS: &str = "flo";
 
 static JWT_SECRET_BASE64: Lazy<String> =
   Lazy::new(|| std::env::var("JWT_SECRET_BASE64").expect("env JWT_SECRET_BASE64"));
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObserverToken {
   pub sub: String,
+  pub iss: String,
+  pub aud: String,
+  /// Unique token id, used to revoke the token
+  pub jti: String,
   pub game_id: i32,
   pub delay_secs: Option<i64>,
+  /// Maximum number of concurrent connections using this token
+  pub max_viewers: Option<u32>,
   pub exp: usize,
 }
 
+#[derive(Debug)]
+pub struct CreateObserverTokenOptions {
+  pub game_id: i32,
+  pub delay_secs: Option<i64>,
+  /// Name of the API client issuing the token
+  pub issuer: Option<String>,
+  /// Defaults to 15 minutes, capped at 7 days
+  pub expires_in_secs: Option<i64>,
+  pub max_viewers: Option<u32>,
+}
+
 pub fn create_observer_token(game_id: i32, delay_secs: Option<i64>) -> Result<String> {
+  create_observer_token_with_options(CreateObserverTokenOptions {
+    game_id,
+    delay_secs,
+    issuer: None,
+    expires_in_secs: None,
+    max_viewers: None,
+  })
+  .map(|(token, _)| token)
+}
+
+pub fn create_observer_token_with_options(
+  opts: CreateObserverTokenOptions,
+) -> Result<(String, ObserverToken)> {
   static ENCODING_KEY: Lazy<EncodingKey> = Lazy::new(|| {
     EncodingKey::from_base64_secret(&JWT_SECRET_BASE64).expect("DecodingKey::from_base64_secret")
   });
 
-  let exp = Utc::now().timestamp() + TOKEN_EXPIRATION_SECS;
+  let expires_in_secs = opts
+    .expires_in_secs
+    .unwrap_or(TOKEN_EXPIRATION_SECS)
+    .max(1)
+    .min(TOKEN_MAX_EXPIRATION_SECS);
+  let exp = Utc::now().timestamp() + expires_in_secs;
   let claims = ObserverToken {
     sub: TOKEN_SUB.to_string(),
-    game_id,
-    delay_secs,
+    iss: opts.issuer.unwrap_or_else(|| TOKEN_DEFAULT_ISS.to_string()),
+    aud: TOKEN_AUD.to_string(),
+    jti: uuid::Uuid::new_v4().to_string(),
+    game_id: opts.game_id,
+    delay_secs: opts.delay_secs,
+    max_viewers: opts.max_viewers,
     exp: exp as usize,
   };
-  encode(&Header::default(), &claims, &ENCODING_KEY).map_err(Into::into)
+  let token = encode(&Header::default(), &claims, &ENCODING_KEY)?;
+  Ok((token, claims))
 }
 
 pub fn validate_observer_token(token: &str) -> Result<ObserverToken> {
   let decoding_key = DecodingKey::from_base64_secret(&JWT_SECRET_BASE64)?;
-  decode(token, &decoding_key, &Validation::default())
+  let mut validation = Validation {
+    sub: Some(TOKEN_SUB.to_string()),
+    ..Default::default()
+  };
+  validation.set_audience(&[TOKEN_AUD]);
+  decode(token, &decoding_key, &validation)
     .map(|data| data.claims)
     .map_err(|e| match e.kind() {
       ErrorKind::ExpiredSignature => Error::ObserverTokenExpired,
       _ => e.into(),
     })
 }
+
+#[test]
+fn test_observer_token() {
+  std::env::set_var("JWT_SECRET_BASE64", "ZmxvLW9ic2VydmVyLXRva2VuLXRlc3Q=");
+
+  let now = Utc::now().timestamp();
+
+  let (token, claims) = create_observer_token_with_options(CreateObserverTokenOptions {
+    game_id: 1,
+    delay_secs: Some(180),
+    issuer: Some("w3flo".to_string()),
+    expires_in_secs: Some(i64::MAX),
+    max_viewers: Some(2),
+  })
+  .unwrap();
+  let expires_in = claims.exp as i64 - now;
+  assert!(expires_in >= TOKEN_MAX_EXPIRATION_SECS && expires_in <= TOKEN_MAX_EXPIRATION_SECS + 1);
+
+  let decoded = validate_observer_token(&token).unwrap();
+  assert_eq!(decoded.jti, claims.jti);
+  assert_eq!(decoded.iss, "w3flo");
+  assert_eq!(decoded.game_id, 1);
+  assert_eq!(decoded.delay_secs, Some(180));
+  assert_eq!(decoded.max_viewers, Some(2));
+}
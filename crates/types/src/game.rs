@@ -55,6 +55,7 @@ pub enum RejectReason {
   Unknown = 0,
   ClientVersionTooOld = 1,
   InvalidToken = 2,
+  Banned = 3,
 }
 
 #[derive(Debug, S2ProtoUnpack, Serialize)]
@@ -51,6 +51,13 @@ impl W3Map {
     Self::load_info(Self::open_archive_memory(bytes)?)
   }
 
+  pub fn open_memory_with_checksum(bytes: &[u8]) -> Result<(Self, MapChecksum)> {
+    let mut archive = Self::open_archive_memory(bytes)?;
+    let checksum = MapChecksum::compute(&mut archive)?;
+    let map = Self::load_info(archive)?;
+    Ok((map, checksum))
+  }
+
   #[cfg(feature = "w3storage")]
   pub fn open_storage(storage: &W3Storage, path: &str) -> Result<Self> {
     use flo_w3storage::Data;
@@ -0,0 +1,15 @@
+drop table webhook_delivery;
+drop table webhook;
+drop table player_ban;
+drop table player_mute;
+drop table game_player_result;
+drop table game_result;
+drop table game_used_slot;
+drop table game;
+drop table matchmaking_rating;
+drop table matchmaking_queue;
+drop table map_checksum;
+drop table map_catalog;
+drop table player;
+drop table node;
+drop table api_client;
@@ -0,0 +1,291 @@
+-- Equivalent of the PostgreSQL migrations in `migrations/` up to `2022-11-27-031045_webhook`.
+-- Timestamps are UTC text, json columns are text.
+
+create table api_client (
+    id integer not null primary key autoincrement,
+    name text not null,
+    secret_key text not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null
+);
+
+create table node (
+    id integer not null primary key autoincrement,
+    name text not null,
+    location text not null,
+    secret text not null,
+    ip_addr text not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    country_id text not null default 'US',
+    disabled boolean default false not null
+);
+
+create table player (
+    id integer not null primary key autoincrement,
+    name text not null,
+    source integer not null,
+    source_id text not null,
+    source_state text,
+    realm text,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    api_client_id integer not null references api_client(id),
+    unique(api_client_id, source, source_id)
+);
+
+create index player_source_id on player(source, source_id);
+create index player_source on player(source);
+create index player_api_realm on player(realm) where source = 2;
+
+create table map_catalog (
+    id integer not null primary key autoincrement,
+    sha1 text not null,
+    checksum blob not null,
+    crc32 blob not null,
+    file_size integer not null,
+    path text not null,
+    name text not null,
+    description text not null,
+    author text not null,
+    suggested_players text not null,
+    width integer not null,
+    height integer not null,
+    flags integer not null,
+    players text not null,
+    forces text not null,
+    preview blob,
+    uploaded_by integer not null references api_client(id),
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(sha1)
+);
+
+create table map_checksum (
+    id integer not null primary key autoincrement,
+    sha1 text not null,
+    checksum blob not null,
+    unique(sha1)
+);
+
+create index map_checksum_sha1 on map_checksum(sha1);
+
+create table matchmaking_queue (
+    id integer not null primary key autoincrement,
+    api_client_id integer not null references api_client(id),
+    name text not null,
+    team_size integer not null,
+    num_teams integer not null,
+    map_pool text not null,
+    default_rating integer not null,
+    rating_window_initial integer not null,
+    rating_window_growth integer not null,
+    rating_window_max integer not null,
+    max_ping integer not null,
+    accept_timeout_secs integer not null,
+    decline_ban_secs integer not null,
+    enabled boolean not null default true,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(api_client_id, name)
+);
+
+create table matchmaking_rating (
+    id integer not null primary key autoincrement,
+    queue_id integer not null references matchmaking_queue(id),
+    player_id integer not null references player(id),
+    rating integer not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(queue_id, player_id)
+);
+
+create table game (
+    id integer not null primary key autoincrement,
+    name text not null,
+    map_name text not null,
+    status integer not null default 0,
+    node_id integer references node(id),
+    is_private boolean not null,
+    secret integer,
+    is_live boolean not null,
+    max_players integer not null,
+    created_by integer not null references player(id),
+    started_at text,
+    ended_at text,
+    meta text not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    random_seed integer default 0 not null,
+    locked boolean default false not null,
+    mask_player_names boolean default false not null,
+    game_version text,
+    enable_ping_equalizer boolean default false not null,
+    map_catalog_id integer references map_catalog(id),
+    matchmaking_queue_id integer references matchmaking_queue(id)
+);
+
+create index game_status on game(status);
+create index game_node_id on game(node_id) where node_id is not null;
+
+create table game_used_slot (
+    id integer not null primary key autoincrement,
+    game_id integer not null references game(id) on delete cascade,
+    player_id integer references player(id) on delete cascade,
+    slot_index integer not null,
+    team integer not null,
+    color integer not null,
+    computer integer not null,
+    handicap integer not null,
+    status integer not null,
+    race integer not null,
+    client_status integer not null,
+    node_token blob,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    client_status_synced_node_conn_id bigint,
+    unique(game_id, player_id),
+    unique(game_id, slot_index)
+);
+
+create index game_used_slot_game_id on game_used_slot(game_id);
+create index game_used_slot_player_id on game_used_slot(player_id);
+create index game_used_slot_client_status on game_used_slot(client_status);
+
+create table game_result (
+    id integer not null primary key autoincrement,
+    game_id integer not null unique references game(id),
+    node_id integer not null references node(id),
+    duration_ms integer not null,
+    desyncs text not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null
+);
+
+create table game_player_result (
+    id integer not null primary key autoincrement,
+    game_id integer not null references game(id),
+    player_id integer not null references player(id),
+    left_at_ms integer,
+    leave_reason integer,
+    disconnects integer not null,
+    dropped boolean not null,
+    desynced boolean not null,
+    lag_count integer not null,
+    lag_duration_ms integer not null,
+    rtt_min integer,
+    rtt_max integer,
+    rtt_avg integer,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(game_id, player_id)
+);
+
+create table player_mute (
+    id integer not null primary key autoincrement,
+    player_id integer not null references player(id),
+    mute_player_id integer not null references player(id),
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(player_id, mute_player_id)
+);
+
+create index player_mute_player_id on player_mute(player_id);
+create index player_mute_mute_player_id on player_mute(mute_player_id);
+
+create table player_ban (
+    id integer not null primary key autoincrement,
+    player_id integer not null references player(id),
+    ban_type integer not null,
+    ban_expires_at text,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(player_id, ban_type)
+);
+
+create index player_ban_player_id on player_ban(player_id);
+
+create table webhook (
+    id integer not null primary key autoincrement,
+    api_client_id integer not null references api_client(id),
+    url text not null,
+    secret text not null,
+    events text not null,
+    enabled boolean not null default true,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null
+);
+
+create table webhook_delivery (
+    id integer not null primary key autoincrement,
+    webhook_id integer not null references webhook(id) on delete cascade,
+    event_type text not null,
+    game_id integer not null references game(id),
+    payload text not null,
+    status integer not null default 0,
+    attempts integer not null default 0,
+    last_status_code integer,
+    last_error text,
+    next_attempt_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    updated_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null
+);
+
+create index webhook_delivery_pending on webhook_delivery(next_attempt_at) where status = 0;
+
+-- `diesel_manage_updated_at`
+create trigger node_set_updated_at after update on node
+for each row when new.updated_at is old.updated_at
+begin
+    update node set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger player_set_updated_at after update on player
+for each row when new.updated_at is old.updated_at
+begin
+    update player set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger map_catalog_set_updated_at after update on map_catalog
+for each row when new.updated_at is old.updated_at
+begin
+    update map_catalog set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger matchmaking_queue_set_updated_at after update on matchmaking_queue
+for each row when new.updated_at is old.updated_at
+begin
+    update matchmaking_queue set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger matchmaking_rating_set_updated_at after update on matchmaking_rating
+for each row when new.updated_at is old.updated_at
+begin
+    update matchmaking_rating set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger game_set_updated_at after update on game
+for each row when new.updated_at is old.updated_at
+begin
+    update game set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger game_used_slot_set_updated_at after update on game_used_slot
+for each row when new.updated_at is old.updated_at
+begin
+    update game_used_slot set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger webhook_set_updated_at after update on webhook
+for each row when new.updated_at is old.updated_at
+begin
+    update webhook set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+create trigger webhook_delivery_set_updated_at after update on webhook_delivery
+for each row when new.updated_at is old.updated_at
+begin
+    update webhook_delivery set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.id;
+end;
+
+-- `update_game_updated_at_from_slot_proc`
+create trigger update_game_updated_at_slot after update on game_used_slot
+for each row
+begin
+    update game set updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') where id = new.game_id;
+end;
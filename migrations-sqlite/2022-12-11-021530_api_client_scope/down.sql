@@ -0,0 +1,3 @@
+drop table api_audit_log;
+alter table api_client drop column rate_limit;
+alter table api_client drop column scopes;
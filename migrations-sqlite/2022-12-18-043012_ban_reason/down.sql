@@ -0,0 +1,4 @@
+drop table ip_ban;
+drop table player_ban_history;
+alter table player_ban drop column api_client_id;
+alter table player_ban drop column reason;
@@ -0,0 +1,25 @@
+alter table player_ban add column reason text;
+alter table player_ban add column api_client_id integer references api_client(id);
+
+create table player_ban_history (
+    id integer not null primary key autoincrement,
+    player_id integer not null references player(id),
+    ban_type integer not null,
+    reason text,
+    api_client_id integer references api_client(id),
+    ban_expires_at text,
+    ban_created_at text not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null
+);
+
+create index player_ban_history_player_id on player_ban_history(player_id);
+
+create table ip_ban (
+    id integer not null primary key autoincrement,
+    api_client_id integer not null references api_client(id),
+    ip_range text not null,
+    reason text,
+    ban_expires_at text,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    unique(api_client_id, ip_range)
+);
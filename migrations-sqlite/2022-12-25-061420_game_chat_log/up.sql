@@ -0,0 +1,15 @@
+create table game_chat_message (
+    id integer not null primary key autoincrement,
+    game_id integer not null references game(id),
+    player_id integer not null references player(id),
+    scope integer not null,
+    to_player_id integer references player(id),
+    game_time_ms integer not null,
+    sent_at text not null,
+    message text not null,
+    filter_action integer not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null
+);
+
+create index game_chat_message_game_id on game_chat_message(game_id);
+create index game_chat_message_player_id on game_chat_message(player_id);
@@ -0,0 +1,9 @@
+create table game_chat_violation (
+    game_id integer not null references game(id),
+    player_id integer not null references player(id),
+    count integer not null,
+    created_at text default (strftime('%Y-%m-%d %H:%M:%f', 'now')) not null,
+    primary key (game_id, player_id)
+);
+
+create index game_chat_violation_player_id on game_chat_violation(player_id);
@@ -0,0 +1,2 @@
+alter table game drop column map_catalog_id;
+drop table map_catalog;
\ No newline at end of file
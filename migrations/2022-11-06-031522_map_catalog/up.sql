@@ -0,0 +1,25 @@
+create table map_catalog (
+    id serial not null primary key,
+    sha1 text not null,
+    checksum bytea not null,
+    crc32 bytea not null,
+    file_size integer not null,
+    path text not null,
+    name text not null,
+    description text not null,
+    author text not null,
+    suggested_players text not null,
+    width integer not null,
+    height integer not null,
+    flags integer not null,
+    players jsonb not null,
+    forces jsonb not null,
+    preview bytea,
+    uploaded_by integer not null references api_client(id),
+    created_at timestamp with time zone default now() not null,
+    updated_at timestamp with time zone default now() not null,
+    unique(sha1)
+);
+SELECT diesel_manage_updated_at('map_catalog');
+
+alter table game add column map_catalog_id integer references map_catalog(id);
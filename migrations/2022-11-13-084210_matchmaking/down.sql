@@ -0,0 +1,3 @@
+alter table game drop column matchmaking_queue_id;
+drop table matchmaking_rating;
+drop table matchmaking_queue;
\ No newline at end of file
@@ -0,0 +1,33 @@
+create table matchmaking_queue (
+    id serial not null primary key,
+    api_client_id integer not null references api_client(id),
+    name text not null,
+    team_size integer not null,
+    num_teams integer not null,
+    map_pool jsonb not null,
+    default_rating integer not null,
+    rating_window_initial integer not null,
+    rating_window_growth integer not null,
+    rating_window_max integer not null,
+    max_ping integer not null,
+    accept_timeout_secs integer not null,
+    decline_ban_secs integer not null,
+    enabled boolean not null default true,
+    created_at timestamp with time zone default now() not null,
+    updated_at timestamp with time zone default now() not null,
+    unique(api_client_id, name)
+);
+SELECT diesel_manage_updated_at('matchmaking_queue');
+
+create table matchmaking_rating (
+    id serial not null primary key,
+    queue_id integer not null references matchmaking_queue(id),
+    player_id integer not null references player(id),
+    rating integer not null,
+    created_at timestamp with time zone default now() not null,
+    updated_at timestamp with time zone default now() not null,
+    unique(queue_id, player_id)
+);
+SELECT diesel_manage_updated_at('matchmaking_rating');
+
+alter table game add column matchmaking_queue_id integer references matchmaking_queue(id);
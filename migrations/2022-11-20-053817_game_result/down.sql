@@ -0,0 +1,2 @@
+drop table game_player_result;
+drop table game_result;
\ No newline at end of file
@@ -0,0 +1,26 @@
+create table game_result (
+    id serial not null primary key,
+    game_id integer not null unique references game(id),
+    node_id integer not null references node(id),
+    duration_ms integer not null,
+    desyncs jsonb not null,
+    created_at timestamp with time zone default now() not null
+);
+
+create table game_player_result (
+    id serial not null primary key,
+    game_id integer not null references game(id),
+    player_id integer not null references player(id),
+    left_at_ms integer,
+    leave_reason integer,
+    disconnects integer not null,
+    dropped boolean not null,
+    desynced boolean not null,
+    lag_count integer not null,
+    lag_duration_ms integer not null,
+    rtt_min integer,
+    rtt_max integer,
+    rtt_avg integer,
+    created_at timestamp with time zone default now() not null,
+    unique(game_id, player_id)
+);
@@ -0,0 +1,29 @@
+create table webhook (
+    id serial not null primary key,
+    api_client_id integer not null references api_client(id),
+    url text not null,
+    secret text not null,
+    events jsonb not null,
+    enabled boolean not null default true,
+    created_at timestamp with time zone default now() not null,
+    updated_at timestamp with time zone default now() not null
+);
+SELECT diesel_manage_updated_at('webhook');
+
+create table webhook_delivery (
+    id serial not null primary key,
+    webhook_id integer not null references webhook(id) on delete cascade,
+    event_type text not null,
+    game_id integer not null references game(id),
+    payload jsonb not null,
+    status integer not null default 0,
+    attempts integer not null default 0,
+    last_status_code integer,
+    last_error text,
+    next_attempt_at timestamp with time zone default now() not null,
+    created_at timestamp with time zone default now() not null,
+    updated_at timestamp with time zone default now() not null
+);
+SELECT diesel_manage_updated_at('webhook_delivery');
+
+create index webhook_delivery_pending on webhook_delivery(next_attempt_at) where status = 0;
@@ -0,0 +1,14 @@
+alter table api_client add column scopes jsonb not null default '["all"]';
+alter table api_client add column rate_limit integer;
+
+create table api_audit_log (
+    id serial not null primary key,
+    api_client_id integer not null references api_client(id),
+    method text not null,
+    params jsonb not null,
+    status_code integer not null,
+    status_message text,
+    created_at timestamp with time zone default now() not null
+);
+
+create index api_audit_log_api_client_id on api_audit_log(api_client_id);
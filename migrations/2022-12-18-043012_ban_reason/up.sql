@@ -0,0 +1,25 @@
+alter table player_ban add column reason text;
+alter table player_ban add column api_client_id integer references api_client(id);
+
+create table player_ban_history (
+    id serial not null primary key,
+    player_id integer not null references player(id),
+    ban_type integer not null,
+    reason text,
+    api_client_id integer references api_client(id),
+    ban_expires_at timestamp with time zone,
+    ban_created_at timestamp with time zone not null,
+    created_at timestamp with time zone default now() not null
+);
+
+create index player_ban_history_player_id on player_ban_history(player_id);
+
+create table ip_ban (
+    id serial not null primary key,
+    api_client_id integer not null references api_client(id),
+    ip_range text not null,
+    reason text,
+    ban_expires_at timestamp with time zone,
+    created_at timestamp with time zone default now() not null,
+    unique(api_client_id, ip_range)
+);